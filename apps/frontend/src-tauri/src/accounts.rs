@@ -0,0 +1,319 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Manager, State, Wry};
+use tauri_plugin_store::{with_store, StoreCollection};
+use totp_rs::{Algorithm, Secret, TOTP};
+
+use crate::crypto::SecretKey;
+
+const ACCOUNTS_KEY: &str = "totp_accounts";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TotpAccount {
+    pub id: String,
+    pub issuer: Option<String>,
+    pub label: String,
+    // The base32 secret from the otpauth uri, encrypted with the SecretKey
+    pub secret: String,
+    pub algorithm: Algorithm,
+    pub digits: usize,
+    pub step: u64,
+}
+
+impl TotpAccount {
+    pub fn from_uri(uri: &str, key: &SecretKey) -> Result<TotpAccount, String> {
+        let totp = TOTP::from_url_unchecked(uri)
+            .map_err(|e| format!("Not a valid otpauth uri: {:?}", e))?;
+        let id = match &totp.issuer {
+            Some(issuer) => format!("{}:{}", issuer, totp.account_name),
+            None => totp.account_name.clone(),
+        };
+
+        Ok(TotpAccount {
+            id,
+            issuer: totp.issuer.clone(),
+            label: totp.account_name.clone(),
+            secret: key.encrypt(&totp.get_secret_base32())?,
+            algorithm: totp.algorithm,
+            digits: totp.digits,
+            step: totp.step,
+        })
+    }
+
+    pub fn to_totp(&self, key: &SecretKey) -> Result<TOTP, String> {
+        let secret = Secret::Encoded(key.decrypt(&self.secret)?)
+            .to_bytes()
+            .map_err(|e| format!("The stored secret for {} is corrupt: {:?}", self.id, e))?;
+
+        Ok(TOTP::new_unchecked(
+            self.algorithm,
+            self.digits,
+            1,
+            self.step,
+            secret,
+            self.issuer.clone(),
+            self.label.clone(),
+        ))
+    }
+
+    // The code for `time` (unix seconds) and how long it has left.
+    pub fn generate_at(&self, time: u64, key: &SecretKey) -> Result<GeneratedCode, String> {
+        let totp = self.to_totp(key)?;
+
+        Ok(GeneratedCode {
+            code: totp.generate(time),
+            seconds_remaining: totp.step - time % totp.step,
+        })
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct GeneratedCode {
+    pub code: String,
+    pub seconds_remaining: u64,
+}
+
+// What the frontend gets to see of an account, i.e. everything but the secret.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TotpAccountDetails {
+    pub id: String,
+    pub issuer: Option<String>,
+    pub label: String,
+}
+
+impl From<&TotpAccount> for TotpAccountDetails {
+    fn from(account: &TotpAccount) -> TotpAccountDetails {
+        TotpAccountDetails {
+            id: account.id.clone(),
+            issuer: account.issuer.clone(),
+            label: account.label.clone(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(transparent)]
+pub struct TotpAccounts(Vec<TotpAccount>);
+
+impl TotpAccounts {
+    // Nothing stored yet is an empty list, but a value that doesn't parse is
+    // an error so it never gets saved over.
+    pub fn load(value: Option<&Value>) -> Result<TotpAccounts, String> {
+        match value {
+            Some(value) => serde_json::from_value(value.clone())
+                .map_err(|e| format!("The stored accounts are corrupt: {}", e)),
+            None => Ok(TotpAccounts::default()),
+        }
+    }
+
+    pub fn to_value(&self) -> Value {
+        serde_json::to_value(self).unwrap_or_default()
+    }
+
+    // Adding an account that's already stored replaces it.
+    pub fn add(&mut self, account: TotpAccount) {
+        match self.0.iter_mut().find(|stored| stored.id == account.id) {
+            Some(stored) => *stored = account,
+            None => self.0.push(account),
+        }
+    }
+
+    pub fn remove(&mut self, id: &str) -> bool {
+        let count = self.0.len();
+        self.0.retain(|account| account.id != id);
+        self.0.len() != count
+    }
+
+    pub fn get(&self, id: &str) -> Option<&TotpAccount> {
+        self.0.iter().find(|account| account.id == id)
+    }
+
+    pub fn details(&self) -> Vec<TotpAccountDetails> {
+        self.0.iter().map(TotpAccountDetails::from).collect()
+    }
+
+    pub fn generate_at(
+        &self,
+        id: &str,
+        time: u64,
+        key: &SecretKey,
+    ) -> Result<GeneratedCode, String> {
+        self.get(id)
+            .ok_or_else(|| format!("No account {}", id))?
+            .generate_at(time, key)
+    }
+
+    // Encrypts secrets saved in plaintext by older versions, returning how
+    // many there were.
+    pub fn encrypt_plaintext(&mut self, key: &SecretKey) -> Result<usize, String> {
+        let mut count = 0;
+        for account in self.0.iter_mut() {
+            if !SecretKey::is_encrypted(&account.secret) {
+                account.secret = key.encrypt(&account.secret)?;
+                count += 1;
+            }
+        }
+
+        Ok(count)
+    }
+}
+
+fn store_path(app: &AppHandle<Wry>) -> Result<PathBuf, String> {
+    Ok(app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Unable to find the data dir: {}", e))?
+        .join("data.json"))
+}
+
+fn stores(app: &AppHandle<Wry>) -> Result<State<'_, StoreCollection<Wry>>, String> {
+    app.try_state::<StoreCollection<Wry>>()
+        .ok_or_else(|| "The store is not available".to_string())
+}
+
+pub fn read_accounts(app: &AppHandle<Wry>) -> Result<TotpAccounts, String> {
+    with_store(app.clone(), stores(app)?, store_path(app)?, |store| {
+        Ok(TotpAccounts::load(store.get(ACCOUNTS_KEY)))
+    })
+    .map_err(|e| format!("Unable to read the stored accounts: {}", e))?
+}
+
+// Runs `f` against the stored accounts, saving them afterwards. Nothing is
+// saved when the stored accounts can't be read.
+pub fn update_accounts<T>(
+    app: &AppHandle<Wry>,
+    f: impl FnOnce(&mut TotpAccounts) -> T,
+) -> Result<T, String> {
+    with_store(app.clone(), stores(app)?, store_path(app)?, |store| {
+        let mut accounts = match TotpAccounts::load(store.get(ACCOUNTS_KEY)) {
+            Ok(accounts) => accounts,
+            Err(e) => return Ok(Err(e)),
+        };
+        let result = f(&mut accounts);
+        store.insert(ACCOUNTS_KEY.to_string(), accounts.to_value())?;
+        store.save()?;
+
+        Ok(Ok(result))
+    })
+    .map_err(|e| format!("Unable to update the stored accounts: {}", e))?
+}
+
+mod tests {
+    use totp_rs::{Algorithm, Secret, TOTP};
+
+    use serde_json::json;
+
+    use crate::crypto::SecretKey;
+
+    use super::{TotpAccount, TotpAccounts};
+
+    #[test]
+    fn test_add_and_list_accounts() {
+        let key = SecretKey::from_bytes(&[7; 32]);
+        let mut accounts = TotpAccounts::load(None).unwrap();
+        accounts.add(
+            TotpAccount::from_uri(
+                "otpauth://totp/GitHub:alice?secret=JBSWY3DPEHPK3PXP&issuer=GitHub",
+                &key,
+            )
+            .unwrap(),
+        );
+        accounts.add(
+            TotpAccount::from_uri(
+                "otpauth://totp/DigitalOcean:admin@example.com?algorithm=SHA1&digits=6&issuer=DigitalOcean&period=30&secret=KEMMHM7H4IFX6FMY2Y7X4SUPAI3S56XV",
+                &key,
+            )
+            .unwrap(),
+        );
+        assert!(TotpAccount::from_uri("https://example.com", &key).is_err());
+
+        // Survives a trip through the store
+        let accounts = TotpAccounts::load(Some(&accounts.to_value())).unwrap();
+        let details = accounts.details();
+        assert_eq!(details.len(), 2);
+        assert_eq!(details[0].id, "GitHub:alice");
+        assert_eq!(details[1].label, "admin@example.com");
+        let stored = accounts.get("DigitalOcean:admin@example.com").unwrap();
+        assert_eq!(
+            stored.to_totp(&key).unwrap().get_secret_base32(),
+            "KEMMHM7H4IFX6FMY2Y7X4SUPAI3S56XV"
+        );
+
+        let mut accounts = accounts;
+        assert!(accounts.remove("GitHub:alice"));
+        assert!(!accounts.remove("GitHub:alice"));
+        assert_eq!(accounts.details().len(), 1);
+    }
+
+    #[test]
+    fn test_generate_code_for_stored_account() {
+        let key = SecretKey::from_bytes(&[7; 32]);
+        let mut accounts = TotpAccounts::default();
+        accounts.add(
+            TotpAccount::from_uri(
+                "otpauth://totp/Test:dazed?secret=KEMMHM7H4IFX6FMY2Y7X4SUPAI3S56XV&issuer=Test&period=30",
+                &key,
+            )
+            .unwrap(),
+        );
+        let totp = TOTP::new(
+            Algorithm::SHA1,
+            6,
+            1,
+            30,
+            Secret::Encoded("KEMMHM7H4IFX6FMY2Y7X4SUPAI3S56XV".to_string())
+                .to_bytes()
+                .unwrap(),
+            Some("Test".to_string()),
+            "dazed".to_string(),
+        )
+        .unwrap();
+
+        let time = 1_700_000_012;
+        let generated = accounts.generate_at("Test:dazed", time, &key).unwrap();
+        assert_eq!(generated.code, totp.generate(time));
+        assert_eq!(generated.seconds_remaining, 28);
+
+        assert!(accounts.generate_at("Test:nobody", time, &key).is_err());
+    }
+
+    #[test]
+    fn test_secrets_are_encrypted_in_the_store() {
+        let key = SecretKey::from_bytes(&[7; 32]);
+        // As saved before secrets were encrypted
+        let legacy = json!([{
+            "id": "GitHub:alice",
+            "issuer": "GitHub",
+            "label": "alice",
+            "secret": "JBSWY3DPEHPK3PXP",
+            "algorithm": "SHA1",
+            "digits": 6,
+            "step": 30,
+        }]);
+
+        let mut accounts = TotpAccounts::load(Some(&legacy)).unwrap();
+        assert_eq!(accounts.encrypt_plaintext(&key).unwrap(), 1);
+        assert_eq!(accounts.encrypt_plaintext(&key).unwrap(), 0);
+        accounts.add(
+            TotpAccount::from_uri(
+                "otpauth://totp/Test:dazed?secret=KEMMHM7H4IFX6FMY2Y7X4SUPAI3S56XV&issuer=Test",
+                &key,
+            )
+            .unwrap(),
+        );
+
+        let stored = accounts.to_value().to_string();
+        assert!(!stored.contains("JBSWY3DPEHPK3PXP"));
+        assert!(!stored.contains("KEMMHM7H4IFX6FMY2Y7X4SUPAI3S56XV"));
+        assert!(accounts.generate_at("GitHub:alice", 0, &key).is_ok());
+    }
+
+    #[test]
+    fn test_corrupt_accounts_fail_to_load() {
+        assert_eq!(TotpAccounts::load(None).unwrap(), TotpAccounts::default());
+        assert!(TotpAccounts::load(Some(&json!({ "id": "GitHub:alice" }))).is_err());
+        assert!(TotpAccounts::load(Some(&json!([{ "id": "GitHub:alice" }]))).is_err());
+    }
+}
@@ -0,0 +1,274 @@
+use std::{
+    fs,
+    num::NonZeroU32,
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use ring::{
+    aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN},
+    pbkdf2,
+    rand::{SecureRandom, SystemRandom},
+};
+
+const KEYCHAIN_SERVICE: &str = "tauri-app";
+const KEYCHAIN_USER: &str = "totp-secrets";
+// Used instead of the keychain on systems that don't have one
+const PASSPHRASE_VAR: &str = "TAURI_APP_PASSPHRASE";
+const PASSPHRASE_SALT_FILE: &str = "passphrase.salt";
+const PASSPHRASE_ITERATIONS: u32 = 600_000;
+// Marks a stored value as ciphertext, so plaintext left by older versions can
+// be told apart.
+const ENCRYPTED_PREFIX: &str = "enc:v1:";
+
+// Key for the secrets kept in the store. The key itself lives in the OS
+// keychain, never next to the data it protects.
+pub struct SecretKey(LessSafeKey);
+
+impl SecretKey {
+    pub fn from_bytes(bytes: &[u8; 32]) -> SecretKey {
+        let key = UnboundKey::new(&CHACHA20_POLY1305, bytes).expect("chacha20 keys are 32 bytes");
+        SecretKey(LessSafeKey::new(key))
+    }
+
+    // Loads the key from the keychain, or derives it from a passphrase when
+    // there's no keychain to keep it in. Once secrets have been sealed with a
+    // passphrase the keychain is left alone, so they stay readable.
+    pub fn load(data_dir: &Path) -> Result<SecretKey, String> {
+        let salt_path = data_dir.join(PASSPHRASE_SALT_FILE);
+        if !salt_path.exists() {
+            match SecretKey::from_keychain() {
+                Ok(key) => return Ok(key),
+                Err(e) => log::warn!("{}, falling back to a passphrase", e),
+            }
+        }
+
+        let passphrase = std::env::var(PASSPHRASE_VAR).map_err(|_| {
+            format!(
+                "No keychain is available, set {} to protect stored secrets with a passphrase",
+                PASSPHRASE_VAR
+            )
+        })?;
+
+        Ok(SecretKey::from_passphrase(
+            &passphrase,
+            &load_or_create_salt(&salt_path)?,
+        ))
+    }
+
+    pub fn from_passphrase(passphrase: &str, salt: &[u8]) -> SecretKey {
+        let mut bytes = [0u8; 32];
+        pbkdf2::derive(
+            pbkdf2::PBKDF2_HMAC_SHA256,
+            NonZeroU32::new(PASSPHRASE_ITERATIONS).expect("iterations are not zero"),
+            salt,
+            passphrase.as_bytes(),
+            &mut bytes,
+        );
+        SecretKey::from_bytes(&bytes)
+    }
+
+    // Loads the key from the keychain, creating one on first run.
+    pub fn from_keychain() -> Result<SecretKey, String> {
+        let entry = keyring::Entry::new(KEYCHAIN_SERVICE, KEYCHAIN_USER)
+            .map_err(|e| format!("Unable to open the keychain: {}", e))?;
+
+        let encoded = match entry.get_password() {
+            Ok(encoded) => encoded,
+            Err(keyring::Error::NoEntry) => {
+                let mut bytes = [0u8; 32];
+                SystemRandom::new()
+                    .fill(&mut bytes)
+                    .map_err(|_| "Unable to generate a key".to_string())?;
+                let encoded = STANDARD.encode(bytes);
+                entry
+                    .set_password(&encoded)
+                    .map_err(|e| format!("Unable to save the key to the keychain: {}", e))?;
+                encoded
+            }
+            Err(e) => return Err(format!("Unable to read the key from the keychain: {}", e)),
+        };
+
+        let bytes: [u8; 32] = STANDARD
+            .decode(encoded)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| "The key in the keychain is corrupt".to_string())?;
+
+        Ok(SecretKey::from_bytes(&bytes))
+    }
+
+    pub fn is_encrypted(stored: &str) -> bool {
+        stored.starts_with(ENCRYPTED_PREFIX)
+    }
+
+    pub fn encrypt(&self, plaintext: &str) -> Result<String, String> {
+        let mut nonce = [0u8; NONCE_LEN];
+        SystemRandom::new()
+            .fill(&mut nonce)
+            .map_err(|_| "Unable to generate a nonce".to_string())?;
+
+        let mut sealed = plaintext.as_bytes().to_vec();
+        self.0
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::empty(),
+                &mut sealed,
+            )
+            .map_err(|_| "Unable to encrypt the secret".to_string())?;
+
+        let mut stored = nonce.to_vec();
+        stored.extend(sealed);
+        Ok(format!("{}{}", ENCRYPTED_PREFIX, STANDARD.encode(stored)))
+    }
+
+    pub fn decrypt(&self, stored: &str) -> Result<String, String> {
+        let encoded = stored
+            .strip_prefix(ENCRYPTED_PREFIX)
+            .ok_or_else(|| "The secret is not encrypted".to_string())?;
+        let mut bytes = STANDARD
+            .decode(encoded)
+            .map_err(|_| "The encrypted secret is corrupt".to_string())?;
+        if bytes.len() < NONCE_LEN {
+            return Err("The encrypted secret is corrupt".to_string());
+        }
+
+        let mut sealed = bytes.split_off(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(&bytes)
+            .map_err(|_| "The encrypted secret is corrupt".to_string())?;
+        let plaintext = self
+            .0
+            .open_in_place(nonce, Aad::empty(), &mut sealed)
+            .map_err(|_| "Unable to decrypt the secret".to_string())?;
+
+        String::from_utf8(plaintext.to_vec())
+            .map_err(|_| "The decrypted secret is not text".to_string())
+    }
+}
+
+// Loads the SecretKey the first time a command needs it, so the app still
+// starts when there's no keychain and no passphrase set.
+pub struct LazySecretKey {
+    data_dir: PathBuf,
+    key: OnceLock<SecretKey>,
+    loading: Mutex<()>,
+}
+
+impl LazySecretKey {
+    pub fn new(data_dir: PathBuf) -> LazySecretKey {
+        LazySecretKey {
+            data_dir,
+            key: OnceLock::new(),
+            loading: Mutex::new(()),
+        }
+    }
+
+    // `on_load` runs once with the freshly loaded key, e.g. to encrypt
+    // plaintext secrets. If it fails the key is dropped and loaded again
+    // next time.
+    pub fn get(
+        &self,
+        on_load: impl FnOnce(&SecretKey) -> Result<(), String>,
+    ) -> Result<&SecretKey, String> {
+        self.get_with(|| SecretKey::load(&self.data_dir), on_load)
+    }
+
+    fn get_with(
+        &self,
+        load: impl FnOnce() -> Result<SecretKey, String>,
+        on_load: impl FnOnce(&SecretKey) -> Result<(), String>,
+    ) -> Result<&SecretKey, String> {
+        if let Some(key) = self.key.get() {
+            return Ok(key);
+        }
+
+        let _loading = self
+            .loading
+            .lock()
+            .map_err(|_| "The key failed to load on another thread".to_string())?;
+        if let Some(key) = self.key.get() {
+            return Ok(key);
+        }
+
+        let key = load()?;
+        on_load(&key)?;
+        Ok(self.key.get_or_init(|| key))
+    }
+}
+
+// The salt isn't secret, it only has to stay the same between runs.
+fn load_or_create_salt(path: &Path) -> Result<Vec<u8>, String> {
+    if path.exists() {
+        return fs::read(path).map_err(|e| format!("Unable to read the passphrase salt: {}", e));
+    }
+
+    let mut salt = vec![0u8; 16];
+    SystemRandom::new()
+        .fill(&mut salt)
+        .map_err(|_| "Unable to generate a salt".to_string())?;
+    fs::write(path, &salt).map_err(|e| format!("Unable to save the passphrase salt: {}", e))?;
+
+    Ok(salt)
+}
+
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{LazySecretKey, SecretKey};
+
+    #[test]
+    fn test_encrypt_round_trip() {
+        let key = SecretKey::from_bytes(&[7; 32]);
+        let encrypted = key.encrypt("JBSWY3DPEHPK3PXP").unwrap();
+
+        assert!(SecretKey::is_encrypted(&encrypted));
+        assert!(!encrypted.contains("JBSWY3DPEHPK3PXP"));
+        assert_eq!(key.decrypt(&encrypted).unwrap(), "JBSWY3DPEHPK3PXP");
+        // Nonces are random, so the same secret never encrypts the same way
+        assert_ne!(key.encrypt("JBSWY3DPEHPK3PXP").unwrap(), encrypted);
+
+        assert!(SecretKey::from_bytes(&[8; 32]).decrypt(&encrypted).is_err());
+        assert!(key.decrypt("JBSWY3DPEHPK3PXP").is_err());
+    }
+
+    #[test]
+    fn test_passphrase_key_is_stable() {
+        let key = SecretKey::from_passphrase("correct horse", b"salt");
+        let encrypted = key.encrypt("JBSWY3DPEHPK3PXP").unwrap();
+
+        let same = SecretKey::from_passphrase("correct horse", b"salt");
+        assert_eq!(same.decrypt(&encrypted).unwrap(), "JBSWY3DPEHPK3PXP");
+        assert!(SecretKey::from_passphrase("battery staple", b"salt")
+            .decrypt(&encrypted)
+            .is_err());
+        assert!(SecretKey::from_passphrase("correct horse", b"pepper")
+            .decrypt(&encrypted)
+            .is_err());
+    }
+
+    #[test]
+    fn test_lazy_key_loads_once() {
+        let lazy = LazySecretKey::new(PathBuf::new());
+        assert!(lazy
+            .get_with(|| Err("No keychain".to_string()), |_| Ok(()))
+            .is_err());
+        assert!(lazy
+            .get_with(
+                || Ok(SecretKey::from_bytes(&[7; 32])),
+                |_| Err("Unable to update the stored accounts".to_string())
+            )
+            .is_err());
+
+        let encrypted = lazy
+            .get_with(|| Ok(SecretKey::from_bytes(&[7; 32])), |_| Ok(()))
+            .unwrap()
+            .encrypt("JBSWY3DPEHPK3PXP")
+            .unwrap();
+        // Already loaded, so neither closure runs again
+        let key = lazy
+            .get_with(|| panic!("loaded twice"), |_| panic!("migrated twice"))
+            .unwrap();
+        assert_eq!(key.decrypt(&encrypted).unwrap(), "JBSWY3DPEHPK3PXP");
+    }
+}
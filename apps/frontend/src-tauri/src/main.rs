@@ -6,22 +6,29 @@ use std::{
     io::Write,
     path::{Path, PathBuf},
     thread,
-    time::Duration,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use tauri::{
-    command, AppHandle, Emitter, Manager, PhysicalPosition, PhysicalSize, WebviewUrl,
-    WebviewWindowBuilder, Wry,
+    command, AppHandle, Emitter, Manager, PhysicalPosition, PhysicalSize, Runtime, State,
+    WebviewUrl, WebviewWindow, WebviewWindowBuilder, Wry,
 };
 use tauri_plugin_store::{with_store, StoreBuilder, StoreCollection};
 
 use bardecoder;
 use image::{imageops::crop, DynamicImage, ImageBuffer, Rgba, RgbaImage};
+use totp_rs::TOTP;
 
 use std::error::Error;
 
+use accounts::{read_accounts, update_accounts, GeneratedCode, TotpAccount, TotpAccountDetails};
+use crypto::{LazySecretKey, SecretKey};
+
+mod accounts;
+mod crypto;
+
 fn decode_qr_code(image_data: DynamicImage) -> Vec<String> {
     let decoder = bardecoder::default_decoder();
 
@@ -36,6 +43,31 @@ fn decode_qr_code(image_data: DynamicImage) -> Vec<String> {
     uris
 }
 
+#[derive(Serialize, Debug, Default, PartialEq)]
+struct QrScanResults {
+    // Unique, well formed otpauth:// uris
+    otp_uris: Vec<String>,
+    // Everything else that was decoded, e.g. plain links
+    ignored: Vec<String>,
+}
+
+impl QrScanResults {
+    fn from_decoded(uris: Vec<String>) -> QrScanResults {
+        let mut results = QrScanResults::default();
+        for uri in uris {
+            let bucket = match TOTP::from_url_unchecked(&uri) {
+                Ok(_) => &mut results.otp_uris,
+                Err(_) => &mut results.ignored,
+            };
+            if !bucket.contains(&uri) {
+                bucket.push(uri);
+            }
+        }
+
+        results
+    }
+}
+
 #[command]
 async fn set_refresh_token(
     app: AppHandle<Wry>,
@@ -80,7 +112,59 @@ async fn get_refresh_token(app: AppHandle<Wry>) -> Option<Value> {
     .ok()
 }
 
-#[derive(Serialize, Deserialize)]
+// The key for stored secrets, loaded on first use. Secrets saved in plaintext
+// by older versions are encrypted as it loads.
+fn secret_key<'a>(app: &AppHandle<Wry>, key: &'a LazySecretKey) -> Result<&'a SecretKey, String> {
+    key.get(|key| {
+        let encrypted = update_accounts(app, |accounts| accounts.encrypt_plaintext(key))??;
+        if encrypted > 0 {
+            log::info!("encrypted {} stored secrets", encrypted);
+        }
+        Ok(())
+    })
+}
+
+#[command]
+async fn add_totp_account(
+    app: AppHandle<Wry>,
+    key: State<'_, LazySecretKey>,
+    uri: String,
+) -> Result<TotpAccountDetails, String> {
+    let account = TotpAccount::from_uri(&uri, secret_key(&app, &key)?)?;
+    let details = TotpAccountDetails::from(&account);
+    update_accounts(&app, |accounts| accounts.add(account))?;
+
+    Ok(details)
+}
+
+#[command]
+async fn list_totp_accounts(app: AppHandle<Wry>) -> Result<Vec<TotpAccountDetails>, String> {
+    Ok(read_accounts(&app)?.details())
+}
+
+#[command]
+async fn generate_code(
+    app: AppHandle<Wry>,
+    key: State<'_, LazySecretKey>,
+    account_id: String,
+) -> Result<GeneratedCode, String> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| format!("The system clock is before 1970: {}", e))?;
+
+    read_accounts(&app)?.generate_at(&account_id, now.as_secs(), secret_key(&app, &key)?)
+}
+
+#[command]
+async fn remove_totp_account(app: AppHandle<Wry>, id: String) -> Result<(), String> {
+    match update_accounts(&app, |accounts| accounts.remove(&id))? {
+        true => Ok(()),
+        false => Err(format!("No account {}", id)),
+    }
+}
+
+// Where the scanner window was before prep_qr moved it out of the way.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 struct Details {
     x: i32,
     y: i32,
@@ -97,67 +181,212 @@ impl Details {
             height: size.height,
         }
     }
+
+    fn center(&self) -> (i64, i64) {
+        (
+            self.x as i64 + self.width as i64 / 2,
+            self.y as i64 + self.height as i64 / 2,
+        )
+    }
+
+    // The same region in the local coordinates of `monitor`.
+    fn relative_to(&self, monitor: &MonitorBounds) -> Details {
+        Details {
+            x: self.x - monitor.x,
+            y: self.y - monitor.y,
+            width: self.width,
+            height: self.height,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct MonitorBounds {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+}
+
+impl MonitorBounds {
+    fn contains(&self, (x, y): (i64, i64)) -> bool {
+        let left = self.x as i64;
+        let top = self.y as i64;
+        x >= left && x < left + self.width as i64 && y >= top && y < top + self.height as i64
+    }
+}
+
+impl From<&xcap::Monitor> for MonitorBounds {
+    fn from(monitor: &xcap::Monitor) -> MonitorBounds {
+        MonitorBounds {
+            x: monitor.x(),
+            y: monitor.y(),
+            width: monitor.width(),
+            height: monitor.height(),
+        }
+    }
+}
+
+// Index of the monitor showing the centre of the window, or the first one if
+// the centre is somehow off every screen.
+fn monitor_for(monitors: &[MonitorBounds], details: &Details) -> Option<usize> {
+    if monitors.is_empty() {
+        return None;
+    }
+
+    let center = details.center();
+    Some(
+        monitors
+            .iter()
+            .position(|monitor| monitor.contains(center))
+            .unwrap_or(0),
+    )
+}
+
+// The part of `details` that lies on an image of the given size, as
+// (x, y, width, height). None when none of it does.
+fn crop_bounds(
+    image_width: u32,
+    image_height: u32,
+    details: &Details,
+) -> Option<(u32, u32, u32, u32)> {
+    let left = (details.x as i64).max(0);
+    let top = (details.y as i64).max(0);
+    let right = (details.x as i64 + details.width as i64).min(image_width as i64);
+    let bottom = (details.y as i64 + details.height as i64).min(image_height as i64);
+
+    if right <= left || bottom <= top {
+        return None;
+    }
+
+    Some((
+        left as u32,
+        top as u32,
+        (right - left) as u32,
+        (bottom - top) as u32,
+    ))
+}
+
+fn crop_and_decode(buffer: &mut RgbaImage, details: &Details) -> Vec<String> {
+    match crop_bounds(buffer.width(), buffer.height(), details) {
+        Some((x, y, width, height)) => {
+            let cropped_image = crop(buffer, x, y, width, height).to_image();
+            decode_qr_code(cropped_image.into())
+        }
+        None => {
+            log::warn!(
+                "scan region {:?} is outside the {}x{} capture",
+                details,
+                buffer.width(),
+                buffer.height()
+            );
+            vec![]
+        }
+    }
+}
+
+const QR_WINDOW: &str = "Third";
+
+fn qr_window<R: Runtime>(app: &AppHandle<R>) -> Result<WebviewWindow<R>, String> {
+    app.get_webview_window(QR_WINDOW)
+        .ok_or_else(|| "The QR scanner window is not open".to_string())
 }
 
 #[tauri::command]
 fn prep_qr(app: AppHandle<Wry>) -> Result<Details, String> {
-    let window = app.get_webview_window("Third").expect("hmm");
+    let window = qr_window(&app)?;
 
     let position = window
         .outer_position()
-        .expect("unable to get position of window");
-    let size = window.outer_size().expect("unable to get size of window");
+        .map_err(|e| format!("Unable to get the scanner position: {}", e))?;
+    let size = window
+        .outer_size()
+        .map_err(|e| format!("Unable to get the scanner size: {}", e))?;
     window
         .set_position(PhysicalPosition::new(9000, 9000))
-        .expect("Unable to set window opacity");
+        .map_err(|e| format!("Unable to move the scanner out of the way: {}", e))?;
 
-    return Ok(Details::new(position, size));
+    Ok(Details::new(position, size))
 }
 
-#[tauri::command]
-fn scan_qr(app: AppHandle<Wry>, details: Details) -> Vec<String> {
-    println!("capture?");
+// Where the scanner window can be put back to. Lets tests stand in for a real
+// window.
+trait ScannerWindow {
+    fn restore(&self, details: &Details) -> Result<(), String>;
+}
 
-    let window = app.get_webview_window("Third").expect("hmm");
-    let binding = xcap::Monitor::all().unwrap();
-    let monitor = binding.first().unwrap();
-    let image = monitor.capture_image().unwrap();
-    println!("Screenshot taken.");
+impl<R: Runtime> ScannerWindow for WebviewWindow<R> {
+    fn restore(&self, details: &Details) -> Result<(), String> {
+        self.set_size(PhysicalSize::new(details.width, details.height))
+            .map_err(|e| format!("Unable to resize the scanner: {}", e))?;
+        self.set_position(PhysicalPosition::new(details.x, details.y))
+            .map_err(|e| format!("Unable to move the scanner back: {}", e))
+    }
+}
+
+// Puts the scanner back where prep_qr found it if `result` failed, so it
+// isn't left stranded off screen.
+fn restore_on_error<T>(
+    window: &impl ScannerWindow,
+    details: &Details,
+    result: Result<T, String>,
+) -> Result<T, String> {
+    if result.is_err() {
+        if let Err(e) = window.restore(details) {
+            log::warn!("{}", e);
+        }
+    }
+
+    result
+}
+
+fn capture_and_scan(details: &Details) -> Result<QrScanResults, String> {
+    let monitors = xcap::Monitor::all().map_err(|e| format!("Unable to list monitors: {}", e))?;
+    let bounds: Vec<MonitorBounds> = monitors.iter().map(MonitorBounds::from).collect();
+    let index = monitor_for(&bounds, details).ok_or_else(|| "No monitor to capture".to_string())?;
+    let image = monitors[index]
+        .capture_image()
+        .map_err(|e| format!("Screen capture failed: {}", e))?;
 
     // Convert image to a buffer
-    let mut buffer =
-        RgbaImage::from_raw(image.width() as u32, image.height() as u32, image.to_vec())
-            .expect("Failed to create image buffer");
+    let mut buffer = RgbaImage::from_raw(image.width(), image.height(), image.to_vec())
+        .ok_or_else(|| "Unable to read the captured image".to_string())?;
 
-    let cropped_image = crop(
+    Ok(QrScanResults::from_decoded(crop_and_decode(
         &mut buffer,
-        details.x as u32,
-        details.y as u32,
-        details.width,
-        details.height,
-    )
-    .to_image();
-
-    let results = decode_qr_code(cropped_image.into());
-    let original_position = PhysicalPosition::new(details.x.clone(), details.y.clone());
+        &details.relative_to(&bounds[index]),
+    )))
+}
 
-    match results.len().eq(&0) {
-        true => window.set_position(original_position),
-        false => {
-            window.close().expect("close the window");
-            app.emit_to("tauri-app", "qr_results", &results)
-        }
+#[tauri::command]
+fn scan_qr(app: AppHandle<Wry>, details: Details) -> Result<QrScanResults, String> {
+    let window = qr_window(&app)?;
+    let results = restore_on_error(&window, &details, capture_and_scan(&details))?;
+
+    if results.otp_uris.is_empty() {
+        window.restore(&details)?;
+    } else {
+        let closed = window
+            .close()
+            .map_err(|e| format!("Unable to close the scanner: {}", e));
+        restore_on_error(&window, &details, closed)?;
+        app.emit_to("tauri-app", "qr_results", &results)
+            .map_err(|e| format!("Unable to send the scan results: {}", e))?;
     }
-    .expect("something went wrong moving the window");
 
-    results
+    Ok(results)
+}
+
+#[tauri::command]
+fn cancel_qr(app: AppHandle<Wry>, details: Details) -> Result<(), String> {
+    qr_window(&app)?.restore(&details)
 }
 
 #[tauri::command]
-fn start_qr(app: AppHandle<Wry>) {
+fn start_qr(app: AppHandle<Wry>) -> Result<(), String> {
     WebviewWindowBuilder::new(
         &app,
-        "Third",
+        QR_WINDOW,
         tauri::WebviewUrl::App(Path::new("qr-scan").to_path_buf()),
     )
     .always_on_top(true)
@@ -165,11 +394,14 @@ fn start_qr(app: AppHandle<Wry>) {
     .shadow(false)
     .title("Tauri - Third")
     .build()
-    .unwrap();
+    .map_err(|e| format!("Unable to open the scanner: {}", e))?;
+
+    Ok(())
 }
 
 fn main() {
     tauri::Builder::default()
+        .plugin(tauri_plugin_log::Builder::new().build())
         .plugin(tauri_plugin_store::Builder::default().build())
         .setup(|app| {
             let path = app
@@ -187,6 +419,10 @@ fn main() {
                 file.write_all(b"{}")?;
             }
 
+            app.manage(LazySecretKey::new(
+                path.parent().expect("data.json has a parent").to_path_buf(),
+            ));
+
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -194,16 +430,27 @@ fn main() {
             set_refresh_token,
             scan_qr,
             prep_qr,
-            start_qr
+            start_qr,
+            cancel_qr,
+            add_totp_account,
+            list_totp_accounts,
+            remove_totp_account,
+            generate_code
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
 
 mod tests {
-    use std::path::Path;
+    use std::{cell::RefCell, path::Path};
+
+    use image::{DynamicImage, Rgba, RgbaImage};
+    use qrcodegen::{QrCode, QrCodeEcc};
 
-    use crate::decode_qr_code;
+    use crate::{
+        crop_and_decode, crop_bounds, decode_qr_code, monitor_for, qr_window, restore_on_error,
+        Details, MonitorBounds, QrScanResults, ScannerWindow,
+    };
 
     #[test]
     fn test() {
@@ -211,4 +458,164 @@ mod tests {
 
         decode_qr_code(img);
     }
+
+    #[test]
+    fn test_crop_is_clamped_to_capture() {
+        let partly_off_screen = Details {
+            x: -50,
+            y: 80,
+            width: 100,
+            height: 100,
+        };
+        assert_eq!(
+            crop_bounds(200, 150, &partly_off_screen),
+            Some((0, 80, 50, 70))
+        );
+
+        let off_screen = Details {
+            x: 9000,
+            y: 9000,
+            width: 100,
+            height: 100,
+        };
+        assert_eq!(crop_bounds(200, 150, &off_screen), None);
+
+        let mut buffer = RgbaImage::new(200, 150);
+        assert!(crop_and_decode(&mut buffer, &off_screen).is_empty());
+        assert!(crop_and_decode(&mut buffer, &partly_off_screen).is_empty());
+    }
+
+    #[test]
+    fn test_picks_monitor_containing_window() {
+        let monitors = [
+            MonitorBounds {
+                x: 0,
+                y: 0,
+                width: 1920,
+                height: 1080,
+            },
+            MonitorBounds {
+                x: 1920,
+                y: -200,
+                width: 2560,
+                height: 1440,
+            },
+        ];
+        let on_second = Details {
+            x: 2000,
+            y: 100,
+            width: 300,
+            height: 300,
+        };
+
+        assert_eq!(monitor_for(&monitors, &on_second), Some(1));
+        let local = on_second.relative_to(&monitors[1]);
+        assert_eq!((local.x, local.y), (80, 300));
+
+        // Straddling the edge goes to whichever screen has the centre
+        let straddling = Details {
+            x: 1800,
+            y: 100,
+            width: 400,
+            height: 200,
+        };
+        assert_eq!(monitor_for(&monitors, &straddling), Some(1));
+
+        let off_screen = Details {
+            x: 9000,
+            y: 9000,
+            width: 100,
+            height: 100,
+        };
+        assert_eq!(monitor_for(&monitors, &off_screen), Some(0));
+        assert_eq!(monitor_for(&[], &off_screen), None);
+    }
+
+    #[test]
+    fn test_missing_qr_window_is_an_error() {
+        let app = tauri::test::mock_app();
+
+        assert!(qr_window(app.handle()).is_err());
+    }
+
+    // Draws each text as a QR code, side by side on a white background.
+    fn render_qr_codes(texts: &[&str]) -> RgbaImage {
+        let scale = 4;
+        let codes: Vec<QrCode> = texts
+            .iter()
+            .map(|text| QrCode::encode_text(text, QrCodeEcc::Medium).unwrap())
+            .collect();
+        let cell = codes
+            .iter()
+            .map(|code| (code.size() as u32 + 8) * scale)
+            .max()
+            .unwrap();
+
+        let mut image =
+            RgbaImage::from_pixel(cell * codes.len() as u32, cell, Rgba([255, 255, 255, 255]));
+        for (index, code) in codes.iter().enumerate() {
+            for y in 0..code.size() {
+                for x in 0..code.size() {
+                    if !code.get_module(x, y) {
+                        continue;
+                    }
+                    let left = index as u32 * cell + (x as u32 + 4) * scale;
+                    let top = (y as u32 + 4) * scale;
+                    for dy in 0..scale {
+                        for dx in 0..scale {
+                            image.put_pixel(left + dx, top + dy, Rgba([0, 0, 0, 255]));
+                        }
+                    }
+                }
+            }
+        }
+
+        image
+    }
+
+    #[test]
+    fn test_scan_keeps_only_unique_otp_uris() {
+        let otp = "otpauth://totp/Test:alice@example.com?secret=JBSWY3DPEHPK3PXP&issuer=Test";
+        let link = "https://example.com/not-an-otp";
+        let image = render_qr_codes(&[otp, link, otp]);
+
+        let results = QrScanResults::from_decoded(decode_qr_code(DynamicImage::ImageRgba8(image)));
+        assert_eq!(
+            results,
+            QrScanResults {
+                otp_uris: vec![otp.to_string()],
+                ignored: vec![link.to_string()],
+            }
+        );
+    }
+
+    #[derive(Default)]
+    struct FakeWindow {
+        restored_to: RefCell<Option<Details>>,
+    }
+
+    impl ScannerWindow for FakeWindow {
+        fn restore(&self, details: &Details) -> Result<(), String> {
+            *self.restored_to.borrow_mut() = Some(details.clone());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_failed_scan_restores_window() {
+        let details = Details {
+            x: 120,
+            y: 80,
+            width: 300,
+            height: 300,
+        };
+
+        let window = FakeWindow::default();
+        assert!(restore_on_error(&window, &details, Ok(())).is_ok());
+        assert_eq!(*window.restored_to.borrow(), None);
+
+        let failed: Result<(), String> = Err("Screen capture failed".to_string());
+        assert!(restore_on_error(&window, &details, failed).is_err());
+        assert_eq!(*window.restored_to.borrow(), Some(details));
+    }
 }
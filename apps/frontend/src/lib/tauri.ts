@@ -23,7 +23,12 @@ export async function getAccessTokenWithTauri() {
 	}
 }
 
-export async function scanForOtpQrs(details: unknown): Promise<string[]> {
+export type QrScanResults = {
+	otp_uris: string[];
+	ignored: string[];
+};
+
+export async function scanForOtpQrs(details: unknown): Promise<QrScanResults> {
 	return await invoke('scan_qr', { details });
 }
 
@@ -31,10 +36,41 @@ export async function prepForQrCodeScan() {
 	return await invoke('prep_qr');
 }
 
+export async function cancelQrCodeScan(details: unknown) {
+	return await invoke('cancel_qr', { details });
+}
+
 export async function openQrScanner() {
 	return await invoke('start_qr');
 }
 
+export type TotpAccount = {
+	id: string;
+	issuer: string | null;
+	label: string;
+};
+
+export async function addTotpAccount(uri: string): Promise<TotpAccount> {
+	return await invoke('add_totp_account', { uri });
+}
+
+export async function listTotpAccounts(): Promise<TotpAccount[]> {
+	return await invoke('list_totp_accounts');
+}
+
+export async function removeTotpAccount(id: string) {
+	return await invoke('remove_totp_account', { id });
+}
+
+export type GeneratedCode = {
+	code: string;
+	seconds_remaining: number;
+};
+
+export async function generateCode(accountId: string): Promise<GeneratedCode> {
+	return await invoke('generate_code', { accountId });
+}
+
 export function createTauriListeners() {
 	if (!isTauri) {
 		return () => {};
@@ -42,7 +78,7 @@ export function createTauriListeners() {
 	console.log('setup listeners.');
 
 	const unsubscribers: UnlistenFn[] = [];
-	// listen<string[]>('qr_results', async (results) => {
+	// listen<QrScanResults>('qr_results', async (results) => {
 	// 	// const accountDetails = await client.query(['account.preview', results.payload]);
 	// 	// accountDetails.map(createAccount);
 	// }).then((unsub) => unsubscribers.push(unsub));
@@ -2,21 +2,42 @@
 
 export type Procedures = {
     queries: 
+        { key: "lobby.export_replay", input: [string, number], result: string } | 
+        { key: "lobby.find_my_lobby", input: string, result: string | null } | 
+        { key: "lobby.get_lobby_state", input: [string, string], result: LobbyData } | 
+        { key: "lobby.legal_targets", input: [string, string, string], result: FrontendTarget[] } | 
+        { key: "lobby.list_lobbies", input: never, result: LobbyListing[] } | 
+        { key: "lobby.match_history", input: never, result: MatchResult[] } | 
         { key: "version", input: never, result: string },
     mutations: 
+        { key: "authentication.create_account", input: string, result: AccountResponse } | 
+        { key: "authentication.enroll_totp", input: string | null, result: string } | 
         { key: "authentication.login", input: LoginArgs, result: AuthResponse } | 
         { key: "authentication.refresh_token", input: string, result: AuthResponse } | 
+        { key: "authentication.verify_totp", input: string, result: boolean } | 
         { key: "lobby.action_card", input: ActionCardArgs, result: null } | 
         { key: "lobby.attach_card", input: ActionCardArgs, result: null } | 
         { key: "lobby.chat", input: LobbyChatArgs, result: null } | 
+        { key: "lobby.concede", input: string, result: null } | 
         { key: "lobby.create", input: string[], result: LobbyData } | 
+        { key: "lobby.equip_card", input: ActionCardArgs, result: null } | 
+        { key: "lobby.flashback_card", input: FlashbackCardArgs, result: null } | 
         { key: "lobby.join", input: string, result: null } | 
+        { key: "lobby.keep_hand", input: KeepHandArgs, result: null } | 
+        { key: "lobby.kick", input: KickPlayerArgs, result: null } | 
+        { key: "lobby.mulligan", input: string, result: null } | 
+        { key: "lobby.pass_until_my_turn", input: PassUntilMyTurnArgs, result: null } | 
         { key: "lobby.play_card", input: PlayCardArgs, result: null } | 
         { key: "lobby.ready", input: string, result: null } | 
         { key: "lobby.respond_mandatory_ability", input: RespondMandatoryAbility, result: null } | 
         { key: "lobby.respond_optional_ability", input: RespondOptionalAbility, result: null } | 
         { key: "lobby.select_deck", input: SelectDeckArgs, result: null } | 
-        { key: "lobby.turn", input: string, result: null },
+        { key: "lobby.sideboard", input: SideboardArgs, result: null } | 
+        { key: "lobby.start", input: string, result: null } | 
+        { key: "lobby.suspend_card", input: SuspendCardArgs, result: null } | 
+        { key: "lobby.turn", input: string, result: null } | 
+        { key: "lobby.turn_face_up", input: TurnFaceUpArgs, result: null } | 
+        { key: "lobby.update_settings", input: UpdateSettingsArgs, result: null },
     subscriptions: 
         { key: "lobby.subscribe", input: [string, string], result: LobbyCommand }
 };
@@ -33,7 +54,7 @@ export type TurnPhase = "Untap" | "Upkeep" | "Draw" | "Main" | "BeginningOfComba
 
 export type Block = { attacker: FrontendCardTarget; blocker: FrontendCardTarget }
 
-export type LobbyCommand = { Updated: LobbyData } | { Messages: string[] } | { DebugMessage: string } | { TurnMessages: LobbyTurnMessage } | { AskExecuteAbility: ExecuteAbility } | { MandatoryExecuteAbility: ExecuteAbility }
+export type LobbyCommand = { Updated: LobbyData } | { Messages: string[] } | { DebugMessage: string } | { TurnMessages: LobbyTurnMessage } | { AskExecuteAbility: ExecuteAbility } | { MandatoryExecuteAbility: ExecuteAbility } | { DisconnectCountdown: DisconnectCountdown } | { MulliganInfo: MulliganInfo } | { ManaAdded: ManaAdded } | { ManaPoolUpdated: ManaPoolUpdate } | { StackUpdated: StackItemView[] }
 
 export type RespondMandatoryAbility = { code: string; target: FrontendTarget | null; ability_id: string }
 
@@ -41,11 +62,11 @@ export type RespondOptionalAbility = { code: string; target: FrontendTarget | nu
 
 export type FrontendCardTarget = { player_index: number; pile: FrontendPileName; card_index: number }
 
-export type LobbyData = { join_code: string; chat: LobbyChat[]; game_state: GameState }
+export type LobbyData = { join_code: string; chat: LobbyChat[]; game_state: GameState; settings: LobbySettings; match_score: { [key: string]: number }; games_played: number }
 
-export type Card = { creature_type: CreatureType | null; name: string; description: string; card_type: CardType; current_phase: CardPhase; tapped: boolean; stats: StatManager; cost: ManaType[]; is_countered: boolean; id: string }
+export type Card = { creature_type: CreatureType | null; name: string; description: string; card_type: CardType; current_phase: CardPhase; tapped: boolean; stats: StatManager; cost: ManaType[]; is_countered: boolean; id: string; phased_out?: boolean; must_attack?: boolean; suspend?: Suspend | null; flashback?: ManaType[] | null; echo?: ManaType[] | null; face_down?: boolean; morph?: ManaType[] | null }
 
-export type ActionType = "Tap" | "None" | "Instant" | "Attach" | "PlayedCard"
+export type ActionType = "Tap" | "None" | "Instant" | "Attach" | "Equip" | "PlayedCard"
 
 export type PlayerStatus = "Spectator" | "Ready" | "InGame"
 
@@ -53,17 +74,17 @@ export type AbilityDetails = { mana_cost: ManaType[]; required_target: CardRequi
 
 export type LobbyChatArgs = { lobby_id: string; text: string }
 
-export type GameStatus = "NeedsPlayers" | "InGame" | { WaitingForStart: number }
+export type GameStatus = "NeedsPlayers" | "InGame" | { WaitingForStart: number } | "Sideboarding" | "GameOver"
 
-export type CardRequiredTarget = "None" | "OwnedCard" | "AnyPlayer" | "AnyCard" | "EnemyCard" | "EnemyPlayer" | "EnemyCardOrPlayer" | { CardOfType: [CardType, CardTargetTeam] } | { CreatureOfType: [CreatureType, CardTargetTeam] } | "EnemyCardInCombat" | "Spell" | { MultipleCardsOfType: [CardType, number] }
+export type CardRequiredTarget = "None" | "OwnedCard" | "AnyPlayer" | "AnyCard" | "EnemyCard" | "EnemyPlayer" | "EnemyCardOrPlayer" | { CardOfType: [CardType, CardTargetTeam] } | { CreatureOfType: [CreatureType, CardTargetTeam] } | "EnemyCardInCombat" | "Spell" | "Ability" | { MultipleCardsOfType: [CardType, number] }
 
-export type PlayCardArgs = { code: string; in_hand_index: number; target: FrontendTarget | null }
+export type PlayCardArgs = { code: string; in_hand_index: number; target: FrontendTarget | null; auto_pay?: boolean; payment?: PaymentChoice[] | null }
 
 export type DeckSelector = "Green" | "Blue" | "Black" | "Angels" | "Red"
 
-export type StatType = "Health" | "Power" | "Toughness" | "Trample" | "Lifelink" | "Flying"
+export type StatType = "Health" | "Power" | "Toughness" | "Trample" | "Lifelink" | "Flying" | "Indestructible" | "Storm" | "SplitSecond" | "Dredge" | "Banding" | "UntapLocked" | "LandsAllowed" | "DrawsPerTurn"
 
-export type FrontendPileName = "Hand" | "Play" | "Spell"
+export type FrontendPileName = "Hand" | "Play" | "Spell" | "Library"
 
 export type Turn = { current_player_index: number; phase: TurnPhase; turn_number: number }
 
@@ -71,15 +92,15 @@ export type LobbyChat = { user_id: string; message: string }
 
 export type CardPhase = { Charging: number } | "Ready" | "Complete" | "Cancelled"
 
-export type ManaPool = { white: number; blue: number; black: number; red: number; green: number; colorless: number; played_card: boolean }
+export type ManaPool = { white: number; blue: number; black: number; red: number; green: number; colorless: number }
 
-export type PlayerState = { public_info: PublicPlayerInfo; hand: CardWithDetails[]; discard_pile: CardWithDetails[]; status: PlayerStatus; is_leader: boolean; player_index: number; priority_queue: PriorityQueue | null; deck: DeckSelector }
+export type PlayerState = { public_info: PublicPlayerInfo; hand: CardWithDetails[]; top_card: CardWithDetails | null; face_down_cards: CardWithDetails[]; discard_pile: CardWithDetails[]; status: PlayerStatus; is_leader: boolean; player_index: number; priority_queue: PriorityQueue | null; deck: DeckSelector; decklist: Decklist | null }
 
 export type LobbyTurnMessage = { messages: string[] }
 
-export type ExecuteAbility = { card: CardWithDetails; details: AbilityDetails; player_id: string }
+export type ExecuteAbility = { card: CardWithDetails; details: AbilityDetails; player_id: string; choices: CardWithDetails[]; costs: Cost[] }
 
-export type PublicGameInfo = { current_turn: Turn | null; priority_queue: PriorityQueue | null; attacks: Attack[]; blocks: Block[] }
+export type PublicGameInfo = { current_turn: Turn | null; priority_queue: PriorityQueue | null; attacks: Attack[]; blocks: Block[]; monarch: string | null; stack: StackItemView[]; deciding_hands: string[] }
 
 export type CardTargetTeam = "Owner" | "Opponent" | "Any"
 
@@ -93,14 +114,58 @@ export type SelectDeckArgs = { code: string; deck: DeckSelector }
 
 export type AuthResponse = { access_token: string | null; refresh_token: string | null; success: boolean }
 
-export type FrontendTarget = { Card: FrontendCardTarget } | { Player: number }
+export type FrontendTarget = { Card: FrontendCardTarget } | { Player: number } | { Cards: FrontendCardTarget[] }
 
 export type Stat = { stat_type: StatType; intensity: number }
 
-export type PublicPlayerInfo = { hand_size: number; cards_in_play: CardWithDetails[]; spells: CardWithDetails[]; mana_pool: ManaPool; health: number }
+export type PublicPlayerInfo = { hand_size: number; cards_in_play: CardWithDetails[]; spells: CardWithDetails[]; mana_pool: ManaPool; health: number; revealed_top_card: CardWithDetails | null }
 
 export type GameState = { players: { [key: string]: PlayerState }; public_info: PublicGameInfo; status: GameStatus }
 
-export type CardType = "Creature" | "Enchantment" | "Instant" | "Sorcery" | "Artifact" | { BasicLand: ManaType }
+export type CardType = "Creature" | "Enchantment" | "Instant" | "Sorcery" | "Artifact" | { BasicLand: ManaType } | { DualLand: [ManaType, ManaType] }
 
 export type StatManager = { stats: { [key: string]: Stat } }
+
+export type LobbySettings = { max_players: number; reserved_seats: string[]; ranked?: boolean; disconnect_grace_secs?: number; best_of?: number; auto_pass_priority?: boolean; skip_empty_priority_windows?: boolean; public?: boolean; singleton?: boolean }
+
+export type KickPlayerArgs = { code: string; user_id: string }
+
+export type UpdateSettingsArgs = { code: string; settings: LobbySettings }
+
+export type AccountResponse = { email: string; has_totp: boolean }
+
+export type MatchResult = { id: string; lobby_code: string; players: string[]; winner: string | null; turn_count: number; duration_seconds: number }
+
+export type DisconnectCountdown = { user_id: string; seconds_remaining: number }
+
+export type MulliganInfo = { player_id: string; hand: CardWithDetails[]; mulligans_taken: number; bottom_count: number }
+
+export type KeepHandArgs = { code: string; bottom: number[] }
+
+export type Decklist = { main: Card[]; sideboard: Card[]; min_size: number }
+
+export type SideboardArgs = { code: string; remove: string[]; add: string[] }
+
+export type Cost = { Mana: ManaType[] } | { Life: number } | "Tap" | { TapOther: CardRequiredTarget } | { Sacrifice: CardRequiredTarget } | { Discard: number }
+
+export type LobbyListing = { join_code: string; player_count: number; max_players: number; ranked: boolean; best_of: number }
+
+export type ManaAdded = { player_id: string; card_id: string; mana: ManaType[] }
+
+export type StackItemKind = "Spell" | "Ability" | "Trigger"
+
+export type StackItemView = { kind: StackItemKind; card_id: string | null; name: string }
+
+export type ManaPoolUpdate = { player_id: string; mana_pool: ManaPool }
+
+export type PaymentChoice = { card_id: string; mana: ManaType }
+
+export type Suspend = { time_counters: number; cost: ManaType[] }
+
+export type PassUntilMyTurnArgs = { code: string; passing: boolean }
+
+export type FlashbackCardArgs = { code: string; graveyard_index: number; target: FrontendTarget | null }
+
+export type SuspendCardArgs = { code: string; in_hand_index: number }
+
+export type TurnFaceUpArgs = { code: string; in_play_index: number }
@@ -0,0 +1,6 @@
+create table if not exists accounts (
+    id text primary key,
+    user_id text not null unique references users(id) on delete cascade,
+    email text not null unique,
+    totp_secret text
+);
@@ -0,0 +1,11 @@
+create table if not exists matches (
+    id text primary key,
+    lobby_code text not null,
+    players text[] not null,
+    winner text,
+    turn_count integer not null,
+    duration_seconds integer not null,
+    finished_at timestamptz not null default now()
+);
+
+create index if not exists matches_players_idx on matches using gin (players);
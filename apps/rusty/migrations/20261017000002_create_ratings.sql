@@ -0,0 +1,5 @@
+create table if not exists ratings (
+    user_id text primary key references users(id) on delete cascade,
+    rating integer not null,
+    games_played integer not null default 0
+);
@@ -5,6 +5,8 @@ pub enum AppError {
     InternalServerError(String),
     BadRequest(String),
     Unauthorized,
+    // Gave up waiting rather than risk a deadlock; worth retrying
+    Timeout(String),
 }
 
 impl From<AppError> for rspc::Error {
@@ -17,6 +19,7 @@ impl From<AppError> for rspc::Error {
                 rspc::Error::new(rspc::ErrorCode::Unauthorized, "Unauthorized".to_owned())
             }
             AppError::BadRequest(s) => rspc::Error::new(rspc::ErrorCode::BadRequest, s),
+            AppError::Timeout(s) => rspc::Error::new(rspc::ErrorCode::Timeout, s),
         }
     }
 }
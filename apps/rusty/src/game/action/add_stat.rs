@@ -1,6 +1,7 @@
 use std::{any::Any, sync::Arc};
 
 use tokio::sync::Mutex;
+use tracing::debug;
 
 use crate::game::{
     card::Card,
@@ -23,7 +24,7 @@ impl CardAction for CardAddStatAction {
         self
     }
     async fn apply(&self, game: &mut Game, card: Arc<Mutex<Card>>, target: EffectTarget) {
-        println!("add stat? target: {:?}", target);
+        debug!("add stat? target: {:?}", target);
         let mut card = card.lock().await;
         card.add_stat(self.id.clone(), self.stat.clone());
     }
@@ -0,0 +1,123 @@
+use std::{any::Any, sync::Arc};
+
+use rand::Rng;
+use tokio::sync::Mutex;
+
+use crate::game::{card::Card, effects::EffectTarget, Game};
+
+use super::CardAction;
+
+// Flips a coin with the game's rng, running `on_heads` or `on_tails` with the
+// same card and target.
+#[derive(Debug, Clone)]
+pub struct FlipCoinAction {
+    pub on_heads: Arc<dyn CardAction + Send + Sync>,
+    pub on_tails: Option<Arc<dyn CardAction + Send + Sync>>,
+}
+
+#[async_trait::async_trait]
+impl CardAction for FlipCoinAction {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    async fn apply(&self, game: &mut Game, card: Arc<Mutex<Card>>, target: EffectTarget) {
+        let heads = game.rng.gen_bool(0.5);
+        let name = card.lock().await.name.clone();
+        game.add_turn_message(format!(
+            "{} flipped {}",
+            name,
+            if heads { "heads" } else { "tails" }
+        ));
+
+        let branch = if heads {
+            Some(&self.on_heads)
+        } else {
+            self.on_tails.as_ref()
+        };
+        if let Some(action) = branch {
+            action.apply(game, card, target).await;
+        }
+    }
+}
+
+// Rolls a die with `sides` faces. A roll of `succeeds_at` or more runs
+// `on_success`, anything lower runs `on_failure`.
+#[derive(Debug, Clone)]
+pub struct RollDieAction {
+    pub sides: u8,
+    pub succeeds_at: u8,
+    pub on_success: Arc<dyn CardAction + Send + Sync>,
+    pub on_failure: Option<Arc<dyn CardAction + Send + Sync>>,
+}
+
+#[async_trait::async_trait]
+impl CardAction for RollDieAction {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    async fn apply(&self, game: &mut Game, card: Arc<Mutex<Card>>, target: EffectTarget) {
+        let roll = game.rng.gen_range(1..=self.sides.max(1));
+        let name = card.lock().await.name.clone();
+        game.add_turn_message(format!("{} rolled a {} on a d{}", name, roll, self.sides));
+
+        let branch = if roll >= self.succeeds_at {
+            Some(&self.on_success)
+        } else {
+            self.on_failure.as_ref()
+        };
+        if let Some(action) = branch {
+            action.apply(game, card, target).await;
+        }
+    }
+}
+
+mod test {
+    use std::sync::Arc;
+
+    use rand::Rng;
+
+    use crate::game::{
+        action::{CardActionTarget, CardActionWrapper, DrawCardCardAction},
+        decks::black::create_hydra,
+        player::Player,
+        rng::GameRng,
+        Game,
+    };
+
+    #[tokio::test]
+    async fn test_seeded_coin_flip_picks_branch() {
+        let seed = 3;
+        let heads = GameRng::seeded(seed).gen_bool(0.5);
+
+        let mut game = Game::new().with_seed(seed);
+        let player = game
+            .add_player(Player::new(
+                "test",
+                20,
+                vec![create_hydra(), create_hydra(), create_hydra()],
+            ))
+            .await;
+        let source = player.lock().await.draw_card().unwrap();
+        game.start_turn(0).await;
+
+        // Heads draws one card, tails draws two
+        game.add_to_stack(Arc::new(CardActionWrapper {
+            card: source,
+            action: Arc::new(super::FlipCoinAction {
+                on_heads: Arc::new(DrawCardCardAction::one(CardActionTarget::SelfOwner)),
+                on_tails: Some(Arc::new(DrawCardCardAction {
+                    target: CardActionTarget::SelfOwner,
+                    count: 2,
+                })),
+            }),
+            target: None,
+        }));
+        game.resolve_stack().await;
+
+        let expected = if heads { 2 } else { 3 };
+        assert_eq!(player.lock().await.cards_in_hand.len(), expected);
+        let side = if heads { "heads" } else { "tails" };
+        let message = format!("Voracious Hydra flipped {}", side);
+        assert!(game.turn_messages.contains(&message));
+    }
+}
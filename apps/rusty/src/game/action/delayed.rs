@@ -0,0 +1,141 @@
+use std::{any::Any, sync::Arc};
+
+use tokio::sync::Mutex;
+use tracing::warn;
+
+use crate::game::{
+    card::Card,
+    effects::EffectTarget,
+    mana::ManaType,
+    player::Player,
+    turn::{Turn, TurnPhase},
+    zone::Zone,
+    Ability, ActionType, Game,
+};
+
+use super::{CardAction, CardRequiredTarget};
+
+// "At the beginning of your next upkeep, ...". Fires the first time `phase`
+// starts on `player`'s turn after it was set up, then it's gone.
+#[derive(Debug, Clone)]
+pub struct DelayedTrigger {
+    pub player: Arc<Mutex<Player>>,
+    pub phase: TurnPhase,
+    pub card: Arc<Mutex<Card>>,
+    pub action: Arc<dyn CardAction + Send + Sync>,
+    // The turn number and phase it was set up in
+    pub scheduled: (i32, TurnPhase),
+}
+
+impl DelayedTrigger {
+    pub fn is_due(&self, turn: &Turn) -> bool {
+        let (turn_number, phase) = self.scheduled;
+        Arc::ptr_eq(&turn.current_player, &self.player)
+            && turn.phase == self.phase
+            && (turn.turn_number, turn.phase.index()) > (turn_number, phase.index())
+    }
+}
+
+// Sets up `action` to happen to this card at the beginning of its
+// controller's next `phase`.
+#[derive(Debug, Clone)]
+pub struct DelayedTriggerAction {
+    pub phase: TurnPhase,
+    pub action: Arc<dyn CardAction + Send + Sync>,
+}
+
+#[async_trait::async_trait]
+impl CardAction for DelayedTriggerAction {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    async fn apply(&self, game: &mut Game, card: Arc<Mutex<Card>>, _target: EffectTarget) {
+        let Some(controller) = card.lock().await.controller() else {
+            return;
+        };
+        game.schedule_delayed_trigger(controller, self.phase, card, Arc::clone(&self.action));
+    }
+}
+
+// Sacrifices the card itself, if it's still around to be sacrificed.
+#[derive(Debug, Clone)]
+pub struct SacrificeSelfAction {}
+
+#[async_trait::async_trait]
+impl CardAction for SacrificeSelfAction {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    async fn apply(&self, game: &mut Game, card: Arc<Mutex<Card>>, _target: EffectTarget) {
+        let Some(controller) = card.lock().await.controller() else {
+            return;
+        };
+        if controller
+            .lock()
+            .await
+            .zone_contains(Zone::Battlefield, &card)
+        {
+            if let Err(err) = game.sacrifice_card(&card).await {
+                warn!("{}", err);
+            }
+        }
+    }
+}
+
+// The upkeep trigger echo sets up as the card enters: its controller pays
+// the echo cost or sacrifices it. Letting the upkeep pass is not paying.
+#[derive(Debug, Clone)]
+pub struct EchoAction {
+    pub cost: Vec<ManaType>,
+}
+
+#[async_trait::async_trait]
+impl CardAction for EchoAction {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    async fn apply(&self, game: &mut Game, card: Arc<Mutex<Card>>, _target: EffectTarget) {
+        let Some(controller) = card.lock().await.controller() else {
+            return;
+        };
+        if !controller
+            .lock()
+            .await
+            .zone_contains(Zone::Battlefield, &card)
+        {
+            return;
+        }
+
+        game.request_player_ability(
+            Ability::new(
+                Arc::clone(&card),
+                self.cost.clone(),
+                CardRequiredTarget::None,
+                Arc::new(|_| Arc::new(EchoPaidAction {})),
+                format!(
+                    "Pay echo {} or sacrifice this",
+                    self.cost.iter().map(ManaType::format).collect::<String>()
+                ),
+                ActionType::None,
+            )
+            .with_chooser(controller)
+            .with_declined(Arc::new(SacrificeSelfAction {}))
+            .with_declines_on_lapse(),
+        )
+        .await;
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EchoPaidAction {}
+
+#[async_trait::async_trait]
+impl CardAction for EchoPaidAction {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    async fn apply(&self, game: &mut Game, card: Arc<Mutex<Card>>, _target: EffectTarget) {
+        let name = card.lock().await.name.clone();
+        game.add_turn_message(format!("Paid echo for {}", name));
+    }
+}
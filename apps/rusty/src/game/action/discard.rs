@@ -0,0 +1,226 @@
+use std::{any::Any, sync::Arc};
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use tokio::sync::Mutex;
+use tracing::warn;
+
+use crate::game::{
+    card::Card, effects::EffectTarget, player::Player, zone::Zone, Ability, ActionType, Game,
+};
+
+use super::{CardAction, CardRequiredTarget};
+
+// Who decides which cards leave the discarding player's hand.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Type)]
+pub enum Chooser {
+    Random,
+    // The discarding player picks
+    Owner,
+    // The source's controller picks from the revealed hand
+    Opponent,
+}
+
+// Makes the target player (or the controller of the target card) discard
+// `count` cards from their hand.
+#[derive(Debug, Clone)]
+pub struct DiscardAction {
+    pub count: u8,
+    pub chooser: Chooser,
+}
+
+#[async_trait::async_trait]
+impl CardAction for DiscardAction {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    async fn apply(&self, game: &mut Game, card: Arc<Mutex<Card>>, target: EffectTarget) {
+        let player = match target {
+            EffectTarget::Player(player) => player,
+            EffectTarget::Card(target_card) => target_card.lock().await.controller().unwrap(),
+            EffectTarget::Cards(_) => {
+                warn!("Discarding needs a single player");
+                return;
+            }
+        };
+
+        match self.chooser {
+            Chooser::Random => {
+                for _ in 0..self.count {
+                    let hand_size = player.lock().await.cards_in_hand.len();
+                    if hand_size == 0 {
+                        break;
+                    }
+
+                    let index = game.rng.gen_range(0..hand_size);
+                    let discarded = player.lock().await.cards_in_hand[index].clone();
+                    if let Err(err) = discard_card(game, &player, discarded).await {
+                        warn!("{}", err);
+                        break;
+                    }
+                }
+            }
+            Chooser::Owner | Chooser::Opponent => {
+                ask_for_discard(game, card, player, self.count, self.chooser).await;
+            }
+        }
+    }
+}
+
+// Second half of a chosen discard, run with the card that was picked.
+#[derive(Debug, Clone)]
+pub struct DiscardChosenCardAction {
+    pub player: Arc<Mutex<Player>>,
+    pub count: u8,
+    pub chooser: Chooser,
+}
+
+#[async_trait::async_trait]
+impl CardAction for DiscardChosenCardAction {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    async fn apply(&self, game: &mut Game, card: Arc<Mutex<Card>>, target: EffectTarget) {
+        if let EffectTarget::Card(chosen) = target {
+            let in_hand = self
+                .player
+                .lock()
+                .await
+                .cards_in_hand
+                .iter()
+                .any(|hand_card| Arc::ptr_eq(hand_card, &chosen));
+
+            // Not a card from their hand, ask again
+            if !in_hand {
+                ask_for_discard(game, card, self.player.clone(), self.count, self.chooser).await;
+                return;
+            }
+            if let Err(err) = discard_card(game, &self.player, chosen).await {
+                warn!("{}", err);
+                return;
+            }
+        }
+
+        if self.count > 1 {
+            ask_for_discard(
+                game,
+                card,
+                self.player.clone(),
+                self.count - 1,
+                self.chooser,
+            )
+            .await;
+        }
+    }
+}
+
+async fn ask_for_discard(
+    game: &mut Game,
+    card: Arc<Mutex<Card>>,
+    player: Arc<Mutex<Player>>,
+    count: u8,
+    chooser: Chooser,
+) {
+    let choices = player.lock().await.cards_in_hand.clone();
+    if choices.is_empty() {
+        return;
+    }
+
+    let asked = match chooser {
+        Chooser::Opponent => card.lock().await.controller().unwrap(),
+        _ => player.clone(),
+    };
+    let name = player.lock().await.name.clone();
+
+    game.ask_mandatory_player_ability(
+        Ability::new(
+            card,
+            vec![],
+            CardRequiredTarget::AnyCard,
+            Arc::new(move |_| {
+                Arc::new(DiscardChosenCardAction {
+                    player: player.clone(),
+                    count,
+                    chooser,
+                })
+            }),
+            format!("Choose a card for {} to discard", name),
+            ActionType::None,
+        )
+        .with_choices(choices)
+        .with_chooser(asked),
+    )
+    .await;
+}
+
+// Puts a card from the player's hand into its owner's graveyard.
+pub(crate) async fn discard_card(
+    game: &mut Game,
+    player: &Arc<Mutex<Player>>,
+    card: Arc<Mutex<Card>>,
+) -> Result<(), String> {
+    let name = card.lock().await.name.clone();
+    let player_name = player.lock().await.name.clone();
+
+    game.move_card(&card, Zone::Hand, Zone::Graveyard).await?;
+    game.add_turn_message(format!("{} discarded {}", player_name, name));
+
+    Ok(())
+}
+
+mod test {
+    use std::sync::Arc;
+
+    use rand::Rng;
+
+    use crate::game::{
+        action::CardActionWrapper,
+        decks::{black::create_hydra, blue::create_counterspell, blue::create_unsummon},
+        effects::EffectTarget,
+        player::Player,
+        rng::GameRng,
+        Game,
+    };
+
+    #[tokio::test]
+    async fn test_seeded_random_discard() {
+        let mut game = Game::new().with_seed(7);
+        let player = game
+            .add_player(Player::new(
+                "test",
+                20,
+                vec![create_hydra(), create_counterspell(), create_unsummon()],
+            ))
+            .await;
+        let source = player.lock().await.draw_card().unwrap();
+        player.lock().await.draw_card();
+        player.lock().await.draw_card();
+        game.start_turn(0).await;
+
+        let hand = player.lock().await.cards_in_hand.clone();
+        let expected = hand[GameRng::seeded(7).gen_range(0..hand.len())].clone();
+
+        game.add_to_stack(Arc::new(CardActionWrapper {
+            card: source,
+            action: Arc::new(super::DiscardAction {
+                count: 1,
+                chooser: super::Chooser::Random,
+            }),
+            target: Some(EffectTarget::Player(player.clone())),
+        }));
+        game.resolve_stack().await;
+
+        let player = player.lock().await;
+        assert_eq!(player.cards_in_hand.len(), 2);
+        assert!(!player
+            .cards_in_hand
+            .iter()
+            .any(|card| Arc::ptr_eq(card, &expected)));
+        assert!(player
+            .deck
+            .destroyed_pile
+            .iter()
+            .any(|card| Arc::ptr_eq(card, &expected)));
+    }
+}
@@ -0,0 +1,220 @@
+use std::{any::Any, sync::Arc};
+
+use tokio::sync::Mutex;
+
+use crate::game::{
+    card::Card,
+    effects::EffectTarget,
+    player::Player,
+    stat::{StatType, Stats},
+    zone::Zone,
+    Ability, ActionType, Game,
+};
+
+use super::{CardAction, CardRequiredTarget, PlayerAction};
+
+// What made a player draw, so replacements can tell the draw step's draw
+// apart from a card telling them to draw.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DrawSource {
+    DrawStep,
+    Effect,
+}
+
+// "If that player would draw a card, instead ...". Swaps one draw for
+// `instead`, or every draw while it's registered unless `once` is set.
+// One for the draw step skips the whole step rather than each of its draws.
+#[derive(Debug, Clone)]
+pub struct DrawReplacement {
+    pub player: Arc<Mutex<Player>>,
+    // Only replaces draws from this source, any draw if None
+    pub from: Option<DrawSource>,
+    // Nothing if the draw is just skipped
+    pub instead: Option<Arc<dyn PlayerAction + Send + Sync>>,
+    pub once: bool,
+    // The permanent whose static ability this is. It stops applying once
+    // that permanent leaves its player's battlefield.
+    pub source: Option<Arc<Mutex<Card>>>,
+}
+
+impl DrawReplacement {
+    pub fn replaces(&self, player: &Arc<Mutex<Player>>, source: DrawSource) -> bool {
+        Arc::ptr_eq(&self.player, player) && self.from.is_none_or(|from| from == source)
+    }
+
+    pub async fn is_active(&self) -> bool {
+        match &self.source {
+            Some(source) => self
+                .player
+                .lock()
+                .await
+                .zone_contains(Zone::Battlefield, source),
+            None => true,
+        }
+    }
+}
+
+// "Skip your draw step", optionally doing something else instead, for as
+// long as this permanent is in play. Registered as it enters.
+#[derive(Debug, Clone)]
+pub struct SkipDrawStepAction {
+    pub instead: Option<Arc<dyn PlayerAction + Send + Sync>>,
+}
+
+#[async_trait::async_trait]
+impl CardAction for SkipDrawStepAction {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    async fn apply(&self, game: &mut Game, card: Arc<Mutex<Card>>, _target: EffectTarget) {
+        let Some(controller) = card.lock().await.controller() else {
+            return;
+        };
+        game.add_draw_replacement(DrawReplacement {
+            player: controller,
+            from: Some(DrawSource::DrawStep),
+            instead: self.instead.clone(),
+            once: false,
+            source: Some(card),
+        });
+    }
+}
+
+// Cards in the player's graveyard they could dredge right now. Dredging
+// needs at least that many cards left to mill.
+pub(crate) async fn dredgeable_cards(player: &Arc<Mutex<Player>>) -> Vec<Arc<Mutex<Card>>> {
+    let mut player = player.lock().await;
+    let library_size = player.zone_mut(Zone::Library).len();
+    let mut cards = vec![];
+    for card in player.zone_mut(Zone::Graveyard).iter() {
+        let dredge = card.lock().await.get_stat_value(StatType::Dredge);
+        if dredge > 0 && dredge as usize <= library_size {
+            cards.push(Arc::clone(card));
+        }
+    }
+    cards
+}
+
+// Asks the player whether to dredge instead of drawing. Returns false if
+// there's nothing to dredge, otherwise the draw and the `remaining` draws
+// after it wait on their answer. Letting it lapse is drawing as normal.
+pub(crate) async fn offer_dredge(
+    game: &mut Game,
+    player: &Arc<Mutex<Player>>,
+    remaining: u8,
+    source: DrawSource,
+) -> bool {
+    let choices = dredgeable_cards(player).await;
+    let Some(first) = choices.first().cloned() else {
+        return false;
+    };
+
+    let dredging = Arc::clone(player);
+    game.request_player_ability(
+        Ability::new(
+            first,
+            vec![],
+            CardRequiredTarget::AnyCard,
+            Arc::new(move |_| {
+                Arc::new(DredgeAction {
+                    player: dredging.clone(),
+                    remaining,
+                    source,
+                })
+            }),
+            "Dredge a card from your graveyard instead of drawing".to_string(),
+            ActionType::None,
+        )
+        .with_choices(choices)
+        .with_chooser(Arc::clone(player))
+        .with_declined(Arc::new(DrawInsteadOfDredgingAction {
+            player: Arc::clone(player),
+            remaining,
+            source,
+        }))
+        .with_declines_on_lapse(),
+    )
+    .await;
+
+    true
+}
+
+// Mills as many cards as the chosen card's dredge, then returns it from the
+// graveyard to hand in place of the draw.
+#[derive(Debug, Clone)]
+pub struct DredgeAction {
+    pub player: Arc<Mutex<Player>>,
+    // Draws still to come once this one is done
+    pub remaining: u8,
+    pub source: DrawSource,
+}
+
+#[async_trait::async_trait]
+impl CardAction for DredgeAction {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    async fn apply(&self, game: &mut Game, _card: Arc<Mutex<Card>>, target: EffectTarget) {
+        let chosen = match target {
+            EffectTarget::Card(chosen) => Some(chosen),
+            _ => None,
+        };
+        let dredgeable = dredgeable_cards(&self.player).await;
+        let Some(chosen) =
+            chosen.filter(|chosen| dredgeable.iter().any(|card| Arc::ptr_eq(card, chosen)))
+        else {
+            // Not something they could dredge, so they draw after all
+            self.player.lock().await.draw_card();
+            game.player_draws(&self.player, self.remaining, self.source)
+                .await;
+            return;
+        };
+
+        let (name, amount) = {
+            let card = chosen.lock().await;
+            (card.name.clone(), card.get_stat_value(StatType::Dredge))
+        };
+        for _ in 0..amount {
+            let top = self
+                .player
+                .lock()
+                .await
+                .zone_mut(Zone::Library)
+                .last()
+                .cloned();
+            if let Some(top) = top {
+                game.move_card(&top, Zone::Library, Zone::Graveyard)
+                    .await
+                    .ok();
+            }
+        }
+        game.move_card(&chosen, Zone::Graveyard, Zone::Hand)
+            .await
+            .ok();
+
+        let player_name = self.player.lock().await.name.clone();
+        game.add_turn_message(format!("{} dredged {}", player_name, name));
+        game.player_draws(&self.player, self.remaining, self.source)
+            .await;
+    }
+}
+
+// The draw a player turned dredging down for.
+#[derive(Debug, Clone)]
+pub struct DrawInsteadOfDredgingAction {
+    pub player: Arc<Mutex<Player>>,
+    pub remaining: u8,
+    pub source: DrawSource,
+}
+
+#[async_trait::async_trait]
+impl CardAction for DrawInsteadOfDredgingAction {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    async fn apply(&self, game: &mut Game, _card: Arc<Mutex<Card>>, _target: EffectTarget) {
+        self.player.lock().await.draw_card();
+        game.player_draws(&self.player, self.remaining, self.source)
+            .await;
+    }
+}
@@ -20,10 +20,18 @@ impl CardAction for GenerateManaAction {
         self
     }
     async fn apply(&self, game: &mut Game, card: Arc<Mutex<Card>>, target: EffectTarget) {
-        let owner = card.lock().await.owner.clone().unwrap();
-        let player = &mut owner.lock().await;
-        for mana in &self.mana_to_add {
-            player.mana_pool.add_mana(*mana);
-        }
+        let (owner, card_id) = {
+            let card = card.lock().await;
+            (card.controller().unwrap(), card.id.clone())
+        };
+        let player_id = {
+            let player = &mut owner.lock().await;
+            for mana in &self.mana_to_add {
+                player.mana_pool.add_mana(*mana);
+            }
+            player.name.clone()
+        };
+        game.mana_added(player_id, card_id, self.mana_to_add.clone());
+        game.mana_pool_updated(&owner).await;
     }
 }
@@ -1,5 +1,11 @@
 pub mod add_stat;
+pub mod chance;
+pub mod delayed;
+pub mod discard;
+pub mod draw;
 pub mod generate_mana;
+pub mod morph;
+pub mod search_library;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use specta::Type;
@@ -12,15 +18,20 @@ use std::{
     sync::Arc,
 };
 use tokio::sync::Mutex;
+use tracing::{debug, warn};
 use ulid::Ulid;
 
+use draw::DrawSource;
+
 use super::{
     card::{Card, CardType, CreatureType},
-    effects::{Effect, EffectID, EffectTarget, ExpireContract},
+    cost::Cost,
+    effects::{DynamicValue, Effect, EffectID, EffectTarget, ExpireContract, StatModifierEffect},
     mana::ManaType,
     player::Player,
     stat::{StatType, Stats},
     turn::{Turn, TurnPhase},
+    zone::Zone,
     Ability, ActionType, FrontendCardTarget, FrontendTarget, Game,
 };
 use crate::{game::stat::Stat, lobby::manager::LobbyCommand};
@@ -45,6 +56,9 @@ impl PlayerActionTrigger {
     pub(crate) async fn applies_in_phase(&self, turn: Turn, player: Arc<Mutex<Player>>) -> bool {
         match &self.trigger_type {
             ActionTriggerType::Attached => true,
+            ActionTriggerType::Equip(_) => {
+                turn.phase == TurnPhase::Main || turn.phase == TurnPhase::Main2
+            }
             ActionTriggerType::CardDestroyed => true,
             ActionTriggerType::PhaseStarted(phases, trigger_target) => match trigger_target {
                 TriggerTarget::Owner => {
@@ -81,6 +95,12 @@ impl PlayerActionTrigger {
             ActionTriggerType::DamageApplied => turn.phase == TurnPhase::CombatDamage,
             ActionTriggerType::OtherCardPlayed(trigger_target) => true,
             ActionTriggerType::CreatureTypeCardPlayed(trigger_target, creature_type) => true,
+            ActionTriggerType::Attacks(_) => turn.phase == TurnPhase::DeclareAttackers,
+            ActionTriggerType::Blocks | ActionTriggerType::BecomesBlocked => {
+                turn.phase == TurnPhase::DeclareBlockers
+            }
+            ActionTriggerType::LifeGained(_) => true,
+            ActionTriggerType::CreatureDied(_) => true,
         }
     }
 }
@@ -114,6 +134,89 @@ pub struct PlayCardAction {
 }
 
 impl PlayCardAction {
+    // Whether the spell still has something to resolve against. A spell with
+    // several targets only fizzles once none of them are legal.
+    async fn has_legal_target(&self, game: &Game) -> bool {
+        match &self.target {
+            Some(EffectTarget::Card(target_card)) => self.is_legal_target(game, target_card).await,
+            Some(EffectTarget::Cards(target_cards)) => {
+                for target_card in target_cards {
+                    if self.is_legal_target(game, target_card).await {
+                        return true;
+                    }
+                }
+                false
+            }
+            _ => true,
+        }
+    }
+
+    // The targets it resolves against, leaving out any of several that are no
+    // longer legal.
+    async fn resolving_target(&self, game: &Game) -> Option<EffectTarget> {
+        match &self.target {
+            Some(EffectTarget::Cards(target_cards)) => {
+                let mut legal = vec![];
+                for target_card in target_cards {
+                    if self.is_legal_target(game, target_card).await {
+                        legal.push(Arc::clone(target_card));
+                    }
+                }
+                Some(EffectTarget::Cards(legal))
+            }
+            target => target.clone(),
+        }
+    }
+
+    // Whether this card the spell was cast at is still around and still fits
+    // what the spell asks for.
+    async fn is_legal_target(&self, game: &Game, target_card: &Arc<Mutex<Card>>) -> bool {
+        let target_card = Arc::clone(target_card);
+
+        let required_targets: Vec<CardRequiredTarget> = self
+            .card_arc
+            .lock()
+            .await
+            .triggers
+            .iter()
+            .filter(|trigger| trigger.trigger_type == ActionTriggerType::CardPlayedFromHand)
+            .map(|trigger| trigger.card_required_target.clone())
+            .filter(|required| required != &CardRequiredTarget::None)
+            .collect();
+        if required_targets.is_empty() {
+            return true;
+        }
+        // Only the exact ability it was cast at will do, not another from the same card
+        if required_targets.contains(&CardRequiredTarget::Ability) {
+            let id = self.card_arc.lock().await.id.clone();
+            return game.targeted_ability_index(&id).is_some();
+        }
+
+        let targets_spell = required_targets.contains(&CardRequiredTarget::Spell);
+        let mut controller = None;
+        for player_arc in &game.players {
+            let player = player_arc.lock().await;
+            let zone = if targets_spell {
+                &player.spells
+            } else {
+                &player.cards_in_play
+            };
+            if zone.iter().any(|card| Arc::ptr_eq(card, &target_card)) {
+                controller = Some(Arc::clone(player_arc));
+                break;
+            }
+        }
+
+        let Some(controller) = controller else {
+            return false;
+        };
+        let owned = Arc::ptr_eq(&controller, &self.player_arc);
+        let target = target_card.lock().await;
+        required_targets
+            .iter()
+            .any(|required| game.card_target_matches(required, &target_card, &target, owned))
+    }
+
     pub fn new(
         player_arc: Arc<Mutex<Player>>,
         card_arc: Arc<Mutex<Card>>,
@@ -130,6 +233,79 @@ impl PlayCardAction {
 #[derive(Debug)]
 pub struct CounterSpellAction {}
 
+// Counters an activated or triggered ability, leaving spells alone.
+#[derive(Debug)]
+pub struct StifleAction {}
+
+// Checks a trigger's condition again on resolution, doing nothing if it no
+// longer holds.
+pub struct InterveningIfAction {
+    pub condition: TriggerCondition,
+    pub action: Arc<dyn CardAction + Send + Sync>,
+}
+
+impl Debug for InterveningIfAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InterveningIfAction")
+            .field("action", &self.action)
+            .finish()
+    }
+}
+
+#[async_trait::async_trait]
+impl CardAction for InterveningIfAction {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    async fn apply(&self, game: &mut Game, card_arc: Arc<Mutex<Card>>, target: EffectTarget) {
+        if !(self.condition)(Arc::clone(&card_arc)).await {
+            let name = card_arc.lock().await.name.clone();
+            game.add_turn_message(format!("{}'s trigger no longer applies.", name));
+            return;
+        }
+
+        self.action.apply(game, card_arc, target).await;
+    }
+
+    async fn apply_with_amount(
+        &self,
+        game: &mut Game,
+        card_arc: Arc<Mutex<Card>>,
+        target: EffectTarget,
+        amount: i8,
+    ) {
+        if !(self.condition)(Arc::clone(&card_arc)).await {
+            let name = card_arc.lock().await.name.clone();
+            game.add_turn_message(format!("{}'s trigger no longer applies.", name));
+            return;
+        }
+
+        self.action
+            .apply_with_amount(game, card_arc, target, amount)
+            .await;
+    }
+}
+
+// Copies the target spell, aimed at whatever the original was cast at.
+#[derive(Debug)]
+pub struct CopySpellAction {}
+
+#[async_trait::async_trait]
+impl CardAction for CopySpellAction {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    async fn apply(&self, game: &mut Game, _card_arc: Arc<Mutex<Card>>, target: EffectTarget) {
+        if let EffectTarget::Card(spell) = &target {
+            if let Err(e) = game.copy_spell(spell, None).await {
+                warn!("{}", e);
+            }
+        } else {
+            debug!("Target spell is not on the stack.");
+        }
+    }
+}
+
 #[async_trait::async_trait]
 impl CardAction for CounterSpellAction {
     fn as_any(&self) -> &dyn Any {
@@ -137,49 +313,101 @@ impl CardAction for CounterSpellAction {
     }
     async fn apply(&self, game: &mut Game, card_arc: Arc<Mutex<Card>>, target: EffectTarget) {
         if let EffectTarget::Card(target_action_arc) = &target {
+            // Abilities and triggers from the same card aren't spells
+            if !game.is_on_stack_as(target_action_arc, StackItemKind::Spell) {
+                debug!("Target is not a spell on the stack.");
+                return;
+            }
             target_action_arc.lock().await.is_countered = true;
             game.debug("Countered a spell on the stack.");
         } else {
-            println!("Target spell is not on the stack.");
+            debug!("Target spell is not on the stack.");
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl CardAction for StifleAction {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    async fn apply(&self, game: &mut Game, card: Arc<Mutex<Card>>, _target: EffectTarget) {
+        if let Err(e) = game.counter_ability(&card).await {
+            debug!("{}", e);
         }
     }
 }
 
 #[async_trait::async_trait]
 impl Action for PlayCardAction {
+    fn as_spell(&self) -> Option<&PlayCardAction> {
+        Some(self)
+    }
+
+    fn stack_kind(&self) -> StackItemKind {
+        StackItemKind::Spell
+    }
+
+    fn stack_card(&self) -> Option<&Arc<Mutex<Card>>> {
+        Some(&self.card_arc)
+    }
+
+    fn stack_target(&self) -> Option<&EffectTarget> {
+        self.target.as_ref()
+    }
+
     async fn apply(&self, game: &mut Game) {
-        println!("play card triggered.");
-        if self.card_arc.lock().await.is_countered {
-            // Move the card to the graveyard
-            {
-                let mut player = self.player_arc.lock().await;
-                player.deck.destroy(Arc::clone(&self.card_arc));
+        debug!("resolving spell");
+        if !self
+            .player_arc
+            .lock()
+            .await
+            .zone_contains(Zone::Stack, &self.card_arc)
+        {
+            debug!("spell already left the stack");
+            return;
+        }
+
+        let is_countered = self.card_arc.lock().await.is_countered;
+        let fizzled = !is_countered && !self.has_legal_target(game).await;
+        if is_countered || fizzled {
+            // Move the card to its owner's graveyard
+            let (id, name) = {
+                let mut card = self.card_arc.lock().await;
+                card.is_countered = false;
+                card.owner.get_or_insert(self.player_arc.clone());
+                (card.id.clone(), card.name.clone())
+            };
+            game.ability_targets.remove(&id);
+            game.move_card(&self.card_arc, Zone::Stack, Zone::Graveyard)
+                .await
+                .ok();
+            if fizzled {
+                game.add_turn_message(format!(
+                    "Spell {} fizzled and was moved to graveyard.",
+                    name
+                ));
+            } else {
+                game.add_turn_message(format!(
+                    "Spell {} was countered and moved to graveyard.",
+                    name
+                ));
             }
-            game.add_turn_message(format!(
-                "Spell {} was countered and moved to graveyard.",
-                self.card_arc.lock().await.name
-            ));
         } else {
             {
                 let card = self.card_arc.lock().await;
                 game.add_turn_message(format!(
                     "{} has played {}.",
-                    card.owner.clone().unwrap().lock().await.name,
+                    card.controller().unwrap().lock().await.name,
                     card.name
                 ));
             }
-            // Move the card to the battlefield
-            {
-                let mut player = self.player_arc.lock().await;
-                player.cards_in_play.push(Arc::clone(&self.card_arc));
-            }
-
-            // Set the target and owner on the card
+            // Set the target before it lands so its enter triggers see it
+            let target = self.resolving_target(game).await;
             {
                 let mut card_lock = self.card_arc.lock().await;
-                card_lock.target = self.target.clone();
-                card_lock.action_target = self.target.clone();
-                card_lock.owner = Some(self.player_arc.clone());
+                card_lock.target = target.clone();
+                card_lock.action_target = target;
             }
 
             // Collect and execute any immediate actions
@@ -209,21 +437,37 @@ impl Action for PlayCardAction {
             //     actions
             // };
 
-            let mut actions = {
-                let more_actions = game.collect_card_played_actions(&self.card_arc).await;
-                more_actions
+            // Entering the battlefield sets the controller and fires its triggers
+            game.move_card(&self.card_arc, Zone::Stack, Zone::Battlefield)
+                .await
+                .ok();
+
+            let (flashed_back, one_shot) = {
+                let mut card = self.card_arc.lock().await;
+                (
+                    std::mem::take(&mut card.flashed_back),
+                    matches!(card.card_type, CardType::Instant | CardType::Sorcery),
+                )
             };
-
-            println!("instant actions? {:?}", actions);
-
-            game.execute_actions(&mut actions).await;
+            if flashed_back {
+                game.move_card(&self.card_arc, Zone::Battlefield, Zone::Exile)
+                    .await
+                    .ok();
+                let name = self.card_arc.lock().await.name.clone();
+                game.add_turn_message(format!("{} was exiled after its flashback.", name));
+            } else if one_shot {
+                // Its effect has happened, so it doesn't stay around to fire again
+                game.move_card(&self.card_arc, Zone::Battlefield, Zone::Graveyard)
+                    .await
+                    .ok();
+            }
 
             // Handle special cases, e.g., if the card is a land
             {
                 let card_lock = self.card_arc.lock().await;
-                if let CardType::BasicLand(_) = card_lock.card_type {
+                if !card_lock.card_type.is_spell() {
                     let mut player = self.player_arc.lock().await;
-                    player.mana_pool.played_card = true;
+                    player.lands_played_this_turn += 1;
                 }
             }
         }
@@ -240,26 +484,22 @@ impl CardAction for ReturnToHandAction {
     }
     async fn apply(&self, game: &mut Game, card_arc: Arc<Mutex<Card>>, target: EffectTarget) {
         if let EffectTarget::Card(target_card_arc) = target {
-            let owner_arc = {
-                let target_card = target_card_arc.lock().await;
-                target_card.owner.clone()
-            };
-
-            if let Some(owner_arc) = owner_arc {
-                let mut owner = owner_arc.lock().await;
-                owner.return_card_to_hand(&target_card_arc).await;
-                println!(
-                    "Returned {} to {}'s hand.",
-                    target_card_arc.lock().await.name,
-                    owner.name
-                );
+            if let Err(err) = game.return_card_to_owner_hand(&target_card_arc).await {
+                warn!("{}", err);
+                return;
             }
+            let name = target_card_arc.lock().await.name.clone();
+            debug!(card = %name, "returned to its owner's hand");
         } else {
-            println!("No valid target for ReturnToHandAction.");
+            debug!("No valid target for ReturnToHandAction.");
         }
     }
 }
 
+// An intervening "if" on a trigger, asked about the card with the trigger.
+pub type TriggerCondition =
+    Arc<dyn Fn(Arc<Mutex<Card>>) -> Pin<Box<dyn Future<Output = bool> + Send>> + Send + Sync>;
+
 #[derive(Clone)]
 pub struct CardActionTrigger {
     pub id: String,
@@ -271,6 +511,7 @@ pub struct CardActionTrigger {
             + Send
             + Sync,
     >,
+    pub condition: Option<TriggerCondition>,
 }
 
 impl Debug for CardActionTrigger {
@@ -280,11 +521,40 @@ impl Debug for CardActionTrigger {
             .field("trigger_type", &self.trigger_type)
             .field("action", &self.action)
             .field("card_required_target", &self.card_required_target)
+            .field("has_condition", &self.condition.is_some())
             .finish()
     }
 }
 
 impl CardActionTrigger {
+    // Mana abilities resolve on the spot: no stack, no priority, nothing to counter.
+    pub fn is_mana_ability(&self) -> bool {
+        self.card_required_target == CardRequiredTarget::None
+            && self
+                .action
+                .as_any()
+                .downcast_ref::<generate_mana::GenerateManaAction>()
+                .is_some()
+    }
+
+    // Only triggers while `condition` holds, and does nothing when it resolves
+    // if the condition has stopped holding by then.
+    pub fn with_condition(mut self, condition: TriggerCondition) -> Self {
+        self.action = Arc::new(InterveningIfAction {
+            condition: condition.clone(),
+            action: self.action,
+        });
+        self.condition = Some(condition);
+        self
+    }
+
+    pub async fn condition_met(&self, card: &Arc<Mutex<Card>>) -> bool {
+        match &self.condition {
+            Some(condition) => condition(Arc::clone(card)).await,
+            None => true,
+        }
+    }
+
     pub fn new_with_requirements(
         trigger_type: ActionTriggerType,
         card_required_target: CardRequiredTarget,
@@ -301,6 +571,7 @@ impl CardActionTrigger {
             card_required_target,
             action,
             requirements,
+            condition: None,
         }
     }
 
@@ -321,6 +592,7 @@ impl CardActionTrigger {
                     Box::pin(async move { true })
                 },
             ),
+            condition: None,
         }
     }
 
@@ -329,7 +601,7 @@ impl CardActionTrigger {
             ActionTriggerType::PhaseStarted(vec, trigger_target) => {
                 let owner = {
                     if trigger_target == &TriggerTarget::Owner {
-                        &card.lock().await.owner.clone()
+                        &card.lock().await.controller()
                     } else {
                         &None
                     }
@@ -372,7 +644,7 @@ impl CardActionTrigger {
                 self.action.apply(game, card, effect_target).await;
             }
             x => {
-                println!("nothing triggered for {:?}", x);
+                debug!("nothing triggered for {:?}", x);
             }
         }
     }
@@ -488,9 +760,39 @@ pub enum CardRequiredTarget {
     CreatureOfType(CreatureType, CardTargetTeam),
     EnemyCardInCombat,
     Spell,
+    // An activated or triggered ability on the stack, picked by its card
+    Ability,
     MultipleCardsOfType(CardType, i8),
 }
 
+impl CardRequiredTarget {
+    // Whether `card` satisfies this requirement, where `owned` says if the card
+    // belongs to the player choosing the target.
+    pub fn matches_card(&self, card: &Card, owned: bool) -> bool {
+        match self {
+            CardRequiredTarget::None
+            | CardRequiredTarget::AnyPlayer
+            | CardRequiredTarget::EnemyPlayer => false,
+            CardRequiredTarget::OwnedCard => owned,
+            CardRequiredTarget::AnyCard => true,
+            CardRequiredTarget::EnemyCard
+            | CardRequiredTarget::EnemyCardOrPlayer
+            | CardRequiredTarget::EnemyCardInCombat => !owned,
+            CardRequiredTarget::CardOfType(card_type, team) => {
+                card.card_type == *card_type && team.matches(owned)
+            }
+            CardRequiredTarget::CreatureOfType(creature_type, team) => {
+                card.card_type == CardType::Creature
+                    && card.creature_type.as_ref() == Some(creature_type)
+                    && team.matches(owned)
+            }
+            CardRequiredTarget::Spell => card.card_type.is_spell(),
+            CardRequiredTarget::Ability => true,
+            CardRequiredTarget::MultipleCardsOfType(card_type, _) => card.card_type == *card_type,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
 pub enum CardActionTarget {
     SelfCard,
@@ -506,6 +808,16 @@ pub enum CardTargetTeam {
     Any,
 }
 
+impl CardTargetTeam {
+    pub fn matches(&self, owned: bool) -> bool {
+        match self {
+            CardTargetTeam::Owner => owned,
+            CardTargetTeam::Opponent => !owned,
+            CardTargetTeam::Any => true,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
 pub enum PlayerActionTarget {
     Owner,
@@ -515,6 +827,65 @@ pub enum PlayerActionTarget {
 #[async_trait]
 pub trait Action: Send + Sync + Debug {
     async fn apply(&self, game: &mut Game);
+
+    // Spells waiting on the stack, so they can be found again to be copied.
+    fn as_spell(&self) -> Option<&PlayCardAction> {
+        None
+    }
+
+    // What this is while it waits on the stack. Anything not pushed as a spell
+    // or an activated ability is a trigger.
+    fn stack_kind(&self) -> StackItemKind {
+        StackItemKind::Trigger
+    }
+
+    // The card the item came from, if any.
+    fn stack_card(&self) -> Option<&Arc<Mutex<Card>>> {
+        None
+    }
+
+    // What the item was aimed at, if it has a target.
+    fn stack_target(&self) -> Option<&EffectTarget> {
+        None
+    }
+}
+
+#[derive(Type, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum StackItemKind {
+    Spell,
+    Ability,
+    Trigger,
+}
+
+// Tags an action with what kind of stack item it is, for actions that could be
+// any of them (e.g. a CardActionWrapper).
+#[derive(Debug)]
+pub struct StackItem {
+    pub kind: StackItemKind,
+    pub action: Arc<dyn Action + Send + Sync>,
+}
+
+#[async_trait]
+impl Action for StackItem {
+    async fn apply(&self, game: &mut Game) {
+        self.action.apply(game).await;
+    }
+
+    fn as_spell(&self) -> Option<&PlayCardAction> {
+        self.action.as_spell()
+    }
+
+    fn stack_kind(&self) -> StackItemKind {
+        self.kind
+    }
+
+    fn stack_card(&self) -> Option<&Arc<Mutex<Card>>> {
+        self.action.stack_card()
+    }
+
+    fn stack_target(&self) -> Option<&EffectTarget> {
+        self.action.stack_target()
+    }
 }
 #[derive(Debug, Clone)]
 pub enum CardActionType {
@@ -535,14 +906,37 @@ pub enum ActionTriggerType {
     CreatureTypeCardPlayed(TriggerTarget, CreatureType),
     DamageApplied,
     Attached,
+    // Equipment: moved onto a creature for the mana cost, only as a sorcery
+    Equip(Vec<ManaType>),
     Detached,
     Continuous,
+    // fires when this card is declared as an attacker
+    Attacks(TriggerTarget),
+    // fire for the blocker and the attacker it blocks, targeting the card itself
+    Blocks,
+    BecomesBlocked,
+    // Owner is this card's controller, Target any opponent
+    LifeGained(TriggerTarget),
+    // Watches creatures dying; Owner for ones this card's controller controls,
+    // Target for an opponent's and Any for all of them
+    CreatureDied(TriggerTarget),
 }
 
 #[async_trait::async_trait]
 pub trait CardAction: Send + Sync + Debug + 'static {
     async fn apply(&self, game: &mut Game, card: Arc<Mutex<Card>>, target: EffectTarget);
     fn as_any(&self) -> &dyn Any;
+
+    // Used by damage and life gain triggers; actions that scale with the amount override this.
+    async fn apply_with_amount(
+        &self,
+        game: &mut Game,
+        card: Arc<Mutex<Card>>,
+        target: EffectTarget,
+        _amount: i8,
+    ) {
+        self.apply(game, card, target).await;
+    }
 }
 
 #[derive(Clone)]
@@ -562,14 +956,62 @@ impl Debug for CardActionWrapper {
 
 #[async_trait]
 impl Action for CardActionWrapper {
+    fn stack_card(&self) -> Option<&Arc<Mutex<Card>>> {
+        Some(&self.card)
+    }
+
+    fn stack_target(&self) -> Option<&EffectTarget> {
+        self.target.as_ref()
+    }
+
     async fn apply(&self, game: &mut Game) {
         let card = Arc::clone(&self.card);
 
+        match self
+            .target
+            .clone()
+            .unwrap_or(EffectTarget::Card(card.clone()))
+        {
+            // Each of several targets gets the action to itself
+            EffectTarget::Cards(targets) => {
+                for target in targets {
+                    self.action
+                        .apply(game, card.clone(), EffectTarget::Card(target))
+                        .await;
+                }
+            }
+            target => self.action.apply(game, card, target).await,
+        }
+    }
+}
+
+// A triggered action that also carries the amount of damage or life that set it off.
+#[derive(Clone)]
+pub struct AmountTriggerWrapper {
+    pub action: Arc<dyn CardAction + Send + Sync>,
+    pub card: Arc<Mutex<Card>>,
+    pub target: EffectTarget,
+    pub amount: i8,
+}
+
+impl Debug for AmountTriggerWrapper {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AmountTriggerWrapper")
+            .field("action", &self.action)
+            .field("amount", &self.amount)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl Action for AmountTriggerWrapper {
+    async fn apply(&self, game: &mut Game) {
         self.action
-            .apply(
+            .apply_with_amount(
                 game,
-                card.clone(),
-                self.target.clone().unwrap_or(EffectTarget::Card(card)),
+                Arc::clone(&self.card),
+                self.target.clone(),
+                self.amount,
             )
             .await;
     }
@@ -616,7 +1058,7 @@ impl PlayerAction for ResetManaPoolAction {
     async fn apply(&self, game: &mut Game, player_index: usize) {
         {
             let mut player = game.players[player_index].lock().await;
-            println!("reseting mana pool.");
+            debug!("reseting mana pool.");
 
             player.mana_pool.empty_pool();
         }
@@ -628,6 +1070,33 @@ impl PlayerAction for ResetManaPoolAction {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct CleanupSpellsAction {}
+
+#[async_trait]
+impl PlayerAction for CleanupSpellsAction {
+    async fn apply(&self, game: &mut Game, player_index: usize) {
+        if !game.event_stack.is_empty() {
+            return;
+        }
+
+        // Spells leave the stack when they resolve or get countered, so
+        // anything still there once the stack is empty never left it
+        let player_arc = Arc::clone(&game.players[player_index]);
+        let leftovers = player_arc.lock().await.spells.clone();
+        for card in leftovers {
+            let name = card.lock().await.name.clone();
+            game.move_card(&card, Zone::Stack, Zone::Graveyard)
+                .await
+                .ok();
+            game.add_turn_message(format!(
+                "Spell {} never resolved and was moved to graveyard.",
+                name
+            ));
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct UntapAllAction {}
 
@@ -637,10 +1106,141 @@ impl PlayerAction for UntapAllAction {
         let mut player = game.players[player_index].lock().await;
 
         for card in player.cards_in_play.iter() {
-            card.lock().await.untap();
+            let mut card = card.lock().await;
+            // Phasing happens before anything untaps
+            card.phased_out = false;
+            if card.get_stat_value(StatType::UntapLocked) > 0 {
+                continue;
+            }
+            card.untap();
+        }
+        debug!("untap all.");
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TapTargetAction {}
+
+#[async_trait]
+impl CardAction for TapTargetAction {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    async fn apply(&self, _game: &mut Game, _card_arc: Arc<Mutex<Card>>, target: EffectTarget) {
+        if let EffectTarget::Card(target_card) = &target {
+            target_card.lock().await.tapped = true;
+        }
+    }
+}
+
+// Gives the target a stat bonus that wears off when the turn ends.
+#[derive(Debug, Clone)]
+pub struct ModifyStatUntilEndOfTurnAction {
+    pub stat_type: StatType,
+    pub amount: i8,
+}
+
+#[async_trait]
+impl CardAction for ModifyStatUntilEndOfTurnAction {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    async fn apply(&self, game: &mut Game, card_arc: Arc<Mutex<Card>>, target: EffectTarget) {
+        // The spell is gone by the time this wears off, so hang it off the
+        // creature instead; it goes away early if that leaves play.
+        let source = match &target {
+            EffectTarget::Card(target_card) => Arc::clone(target_card),
+            _ => card_arc,
+        };
+        let effect = StatModifierEffect::new(
+            target,
+            self.stat_type,
+            self.amount,
+            ExpireContract::Turns(1),
+            Some(source),
+        );
+        game.effect_manager
+            .add_effect(effect.get_id().clone(), Arc::new(Mutex::new(effect)));
+    }
+}
+
+// Taps the target card and keeps it from untapping for as long as the source
+// card's effect is around.
+#[derive(Debug, Clone)]
+pub struct TapAndLockTargetAction {}
+
+#[async_trait]
+impl CardAction for TapAndLockTargetAction {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    async fn apply(&self, game: &mut Game, card_arc: Arc<Mutex<Card>>, target: EffectTarget) {
+        if let EffectTarget::Card(target_card) = &target {
+            target_card.lock().await.tapped = true;
+
+            let effect = StatModifierEffect::new(
+                target.clone(),
+                StatType::UntapLocked,
+                1,
+                ExpireContract::Never,
+                Some(Arc::clone(&card_arc)),
+            );
+            game.effect_manager
+                .add_effect(effect.get_id().clone(), Arc::new(Mutex::new(effect)));
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ExtraTurnAction {
+    pub target: PlayerActionTarget,
+}
+
+#[async_trait]
+impl CardAction for ExtraTurnAction {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    async fn apply(&self, game: &mut Game, card_arc: Arc<Mutex<Card>>, _target: EffectTarget) {
+        let owner = card_arc.lock().await.controller().unwrap();
+        if let Some(player) = game.player_for_target(&owner, &self.target) {
+            game.queue_extra_turn(&player);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SkipTurnAction {
+    pub target: PlayerActionTarget,
+}
+
+#[async_trait]
+impl CardAction for SkipTurnAction {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    async fn apply(&self, game: &mut Game, card_arc: Arc<Mutex<Card>>, _target: EffectTarget) {
+        let owner = card_arc.lock().await.controller().unwrap();
+        if let Some(player) = game.player_for_target(&owner, &self.target) {
+            game.skip_next_turn(&player);
+        }
+    }
+}
+
+// Takes control of the target permanent for the rest of the game.
+#[derive(Debug, Clone)]
+pub struct GainControlAction {}
+
+#[async_trait]
+impl CardAction for GainControlAction {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    async fn apply(&self, game: &mut Game, card_arc: Arc<Mutex<Card>>, target: EffectTarget) {
+        let controller = card_arc.lock().await.controller();
+        if let (EffectTarget::Card(target_card), Some(controller)) = (target, controller) {
+            game.gain_control(&target_card, &controller).await;
         }
-        println!("untap all.");
-        player.mana_pool.played_card = false;
     }
 }
 
@@ -652,18 +1252,192 @@ pub struct DrawCardAction {
 #[async_trait]
 impl PlayerAction for DrawCardAction {
     async fn apply(&self, game: &mut Game, player_index: usize) {
-        match self.target {
-            PlayerActionTarget::Owner => {
-                game.players[player_index].lock().await.draw_card();
-            }
+        let player = match self.target {
+            PlayerActionTarget::Owner => Arc::clone(&game.players[player_index]),
             PlayerActionTarget::Opponent => {
                 let opponent_index = (player_index + 1) % game.players.len();
-                game.players[opponent_index].lock().await.draw_card();
+                Arc::clone(&game.players[opponent_index])
+            }
+        };
+        game.player_draws(&player, 1, DrawSource::Effect).await;
+    }
+}
+
+// The draw step's draw, which effects can skip or add to.
+#[derive(Debug, Clone)]
+pub struct DrawStepAction {}
+
+#[async_trait]
+impl PlayerAction for DrawStepAction {
+    async fn apply(&self, game: &mut Game, player_index: usize) {
+        let player_arc = Arc::clone(&game.players[player_index]);
+        if game.skip_draw_step(&player_arc).await {
+            return;
+        }
+
+        let mut player = player_arc.lock().await;
+        if player.skip_next_draw {
+            player.skip_next_draw = false;
+            let name = player.name.clone();
+            drop(player);
+            game.add_turn_message(format!("{} skipped their draw", name));
+            return;
+        }
+
+        let draws = player.draws_per_turn();
+        drop(player);
+        game.player_draws(&player_arc, draws, DrawSource::DrawStep)
+            .await;
+    }
+}
+
+// The monarch's extra card, drawn at the beginning of their end step.
+#[derive(Debug, Clone)]
+pub struct MonarchDrawAction {}
+
+#[async_trait]
+impl PlayerAction for MonarchDrawAction {
+    async fn apply(&self, game: &mut Game, player_index: usize) {
+        let player = Arc::clone(&game.players[player_index]);
+        if game.is_monarch(&player) {
+            game.player_draws(&player, 1, DrawSource::Effect).await;
+        }
+    }
+}
+
+// Takes a time counter off each of the player's suspended cards at their
+// upkeep, casting any that lose their last one without paying for them.
+#[derive(Debug, Clone)]
+pub struct SuspendUpkeepAction {}
+
+#[async_trait]
+impl PlayerAction for SuspendUpkeepAction {
+    async fn apply(&self, game: &mut Game, player_index: usize) {
+        let player = Arc::clone(&game.players[player_index]);
+        let exiled = player.lock().await.deck.exiled.clone();
+        for card in exiled {
+            let (ready, name) = {
+                let mut card = card.lock().await;
+                if card.suspend.is_none() {
+                    continue;
+                }
+                (card.count_down(), card.name.clone())
+            };
+            if !ready {
+                continue;
+            }
+
+            if let Err(err) = game.move_card(&card, Zone::Exile, Zone::Stack).await {
+                game.debug(&err);
+                continue;
+            }
+            game.add_to_stack(Arc::new(PlayCardAction::new(
+                Arc::clone(&player),
+                Arc::clone(&card),
+                None,
+            )));
+            game.spells_cast_this_turn = game.spells_cast_this_turn.saturating_add(1);
+            game.add_turn_message(format!("{} was cast from suspend", name));
+        }
+    }
+}
+
+// The card's controller becomes the monarch.
+#[derive(Debug, Clone)]
+pub struct BecomeMonarchAction {}
+
+#[async_trait]
+impl CardAction for BecomeMonarchAction {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    async fn apply(&self, game: &mut Game, card: Arc<Mutex<Card>>, _target: EffectTarget) {
+        let controller = card.lock().await.controller();
+        if let Some(controller) = controller {
+            game.become_monarch(&controller).await;
+        }
+    }
+}
+
+// Makes the target player (or the target card's controller) skip their next draw step.
+#[derive(Debug, Clone)]
+pub struct SkipNextDrawAction {}
+
+#[async_trait]
+impl CardAction for SkipNextDrawAction {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    async fn apply(&self, _game: &mut Game, _card: Arc<Mutex<Card>>, target: EffectTarget) {
+        let player = match target {
+            EffectTarget::Player(player) => player,
+            EffectTarget::Card(target_card) => target_card.lock().await.controller().unwrap(),
+            EffectTarget::Cards(_) => {
+                warn!("Skipping a draw needs a single player");
+                return;
+            }
+        };
+        player.lock().await.skip_next_draw = true;
+    }
+}
+
+// Prevents the first combat damage the target player or creature would take
+// this turn, however much it is.
+#[derive(Debug, Clone)]
+pub struct PreventFirstCombatDamageAction {}
+
+#[async_trait]
+impl CardAction for PreventFirstCombatDamageAction {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    async fn apply(&self, _game: &mut Game, _card: Arc<Mutex<Card>>, target: EffectTarget) {
+        match target {
+            EffectTarget::Player(player) => player.lock().await.prevent_next_combat_damage = true,
+            EffectTarget::Card(target_card) => {
+                target_card.lock().await.prevent_next_combat_damage = true
+            }
+            EffectTarget::Cards(cards) => {
+                for target_card in cards {
+                    target_card.lock().await.prevent_next_combat_damage = true;
+                }
             }
         }
     }
 }
 
+// Goads the target creature: until the turn ends it attacks if able, and
+// attacks someone other than this card's controller if it can.
+#[derive(Debug, Clone)]
+pub struct GoadAction {}
+
+#[async_trait]
+impl CardAction for GoadAction {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    async fn apply(&self, _game: &mut Game, card: Arc<Mutex<Card>>, target: EffectTarget) {
+        let goader = card.lock().await.controller();
+        if let (EffectTarget::Card(target_card), Some(goader)) = (target, goader) {
+            target_card.lock().await.goaded_by = Some(goader);
+        }
+    }
+}
+
+// Ends the turn: exiles the stack and skips to cleanup
+#[derive(Debug, Clone)]
+pub struct EndTheTurnAction {}
+
+#[async_trait]
+impl CardAction for EndTheTurnAction {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    async fn apply(&self, game: &mut Game, _card: Arc<Mutex<Card>>, _target: EffectTarget) {
+        game.end_turn_now().await;
+    }
+}
+
 #[async_trait]
 pub trait Attachable: Debug + Send + Sync {
     async fn attach(
@@ -698,17 +1472,104 @@ impl CardAction for CardDamageAction {
                 let offense = card.get_stat_value(StatType::Power);
                 let defense = stats.get_stat_value(StatType::Toughness);
                 let total = offense - defense;
-                println!("Do damage {} to {:?}", total, stats);
+                debug!("Do damage {} to {:?}", total, stats);
                 stats.add_stat(
                     Ulid::new().to_string(),
                     Stat::new(StatType::Health, -1 * total),
                 );
             }
-            _ => todo!(),
+            EffectTarget::Card(_) | EffectTarget::Cards(_) => {
+                warn!("Card damage against a creature is not supported");
+            }
+        }
+    }
+}
+
+// Deals a fixed amount of damage to the target player or creature.
+#[derive(Debug, Clone)]
+pub struct DamageTargetAction {
+    pub amount: i8,
+}
+
+#[async_trait]
+impl CardAction for DamageTargetAction {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    async fn apply(&self, _game: &mut Game, _card: Arc<Mutex<Card>>, target: EffectTarget) {
+        match target {
+            EffectTarget::Player(player) => {
+                player.lock().await.stat_manager.add_stat(
+                    Ulid::new().to_string(),
+                    Stat::new(StatType::Health, -self.amount),
+                );
+            }
+            EffectTarget::Card(target_card) => {
+                target_card.lock().await.damage_taken += self.amount;
+            }
+            EffectTarget::Cards(cards) => {
+                for target_card in cards {
+                    target_card.lock().await.damage_taken += self.amount;
+                }
+            }
         }
     }
 }
 
+// Each opponent of the card's controller loses `amount` life, and the
+// controller gains whatever was lost.
+#[derive(Debug, Clone)]
+pub struct DrainOpponentsAction {
+    pub amount: i8,
+}
+
+#[async_trait]
+impl CardAction for DrainOpponentsAction {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    async fn apply(&self, game: &mut Game, card: Arc<Mutex<Card>>, _target: EffectTarget) {
+        let Some(controller) = card.lock().await.controller() else {
+            return;
+        };
+
+        let mut drained = 0;
+        for player in game.players.iter() {
+            if Arc::ptr_eq(player, &controller) {
+                continue;
+            }
+            player.lock().await.stat_manager.add_stat(
+                Ulid::new().to_string(),
+                Stat::new(StatType::Health, -self.amount),
+            );
+            drained += self.amount;
+        }
+
+        game.gain_life(&controller, drained).await;
+    }
+}
+
+// Destroys every card in play matching `filter`, e.g. all creatures.
+#[derive(Debug, Clone)]
+pub struct WrathAction {
+    pub filter: CardRequiredTarget,
+}
+
+#[async_trait]
+impl CardAction for WrathAction {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    async fn apply(&self, game: &mut Game, card: Arc<Mutex<Card>>, _target: EffectTarget) {
+        let (name, controller) = {
+            let card = card.lock().await;
+            (card.name.clone(), card.controller())
+        };
+        let destroyed = game.destroy_all(&self.filter, controller.as_ref()).await;
+        game.add_turn_message(format!("{} destroyed {} cards.", name, destroyed));
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct DestroyTargetCAction {}
 
@@ -720,10 +1581,19 @@ impl CardAction for DestroyTargetCAction {
     async fn apply(&self, game: &mut Game, card: Arc<Mutex<Card>>, target: EffectTarget) {
         match target {
             EffectTarget::Card(target_card) => {
-                game.destroy_card(&target_card).await;
+                if let Err(err) = game.destroy_card(&target_card).await {
+                    warn!("{}", err);
+                }
+            }
+            EffectTarget::Cards(cards) => {
+                for target_card in cards {
+                    if let Err(err) = game.destroy_card(&target_card).await {
+                        warn!("{}", err);
+                    }
+                }
             }
             EffectTarget::Player(_) => {
-                println!("Cannot destroy a player");
+                warn!("Cannot destroy a player");
             }
         }
     }
@@ -739,11 +1609,25 @@ impl CardAction for DeclareBlockerAction {
     }
     async fn apply(&self, game: &mut Game, card: Arc<Mutex<Card>>, target: EffectTarget) {
         match target {
-            EffectTarget::Player(arc) => todo!(),
+            EffectTarget::Player(_) => {
+                warn!("Cannot block a player");
+            }
+            EffectTarget::Cards(_) => {
+                warn!("Can only block one attacker");
+            }
             EffectTarget::Card(arc) => {
                 game.combat
                     .declare_blocker(Arc::clone(&card), Arc::clone(&arc))
                     .await;
+
+                let mut actions =
+                    Card::collect_triggered_actions(&card, &ActionTriggerType::Blocks).await;
+                actions.extend(
+                    Card::collect_triggered_actions(&arc, &ActionTriggerType::BecomesBlocked).await,
+                );
+                for action in actions {
+                    game.add_to_stack(action);
+                }
             }
         }
     }
@@ -755,11 +1639,57 @@ pub struct CombatAction {}
 #[async_trait::async_trait]
 impl PlayerAction for CombatAction {
     async fn apply(&self, game: &mut Game, player_index: usize) {
-        let destroyed_cards = game.combat.resolve_combat().await;
-        for card in destroyed_cards {
-            game.destroy_card(&card).await;
+        // Damage waits until every multi-blocked attacker's order is picked
+        if game.ask_for_damage_orders().await {
+            return;
         }
-        game.handle_deaths().await;
+        game.deal_combat_damage().await;
+    }
+}
+
+// Picks the next blocker an attacker deals its damage to, then asks for the
+// one after that until they're all in order.
+#[derive(Debug, Clone)]
+pub struct OrderBlockersAction {
+    pub attacker: Arc<Mutex<Card>>,
+    pub chosen: Vec<Arc<Mutex<Card>>>,
+}
+
+#[async_trait::async_trait]
+impl CardAction for OrderBlockersAction {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    async fn apply(&self, game: &mut Game, _card: Arc<Mutex<Card>>, target: EffectTarget) {
+        let mut chosen = self.chosen.clone();
+        if let EffectTarget::Card(blocker) = target {
+            let blocking = game
+                .combat
+                .blockers_of(&self.attacker)
+                .iter()
+                .any(|card| Arc::ptr_eq(card, &blocker));
+            // Anything else just gets asked again
+            if blocking && !chosen.iter().any(|card| Arc::ptr_eq(card, &blocker)) {
+                chosen.push(blocker);
+            }
+        }
+        game.ask_damage_order(Arc::clone(&self.attacker), chosen)
+            .await;
+    }
+}
+
+// Takes every creature out of combat once combat is over.
+#[derive(Debug, Clone)]
+pub struct EndOfCombatAction {}
+
+#[async_trait::async_trait]
+impl PlayerAction for EndOfCombatAction {
+    async fn apply(&self, game: &mut Game, _player_index: usize) {
+        // Nobody finished ordering blockers, so damage went in declared order
+        if !game.combat.awaiting_order.is_empty() {
+            game.deal_combat_damage().await;
+        }
+        game.combat.clear();
     }
 }
 
@@ -772,13 +1702,22 @@ impl CardAction for DeclareAttackerAction {
         self
     }
     async fn apply(&self, game: &mut Game, card: Arc<Mutex<Card>>, target: EffectTarget) {
-        println!(
+        debug!(
             "declaring \n\nattacker: {:?} \ntarget: {:?}\n\n",
             card, target
         );
         game.combat
-            .declare_attacker(Arc::clone(&card), target)
+            .declare_attacker(Arc::clone(&card), target.clone())
+            .await;
+
+        let actions = card
+            .lock()
+            .await
+            .collect_attack_actions(Arc::clone(&card), target)
             .await;
+        for action in actions {
+            game.add_to_stack(action);
+        }
     }
 }
 
@@ -912,7 +1851,7 @@ impl DamageTarget for Card {
     ) {
         // Reduce card's defense or health
         self.modify_stat(StatType::Toughness, -amount);
-        println!("{} takes {} damage.", self.name, amount);
+        debug!(card = %self.name, amount, "took damage");
     }
 }
 
@@ -930,7 +1869,7 @@ impl DamageTarget for Player {
     ) {
         // Reduce player's health
         self.modify_stat(StatType::Health, -amount);
-        println!("{} takes {} damage.", self.name, amount);
+        debug!(player = %self.name, amount, "took damage");
     }
 }
 
@@ -1054,13 +1993,13 @@ impl CardAction for ApplyEffectToCardBasedOnTotalCardType {
         self
     }
     async fn apply(&self, game: &mut Game, card_arc: Arc<Mutex<Card>>, target: EffectTarget) {
-        println!(
+        debug!(
             "effect target for dynamic effect\n\n\ncard: {:?}\ntarget: {:?}\n\n\n",
             card_arc, target
         );
         let owner_arc = {
             let card = card_arc.lock().await;
-            card.owner.clone()
+            card.controller()
         };
 
         if let Some(_) = owner_arc {
@@ -1068,36 +2007,7 @@ impl CardAction for ApplyEffectToCardBasedOnTotalCardType {
             let target = { card_arc.lock().await.attached.clone() };
 
             if let Some(target_arc) = target {
-                let card_type = self.card_type;
-                let amount_calculator = {
-                    Arc::new(move |card_arc: Arc<Mutex<Card>>| -> Pin<Box<dyn Future<Output = i8> + Send>> {
-                        Box::pin(async move {
-                            let mut total = 0;
-
-                            let (card_type, owner) = {
-                                let card =card_arc.lock().await;
-                                let owner = card.owner.clone();
-                                ( card_type, owner )
-                            };
-
-                            if let Some(owner_arc) = owner {
-
-                                let owner = owner_arc.lock().await;
-                                for card_in_play in &owner.cards_in_play {
-                                    let card_type_matches = {
-                                        let card = card_in_play.lock().await;
-                                        card.card_type == card_type
-                                    };
-
-                                    if card_type_matches {
-                                        total += 1;
-                                    }
-                                }
-                            }
-                            total
-                        })
-                    })
-                };
+                let amount_calculator = DynamicValue::PermanentsOfType(self.card_type).calculator();
 
                 let effects = (self.effects_generator)(
                     EffectTarget::Card(target_arc),
@@ -1112,10 +2022,11 @@ impl CardAction for ApplyEffectToCardBasedOnTotalCardType {
                     game.effect_manager.add_effect(effect_id, effect);
                 }
             } else {
-                println!("OH NO, NO ATTACHED CARD ON {}", &card_arc.lock().await.name);
+                let name = card_arc.lock().await.name.clone();
+                warn!(card = %name, "no attached card");
             }
         } else {
-            println!("No owner found for the card.");
+            warn!("No owner found for the card.");
         }
     }
 }
@@ -1141,13 +2052,13 @@ impl CardAction for ApplyEffectToPlayerCreatureType {
         self
     }
     async fn apply(&self, game: &mut Game, card_arc: Arc<Mutex<Card>>, target: EffectTarget) {
-        println!(
+        debug!(
             "effect target for dynamic effect\n\n\ncard: {:?}\ntarget: {:?}\n\n\n",
             card_arc, target
         );
         let owner_arc = {
             let card = card_arc.lock().await;
-            card.owner.clone()
+            card.controller()
         };
 
         if let Some(owner_arc) = owner_arc {
@@ -1174,7 +2085,7 @@ impl CardAction for ApplyEffectToPlayerCreatureType {
                 }
             }
         } else {
-            println!("No owner found for the card.");
+            warn!("No owner found for the card.");
         }
     }
 }
@@ -1200,13 +2111,13 @@ impl CardAction for ApplyEffectToPlayerCardType {
         self
     }
     async fn apply(&self, game: &mut Game, card_arc: Arc<Mutex<Card>>, target: EffectTarget) {
-        println!(
+        debug!(
             "effect target for dynamic effect\n\n\ncard: {:?}\ntarget: {:?}\n\n\n",
             card_arc, target
         );
         let owner_arc = {
             let card = card_arc.lock().await;
-            card.owner.clone()
+            card.controller()
         };
 
         if let Some(owner_arc) = owner_arc {
@@ -1226,8 +2137,16 @@ impl CardAction for ApplyEffectToPlayerCardType {
                         source_card,
                     );
 
-                    for effect in effects {
-                        let effect_id = effect.lock().await.get_final_id();
+                    // Key by source and target so a continuous effect re-applied on
+                    // every action replaces itself rather than stacking
+                    let source_id = card_arc.lock().await.id.clone();
+                    let target_id = card_in_play.lock().await.id.clone();
+                    for (i, effect) in effects.into_iter().enumerate() {
+                        let effect_id = {
+                            let mut effect = effect.lock().await;
+                            effect.set_id(EffectID(format!("{}-{}-{}", source_id, target_id, i)));
+                            effect.get_final_id()
+                        };
 
                         game.effect_manager.add_effect(effect_id, effect);
                     }
@@ -1236,7 +2155,7 @@ impl CardAction for ApplyEffectToPlayerCardType {
                 }
             }
         } else {
-            println!("No owner found for the card.");
+            warn!("No owner found for the card.");
         }
     }
 }
@@ -1261,21 +2180,25 @@ impl CardAction for ApplyEffectToTargetAction {
         self
     }
     async fn apply(&self, game: &mut Game, card_arc: Arc<Mutex<Card>>, target: EffectTarget) {
+        // Spells carry their own target, anything else uses the one it was given
         let target = {
             let card = card_arc.lock().await;
             card.target.clone()
-        }; // Lock is released here
+        } // Lock is released here
+        .unwrap_or(target);
+        // Without a target the wrapper hands over the card itself
+        if matches!(&target, EffectTarget::Card(card) if Arc::ptr_eq(card, &card_arc)) {
+            let name = card_arc.lock().await.name.clone();
+            warn!(card = %name, "no target to apply the effect to");
+            return;
+        }
 
-        if let Some(target) = target {
-            let source_card = Some(Arc::clone(&card_arc)); // Set the source card
-            let effect = (self.effect_generator)(target.clone(), source_card);
-            let effect_id = effect.lock().await.get_final_id();
-            println!("Adding effect {:?} to card {:?}", effect, target);
+        let source_card = Some(Arc::clone(&card_arc)); // Set the source card
+        let effect = (self.effect_generator)(target.clone(), source_card);
+        let effect_id = effect.lock().await.get_final_id();
+        debug!("Adding effect {:?} to card {:?}", effect, target);
 
-            game.effect_manager.add_effect(effect_id, effect);
-        } else {
-            println!("No target specified for card action.");
-        }
+        game.effect_manager.add_effect(effect_id, effect);
     }
 }
 
@@ -1297,17 +2220,17 @@ impl CardAction for DrawCardCardAction {
         self
     }
     async fn apply(&self, game: &mut Game, card_arc: Arc<Mutex<Card>>, target: EffectTarget) {
-        let card = card_arc.lock().await;
-        let owner = card.owner.as_ref().unwrap();
-        for _ in 0..self.count {
-            owner.lock().await.draw_card();
-        }
+        let owner = card_arc.lock().await.controller().unwrap();
+        game.player_draws(&owner, self.count.max(0) as u8, DrawSource::Effect)
+            .await;
     }
 }
 
 #[derive(Clone)]
 pub struct CastMandatoryAdditionalAbility {
     pub mana: Vec<ManaType>,
+    // Paid alongside the mana
+    pub costs: Vec<Cost>,
     pub target: CardRequiredTarget,
     pub ability: Arc<dyn Fn(Arc<Mutex<Card>>) -> Arc<dyn CardAction + Send + Sync> + Send + Sync>,
     pub description: String,
@@ -1325,25 +2248,30 @@ impl CardAction for CastMandatoryAdditionalAbility {
         self
     }
     async fn apply(&self, game: &mut Game, card_arc: Arc<Mutex<Card>>, target: EffectTarget) {
-        let owner = card_arc.lock().await.owner.clone();
+        let owner = card_arc.lock().await.controller();
 
         if let Some(owner_arc) = owner {
             let can_pay_mana_cost = { owner_arc.lock().await.can_pay_mana(&self.mana).await };
-            if can_pay_mana_cost {
-                game.ask_mandatory_player_ability(Ability::new(
-                    card_arc.clone(),
-                    self.mana.clone(),
-                    self.target.clone(),
-                    self.ability.clone(),
-                    self.description.clone(),
-                    self.action_type.clone(),
-                ))
-                .await;
+            if !can_pay_mana_cost {
+                debug!("Not enough mana to activate the ability.");
+            } else if let Err(err) = game.check_costs(&card_arc, &owner_arc, &self.costs).await {
+                warn!("Can't activate the ability: {}", err);
             } else {
-                println!("Not enough mana to activate the ability.");
+                game.ask_mandatory_player_ability(
+                    Ability::new(
+                        card_arc.clone(),
+                        self.mana.clone(),
+                        self.target.clone(),
+                        self.ability.clone(),
+                        self.description.clone(),
+                        self.action_type.clone(),
+                    )
+                    .with_costs(self.costs.clone()),
+                )
+                .await;
             }
         } else {
-            println!("No owner found for the card.");
+            warn!("No owner found for the card.");
         }
     }
 }
@@ -1351,6 +2279,8 @@ impl CardAction for CastMandatoryAdditionalAbility {
 #[derive(Clone)]
 pub struct CastOptionalAdditionalAbility {
     pub mana: Vec<ManaType>,
+    // Paid alongside the mana
+    pub costs: Vec<Cost>,
     pub target: CardRequiredTarget,
     pub ability: Arc<dyn Fn(Arc<Mutex<Card>>) -> Arc<dyn CardAction + Send + Sync> + Send + Sync>,
     pub description: String,
@@ -1368,25 +2298,30 @@ impl CardAction for CastOptionalAdditionalAbility {
         self
     }
     async fn apply(&self, game: &mut Game, card_arc: Arc<Mutex<Card>>, target: EffectTarget) {
-        let owner = card_arc.lock().await.owner.clone();
+        let owner = card_arc.lock().await.controller();
 
         if let Some(owner_arc) = owner {
             let can_pay_mana_cost = { owner_arc.lock().await.can_pay_mana(&self.mana).await };
-            if can_pay_mana_cost {
-                game.request_player_ability(Ability::new(
-                    card_arc.clone(),
-                    self.mana.clone(),
-                    self.target.clone(),
-                    self.ability.clone(),
-                    self.description.clone(),
-                    self.action_type.clone(),
-                ))
-                .await;
+            if !can_pay_mana_cost {
+                debug!("Not enough mana to activate the ability.");
+            } else if let Err(err) = game.check_costs(&card_arc, &owner_arc, &self.costs).await {
+                warn!("Can't activate the ability: {}", err);
             } else {
-                println!("Not enough mana to activate the ability.");
+                game.request_player_ability(
+                    Ability::new(
+                        card_arc.clone(),
+                        self.mana.clone(),
+                        self.target.clone(),
+                        self.ability.clone(),
+                        self.description.clone(),
+                        self.action_type.clone(),
+                    )
+                    .with_costs(self.costs.clone()),
+                )
+                .await;
             }
         } else {
-            println!("No owner found for the card.");
+            warn!("No owner found for the card.");
         }
     }
 }
@@ -0,0 +1,36 @@
+use std::{any::Any, sync::Arc};
+
+use tokio::sync::Mutex;
+
+use crate::game::{card::Card, effects::EffectTarget, zone::Zone, Game};
+
+use super::CardAction;
+
+// Puts the top card of its controller's library onto the battlefield face
+// down, as a 2/2 creature.
+#[derive(Debug, Clone)]
+pub struct ManifestAction {}
+
+#[async_trait::async_trait]
+impl CardAction for ManifestAction {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    async fn apply(&self, game: &mut Game, card: Arc<Mutex<Card>>, _target: EffectTarget) {
+        let Some(controller) = card.lock().await.controller() else {
+            return;
+        };
+        let Some(top) = controller.lock().await.deck.draw_pile.last().cloned() else {
+            return;
+        };
+
+        top.lock().await.turn_face_down();
+        if game
+            .move_card(&top, Zone::Library, Zone::Battlefield)
+            .await
+            .is_ok()
+        {
+            game.add_turn_message("A card was manifested".to_string());
+        }
+    }
+}
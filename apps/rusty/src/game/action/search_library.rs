@@ -0,0 +1,281 @@
+use std::{any::Any, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use tokio::sync::Mutex;
+use tracing::warn;
+
+use crate::game::{card::Card, effects::EffectTarget, zone::Zone, Ability, ActionType, Game};
+
+use super::{CardAction, CardRequiredTarget};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub enum SearchDestination {
+    Hand,
+    Play,
+}
+
+// Reveals the cards in the owner's library that match `filter` to the owner,
+// who picks one to put into `to_zone`. The library is shuffled afterwards.
+#[derive(Debug, Clone)]
+pub struct SearchLibraryAction {
+    pub filter: CardRequiredTarget,
+    pub to_zone: SearchDestination,
+}
+
+#[async_trait::async_trait]
+impl CardAction for SearchLibraryAction {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    async fn apply(&self, game: &mut Game, card: Arc<Mutex<Card>>, _target: EffectTarget) {
+        let owner = card.lock().await.controller().unwrap();
+        let draw_pile = owner.lock().await.deck.draw_pile.clone();
+
+        let mut choices = vec![];
+        for library_card in draw_pile {
+            if self.filter.matches_card(&*library_card.lock().await, true) {
+                choices.push(library_card);
+            }
+        }
+
+        if choices.is_empty() {
+            let name = owner.lock().await.name.clone();
+            ShuffleLibraryAction {}
+                .apply(game, card, EffectTarget::Player(owner))
+                .await;
+            game.add_turn_message(format!("{} searched their library and found nothing", name));
+            return;
+        }
+
+        let filter = self.filter.clone();
+        let to_zone = self.to_zone.clone();
+        game.ask_mandatory_player_ability(
+            Ability::new(
+                card,
+                vec![],
+                self.filter.clone(),
+                Arc::new(move |_| {
+                    Arc::new(PutSearchedCardAction {
+                        filter: filter.clone(),
+                        to_zone: to_zone.clone(),
+                    })
+                }),
+                "Choose a card from your library".to_string(),
+                ActionType::None,
+            )
+            .with_choices(choices),
+        )
+        .await;
+    }
+}
+
+// Second half of a library search, run with the card the player picked.
+#[derive(Debug, Clone)]
+pub struct PutSearchedCardAction {
+    pub filter: CardRequiredTarget,
+    pub to_zone: SearchDestination,
+}
+
+#[async_trait::async_trait]
+impl CardAction for PutSearchedCardAction {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    async fn apply(&self, game: &mut Game, card: Arc<Mutex<Card>>, target: EffectTarget) {
+        let owner = card.lock().await.controller().unwrap();
+
+        if let EffectTarget::Card(chosen) = target {
+            let in_library = owner.lock().await.zone_contains(Zone::Library, &chosen);
+            if in_library && self.filter.matches_card(&*chosen.lock().await, true) {
+                // Through move_card so a creature put into play enters properly
+                let to = match self.to_zone {
+                    SearchDestination::Hand => Zone::Hand,
+                    SearchDestination::Play => Zone::Battlefield,
+                };
+                if let Err(err) = game.move_card(&chosen, Zone::Library, to).await {
+                    warn!("{}", err);
+                }
+            }
+        }
+
+        ShuffleLibraryAction {}
+            .apply(game, card, EffectTarget::Player(owner))
+            .await;
+    }
+}
+
+// Shuffles the target player's library, or the card's controller's when
+// the target is a card.
+#[derive(Debug, Clone)]
+pub struct ShuffleLibraryAction {}
+
+#[async_trait::async_trait]
+impl CardAction for ShuffleLibraryAction {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    async fn apply(&self, game: &mut Game, card: Arc<Mutex<Card>>, target: EffectTarget) {
+        let player = match target {
+            EffectTarget::Player(player) => player,
+            _ => card.lock().await.controller().unwrap(),
+        };
+        player.lock().await.deck.shuffle(&mut game.rng);
+    }
+}
+
+mod test {
+    use std::sync::Arc;
+
+    use tokio::sync::Mutex;
+
+    use crate::game::{
+        action::{CardActionWrapper, CardRequiredTarget, CardTargetTeam},
+        card::CreatureType,
+        decks::{black::create_hydra, green::create_priest_of_titania},
+        effects::EffectTarget,
+        player::Player,
+        Game,
+    };
+
+    use super::{SearchDestination, SearchLibraryAction, ShuffleLibraryAction};
+
+    #[tokio::test]
+    async fn test_search_library_for_elf() {
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new(
+                "test",
+                20,
+                vec![create_priest_of_titania(), create_hydra(), create_hydra()],
+            ))
+            .await;
+        let source = player.lock().await.draw_card().unwrap();
+        game.start_turn(0).await;
+
+        game.add_to_stack(Arc::new(CardActionWrapper {
+            card: source,
+            action: Arc::new(SearchLibraryAction {
+                filter: CardRequiredTarget::CreatureOfType(
+                    CreatureType::Elf,
+                    CardTargetTeam::Owner,
+                ),
+                to_zone: SearchDestination::Hand,
+            }),
+            target: None,
+        }));
+        game.resolve_stack().await;
+
+        let ability = game.abilities.values().next().unwrap().clone();
+        assert_eq!(ability.choices.len(), 1);
+        let elf = ability.choices[0].clone();
+        assert_eq!(elf.lock().await.creature_type, Some(CreatureType::Elf));
+
+        let ga = Arc::new(Mutex::new(game));
+        Game::respond_player_ability(
+            ga.clone(),
+            &player,
+            ability.id.clone(),
+            true,
+            Some(EffectTarget::Card(elf.clone())),
+        )
+        .await
+        .unwrap();
+
+        let player = player.lock().await;
+        assert!(player
+            .cards_in_hand
+            .iter()
+            .any(|card| Arc::ptr_eq(card, &elf)));
+        assert!(!player
+            .deck
+            .draw_pile
+            .iter()
+            .any(|card| Arc::ptr_eq(card, &elf)));
+        assert_eq!(player.deck.draw_pile.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_search_library_into_play_enters_the_battlefield() {
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new(
+                "test",
+                20,
+                vec![create_priest_of_titania(), create_hydra(), create_hydra()],
+            ))
+            .await;
+        let source = player.lock().await.draw_card().unwrap();
+        game.start_turn(0).await;
+
+        game.add_to_stack(Arc::new(CardActionWrapper {
+            card: source,
+            action: Arc::new(SearchLibraryAction {
+                filter: CardRequiredTarget::CreatureOfType(
+                    CreatureType::Elf,
+                    CardTargetTeam::Owner,
+                ),
+                to_zone: SearchDestination::Play,
+            }),
+            target: None,
+        }));
+        game.resolve_stack().await;
+
+        let ability = game.abilities.values().next().unwrap().clone();
+        let elf = ability.choices[0].clone();
+        let ga = Arc::new(Mutex::new(game));
+        Game::respond_player_ability(
+            ga.clone(),
+            &player,
+            ability.id.clone(),
+            true,
+            Some(EffectTarget::Card(elf.clone())),
+        )
+        .await
+        .unwrap();
+
+        assert!(player
+            .lock()
+            .await
+            .cards_in_play
+            .iter()
+            .any(|card| Arc::ptr_eq(card, &elf)));
+        let controller = elf.lock().await.controller().unwrap();
+        assert!(Arc::ptr_eq(&controller, &player));
+    }
+
+    #[tokio::test]
+    async fn test_shuffle_library_shuffles_the_target_players_deck() {
+        let mut game = Game::new().with_seed(7);
+        let player = game
+            .add_player(Player::new("test", 20, vec![create_priest_of_titania()]))
+            .await;
+        let opponent = game
+            .add_player(Player::new(
+                "opponent",
+                20,
+                (0..20).map(|_| create_hydra()).collect(),
+            ))
+            .await;
+        let source = player.lock().await.draw_card().unwrap();
+        game.start_turn(0).await;
+        let before = opponent.lock().await.deck.draw_pile.clone();
+
+        game.add_to_stack(Arc::new(CardActionWrapper {
+            card: source,
+            action: Arc::new(ShuffleLibraryAction {}),
+            target: Some(EffectTarget::Player(opponent.clone())),
+        }));
+        game.resolve_stack().await;
+
+        let after = opponent.lock().await.deck.draw_pile.clone();
+        assert_eq!(after.len(), before.len());
+        assert!(before
+            .iter()
+            .all(|card| after.iter().any(|other| Arc::ptr_eq(card, other))));
+        assert!(before
+            .iter()
+            .zip(after.iter())
+            .any(|(a, b)| !Arc::ptr_eq(a, b)));
+    }
+}
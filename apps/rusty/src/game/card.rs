@@ -6,20 +6,23 @@ use std::{borrow::Borrow, cell::RefCell};
 
 use serde::{Deserialize, Serialize};
 use specta::Type;
+#[cfg(feature = "cli")]
 use textwrap::fill;
 use tokio::sync::Mutex;
+use tracing::debug;
 use ulid::Ulid;
 
 use crate::error::AppError;
 use crate::game::action;
-use crate::game::effects::{EffectManager, EffectTarget};
+use crate::game::effects::{DynamicValue, EffectTarget};
 
 use super::action::{
-    ActionTriggerType, Attachable, CardAction, CardActionTarget, CardActionTrigger,
-    CardActionWrapper, CardRequiredTarget, PlayerAction, PlayerActionTrigger, ResetCardAction,
+    ActionTriggerType, AmountTriggerWrapper, CardActionTrigger, CardActionWrapper,
+    CardRequiredTarget, ResetCardAction,
 };
 
 use super::effects::EffectID;
+use super::error::GameError;
 use super::mana::ManaType;
 use super::player;
 use super::turn::Turn;
@@ -46,6 +49,7 @@ pub enum CardType {
     Sorcery,
     Artifact,
     BasicLand(ManaType),
+    DualLand(ManaType, ManaType),
     // Land(Vec<ManaType>),
 }
 
@@ -53,6 +57,7 @@ impl CardType {
     pub fn is_spell(&self) -> bool {
         match self {
             CardType::BasicLand(_) => false,
+            CardType::DualLand(_, _) => false,
             // CardType::Land(_) => false,
             _ => true,
         }
@@ -67,6 +72,15 @@ pub enum CardPhase {
     Cancelled,
 }
 
+// Suspend N: pay `cost` to exile it from hand with N time counters instead of
+// casting it. One comes off each of its owner's upkeeps, and it's cast for
+// free once the last one does.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Type)]
+pub struct Suspend {
+    pub time_counters: u8,
+    pub cost: Vec<ManaType>,
+}
+
 #[derive(Type, Debug, Deserialize, Serialize, Clone)]
 pub struct Card {
     pub creature_type: Option<CreatureType>,
@@ -84,6 +98,8 @@ pub struct Card {
     #[serde(skip_serializing, skip_deserializing)]
     pub owner: Option<Arc<Mutex<Player>>>,
     #[serde(skip_serializing, skip_deserializing)]
+    pub controller: Option<Arc<Mutex<Player>>>,
+    #[serde(skip_serializing, skip_deserializing)]
     pub attached: Option<Arc<Mutex<Card>>>,
     #[serde(skip_serializing, skip_deserializing)]
     pub action_target: Option<EffectTarget>,
@@ -93,6 +109,69 @@ pub struct Card {
     pub damage_taken: i8,
     pub is_countered: bool,
     pub id: String,
+    #[serde(skip_serializing, skip_deserializing)]
+    pub enters_tapped: bool,
+    // +1/+1 counters put on the card as it hits the battlefield
+    #[serde(skip_serializing, skip_deserializing)]
+    pub enters_with_counters: i8,
+    // Affinity: the generic part of the cost drops by one for each of these
+    #[serde(skip_serializing, skip_deserializing)]
+    pub cost_reduction: Option<DynamicValue>,
+    // Phased out cards are treated as though they don't exist until they
+    // phase back in at their controller's next untap
+    #[serde(default)]
+    pub phased_out: bool,
+    // The next combat damage dealt to this card this turn is prevented
+    #[serde(skip_serializing, skip_deserializing)]
+    pub prevent_next_combat_damage: bool,
+    // Attacks each combat if able
+    #[serde(default)]
+    pub must_attack: bool,
+    // Goaded: attacks this turn if able, and someone other than this player if it can
+    #[serde(skip_serializing, skip_deserializing)]
+    pub goaded_by: Option<Arc<Mutex<Player>>>,
+    #[serde(default)]
+    pub suspend: Option<Suspend>,
+    // Flashback: can also be cast from the graveyard for this cost
+    #[serde(default)]
+    pub flashback: Option<Vec<ManaType>>,
+    // Echo: pay this at the controller's next upkeep after it enters, or
+    // sacrifice it
+    #[serde(default)]
+    pub echo: Option<Vec<ManaType>>,
+    // Cast with flashback, so it's exiled rather than going anywhere else
+    // once it leaves the stack
+    #[serde(skip_serializing, skip_deserializing)]
+    pub flashed_back: bool,
+    // Face down it's a nameless 2/2 creature with no abilities, whatever it
+    // really is. Only its controller gets to see the real card.
+    #[serde(default)]
+    pub face_down: bool,
+    // Morph: can be turned face up for this cost while face down
+    #[serde(default)]
+    pub morph: Option<Vec<ManaType>>,
+    // What the card really is while it's face down
+    #[serde(skip_serializing, skip_deserializing)]
+    pub hidden_face: Option<HiddenFace>,
+}
+
+#[derive(Debug, Clone)]
+pub struct HiddenFace {
+    pub card_type: CardType,
+    pub creature_type: Option<CreatureType>,
+    pub stats: StatManager,
+    pub triggers: Vec<CardActionTrigger>,
+}
+
+// What `render` draws, as data for a frontend to lay out itself.
+#[derive(Type, Debug, Serialize, Clone, PartialEq)]
+pub struct CardView {
+    pub name: String,
+    pub cost: Vec<ManaType>,
+    pub power: i8,
+    pub toughness: i8,
+    pub description: String,
+    pub tapped: bool,
 }
 
 impl Card {
@@ -118,11 +197,26 @@ impl Card {
             stats: StatManager::new(stats),
             cost,
             owner: None,
+            controller: None,
             attached: None,
             action_target: None,
             damage_taken: 0,
             damage_dealt_to_players: 0,
             is_countered: false,
+            enters_tapped: false,
+            enters_with_counters: 0,
+            cost_reduction: None,
+            phased_out: false,
+            prevent_next_combat_damage: false,
+            must_attack: false,
+            goaded_by: None,
+            suspend: None,
+            flashback: None,
+            echo: None,
+            flashed_back: false,
+            face_down: false,
+            morph: None,
+            hidden_face: None,
         };
         card.triggers.push(CardActionTrigger::new(
             ActionTriggerType::CardDestroyed,
@@ -133,6 +227,192 @@ impl Card {
         card
     }
 
+    pub fn with_must_attack(mut self) -> Self {
+        self.must_attack = true;
+        self
+    }
+
+    pub fn with_suspend(mut self, time_counters: u8, cost: Vec<ManaType>) -> Self {
+        self.suspend = Some(Suspend {
+            time_counters,
+            cost,
+        });
+        self
+    }
+
+    pub fn with_flashback(mut self, cost: Vec<ManaType>) -> Self {
+        self.flashback = Some(cost);
+        self
+    }
+
+    pub fn with_echo(mut self, cost: Vec<ManaType>) -> Self {
+        self.echo = Some(cost);
+        self
+    }
+
+    pub fn with_morph(mut self, cost: Vec<ManaType>) -> Self {
+        self.morph = Some(cost);
+        self
+    }
+
+    // Puts the real card aside, leaving a 2/2 creature with no abilities.
+    // With morph its controller can turn it face up, see Game::turn_face_up.
+    pub fn turn_face_down(&mut self) {
+        if self.face_down {
+            return;
+        }
+
+        let mut stats = StatManager::default();
+        stats.add_stat(
+            format!("{}-face-down-power", self.id),
+            Stat::new(StatType::Power, 2),
+        );
+        stats.add_stat(
+            format!("{}-face-down-toughness", self.id),
+            Stat::new(StatType::Toughness, 2),
+        );
+        let triggers = vec![CardActionTrigger::new(
+            ActionTriggerType::CardDestroyed,
+            CardRequiredTarget::None,
+            Arc::new(ResetCardAction {}),
+        )];
+
+        self.hidden_face = Some(HiddenFace {
+            card_type: std::mem::replace(&mut self.card_type, CardType::Creature),
+            creature_type: self.creature_type.take(),
+            stats: std::mem::replace(&mut self.stats, stats),
+            triggers: std::mem::replace(&mut self.triggers, triggers),
+        });
+        self.face_down = true;
+    }
+
+    // Brings the real card back. Anything done to the 2/2 while it was face
+    // down, like counters or pumps, stays on it.
+    pub fn turn_face_up(&mut self) {
+        let Some(hidden) = self.hidden_face.take() else {
+            return;
+        };
+
+        let mut stats = std::mem::replace(&mut self.stats, hidden.stats);
+        stats.remove_stat(format!("{}-face-down-power", self.id));
+        stats.remove_stat(format!("{}-face-down-toughness", self.id));
+        for (id, stat) in stats.stats {
+            self.stats.add_stat(id, stat);
+        }
+        self.card_type = hidden.card_type;
+        self.creature_type = hidden.creature_type;
+        self.triggers = hidden.triggers;
+        self.face_down = false;
+    }
+
+    // What everyone but its controller sees of a face down card
+    pub fn face_down_view(&self) -> Card {
+        let mut card = Card::new(
+            "",
+            "",
+            vec![],
+            self.current_phase.clone(),
+            CardType::Creature,
+            vec![],
+            vec![],
+        );
+        card.id = self.id.clone();
+        card.tapped = self.tapped;
+        card.stats = self.stats.clone();
+        card.phased_out = self.phased_out;
+        card.face_down = true;
+        card
+    }
+
+    // What its controller sees of a face down card: the real one, still marked
+    // face down
+    pub fn hidden_face_view(&self) -> Card {
+        let mut card = self.clone();
+        card.turn_face_up();
+        card.face_down = self.face_down;
+        card
+    }
+
+    // The name everyone can see, blank while it's face down
+    pub fn public_name(&self) -> String {
+        if self.face_down {
+            self.face_down_view().name
+        } else {
+            self.name.clone()
+        }
+    }
+
+    // Takes a counter off a charging card, returning whether that made it ready
+    pub fn count_down(&mut self) -> bool {
+        if let CardPhase::Charging(remaining) = &mut self.current_phase {
+            if *remaining > 0 {
+                *remaining -= 1;
+                if *remaining == 0 {
+                    self.current_phase = CardPhase::Ready;
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    pub fn with_enters_tapped(mut self) -> Self {
+        self.enters_tapped = true;
+        self
+    }
+
+    pub fn with_counters(mut self, counters: i8) -> Self {
+        self.enters_with_counters = counters;
+        self
+    }
+
+    pub fn with_cost_reduction(mut self, reduction: DynamicValue) -> Self {
+        self.cost_reduction = Some(reduction);
+        self
+    }
+
+    // What casting the card costs right now. Reductions only ever take away
+    // generic mana, never colored pips.
+    pub async fn cost_to_cast(card_arc: &Arc<Mutex<Card>>) -> Vec<ManaType> {
+        let (mut cost, reduction) = {
+            let card = card_arc.lock().await;
+            (card.cost.clone(), card.cost_reduction.clone())
+        };
+        let Some(reduction) = reduction else {
+            return cost;
+        };
+
+        let mut remaining = reduction.calculate(card_arc).await.max(0);
+        cost.retain(|mana| {
+            if *mana == ManaType::Colorless && remaining > 0 {
+                remaining -= 1;
+                false
+            } else {
+                true
+            }
+        });
+        cost
+    }
+
+    // Taps the card and puts its counters on as it enters the battlefield.
+    pub fn enter_battlefield(&mut self) {
+        if self.enters_tapped {
+            self.tapped = true;
+        }
+
+        if self.enters_with_counters != 0 {
+            let counters = self.enters_with_counters;
+            self.stats.add_stat(
+                format!("{}-counters-power", self.id),
+                Stat::new(StatType::Power, counters),
+            );
+            self.stats.add_stat(
+                format!("{}-counters-toughness", self.id),
+                Stat::new(StatType::Toughness, counters),
+            );
+        }
+    }
+
     pub fn is_useless(&self, has_effects: bool) -> bool {
         let has_triggers = self
             .triggers
@@ -143,8 +423,14 @@ impl Card {
                 ActionTriggerType::PhaseStarted(vec, trigger_target) => true,
                 ActionTriggerType::CreatureTypeCardPlayed(trigger_target, creature_type) => true,
                 ActionTriggerType::Attached => true,
+                ActionTriggerType::Equip(_) => true,
                 ActionTriggerType::DamageApplied => true,
                 ActionTriggerType::OtherCardPlayed(_) => true,
+                ActionTriggerType::Attacks(_) => true,
+                ActionTriggerType::Blocks => true,
+                ActionTriggerType::BecomesBlocked => true,
+                ActionTriggerType::LifeGained(_) => true,
+                ActionTriggerType::CreatureDied(_) => true,
                 ActionTriggerType::Continuous => false,
                 ActionTriggerType::Detached => false,
                 ActionTriggerType::CardDestroyed => false,
@@ -152,7 +438,7 @@ impl Card {
             .count()
             > 0;
 
-        println!(
+        debug!(
             "has_triggers: {:?}\nhas_effects: {}\n\n\n",
             has_triggers, has_effects
         );
@@ -234,6 +520,180 @@ impl Card {
         actions
     }
 
+    pub async fn collect_equip_actions(
+        &self,
+        card_arc: Arc<Mutex<Card>>,
+        target: Option<EffectTarget>,
+    ) -> Vec<Arc<dyn Action + Send + Sync>> {
+        let mut actions: Vec<Arc<dyn Action + Send + Sync>> = Vec::new();
+
+        for action_trigger in &self.triggers {
+            if let ActionTriggerType::Equip(_) = &action_trigger.trigger_type {
+                actions.push(Arc::new(CardActionWrapper {
+                    card: Arc::clone(&card_arc),
+                    action: action_trigger.action.clone(),
+                    target: target.clone(),
+                }));
+            }
+        }
+
+        actions
+    }
+
+    // Owner targets the attacking player, Target whatever is being attacked and
+    // Any the attacker itself.
+    pub async fn collect_attack_actions(
+        &self,
+        card_arc: Arc<Mutex<Card>>,
+        attack_target: EffectTarget,
+    ) -> Vec<Arc<dyn Action + Send + Sync>> {
+        let mut actions: Vec<Arc<dyn Action + Send + Sync>> = Vec::new();
+
+        for action_trigger in &self.triggers {
+            if let ActionTriggerType::Attacks(trigger_target) = &action_trigger.trigger_type {
+                let target = match trigger_target {
+                    action::TriggerTarget::Owner => self.controller().map(EffectTarget::Player),
+                    action::TriggerTarget::Target => Some(attack_target.clone()),
+                    action::TriggerTarget::Any => None,
+                };
+
+                actions.push(Arc::new(CardActionWrapper {
+                    card: Arc::clone(&card_arc),
+                    action: action_trigger.action.clone(),
+                    target,
+                }));
+            }
+        }
+
+        actions
+    }
+
+    pub fn collect_damage_actions(
+        &self,
+        card_arc: Arc<Mutex<Card>>,
+        damaged: EffectTarget,
+        amount: i8,
+    ) -> Vec<Arc<dyn Action + Send + Sync>> {
+        let mut actions: Vec<Arc<dyn Action + Send + Sync>> = Vec::new();
+
+        for action_trigger in &self.triggers {
+            if action_trigger.trigger_type == ActionTriggerType::DamageApplied {
+                actions.push(Arc::new(AmountTriggerWrapper {
+                    card: Arc::clone(&card_arc),
+                    action: action_trigger.action.clone(),
+                    target: damaged.clone(),
+                    amount,
+                }));
+            }
+        }
+
+        actions
+    }
+
+    pub fn collect_life_gained_actions(
+        &self,
+        card_arc: Arc<Mutex<Card>>,
+        player: &Arc<Mutex<Player>>,
+        amount: i8,
+    ) -> Vec<Arc<dyn Action + Send + Sync>> {
+        let mut actions: Vec<Arc<dyn Action + Send + Sync>> = Vec::new();
+        let controller = match self.controller() {
+            Some(controller) => controller,
+            None => return actions,
+        };
+        let is_controller = Arc::ptr_eq(&controller, player);
+
+        for action_trigger in &self.triggers {
+            if let ActionTriggerType::LifeGained(trigger_target) = &action_trigger.trigger_type {
+                let applies = match trigger_target {
+                    action::TriggerTarget::Owner => is_controller,
+                    action::TriggerTarget::Target => !is_controller,
+                    action::TriggerTarget::Any => true,
+                };
+
+                if applies {
+                    actions.push(Arc::new(AmountTriggerWrapper {
+                        card: Arc::clone(&card_arc),
+                        action: action_trigger.action.clone(),
+                        target: EffectTarget::Player(Arc::clone(player)),
+                        amount,
+                    }));
+                }
+            }
+        }
+
+        actions
+    }
+
+    // Targets the creature that died, which is no longer in play by the time
+    // these resolve.
+    pub async fn collect_creature_died_actions(
+        card_arc: &Arc<Mutex<Card>>,
+        died: &Arc<Mutex<Card>>,
+        died_controller: &Arc<Mutex<Player>>,
+    ) -> Vec<Arc<dyn Action + Send + Sync>> {
+        let mut actions: Vec<Arc<dyn Action + Send + Sync>> = Vec::new();
+        let (controller, triggers) = {
+            let card = card_arc.lock().await;
+            if card.phased_out {
+                return actions;
+            }
+            match card.controller() {
+                Some(controller) => (controller, card.triggers.clone()),
+                None => return actions,
+            }
+        };
+        let is_controller = Arc::ptr_eq(&controller, died_controller);
+
+        for action_trigger in &triggers {
+            if let ActionTriggerType::CreatureDied(trigger_target) = &action_trigger.trigger_type {
+                let applies = match trigger_target {
+                    action::TriggerTarget::Owner => is_controller,
+                    action::TriggerTarget::Target => !is_controller,
+                    action::TriggerTarget::Any => true,
+                };
+
+                if applies && action_trigger.condition_met(card_arc).await {
+                    actions.push(Arc::new(CardActionWrapper {
+                        card: Arc::clone(card_arc),
+                        action: action_trigger.action.clone(),
+                        target: Some(EffectTarget::Card(Arc::clone(died))),
+                    }));
+                }
+            }
+        }
+
+        actions
+    }
+
+    pub async fn collect_triggered_actions(
+        card_arc: &Arc<Mutex<Card>>,
+        trigger_type: &ActionTriggerType,
+    ) -> Vec<Arc<dyn Action + Send + Sync>> {
+        let mut actions: Vec<Arc<dyn Action + Send + Sync>> = Vec::new();
+        let triggers = {
+            let card = card_arc.lock().await;
+            if card.phased_out {
+                return actions;
+            }
+            card.triggers.clone()
+        };
+
+        for action_trigger in &triggers {
+            if &action_trigger.trigger_type == trigger_type
+                && action_trigger.condition_met(card_arc).await
+            {
+                actions.push(Arc::new(CardActionWrapper {
+                    card: Arc::clone(card_arc),
+                    action: action_trigger.action.clone(),
+                    target: None,
+                }));
+            }
+        }
+
+        actions
+    }
+
     pub async fn collect_manual_actions_old(
         &self,
         card_arc: Arc<Mutex<Card>>,
@@ -276,7 +736,7 @@ impl Card {
         target: Option<EffectTarget>,
         trigger_id: String,
         game: Arc<Mutex<Game>>,
-    ) -> (Vec<Arc<dyn Action + Send + Sync>>, bool) {
+    ) -> Result<(Vec<Arc<dyn Action + Send + Sync>>, bool), GameError> {
         let mut actions: Vec<Arc<dyn Action + Send + Sync>> = Vec::new();
         let mut requires_tap = false;
 
@@ -284,7 +744,7 @@ impl Card {
         for action_trigger in &triggers {
             match &action_trigger.trigger_type {
                 ActionTriggerType::AbilityWithinPhases(
-                    _,
+                    ability,
                     mana_requirements,
                     allowed_phases,
                     tap_required,
@@ -292,18 +752,47 @@ impl Card {
                     if trigger_id != action_trigger.id {
                         continue;
                     }
-                    println!("we were triggered");
-                    let in_phases = allowed_phases.is_none()
-                        || allowed_phases.as_ref().unwrap().contains(&turn_phase);
+                    debug!("we were triggered");
+                    if let Some(allowed) = allowed_phases {
+                        if !allowed.contains(&turn_phase) {
+                            return Err(GameError::WrongPhase {
+                                ability: ability.clone(),
+                                phase: turn_phase,
+                                allowed: allowed.clone(),
+                            });
+                        }
+                    }
 
-                    println!("getting requirements game: {:?} card: {:?}", game, card_arc);
+                    debug!("getting requirements game: {:?} card: {:?}", game, card_arc);
                     let meets_requirements =
                         (action_trigger.requirements)(Arc::clone(&game), Arc::clone(&card_arc))
                             .await;
 
-                    println!("here is where it matters: {}", meets_requirements);
-
-                    if in_phases && meets_requirements {
+                    debug!("here is where it matters: {}", meets_requirements);
+
+                    let legal_target = match &target {
+                        Some(EffectTarget::Card(target_card))
+                            if action_trigger.card_required_target != CardRequiredTarget::None =>
+                        {
+                            let controller = card_arc.lock().await.controller();
+                            let target = target_card.lock().await;
+                            let owned = match (&controller, &target.controller()) {
+                                (Some(controller), Some(target_controller)) => {
+                                    Arc::ptr_eq(controller, target_controller)
+                                }
+                                _ => false,
+                            };
+                            game.lock().await.card_target_matches(
+                                &action_trigger.card_required_target,
+                                target_card,
+                                &target,
+                                owned,
+                            )
+                        }
+                        _ => true,
+                    };
+
+                    if meets_requirements && legal_target {
                         requires_tap = tap_required.clone();
                         actions.push(Arc::new(CardActionWrapper {
                             card: Arc::clone(&card_arc),
@@ -316,7 +805,7 @@ impl Card {
             }
         }
 
-        (actions, requires_tap)
+        Ok((actions, requires_tap))
     }
 
     pub async fn collect_phase_based_actions(
@@ -326,60 +815,91 @@ impl Card {
     ) -> Vec<Arc<dyn Action + Send + Sync>> {
         let mut phase_based_actions: Vec<Arc<dyn Action + Send + Sync>> = Vec::new();
 
-        let card = card_arc.lock().await;
-
-        let owner = match &card.owner {
-            Some(owner) => Arc::clone(owner),
-            None => return phase_based_actions,
+        let (owner, triggers) = {
+            let card = card_arc.lock().await;
+            if card.phased_out {
+                return phase_based_actions;
+            }
+            match card.controller() {
+                Some(owner) => (owner, card.triggers.clone()),
+                None => return phase_based_actions,
+            }
         };
 
-        for action_trigger in &card.triggers {
-            if let ActionTriggerType::PhaseStarted(trigger_phase, trigger_target) =
-                &action_trigger.trigger_type
-            {
-                let is_owner = Arc::ptr_eq(&turn.current_player, &owner);
-                if trigger_phase.contains(&turn.phase)
-                    && match trigger_target {
-                        super::action::TriggerTarget::Owner => is_owner,
-                        super::action::TriggerTarget::Target => !is_owner,
-                        super::action::TriggerTarget::Any => true,
+        for action_trigger in &triggers {
+            let fired: Option<CardActionWrapper> =
+                if let ActionTriggerType::PhaseStarted(trigger_phase, trigger_target) =
+                    &action_trigger.trigger_type
+                {
+                    let is_owner = Arc::ptr_eq(&turn.current_player, &owner);
+                    if trigger_phase.contains(&turn.phase)
+                        && match trigger_target {
+                            super::action::TriggerTarget::Owner => is_owner,
+                            super::action::TriggerTarget::Target => !is_owner,
+                            super::action::TriggerTarget::Any => true,
+                        }
+                    {
+                        Some(CardActionWrapper {
+                            card: Arc::clone(card_arc),
+                            action: action_trigger.action.clone(),
+                            target: match trigger_target {
+                                action::TriggerTarget::Owner => {
+                                    Some(EffectTarget::Player(Arc::clone(&owner)))
+                                }
+                                action::TriggerTarget::Target => {
+                                    card_arc.lock().await.action_target.clone()
+                                }
+                                action::TriggerTarget::Any => None,
+                            },
+                        })
+                    } else {
+                        None
                     }
+                } else if trigger_type == action_trigger.trigger_type {
+                    Some(CardActionWrapper {
+                        card: Arc::clone(card_arc),
+                        action: action_trigger.action.clone(),
+                        target: None,
+                    })
+                } else if action_trigger.trigger_type == ActionTriggerType::CardPlayedFromHand
+                    && trigger_type != ActionTriggerType::CardDestroyed
                 {
-                    phase_based_actions.push(Arc::new(CardActionWrapper {
+                    // println!("EXECUTING THIS {}", name);
+                    Some(CardActionWrapper {
                         card: Arc::clone(card_arc),
                         action: action_trigger.action.clone(),
-                        target: match trigger_target {
-                            action::TriggerTarget::Owner => {
-                                Some(EffectTarget::Player(Arc::clone(&owner)))
-                            }
-                            action::TriggerTarget::Target => {
-                                card_arc.lock().await.action_target.clone()
-                            }
-                            action::TriggerTarget::Any => None,
-                        },
-                    }));
+                        target: None,
+                    })
+                } else {
+                    None
+                };
+
+            // An intervening if keeps the trigger off the stack entirely
+            if let Some(fired) = fired {
+                if action_trigger.condition_met(card_arc).await {
+                    phase_based_actions.push(Arc::new(fired));
                 }
-            } else if &trigger_type == &action_trigger.trigger_type {
-                phase_based_actions.push(Arc::new(CardActionWrapper {
-                    card: Arc::clone(card_arc),
-                    action: action_trigger.action.clone(),
-                    target: None,
-                }));
-            } else if action_trigger.trigger_type == ActionTriggerType::CardPlayedFromHand
-                && trigger_type != ActionTriggerType::CardDestroyed
-            {
-                // println!("EXECUTING THIS {}", name);
-                phase_based_actions.push(Arc::new(CardActionWrapper {
-                    card: Arc::clone(card_arc),
-                    action: action_trigger.action.clone(),
-                    target: None,
-                }));
             }
         }
 
         phase_based_actions
     }
 
+    // Converted mana cost: every symbol counts once, generic or colored.
+    pub fn mana_value(&self) -> u8 {
+        u8::try_from(self.cost.len()).unwrap_or(u8::MAX)
+    }
+
+    // Symbols of one color in the cost. Generic mana has no color, so asking
+    // for Colorless is always 0.
+    pub fn colored_pips(&self, color: ManaType) -> u8 {
+        if color == ManaType::Colorless {
+            return 0;
+        }
+        let pips = self.cost.iter().filter(|mana| **mana == color).count();
+        u8::try_from(pips).unwrap_or(u8::MAX)
+    }
+
     pub fn format_mana_cost(&self) -> String {
         let mut formatted_mana = String::new();
         let mut colorless_count = 0;
@@ -413,7 +933,7 @@ impl Card {
             return Err("Card already tapped");
         }
 
-        println!("{} was tapped", self.name);
+        debug!("{} was tapped", self.name);
 
         self.tapped = true;
 
@@ -439,6 +959,18 @@ impl Card {
     //     }
     // }
 
+    pub fn view(&self) -> CardView {
+        CardView {
+            name: self.name.clone(),
+            cost: self.cost.clone(),
+            power: self.get_stat_value(StatType::Power),
+            toughness: self.get_stat_value(StatType::Toughness),
+            description: self.description.clone(),
+            tapped: self.tapped,
+        }
+    }
+
+    #[cfg(feature = "cli")]
     pub fn render(&self, width: usize) -> Vec<String> {
         let mut lines = Vec::new();
 
@@ -482,6 +1014,12 @@ impl Card {
         lines
     }
 
+    // The player currently in control of the card. Falls back to the owner for
+    // cards nobody has taken control of.
+    pub fn controller(&self) -> Option<Arc<Mutex<Player>>> {
+        self.controller.clone().or_else(|| self.owner.clone())
+    }
+
     pub(crate) fn untap(&mut self) {
         self.tapped = false;
     }
@@ -490,6 +1028,7 @@ impl Card {
 impl Stats for Card {
     fn add_stat(&mut self, id: String, stat: Stat) {
         self.stats.add_stat(id, stat);
+        #[cfg(feature = "cli")]
         println!("{}", self.render(30).join("\n"));
     }
 
@@ -577,3 +1116,79 @@ pub mod card {
     pub(crate) use create_creature_card;
     pub(crate) use create_multiple_cards;
 }
+
+mod test {
+    use std::sync::Arc;
+
+    use tokio::sync::Mutex;
+
+    use crate::game::{
+        card::{Card, CardPhase, CardType},
+        decks::green::create_leaf_crowned_visionary,
+        error::GameError,
+        mana::ManaType,
+        player::Player,
+        turn::TurnPhase,
+        Game,
+    };
+
+    #[test]
+    fn test_mana_value_and_colored_pips() {
+        // {2}{G}{G}
+        let card = Card::new(
+            "Test",
+            "",
+            vec![],
+            CardPhase::Ready,
+            CardType::Creature,
+            vec![],
+            vec![
+                ManaType::Colorless,
+                ManaType::Colorless,
+                ManaType::Green,
+                ManaType::Green,
+            ],
+        );
+
+        assert_eq!(card.mana_value(), 4);
+        assert_eq!(card.colored_pips(ManaType::Green), 2);
+        assert_eq!(card.colored_pips(ManaType::Blue), 0);
+        assert_eq!(card.colored_pips(ManaType::Colorless), 0);
+    }
+
+    #[tokio::test]
+    async fn test_attacking_during_main_is_the_wrong_phase() {
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new(
+                "test",
+                20,
+                vec![create_leaf_crowned_visionary()],
+            ))
+            .await;
+        game.start_turn(0).await;
+        game.current_turn.as_mut().unwrap().phase = TurnPhase::Main;
+
+        let card = {
+            let mut player = player.lock().await;
+            player.draw_card();
+            let card = player.cards_in_hand.pop().unwrap();
+            player.cards_in_play.push(card.clone());
+            card
+        };
+        let attack_id = card.lock().await.triggers[0].id.clone();
+
+        let game = Arc::new(Mutex::new(game));
+        let result =
+            Card::collect_manual_actions(card.clone(), TurnPhase::Main, None, attack_id, game)
+                .await;
+        assert_eq!(
+            result.unwrap_err(),
+            GameError::WrongPhase {
+                ability: "Attack".to_string(),
+                phase: TurnPhase::Main,
+                allowed: vec![TurnPhase::DeclareAttackers],
+            }
+        );
+    }
+}
@@ -8,12 +8,23 @@ use super::{
 use crate::game::stat::{Stat, StatType, Stats};
 use std::sync::Arc;
 use tokio::sync::Mutex;
+use tracing::{debug, warn};
 use ulid::Ulid;
 
+// A blocker and the attacker it's blocking
+pub type Block = (Arc<Mutex<Card>>, Arc<Mutex<Card>>);
+// An attacker and its blockers, in the order it deals damage to them
+pub type DamageOrder = (Arc<Mutex<Card>>, Vec<Arc<Mutex<Card>>>);
+
 #[derive(Debug, Default)]
 pub struct Combat {
     pub attackers: Vec<(Arc<Mutex<Card>>, EffectTarget)>, // Attacking creatures and their targets
-    pub blockers: Vec<(Arc<Mutex<Card>>, Arc<Mutex<Card>>)>, // Blockers and the creatures they are blocking
+    pub blockers: Vec<Block>, // Blockers and the creatures they are blocking
+    pub damage_dealt: Vec<(Arc<Mutex<Card>>, EffectTarget, i8)>, // Source, what it hit and how hard
+    // The order an attacker deals damage to its blockers in, once someone picked it
+    pub damage_order: Vec<DamageOrder>,
+    // Attackers whose damage order is still being picked
+    pub awaiting_order: Vec<Arc<Mutex<Card>>>,
 }
 
 impl Combat {
@@ -21,8 +32,107 @@ impl Combat {
         Self::default()
     }
 
+    /// Whether the card has been declared as an attacker
+    pub fn is_attacking(&self, card: &Arc<Mutex<Card>>) -> bool {
+        self.attackers
+            .iter()
+            .any(|(attacker, _)| Arc::ptr_eq(attacker, card))
+    }
+
+    /// Whether the card has been declared as a blocker
+    pub fn is_blocking(&self, card: &Arc<Mutex<Card>>) -> bool {
+        self.blockers
+            .iter()
+            .any(|(blocker, _)| Arc::ptr_eq(blocker, card))
+    }
+
+    /// Whether the card is attacking or blocking
+    pub fn is_in_combat(&self, card: &Arc<Mutex<Card>>) -> bool {
+        self.is_attacking(card) || self.is_blocking(card)
+    }
+
+    /// Remove every creature from combat
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Take a card out of combat, whichever side it was on
+    pub fn remove(&mut self, card: &Arc<Mutex<Card>>) {
+        self.attackers
+            .retain(|(attacker, _)| !Arc::ptr_eq(attacker, card));
+        self.blockers.retain(|(blocker, attacker)| {
+            !Arc::ptr_eq(blocker, card) && !Arc::ptr_eq(attacker, card)
+        });
+        self.damage_order
+            .retain(|(attacker, _)| !Arc::ptr_eq(attacker, card));
+        for (_, order) in self.damage_order.iter_mut() {
+            order.retain(|blocker| !Arc::ptr_eq(blocker, card));
+        }
+        self.awaiting_order
+            .retain(|attacker| !Arc::ptr_eq(attacker, card));
+    }
+
+    /// Everything blocking the attacker, in the order they were declared
+    pub fn blockers_of(&self, attacker: &Arc<Mutex<Card>>) -> Vec<Arc<Mutex<Card>>> {
+        self.blockers
+            .iter()
+            .filter(|(_, blocked)| Arc::ptr_eq(blocked, attacker))
+            .map(|(blocker, _)| Arc::clone(blocker))
+            .collect()
+    }
+
+    /// Attackers with more than one blocker and no damage order picked yet
+    pub fn needing_damage_order(&self) -> Vec<Arc<Mutex<Card>>> {
+        self.attackers
+            .iter()
+            .map(|(attacker, _)| attacker)
+            .filter(|attacker| self.blockers_of(attacker).len() > 1)
+            .filter(|attacker| {
+                !self
+                    .damage_order
+                    .iter()
+                    .any(|(ordered, _)| Arc::ptr_eq(ordered, attacker))
+            })
+            .map(Arc::clone)
+            .collect()
+    }
+
+    /// Sets the order the attacker's damage goes to its blockers in
+    pub fn set_damage_order(&mut self, attacker: &Arc<Mutex<Card>>, order: Vec<Arc<Mutex<Card>>>) {
+        self.damage_order
+            .retain(|(ordered, _)| !Arc::ptr_eq(ordered, attacker));
+        self.damage_order.push((Arc::clone(attacker), order));
+        self.awaiting_order
+            .retain(|waiting| !Arc::ptr_eq(waiting, attacker));
+    }
+
+    /// The attacker's blockers in damage order, falling back to the order
+    /// they were declared in for anything that wasn't ordered
+    fn blockers_in_damage_order(&self, attacker: &Arc<Mutex<Card>>) -> Vec<Arc<Mutex<Card>>> {
+        let mut blockers = self.blockers_of(attacker);
+        let Some((_, order)) = self
+            .damage_order
+            .iter()
+            .find(|(ordered, _)| Arc::ptr_eq(ordered, attacker))
+        else {
+            return blockers;
+        };
+
+        let mut ordered: Vec<Arc<Mutex<Card>>> = order
+            .iter()
+            .filter(|blocker| blockers.iter().any(|b| Arc::ptr_eq(b, blocker)))
+            .map(Arc::clone)
+            .collect();
+        blockers.retain(|blocker| !ordered.iter().any(|o| Arc::ptr_eq(o, blocker)));
+        ordered.extend(blockers);
+        ordered
+    }
+
     /// Declare an attacker
     pub async fn declare_attacker(&mut self, card: Arc<Mutex<Card>>, target: EffectTarget) {
+        if card.lock().await.phased_out {
+            return;
+        }
         self.attackers.push((card, target));
     }
 
@@ -32,119 +142,177 @@ impl Combat {
         blocker_card: Arc<Mutex<Card>>,
         attacker_card: Arc<Mutex<Card>>,
     ) {
+        if blocker_card.lock().await.phased_out {
+            return;
+        }
         self.blockers.push((blocker_card, attacker_card));
     }
 
     pub async fn resolve_combat(&mut self) -> Vec<Arc<Mutex<Card>>> {
-        println!("Resolving combat damage.");
-        let mut destroyed_cards = Vec::new();
+        debug!("Resolving combat damage.");
+        let mut destroyed_cards: Vec<Arc<Mutex<Card>>> = Vec::new();
+        let mut damage_dealt = Vec::new();
 
-        // First, resolve damage to blockers
+        // First, every blocker hits the attacker it's blocking
         for (blocking_card_arc, attacker_card_arc) in &self.blockers {
-            let attacker_damage = {
-                let attacker_card = attacker_card_arc.lock().await;
-                attacker_card.get_stat_value(StatType::Power)
-            };
-
-            let blocker_toughness = {
+            let blocker_damage = {
                 let blocker_card = blocking_card_arc.lock().await;
-                blocker_card.get_stat_value(StatType::Toughness)
+                blocker_card.get_stat_value(StatType::Power)
             };
+            let attacker_toughness = {
+                let attacker_card = attacker_card_arc.lock().await;
+                attacker_card.get_stat_value(StatType::Toughness)
+            };
+            let blocker_damage = Self::after_prevention(
+                blocker_damage,
+                &EffectTarget::Card(Arc::clone(attacker_card_arc)),
+            )
+            .await;
+            let mut attacker_card = attacker_card_arc.lock().await;
+            attacker_card.damage_taken += blocker_damage;
+            damage_dealt.push((
+                Arc::clone(blocking_card_arc),
+                EffectTarget::Card(Arc::clone(attacker_card_arc)),
+                blocker_damage,
+            ));
+            debug!(
+                "Blocker {} deals {} damage to attacker {}",
+                blocking_card_arc.lock().await.name,
+                blocker_damage,
+                attacker_card.name
+            );
 
-            // Apply damage to the blocker
-            {
-                let mut blocker_card = blocking_card_arc.lock().await;
-                blocker_card.damage_taken += attacker_damage;
-                println!(
-                    "Attacker {} deals {} damage to blocker {}",
-                    attacker_card_arc.lock().await.name,
-                    attacker_damage,
-                    blocker_card.name
-                );
-
-                // Check if blocker is destroyed
-                if blocker_card.damage_taken >= blocker_toughness {
-                    println!("Blocker {} is destroyed!", blocker_card.name);
-                    destroyed_cards.push(Arc::clone(&blocking_card_arc));
-                }
-            }
-
-            // Apply damage to the attacker (from the blocker)
+            // Check if attacker is destroyed
+            if attacker_card.damage_taken >= attacker_toughness
+                && !destroyed_cards
+                    .iter()
+                    .any(|card| Arc::ptr_eq(card, attacker_card_arc))
             {
-                let blocker_damage = {
-                    let blocker_card = blocking_card_arc.lock().await;
-                    blocker_card.get_stat_value(StatType::Power)
-                };
-                let attacker_toughness = {
-                    let attacker_card = attacker_card_arc.lock().await;
-                    attacker_card.get_stat_value(StatType::Toughness)
-                };
-                let mut attacker_card = attacker_card_arc.lock().await;
-                attacker_card.damage_taken += blocker_damage;
-                println!(
-                    "Blocker {} deals {} damage to attacker {}",
-                    blocking_card_arc.lock().await.name,
-                    blocker_damage,
-                    attacker_card.name
-                );
-
-                // Check if attacker is destroyed
-                if attacker_card.damage_taken >= attacker_toughness {
-                    println!("Attacker {} is destroyed!", attacker_card.name);
-                    destroyed_cards.push(Arc::clone(attacker_card_arc));
-                }
+                debug!("Attacker {} is destroyed!", attacker_card.name);
+                destroyed_cards.push(Arc::clone(attacker_card_arc));
             }
         }
 
-        // Then, resolve unblocked attackers and handle Trample
+        // Then each attacker deals its damage, to its blockers in damage order
+        // or to whatever it's attacking if nothing blocked it
         for (attacker_card_arc, target) in &self.attackers {
-            // Check if this attacker was blocked
-            let mut is_blocked = false;
-            let mut total_blocker_toughness = 0;
-
-            for (blocking_card_arc, blocked_attacker_card_arc) in &self.blockers {
-                if Arc::ptr_eq(attacker_card_arc, blocked_attacker_card_arc) {
-                    is_blocked = true;
-                    let blocker_toughness = {
-                        let blocker_card = blocking_card_arc.lock().await;
-                        blocker_card.get_stat_value(StatType::Toughness)
-                    };
-                    total_blocker_toughness += blocker_toughness;
-                }
-            }
-
             let attacker_damage = {
                 let attacker_card = attacker_card_arc.lock().await;
                 attacker_card.get_stat_value(StatType::Power)
             };
 
-            // Handle unblocked attackers
-            if !is_blocked {
+            let blockers = self.blockers_in_damage_order(attacker_card_arc);
+            if blockers.is_empty() {
+                let attacker_damage = Self::after_prevention(attacker_damage, target).await;
                 self.apply_damage_to_target(attacker_damage, target, attacker_card_arc)
                     .await;
-            } else {
-                // Handle blocked attackers and check for Trample
-                let has_trample = {
-                    let attacker_card = attacker_card_arc.lock().await;
-                    attacker_card.get_stat_value(StatType::Trample) > 0
+                damage_dealt.push((
+                    Arc::clone(attacker_card_arc),
+                    target.clone(),
+                    attacker_damage,
+                ));
+                continue;
+            }
+
+            let has_trample = {
+                let attacker_card = attacker_card_arc.lock().await;
+                attacker_card.get_stat_value(StatType::Trample) > 0
+            };
+
+            // Each blocker needs lethal damage before the next one gets any.
+            // The last takes whatever's left unless it can trample over.
+            let mut remaining = attacker_damage.max(0);
+            for (index, blocking_card_arc) in blockers.iter().enumerate() {
+                let lethal = {
+                    let blocker_card = blocking_card_arc.lock().await;
+                    (blocker_card.get_stat_value(StatType::Toughness) - blocker_card.damage_taken)
+                        .max(0)
+                };
+                let is_last = index == blockers.len() - 1;
+                let assigned = if is_last && !has_trample {
+                    remaining
+                } else {
+                    remaining.min(lethal)
                 };
+                remaining -= assigned;
 
-                if has_trample {
-                    let excess_damage = attacker_damage.saturating_sub(total_blocker_toughness);
-                    if excess_damage > 0 {
-                        self.apply_damage_to_target(excess_damage, target, attacker_card_arc)
-                            .await;
-                    }
+                let assigned = Self::after_prevention(
+                    assigned,
+                    &EffectTarget::Card(Arc::clone(blocking_card_arc)),
+                )
+                .await;
+                let mut blocker_card = blocking_card_arc.lock().await;
+                blocker_card.damage_taken += assigned;
+                damage_dealt.push((
+                    Arc::clone(attacker_card_arc),
+                    EffectTarget::Card(Arc::clone(blocking_card_arc)),
+                    assigned,
+                ));
+                debug!(
+                    "Attacker {} deals {} damage to blocker {}",
+                    attacker_card_arc.lock().await.name,
+                    assigned,
+                    blocker_card.name
+                );
+
+                // Check if blocker is destroyed
+                if blocker_card.damage_taken >= blocker_card.get_stat_value(StatType::Toughness) {
+                    debug!("Blocker {} is destroyed!", blocker_card.name);
+                    destroyed_cards.push(Arc::clone(blocking_card_arc));
+                }
+            }
+
+            if has_trample {
+                let excess_damage = Self::after_prevention(remaining, target).await;
+                if excess_damage > 0 {
+                    self.apply_damage_to_target(excess_damage, target, attacker_card_arc)
+                        .await;
+                    damage_dealt.push((
+                        Arc::clone(attacker_card_arc),
+                        target.clone(),
+                        excess_damage,
+                    ));
                 }
             }
         }
 
         self.attackers.clear();
         self.blockers.clear();
+        self.damage_order.clear();
+        self.awaiting_order.clear();
+        self.damage_dealt.extend(
+            damage_dealt
+                .into_iter()
+                .filter(|(_, _, amount)| *amount > 0),
+        );
 
         destroyed_cards
     }
 
+    /// How much of `damage` gets through, using up the target's prevention
+    /// shield if it has one
+    async fn after_prevention(damage: i8, target: &EffectTarget) -> i8 {
+        if damage <= 0 {
+            return damage;
+        }
+        let shielded = match target {
+            EffectTarget::Player(player_arc) => {
+                let mut player = player_arc.lock().await;
+                std::mem::take(&mut player.prevent_next_combat_damage)
+            }
+            EffectTarget::Card(card_arc) => {
+                let mut card = card_arc.lock().await;
+                std::mem::take(&mut card.prevent_next_combat_damage)
+            }
+            EffectTarget::Cards(_) => false,
+        };
+        if shielded {
+            0
+        } else {
+            damage
+        }
+    }
+
     async fn apply_damage_to_target(
         &self,
         damage: i8,
@@ -164,7 +332,7 @@ impl Combat {
                 {
                     attacker_card_arc.lock().await.damage_dealt_to_players = damage.clone();
                 }
-                println!(
+                debug!(
                     "Attacker {} deals {} damage to player {}",
                     attacker_card_arc.lock().await.name,
                     damage,
@@ -177,7 +345,7 @@ impl Combat {
                 let mut card = card_arc.lock().await;
                 let toughness = card.get_stat_value(StatType::Toughness);
                 card.damage_taken += damage;
-                println!(
+                debug!(
                     "Attacker {} deals {} damage to card {}",
                     attacker_card_arc.lock().await.name,
                     damage,
@@ -185,12 +353,16 @@ impl Combat {
                 );
 
                 if card.damage_taken >= toughness {
-                    println!("Card {} is destroyed!", card.name);
+                    debug!("Card {} is destroyed!", card.name);
                     Some(Arc::clone(card_arc))
                 } else {
                     None
                 }
             }
+            EffectTarget::Cards(_) => {
+                warn!("Combat damage goes to one target at a time");
+                None
+            }
         }
     }
 }
@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use super::{action::CardRequiredTarget, mana::ManaType};
+
+// One part of what a player pays to activate an ability. An ability can
+// combine several, e.g. "pay 2 life and tap this".
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub enum Cost {
+    Mana(Vec<ManaType>),
+    Life(i8),
+    // Tap the card the ability is on
+    Tap,
+    // Tap an untapped card you control, other than this one
+    TapOther(CardRequiredTarget),
+    Sacrifice(CardRequiredTarget),
+    // Discard this many cards at random
+    Discard(u8),
+}
+
+impl Cost {
+    // All the mana across a list of costs, in the order it was listed.
+    pub fn total_mana(costs: &[Cost]) -> Vec<ManaType> {
+        costs
+            .iter()
+            .flat_map(|cost| match cost {
+                Cost::Mana(mana) => mana.clone(),
+                _ => vec![],
+            })
+            .collect()
+    }
+}
@@ -1,14 +1,19 @@
 use crate::game::{
     action::{
-        generate_mana::GenerateManaAction, ActionTriggerType, ApplyDynamicEffectToCard,
-        ApplyEffectToCardBasedOnTotalCardType, AsyncClosureAction, AsyncClosureWithCardAction,
-        CardActionTrigger, CardRequiredTarget, CardTargetTeam, DeclareAttackerAction,
-        DeclareBlockerAction, PlayerActionTarget, TriggerTarget,
+        discard::{Chooser, DiscardAction},
+        draw::SkipDrawStepAction,
+        generate_mana::GenerateManaAction,
+        ActionTriggerType, ApplyDynamicEffectToCard, ApplyEffectToCardBasedOnTotalCardType,
+        AsyncClosureAction, AsyncClosureWithCardAction, CardActionTarget, CardActionTrigger,
+        CardRequiredTarget, CardTargetTeam, CastOptionalAdditionalAbility, DeclareAttackerAction,
+        DeclareBlockerAction, DrainOpponentsAction, DrawCardCardAction, PlayerActionTarget,
+        TriggerTarget,
     },
     card::{
         card::{create_creature_card, create_multiple_cards},
         Card, CardPhase, CardType, CreatureType,
     },
+    cost::Cost,
     decks::duplicate_card,
     effects::{
         DynamicStatModifierEffect, Effect, EffectID, EffectTarget, ExpireContract, LifeLinkAction,
@@ -17,7 +22,7 @@ use crate::game::{
     player::Player,
     stat::{Stat, StatType, Stats},
     turn::TurnPhase,
-    Game,
+    ActionType, Game,
 };
 use std::{f32::consts::E, future::Future, mem::zeroed, pin::Pin, sync::Arc};
 
@@ -65,6 +70,86 @@ pub fn create_vengful_spirit() -> Card {
     )
 }
 
+pub fn create_zulaport_cutthroat() -> Card {
+    create_creature_card!(
+        "Zulaport Cutthroat",
+        CreatureType::None,
+        "Whenever Zulaport Cutthroat or another creature you control dies, each opponent loses 1 life and you gain 1 life.",
+        1, // Damage
+        1, // Defense
+        [ManaType::Black],
+        [],
+        CardActionTrigger::new(
+            ActionTriggerType::CreatureDied(TriggerTarget::Owner),
+            CardRequiredTarget::None,
+            Arc::new(DrainOpponentsAction { amount: 1 })
+        )
+    )
+}
+
+pub fn create_bloodsworn_scholar() -> Card {
+    create_creature_card!(
+        "Bloodsworn Scholar",
+        CreatureType::None,
+        "Pay 2 life, {T}: Draw a card.",
+        1, // Damage
+        1, // Defense
+        [ManaType::Black],
+        [],
+        CardActionTrigger::new(
+            ActionTriggerType::AbilityWithinPhases(
+                "Pay 2 life, {T}: Draw a card".to_string(),
+                vec![],
+                None,
+                false
+            ),
+            CardRequiredTarget::None,
+            Arc::new(CastOptionalAdditionalAbility {
+                action_type: ActionType::Tap,
+                mana: vec![],
+                costs: vec![Cost::Life(2), Cost::Tap],
+                target: CardRequiredTarget::None,
+                description: "Pay 2 life and tap Bloodsworn Scholar to draw a card".to_string(),
+                ability: Arc::new(|_| Arc::new(DrawCardCardAction::one(
+                    CardActionTarget::SelfOwner
+                ))),
+            })
+        )
+    )
+}
+
+pub fn create_stinkweed_imp() -> Card {
+    let mut card = create_creature_card!(
+        "Stinkweed Imp",
+        CreatureType::None,
+        "Flying. Dredge 5 (If you would draw a card, you may mill 5 cards instead. If you do, return this card from your graveyard to your hand.)",
+        1, // Damage
+        2, // Defense
+        [ManaType::Black, ManaType::Colorless, ManaType::Colorless],
+        [StatType::Flying]
+    );
+    card.stats
+        .add_stat(Ulid::new().to_string(), Stat::new(StatType::Dredge, 5));
+    card
+}
+
+pub fn create_hollow_ascetic() -> Card {
+    create_creature_card!(
+        "Hollow Ascetic",
+        CreatureType::None,
+        "Skip your draw step.",
+        3,
+        3,
+        [ManaType::Black, ManaType::Colorless],
+        [],
+        CardActionTrigger::new(
+            ActionTriggerType::CardPlayedFromHand,
+            CardRequiredTarget::None,
+            Arc::new(SkipDrawStepAction { instead: None })
+        )
+    )
+}
+
 pub fn create_hydra() -> Card {
     create_creature_card!(
         "Voracious Hydra",
@@ -83,7 +168,7 @@ pub fn create_hydra() -> Card {
                         Box::pin(async move {
                             let owner = {
                                 let card = card_arc.lock().await;
-                                card.owner.clone()
+                                card.controller()
                             };
 
                             if let Some(owner_arc) = owner {
@@ -184,6 +269,25 @@ pub fn create_blanchwood_armor() -> Card {
     )
 }
 
+pub fn create_mind_rot() -> Card {
+    Card::new(
+        "Mind Rot",
+        "Target player discards two cards.",
+        vec![CardActionTrigger::new(
+            ActionTriggerType::CardPlayedFromHand,
+            CardRequiredTarget::AnyPlayer,
+            Arc::new(DiscardAction {
+                count: 2,
+                chooser: Chooser::Owner,
+            }),
+        )],
+        CardPhase::Ready,
+        CardType::Sorcery,
+        vec![],
+        vec![ManaType::Black, ManaType::Colorless, ManaType::Colorless],
+    )
+}
+
 pub fn create_black_deck() -> Vec<Card> {
     let mut deck: Vec<Card> = vec![];
     deck.append(&mut duplicate_card(create_blanchwood_armor(), 4));
@@ -193,20 +297,41 @@ pub fn create_black_deck() -> Vec<Card> {
     deck
 }
 
+pub fn create_black_sideboard() -> Vec<Card> {
+    let mut sideboard: Vec<Card> = vec![];
+    sideboard.append(&mut duplicate_card(create_vengful_spirit(), 3));
+    sideboard.append(&mut duplicate_card(create_zulaport_cutthroat(), 2));
+    sideboard.append(&mut duplicate_card(create_bloodsworn_scholar(), 2));
+    sideboard.append(&mut duplicate_card(create_stinkweed_imp(), 2));
+    sideboard.append(&mut duplicate_card(create_mind_rot(), 2));
+    sideboard.append(&mut duplicate_card(create_hollow_ascetic(), 2));
+
+    sideboard
+}
+
 mod test {
     use std::sync::Arc;
 
     use tokio::sync::{Mutex, RwLock};
 
     use crate::game::{
-        card::Card,
+        action::{discard::discard_card, draw::DrawSource, ActionTriggerType},
+        card::{Card, CardPhase},
+        cost::Cost,
         decks::{
-            black::{create_hydra, create_swamp},
+            black::{
+                create_bloodsworn_scholar, create_hollow_ascetic, create_hydra, create_mind_rot,
+                create_stinkweed_imp, create_swamp, create_vengful_spirit,
+                create_zulaport_cutthroat,
+            },
             Deck,
         },
         effects::EffectTarget,
         mana,
         player::Player,
+        stat::{StatType, Stats},
+        turn::TurnPhase,
+        zone::Zone,
         Game,
     };
 
@@ -258,4 +383,275 @@ mod test {
 
         // ga.lock().await.advance_turn().await;
     }
+
+    #[tokio::test]
+    async fn test_sacrificed_creature_drains_each_opponent() {
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new(
+                "test",
+                20,
+                vec![create_vengful_spirit(), create_zulaport_cutthroat()],
+            ))
+            .await;
+        let opponent = game.add_player(Player::new("opponent", 20, vec![])).await;
+        game.start_turn(0).await;
+
+        let mut in_play = vec![];
+        for _ in 0..2 {
+            let mut player_lock = player.lock().await;
+            player_lock.draw_card();
+            let card = player_lock.cards_in_hand.pop().unwrap();
+            card.lock().await.controller = Some(player.clone());
+            player_lock.cards_in_play.push(card.clone());
+            in_play.push(card);
+        }
+        let spirit = &in_play[1];
+
+        game.sacrifice_card(spirit).await.unwrap();
+
+        let player = player.lock().await;
+        assert_eq!(player.cards_in_play.len(), 1);
+        assert_eq!(player.get_stat_value(StatType::Health), 21);
+        assert_eq!(opponent.lock().await.get_stat_value(StatType::Health), 19);
+    }
+
+    #[tokio::test]
+    async fn test_ability_costing_life_and_tap_draws_a_card() {
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new(
+                "test",
+                20,
+                vec![create_swamp(), create_bloodsworn_scholar()],
+            ))
+            .await;
+        game.start_turn(0).await;
+
+        let scholar = {
+            let mut player_lock = player.lock().await;
+            player_lock.draw_card();
+            let scholar = player_lock.cards_in_hand.pop().unwrap();
+            player_lock.cards_in_play.push(scholar.clone());
+            scholar
+        };
+        let trigger_id = {
+            let mut scholar = scholar.lock().await;
+            scholar.controller = Some(player.clone());
+            scholar.current_phase = CardPhase::Ready;
+            scholar
+                .triggers
+                .iter()
+                .find(|trigger| {
+                    matches!(
+                        &trigger.trigger_type,
+                        ActionTriggerType::AbilityWithinPhases(description, _, _, _)
+                            if description.starts_with("Pay 2 life")
+                    )
+                })
+                .unwrap()
+                .id
+                .clone()
+        };
+
+        game.activate_card_action(&player, 0, None, trigger_id)
+            .await
+            .unwrap();
+        let ability_id = game.abilities.keys().next().unwrap().clone();
+
+        let ga = Arc::new(Mutex::new(game));
+        Game::respond_player_ability(ga.clone(), &player, ability_id, true, None)
+            .await
+            .unwrap();
+
+        {
+            let player = player.lock().await;
+            assert_eq!(player.get_stat_value(StatType::Health), 18);
+            assert_eq!(player.cards_in_hand.len(), 1);
+        }
+        assert!(scholar.lock().await.tapped);
+
+        // Already tapped, so it can't be paid for a second time
+        let costs = [Cost::Life(2), Cost::Tap];
+        let game = ga.lock().await;
+        assert!(game.check_costs(&scholar, &player, &costs).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_dredging_instead_of_drawing() {
+        let mut game = Game::new();
+        let mut deck: Vec<Card> = (0..6).map(|_| create_swamp()).collect();
+        deck.push(create_stinkweed_imp());
+        let player = game.add_player(Player::new("test", 20, deck)).await;
+        game.start_turn(0).await;
+
+        let imp = player.lock().await.draw_card().unwrap();
+        game.move_card(&imp, Zone::Hand, Zone::Graveyard)
+            .await
+            .unwrap();
+
+        game.player_draws(&player, 1, DrawSource::Effect).await;
+        assert!(player.lock().await.cards_in_hand.is_empty());
+        let ability_id = game.abilities.keys().next().unwrap().clone();
+
+        let ga = Arc::new(Mutex::new(game));
+        Game::respond_player_ability(
+            ga.clone(),
+            &player,
+            ability_id,
+            true,
+            Some(EffectTarget::Card(imp.clone())),
+        )
+        .await
+        .unwrap();
+
+        let mut player = player.lock().await;
+        assert_eq!(player.cards_in_hand.len(), 1);
+        assert!(Arc::ptr_eq(&player.cards_in_hand[0], &imp));
+        assert_eq!(player.zone_mut(Zone::Library).len(), 1);
+        assert_eq!(player.zone_mut(Zone::Graveyard).len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_each_draw_waits_on_the_dredge_before_it() {
+        let mut game = Game::new();
+        let mut deck: Vec<Card> = (0..8).map(|_| create_swamp()).collect();
+        deck.push(create_stinkweed_imp());
+        let player = game.add_player(Player::new("test", 20, deck)).await;
+        game.start_turn(0).await;
+
+        // Discarded cards are in the graveyard like any other
+        let imp = player.lock().await.draw_card().unwrap();
+        discard_card(&mut game, &player, imp.clone()).await.unwrap();
+
+        game.player_draws(&player, 2, DrawSource::Effect).await;
+        assert_eq!(game.abilities.len(), 1);
+        assert!(player.lock().await.cards_in_hand.is_empty());
+        let ability_id = game.abilities.keys().next().unwrap().clone();
+
+        let ga = Arc::new(Mutex::new(game));
+        Game::respond_player_ability(
+            ga.clone(),
+            &player,
+            ability_id,
+            true,
+            Some(EffectTarget::Card(imp.clone())),
+        )
+        .await
+        .unwrap();
+
+        // Nothing left to dredge, so the second draw is a normal one
+        assert!(ga.lock().await.abilities.is_empty());
+        let mut player = player.lock().await;
+        assert_eq!(player.cards_in_hand.len(), 2);
+        assert!(Arc::ptr_eq(&player.cards_in_hand[0], &imp));
+        assert_eq!(player.zone_mut(Zone::Library).len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_unanswered_dredge_draws_as_normal() {
+        let mut game = Game::new();
+        let mut deck: Vec<Card> = (0..6).map(|_| create_swamp()).collect();
+        deck.push(create_stinkweed_imp());
+        let player = game.add_player(Player::new("test", 20, deck)).await;
+        game.start_turn(0).await;
+
+        let imp = player.lock().await.draw_card().unwrap();
+        game.move_card(&imp, Zone::Hand, Zone::Graveyard)
+            .await
+            .unwrap();
+        game.player_draws(&player, 1, DrawSource::Effect).await;
+        assert_eq!(game.abilities.len(), 1);
+
+        game.current_turn.as_mut().unwrap().phase = TurnPhase::Main;
+        game.sweep_stale_abilities().await;
+
+        assert!(game.abilities.is_empty());
+        let mut player = player.lock().await;
+        assert_eq!(player.cards_in_hand.len(), 1);
+        assert!(!Arc::ptr_eq(&player.cards_in_hand[0], &imp));
+        assert_eq!(player.zone_mut(Zone::Library).len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_mind_rot_makes_the_target_discard_two() {
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new("test", 20, vec![create_mind_rot()]))
+            .await;
+        let opponent = game
+            .add_player(Player::new(
+                "opponent",
+                20,
+                (0..3).map(|_| create_swamp()).collect(),
+            ))
+            .await;
+        game.start_turn(0).await;
+        player.lock().await.draw_card();
+        for _ in 0..3 {
+            opponent.lock().await.draw_card();
+        }
+        for _ in 0..3 {
+            player
+                .lock()
+                .await
+                .mana_pool
+                .add_mana(mana::ManaType::Black);
+        }
+
+        game.play_card(&player, 0, Some(EffectTarget::Player(opponent.clone())))
+            .await
+            .unwrap();
+        game.resolve_stack().await;
+
+        // The opponent picks each card, one at a time
+        let ga = Arc::new(Mutex::new(game));
+        for _ in 0..2 {
+            let (ability_id, choice) = {
+                let game = ga.lock().await;
+                let ability_id = game.abilities.keys().next().unwrap().clone();
+                let choice = game.ability_choice(&ability_id, 0).unwrap();
+                (ability_id, choice)
+            };
+            Game::respond_player_ability(
+                ga.clone(),
+                &opponent,
+                ability_id,
+                true,
+                Some(EffectTarget::Card(choice)),
+            )
+            .await
+            .unwrap();
+        }
+
+        assert!(ga.lock().await.abilities.is_empty());
+        let mut opponent = opponent.lock().await;
+        assert_eq!(opponent.cards_in_hand.len(), 1);
+        assert_eq!(opponent.zone_mut(Zone::Graveyard).len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_hollow_ascetic_skips_the_draw_step() {
+        let mut game = Game::new();
+        let mut deck: Vec<Card> = (0..4).map(|_| create_swamp()).collect();
+        deck.push(create_hollow_ascetic());
+        let player = game.add_player(Player::new("test", 20, deck)).await;
+        game.start_turn(0).await;
+        {
+            let mut player = player.lock().await;
+            player.draw_card();
+            player.mana_pool.add_mana(mana::ManaType::Black);
+            player.mana_pool.add_mana(mana::ManaType::Black);
+        }
+
+        game.play_card(&player, 0, None).await.unwrap();
+        game.resolve_stack().await;
+        while game.current_phase() != TurnPhase::Draw {
+            game.advance_turn().await;
+        }
+
+        let mut player = player.lock().await;
+        assert!(player.cards_in_hand.is_empty());
+        assert_eq!(player.zone_mut(Zone::Library).len(), 4);
+    }
 }
@@ -2,8 +2,10 @@ use crate::game::{
     action::{
         generate_mana::GenerateManaAction, ActionTriggerType, AsyncClosureAction,
         AsyncClosureWithCardAction, CardActionTarget, CardActionTrigger, CardRequiredTarget,
-        CardTargetTeam, CounterSpellAction, DeclareAttackerAction, DeclareBlockerAction,
-        DrawCardCardAction, PlayerActionTarget, ReturnToHandAction, TriggerTarget,
+        CardTargetTeam, CopySpellAction, CounterSpellAction, DeclareAttackerAction,
+        DeclareBlockerAction, DrawCardCardAction, EndTheTurnAction, ExtraTurnAction,
+        GainControlAction, PlayerActionTarget, ReturnToHandAction, SkipNextDrawAction,
+        SkipTurnAction, StifleAction, TapAndLockTargetAction, TriggerTarget,
     },
     card::{
         card::{create_creature_card, create_multiple_cards},
@@ -50,7 +52,7 @@ fn create_frost_breath() -> Card {
     )
 }
 
-fn create_island() -> Card {
+pub fn create_island() -> Card {
     Card::new(
         "Island",
         "",
@@ -74,6 +76,38 @@ fn create_island() -> Card {
     )
 }
 
+pub fn create_simic_guildgate() -> Card {
+    let add_mana = |mana: ManaType, label: &str| {
+        CardActionTrigger::new(
+            ActionTriggerType::AbilityWithinPhases(
+                format!("Add 1 {{{}}} to your pool", label),
+                vec![],
+                None,
+                true,
+            ),
+            CardRequiredTarget::None,
+            Arc::new(GenerateManaAction {
+                mana_to_add: vec![mana],
+                target: PlayerActionTarget::Owner,
+            }),
+        )
+    };
+
+    Card::new(
+        "Simic Guildgate",
+        "Simic Guildgate enters the battlefield tapped.",
+        vec![
+            add_mana(ManaType::Blue, "U"),
+            add_mana(ManaType::Green, "G"),
+        ],
+        CardPhase::Ready,
+        CardType::DualLand(ManaType::Blue, ManaType::Green),
+        vec![],
+        vec![],
+    )
+    .with_enters_tapped()
+}
+
 pub fn create_counterspell() -> Card {
     Card::new(
         "Counter Spell",
@@ -90,6 +124,38 @@ pub fn create_counterspell() -> Card {
     )
 }
 
+pub fn create_stifle() -> Card {
+    Card::new(
+        "Stifle",
+        "Counter target activated or triggered ability.",
+        vec![CardActionTrigger::new(
+            ActionTriggerType::CardPlayedFromHand,
+            CardRequiredTarget::Ability,
+            Arc::new(StifleAction {}),
+        )],
+        CardPhase::Ready,
+        CardType::Instant,
+        vec![],
+        vec![ManaType::Blue],
+    )
+}
+
+pub fn create_twincast() -> Card {
+    Card::new(
+        "Twincast",
+        "Copy target spell.",
+        vec![CardActionTrigger::new(
+            ActionTriggerType::CardPlayedFromHand,
+            CardRequiredTarget::Spell,
+            Arc::new(CopySpellAction {}),
+        )],
+        CardPhase::Ready,
+        CardType::Instant,
+        vec![],
+        vec![ManaType::Blue, ManaType::Blue],
+    )
+}
+
 pub fn create_divination() -> Card {
     Card::new(
         "Divination",
@@ -125,6 +191,129 @@ pub fn create_unsummon() -> Card {
     )
 }
 
+pub fn create_claustrophobia() -> Card {
+    Card::new(
+        "Claustrophobia",
+        "Enchant creature. When Claustrophobia enters, tap enchanted creature. Enchanted creature doesn't untap during its controller's untap step.",
+        vec![CardActionTrigger::new(
+            ActionTriggerType::Attached,
+            CardRequiredTarget::CardOfType(CardType::Creature, CardTargetTeam::Any),
+            Arc::new(TapAndLockTargetAction {}),
+        )],
+        CardPhase::Ready,
+        CardType::Enchantment,
+        vec![],
+        vec![ManaType::Blue, ManaType::Blue, ManaType::Colorless],
+    )
+}
+
+pub fn create_time_walk() -> Card {
+    Card::new(
+        "Time Walk",
+        "Take an extra turn after this one.",
+        vec![CardActionTrigger::new(
+            ActionTriggerType::CardPlayedFromHand,
+            CardRequiredTarget::None,
+            Arc::new(ExtraTurnAction {
+                target: PlayerActionTarget::Owner,
+            }),
+        )],
+        CardPhase::Ready,
+        CardType::Sorcery,
+        vec![],
+        vec![ManaType::Blue, ManaType::Colorless],
+    )
+}
+
+pub fn create_meditate() -> Card {
+    Card::new(
+        "Meditate",
+        "Draw four cards. You skip your next turn.",
+        vec![
+            CardActionTrigger::new(
+                ActionTriggerType::CardPlayedFromHand,
+                CardRequiredTarget::None,
+                Arc::new(DrawCardCardAction {
+                    target: CardActionTarget::SelfOwner,
+                    count: 4,
+                }),
+            ),
+            CardActionTrigger::new(
+                ActionTriggerType::CardPlayedFromHand,
+                CardRequiredTarget::None,
+                Arc::new(SkipTurnAction {
+                    target: PlayerActionTarget::Owner,
+                }),
+            ),
+        ],
+        CardPhase::Ready,
+        CardType::Instant,
+        vec![],
+        vec![ManaType::Blue, ManaType::Colorless, ManaType::Colorless],
+    )
+}
+
+pub fn create_possession() -> Card {
+    Card::new(
+        "Possession",
+        "Gain control of target creature an opponent controls.",
+        vec![CardActionTrigger::new(
+            ActionTriggerType::CardPlayedFromHand,
+            CardRequiredTarget::CardOfType(CardType::Creature, CardTargetTeam::Opponent),
+            Arc::new(GainControlAction {}),
+        )],
+        CardPhase::Ready,
+        CardType::Sorcery,
+        vec![],
+        vec![
+            ManaType::Blue,
+            ManaType::Blue,
+            ManaType::Colorless,
+            ManaType::Colorless,
+            ManaType::Colorless,
+        ],
+    )
+}
+
+pub fn create_fatigue() -> Card {
+    Card::new(
+        "Fatigue",
+        "Target player skips their next draw step.",
+        vec![CardActionTrigger::new(
+            ActionTriggerType::CardPlayedFromHand,
+            CardRequiredTarget::AnyPlayer,
+            Arc::new(SkipNextDrawAction {}),
+        )],
+        CardPhase::Ready,
+        CardType::Sorcery,
+        vec![],
+        vec![ManaType::Blue, ManaType::Colorless],
+    )
+}
+
+pub fn create_time_stop() -> Card {
+    Card::new(
+        "Time Stop",
+        "End the turn.",
+        vec![CardActionTrigger::new(
+            ActionTriggerType::CardPlayedFromHand,
+            CardRequiredTarget::None,
+            Arc::new(EndTheTurnAction {}),
+        )],
+        CardPhase::Ready,
+        CardType::Instant,
+        vec![],
+        vec![
+            ManaType::Blue,
+            ManaType::Blue,
+            ManaType::Colorless,
+            ManaType::Colorless,
+            ManaType::Colorless,
+            ManaType::Colorless,
+        ],
+    )
+}
+
 pub fn create_blue_deck() -> Vec<Card> {
     let mut deck: Vec<Card> = vec![];
     deck.append(&mut duplicate_card(create_counterspell(), 4));
@@ -136,20 +325,44 @@ pub fn create_blue_deck() -> Vec<Card> {
     deck
 }
 
+pub fn create_blue_sideboard() -> Vec<Card> {
+    let mut sideboard: Vec<Card> = vec![];
+    sideboard.append(&mut duplicate_card(create_claustrophobia(), 2));
+    sideboard.append(&mut duplicate_card(create_counterspell(), 1));
+    sideboard.append(&mut duplicate_card(create_twincast(), 2));
+    sideboard.append(&mut duplicate_card(create_stifle(), 2));
+    sideboard.append(&mut duplicate_card(create_simic_guildgate(), 2));
+    sideboard.append(&mut duplicate_card(create_time_walk(), 1));
+    sideboard.append(&mut duplicate_card(create_meditate(), 1));
+    sideboard.append(&mut duplicate_card(create_possession(), 1));
+    sideboard.append(&mut duplicate_card(create_fatigue(), 1));
+    sideboard.append(&mut duplicate_card(create_time_stop(), 1));
+
+    sideboard
+}
+
 mod test {
     use std::sync::Arc;
 
     use tokio::sync::{Mutex, RwLock};
 
     use crate::game::{
+        action::{CardActionTarget, CardActionWrapper, DrawCardCardAction, StackItemKind},
         card::Card,
         decks::{
-            blue::{create_counterspell, create_island},
+            black::create_hydra,
+            blue::{
+                create_claustrophobia, create_counterspell, create_divination, create_fatigue,
+                create_frost_breath, create_island, create_meditate, create_possession,
+                create_simic_guildgate, create_stifle, create_time_stop, create_time_walk,
+            },
             Deck,
         },
         effects::EffectTarget,
-        mana,
+        mana::{self, ManaType},
         player::Player,
+        turn::TurnPhase,
+        zone::Zone,
         Game,
     };
 
@@ -201,4 +414,504 @@ mod test {
 
         // ga.lock().await.advance_turn().await;
     }
+
+    #[tokio::test]
+    async fn test_claustrophobia_keeps_creature_tapped() {
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new(
+                "test",
+                20,
+                vec![create_claustrophobia(), create_hydra()],
+            ))
+            .await;
+
+        let hydra = player.lock().await.draw_card().unwrap();
+        let claustrophobia = player.lock().await.draw_card().unwrap();
+        game.start_turn(0).await;
+
+        {
+            let mut player = player.lock().await;
+            let mut cards: Vec<Arc<Mutex<Card>>> = player.cards_in_hand.drain(..).collect();
+            player.cards_in_play.append(&mut cards);
+        }
+
+        game.attach_card_action(&player, 1, Some(EffectTarget::Card(hydra.clone())))
+            .await
+            .unwrap();
+        assert!(hydra.lock().await.tapped);
+
+        // Two full turns, each going through an untap step
+        for _ in 0..24 {
+            game.advance_turn().await;
+        }
+        assert!(hydra.lock().await.tapped);
+
+        game.destroy_card(&claustrophobia).await.unwrap();
+        for _ in 0..12 {
+            game.advance_turn().await;
+        }
+        assert!(!hydra.lock().await.tapped);
+    }
+
+    #[tokio::test]
+    async fn test_counterspell_cannot_target_an_ability() {
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new(
+                "test",
+                20,
+                vec![create_island(), create_counterspell(), create_hydra()],
+            ))
+            .await;
+        game.start_turn(0).await;
+        let hydra = player.lock().await.draw_card().unwrap();
+        player.lock().await.cards_in_hand.pop();
+        player.lock().await.cards_in_play.push(hydra.clone());
+        player.lock().await.draw_card();
+        for _ in 0..2 {
+            player.lock().await.mana_pool.add_mana(ManaType::Blue);
+        }
+
+        game.add_to_stack_as(
+            StackItemKind::Ability,
+            Arc::new(CardActionWrapper {
+                action: Arc::new(DrawCardCardAction::one(CardActionTarget::SelfOwner)),
+                card: hydra.clone(),
+                target: None,
+            }),
+        );
+        let stack = game.stack_view().await;
+        assert_eq!(stack.len(), 1);
+        assert_eq!(stack[0].kind, StackItemKind::Ability);
+
+        let err = game
+            .play_card(&player, 0, Some(EffectTarget::Card(hydra.clone())))
+            .await
+            .unwrap_err();
+        assert_eq!(err, "Counter Spell can only target a spell");
+
+        // The ability still resolves
+        game.resolve_stack().await;
+        assert_eq!(player.lock().await.cards_in_hand.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_stifled_trigger_never_happens() {
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new(
+                "test",
+                20,
+                vec![create_island(), create_stifle(), create_hydra()],
+            ))
+            .await;
+        game.start_turn(0).await;
+        let hydra = player.lock().await.draw_card().unwrap();
+        player.lock().await.cards_in_hand.pop();
+        player.lock().await.cards_in_play.push(hydra.clone());
+        player.lock().await.draw_card();
+        player.lock().await.mana_pool.add_mana(ManaType::Blue);
+
+        game.add_to_stack(Arc::new(CardActionWrapper {
+            action: Arc::new(DrawCardCardAction::one(CardActionTarget::SelfOwner)),
+            card: hydra.clone(),
+            target: None,
+        }));
+        assert_eq!(game.stack_view().await[0].kind, StackItemKind::Trigger);
+
+        game.play_card(&player, 0, Some(EffectTarget::Card(hydra.clone())))
+            .await
+            .unwrap();
+        game.resolve_stack().await;
+
+        assert!(game.event_stack.is_empty());
+        assert_eq!(player.lock().await.cards_in_hand.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_stifle_fizzles_once_its_ability_is_gone() {
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new(
+                "test",
+                20,
+                vec![create_island(), create_stifle(), create_hydra()],
+            ))
+            .await;
+        game.start_turn(0).await;
+        let hydra = player.lock().await.draw_card().unwrap();
+        player.lock().await.cards_in_hand.pop();
+        player.lock().await.cards_in_play.push(hydra.clone());
+        player.lock().await.draw_card();
+        player.lock().await.mana_pool.add_mana(ManaType::Blue);
+
+        game.add_to_stack(Arc::new(CardActionWrapper {
+            action: Arc::new(DrawCardCardAction::one(CardActionTarget::SelfOwner)),
+            card: hydra.clone(),
+            target: None,
+        }));
+        game.play_card(&player, 0, Some(EffectTarget::Card(hydra.clone())))
+            .await
+            .unwrap();
+        // The ability Stifle was cast at goes away and another from the same
+        // card is waiting under it
+        game.event_stack.remove(0);
+        game.event_stack.insert(
+            0,
+            Arc::new(CardActionWrapper {
+                action: Arc::new(DrawCardCardAction::one(CardActionTarget::SelfOwner)),
+                card: hydra.clone(),
+                target: None,
+            }),
+        );
+        game.resolve_stack().await;
+
+        assert!(game.event_stack.is_empty());
+        assert_eq!(player.lock().await.cards_in_hand.len(), 1);
+        assert!(game
+            .turn_messages
+            .iter()
+            .any(|message| message == "Spell Stifle fizzled and was moved to graveyard."));
+    }
+
+    #[tokio::test]
+    async fn test_stifle_cannot_target_a_spell() {
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new(
+                "test",
+                20,
+                vec![create_island(), create_stifle(), create_divination()],
+            ))
+            .await;
+        game.start_turn(0).await;
+        player.lock().await.draw_card();
+        player.lock().await.draw_card();
+        for _ in 0..3 {
+            player.lock().await.mana_pool.add_mana(ManaType::Blue);
+        }
+
+        let divination = game.play_card(&player, 0, None).await.unwrap();
+        let err = game
+            .play_card(&player, 0, Some(EffectTarget::Card(divination)))
+            .await
+            .unwrap_err();
+        assert_eq!(err, "Stifle can only target an ability");
+    }
+
+    #[tokio::test]
+    async fn test_aura_cannot_be_attached_to_a_land() {
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new(
+                "test",
+                20,
+                vec![create_claustrophobia(), create_hydra(), create_island()],
+            ))
+            .await;
+
+        let island = player.lock().await.draw_card().unwrap();
+        let hydra = player.lock().await.draw_card().unwrap();
+        let claustrophobia = player.lock().await.draw_card().unwrap();
+        game.start_turn(0).await;
+
+        {
+            let mut player = player.lock().await;
+            let mut cards: Vec<Arc<Mutex<Card>>> = player.cards_in_hand.drain(..).collect();
+            player.cards_in_play.append(&mut cards);
+        }
+
+        game.attach_card_action(&player, 2, Some(EffectTarget::Card(hydra.clone())))
+            .await
+            .unwrap();
+
+        // Enchant creature only, so the hydra keeps it
+        let err = game
+            .attach_card_action(&player, 2, Some(EffectTarget::Card(island.clone())))
+            .await
+            .unwrap_err();
+        assert_eq!(err, "Claustrophobia can't be attached to Island");
+        let attached = claustrophobia.lock().await.attached.clone().unwrap();
+        assert!(Arc::ptr_eq(&attached, &hydra));
+        assert!(!island.lock().await.tapped);
+
+        // And it goes down with the creature
+        game.destroy_card(&hydra).await.unwrap();
+        assert!(!player
+            .lock()
+            .await
+            .cards_in_play
+            .iter()
+            .any(|card| Arc::ptr_eq(card, &claustrophobia)));
+    }
+
+    #[tokio::test]
+    async fn test_tap_land_cant_make_mana_the_turn_it_enters() {
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new("test", 20, vec![create_simic_guildgate()]))
+            .await;
+        game.start_turn(0).await;
+        player.lock().await.draw_card();
+
+        let land = game.play_card(&player, 0, None).await.unwrap();
+        game.resolve_stack().await;
+
+        assert!(land.lock().await.tapped);
+        assert!(
+            !player
+                .lock()
+                .await
+                .can_pay_mana(&vec![ManaType::Blue])
+                .await
+        );
+
+        let trigger_id = land.lock().await.triggers[0].id.clone();
+        assert!(game
+            .activate_card_action(&player, 0, None, trigger_id.clone())
+            .await
+            .is_err());
+        assert_eq!(player.lock().await.mana_pool.blue, 0);
+
+        // Usable again once it untaps, but only for one of its colors
+        land.lock().await.tapped = false;
+        {
+            let player = player.lock().await;
+            assert!(player.can_pay_mana(&vec![ManaType::Green]).await);
+            assert!(
+                !player
+                    .can_pay_mana(&vec![ManaType::Blue, ManaType::Green])
+                    .await
+            );
+        }
+        game.activate_card_action(&player, 0, None, trigger_id)
+            .await
+            .unwrap();
+        assert_eq!(player.lock().await.mana_pool.blue, 1);
+    }
+
+    #[tokio::test]
+    async fn test_frost_breath_taps_two_target_creatures() {
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new("test", 20, vec![create_frost_breath()]))
+            .await;
+        let opponent = game
+            .add_player(Player::new(
+                "opponent",
+                20,
+                vec![create_hydra(), create_hydra(), create_hydra()],
+            ))
+            .await;
+        game.start_turn(0).await;
+        {
+            let mut player = player.lock().await;
+            player.draw_card();
+            player.mana_pool.add_mana(ManaType::Blue);
+            player.mana_pool.add_mana(ManaType::Colorless);
+        }
+        let hydras: Vec<Arc<Mutex<Card>>> = {
+            let mut opponent_lock = opponent.lock().await;
+            for _ in 0..3 {
+                let hydra = opponent_lock.draw_card().unwrap();
+                hydra.lock().await.controller = Some(opponent.clone());
+            }
+            let mut hydras: Vec<Arc<Mutex<Card>>> = opponent_lock.cards_in_hand.drain(..).collect();
+            opponent_lock.cards_in_play.append(&mut hydras);
+            opponent_lock.cards_in_play.clone()
+        };
+
+        // Up to two, so three is too many
+        assert!(game
+            .play_card(&player, 0, Some(EffectTarget::Cards(hydras.clone())))
+            .await
+            .is_err());
+
+        game.play_card(&player, 0, Some(EffectTarget::Cards(hydras[..2].to_vec())))
+            .await
+            .unwrap();
+        game.resolve_stack().await;
+
+        assert!(hydras[0].lock().await.tapped);
+        assert!(hydras[1].lock().await.tapped);
+        assert!(!hydras[2].lock().await.tapped);
+    }
+
+    #[tokio::test]
+    async fn test_flashed_back_divination_is_exiled() {
+        let mut game = Game::new();
+        let mut deck: Vec<Card> = (0..4).map(|_| create_island()).collect();
+        deck.push(create_divination().with_flashback(vec![ManaType::Blue, ManaType::Colorless]));
+        let player = game.add_player(Player::new("test", 20, deck)).await;
+        game.start_turn(0).await;
+
+        let divination = player.lock().await.draw_card().unwrap();
+        let err = game.flashback_card(&player, 0, None).await.unwrap_err();
+        assert_eq!(err, "Invalid card index");
+
+        game.move_card(&divination, Zone::Hand, Zone::Graveyard)
+            .await
+            .unwrap();
+        for _ in 0..2 {
+            player.lock().await.mana_pool.add_mana(ManaType::Blue);
+        }
+        game.flashback_card(&player, 0, None).await.unwrap();
+        assert_eq!(game.spells_cast_this_turn, 1);
+        game.resolve_stack().await;
+
+        let mut player = player.lock().await;
+        assert_eq!(player.cards_in_hand.len(), 2);
+        assert!(player.zone_contains(Zone::Exile, &divination));
+        assert!(!player.zone_contains(Zone::Graveyard, &divination));
+        assert!(!player.zone_contains(Zone::Battlefield, &divination));
+        assert!(!divination.lock().await.flashed_back);
+    }
+
+    async fn play_out_turn(game: &mut Game) -> usize {
+        for _ in 0..12 {
+            game.advance_turn().await;
+        }
+
+        game.current_turn.as_ref().unwrap().current_player_index as usize
+    }
+
+    #[tokio::test]
+    async fn test_time_walk_takes_another_turn() {
+        let mut game = Game::new();
+        let mut deck: Vec<Card> = (0..4).map(|_| create_island()).collect();
+        deck.push(create_time_walk());
+        let player = game.add_player(Player::new("test", 20, deck)).await;
+        let opponent_deck: Vec<Card> = (0..4).map(|_| create_island()).collect();
+        game.add_player(Player::new("opponent", 20, opponent_deck))
+            .await;
+        game.start_turn(0).await;
+        {
+            let mut player = player.lock().await;
+            player.draw_card();
+            player.mana_pool.add_mana(ManaType::Blue);
+            player.mana_pool.add_mana(ManaType::Colorless);
+        }
+
+        game.play_card(&player, 0, None).await.unwrap();
+        game.resolve_stack().await;
+
+        assert_eq!(play_out_turn(&mut game).await, 0);
+        assert_eq!(play_out_turn(&mut game).await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_meditate_draws_four_and_skips_a_turn() {
+        let mut game = Game::new();
+        let mut deck: Vec<Card> = (0..8).map(|_| create_island()).collect();
+        deck.push(create_meditate());
+        let player = game.add_player(Player::new("test", 20, deck)).await;
+        game.add_player(Player::new("opponent", 20, vec![])).await;
+        game.start_turn(0).await;
+        {
+            let mut player = player.lock().await;
+            player.draw_card();
+            for _ in 0..3 {
+                player.mana_pool.add_mana(ManaType::Blue);
+            }
+        }
+
+        game.play_card(&player, 0, None).await.unwrap();
+        game.resolve_stack().await;
+        assert_eq!(player.lock().await.cards_in_hand.len(), 4);
+
+        assert_eq!(play_out_turn(&mut game).await, 1);
+        assert_eq!(play_out_turn(&mut game).await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_possession_steals_a_creature() {
+        let mut game = Game::new();
+        let mut deck: Vec<Card> = (0..4).map(|_| create_island()).collect();
+        deck.push(create_possession());
+        let player = game.add_player(Player::new("test", 20, deck)).await;
+        let opponent = game
+            .add_player(Player::new("opponent", 20, vec![create_hydra()]))
+            .await;
+        game.start_turn(0).await;
+        {
+            let mut player = player.lock().await;
+            player.draw_card();
+            for _ in 0..5 {
+                player.mana_pool.add_mana(ManaType::Blue);
+            }
+        }
+        let hydra = opponent.lock().await.draw_card().unwrap();
+        game.move_card(&hydra, Zone::Hand, Zone::Battlefield)
+            .await
+            .unwrap();
+
+        game.play_card(&player, 0, Some(EffectTarget::Card(hydra.clone())))
+            .await
+            .unwrap();
+        game.resolve_stack().await;
+
+        assert!(Arc::ptr_eq(
+            &hydra.lock().await.controller().unwrap(),
+            &player
+        ));
+        assert!(player.lock().await.zone_contains(Zone::Battlefield, &hydra));
+        assert!(opponent.lock().await.cards_in_play.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_fatigue_skips_the_next_draw() {
+        let mut game = Game::new();
+        let mut deck: Vec<Card> = (0..4).map(|_| create_island()).collect();
+        deck.push(create_fatigue());
+        let player = game.add_player(Player::new("test", 20, deck)).await;
+        let opponent_deck: Vec<Card> = (0..4).map(|_| create_island()).collect();
+        let opponent = game
+            .add_player(Player::new("opponent", 20, opponent_deck))
+            .await;
+        game.start_turn(0).await;
+        {
+            let mut player = player.lock().await;
+            player.draw_card();
+            player.mana_pool.add_mana(ManaType::Blue);
+            player.mana_pool.add_mana(ManaType::Colorless);
+        }
+
+        game.play_card(&player, 0, Some(EffectTarget::Player(opponent.clone())))
+            .await
+            .unwrap();
+        game.resolve_stack().await;
+
+        // Through the opponent's turn and back
+        play_out_turn(&mut game).await;
+        assert_eq!(play_out_turn(&mut game).await, 0);
+        let opponent = opponent.lock().await;
+        assert!(opponent.cards_in_hand.is_empty());
+        assert!(!opponent.skip_next_draw);
+    }
+
+    #[tokio::test]
+    async fn test_time_stop_ends_the_turn() {
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new("test", 20, vec![create_time_stop()]))
+            .await;
+        game.add_player(Player::new("opponent", 20, vec![])).await;
+        game.start_turn(0).await;
+        game.skip_to_phase(TurnPhase::Main).await;
+        {
+            let mut player = player.lock().await;
+            player.draw_card();
+            for _ in 0..6 {
+                player.mana_pool.add_mana(ManaType::Blue);
+            }
+        }
+
+        game.play_card(&player, 0, None).await.unwrap();
+        game.resolve_stack().await;
+
+        assert_eq!(game.current_phase(), TurnPhase::Cleanup);
+        assert!(game.event_stack.is_empty());
+        assert!(game.turn_messages.contains(&"The turn ends.".to_string()));
+    }
 }
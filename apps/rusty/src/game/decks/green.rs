@@ -1,18 +1,23 @@
 use crate::game::{
     action::{
-        generate_mana::GenerateManaAction, Action, ActionTriggerType, ApplyEffectToPlayerCardType,
-        AsyncClosureAction, AsyncClosureWithCardAction, CardAction, CardActionTarget,
-        CardActionTrigger, CardActionWrapper, CardRequiredTarget, CardTargetTeam,
+        generate_mana::GenerateManaAction,
+        search_library::{SearchDestination, SearchLibraryAction},
+        Action, ActionTriggerType, ApplyDynamicEffectToCard, ApplyEffectToPlayerCardType,
+        ApplyEffectToTargetAction, AsyncClosureAction, AsyncClosureWithCardAction, CardAction,
+        CardActionTarget, CardActionTrigger, CardActionWrapper, CardRequiredTarget, CardTargetTeam,
         CastMandatoryAdditionalAbility, CastOptionalAdditionalAbility, DeclareAttackerAction,
-        DeclareBlockerAction, DrawCardAction, DrawCardCardAction, PlayerActionTarget,
-        TriggerTarget,
+        DeclareBlockerAction, DrawCardAction, DrawCardCardAction, ModifyStatUntilEndOfTurnAction,
+        PlayerActionTarget, TriggerTarget,
     },
     card::{
         card::{create_creature_card, create_multiple_cards},
         Card, CardPhase, CardType, CreatureType,
     },
     decks::duplicate_card,
-    effects::{Effect, EffectID, EffectTarget, ExpireContract, LifeLinkAction, StatModifierEffect},
+    effects::{
+        DynamicStatModifierEffect, DynamicValue, Effect, EffectID, EffectTarget, ExpireContract,
+        GeneratedEffects, LifeLinkAction, StatModifierEffect,
+    },
     mana::ManaType,
     player::Player,
     stat::{Stat, StatType, Stats},
@@ -22,6 +27,7 @@ use crate::game::{
 use std::{f32::consts::E, future::Future, mem::zeroed, pin::Pin, sync::Arc};
 
 use tokio::sync::Mutex;
+use tracing::debug;
 use ulid::Ulid;
 
 fn create_forest() -> Card {
@@ -70,7 +76,7 @@ pub fn create_priest_of_titania() -> Card {
                  card: Arc<Mutex<Card>>|
                  -> Pin<Box<dyn Future<Output = ()> + Send>> {
                     Box::pin(async move {
-                        let owner = card.lock().await.owner.clone().unwrap();
+                        let owner = card.lock().await.controller().unwrap();
                         let cards_in_play = &owner.lock().await.cards_in_play.clone();
                         for card in cards_in_play {
                             if card.lock().await.creature_type == Some(CreatureType::Elf) {
@@ -99,6 +105,7 @@ pub fn create_wirewood() -> Card {
             Arc::new(CastMandatoryAdditionalAbility {
                 action_type: ActionType::None,
                 mana: vec![],
+                costs: vec![],
                 target: CardRequiredTarget::CreatureOfType(CreatureType::Elf, CardTargetTeam::Owner),
                 description: "Return an Elf you control to its owner's hand: Untap target creature. Activate only once each turn.".to_string(),
                 ability: Arc::new(|card| -> Arc<dyn CardAction + Send + Sync> {
@@ -108,17 +115,14 @@ pub fn create_wirewood() -> Card {
                         Box::pin(async move {
                         let owner_arc = {
                             let card = card.lock().await;
-                            card.owner.clone()
+                            card.controller()
                         };
 
-                        if let Some(owner_arc) = owner_arc {
+                        if owner_arc.is_some() {
                             {
-
-                            let mut owner = owner_arc.lock().await;
                             // Remove the card from the battlefield and add it to the owner's hand
-                            if let Some(index) = owner.cards_in_play.iter().position(|c| Arc::ptr_eq(c, &card)) {
-                                let card_arc = owner.cards_in_play.remove(index);
-                                owner.cards_in_hand.push(card_arc.clone());
+                            if game.lock().await.return_card_to_owner_hand(&card).await.is_err() {
+                                return;
                             }
                         }
 
@@ -128,6 +132,7 @@ pub fn create_wirewood() -> Card {
                                         action: Arc::new(CastMandatoryAdditionalAbility {
                                             action_type: ActionType::None,
                                             mana: vec![],
+                                            costs: vec![],
                                             target: CardRequiredTarget::CardOfType(CardType::Creature, CardTargetTeam::Any),
                                             description:
                                                 "Untap target creature"
@@ -158,13 +163,13 @@ pub fn create_wirewood() -> Card {
                 |game: Arc<Mutex<Game>>, card: Arc<Mutex<Card>>| -> Pin<Box<dyn Future<Output = bool> + Send>> {
                     Box::pin(async move {
                         if let Ok(card) = card.try_lock() {
-                            if let Some(owner) = card.owner.as_ref() {
+                            if let Some(owner) = card.controller().as_ref() {
                                 let creatures = { owner.lock().await.creatures_of_type(CreatureType::Elf).await };
                                 let has_tappables = { game.lock().await.has_tapped_creature_excluding(&creatures).await};
                                 return creatures.len() > 0 && has_tappables;
                             }
                         }
-                        println!("not ready yet {:?}", card);
+                        debug!("not ready yet {:?}", card);
 
                         false
                     })
@@ -190,6 +195,7 @@ pub fn create_leaf_crowned_visionary() -> Card {
             Arc::new(CastOptionalAdditionalAbility {
                 action_type: ActionType::None,
                 mana: vec![ManaType::Green],
+                costs: vec![],
                 target: CardRequiredTarget::None,
                 description:
                     "Whenever you cast an Elf spell, you may pay {G}. If you do, draw a card."
@@ -230,6 +236,146 @@ pub fn create_leaf_crowned_visionary() -> Card {
     )
 }
 
+pub fn create_nyleas_chosen() -> Card {
+    create_creature_card!(
+        "Nylea's Chosen",
+        CreatureType::None,
+        "Nylea's Chosen's power is equal to your devotion to green.",
+        0, // Damage
+        3, // Defense
+        [ManaType::Green, ManaType::Green],
+        [],
+        CardActionTrigger::new(
+            ActionTriggerType::Continuous,
+            CardRequiredTarget::None,
+            Arc::new(ApplyDynamicEffectToCard::new(
+                DynamicValue::ManaSymbols(ManaType::Green).calculator(),
+                Arc::new(
+                    move |target, source_card, amount, effect_id| -> GeneratedEffects {
+                        Box::pin(async move {
+                            let mut effect = DynamicStatModifierEffect::new(
+                                target,
+                                StatType::Power,
+                                amount,
+                                ExpireContract::Never,
+                                Some(source_card.clone()),
+                                false,
+                            );
+                            let id = format!("{}-{}-power", source_card.lock().await.id, effect_id);
+                            effect.id = EffectID(id);
+
+                            let effects: Vec<Arc<Mutex<dyn Effect + Send + Sync>>> =
+                                vec![Arc::new(Mutex::new(effect))];
+                            effects
+                        })
+                    },
+                ),
+            )),
+        )
+    )
+}
+
+pub fn create_durkwood_baloth() -> Card {
+    create_creature_card!(
+        "Durkwood Baloth",
+        CreatureType::None,
+        "Suspend 5—{G} (Rather than cast this card from your hand, you may pay {G} and exile it with five time counters on it. At the beginning of your upkeep, remove a time counter. When the last is removed, cast it without paying its mana cost. It has haste.)",
+        5, // Damage
+        5, // Defense
+        [
+            ManaType::Green,
+            ManaType::Green,
+            ManaType::Colorless,
+            ManaType::Colorless,
+            ManaType::Colorless
+        ],
+        []
+    )
+    .with_suspend(5, vec![ManaType::Green])
+}
+
+pub fn create_elvish_harbinger() -> Card {
+    create_creature_card!(
+        "Elvish Harbinger",
+        CreatureType::Elf,
+        "When Elvish Harbinger enters the battlefield, search your library for an Elf card, put it into your hand, then shuffle.",
+        1,
+        2,
+        [ManaType::Colorless, ManaType::Colorless, ManaType::Green],
+        [],
+        CardActionTrigger::new(
+            ActionTriggerType::CardPlayedFromHand,
+            CardRequiredTarget::None,
+            Arc::new(SearchLibraryAction {
+                filter: CardRequiredTarget::CreatureOfType(CreatureType::Elf, CardTargetTeam::Owner),
+                to_zone: SearchDestination::Hand,
+            })
+        )
+    )
+}
+
+pub fn create_giant_growth() -> Card {
+    let pump = |stat_type| {
+        CardActionTrigger::new(
+            ActionTriggerType::CardPlayedFromHand,
+            CardRequiredTarget::CardOfType(CardType::Creature, CardTargetTeam::Any),
+            Arc::new(ModifyStatUntilEndOfTurnAction {
+                stat_type,
+                amount: 3,
+            }),
+        )
+    };
+
+    Card::new(
+        "Giant Growth",
+        "Target creature gets +3/+3 until end of turn.",
+        vec![pump(StatType::Power), pump(StatType::Toughness)],
+        CardPhase::Ready,
+        CardType::Instant,
+        vec![],
+        vec![ManaType::Green],
+    )
+}
+
+pub fn create_albino_troll() -> Card {
+    create_creature_card!(
+        "Albino Troll",
+        CreatureType::None,
+        "Echo {1}{G} (At the beginning of your upkeep, if this came under your control since the beginning of your last upkeep, sacrifice it unless you pay its echo cost.)",
+        3, // Damage
+        3, // Defense
+        [ManaType::Green, ManaType::Colorless],
+        []
+    )
+    .with_echo(vec![ManaType::Green, ManaType::Colorless])
+}
+
+pub fn create_bonesplitter() -> Card {
+    Card::new(
+        "Bonesplitter",
+        "Equipped creature gets +2/+0. Equip {1}",
+        vec![CardActionTrigger::new(
+            ActionTriggerType::Equip(vec![ManaType::Colorless]),
+            CardRequiredTarget::CardOfType(CardType::Creature, CardTargetTeam::Owner),
+            Arc::new(ApplyEffectToTargetAction {
+                effect_generator: Arc::new(|target, source_card| {
+                    Arc::new(Mutex::new(StatModifierEffect::new(
+                        target,
+                        StatType::Power,
+                        2,
+                        ExpireContract::Never,
+                        source_card,
+                    )))
+                }),
+            }),
+        )],
+        CardPhase::Ready,
+        CardType::Artifact,
+        vec![],
+        vec![ManaType::Colorless],
+    )
+}
+
 pub fn create_green_deck() -> Vec<Card> {
     let mut deck: Vec<Card> = vec![];
     deck.append(&mut duplicate_card(create_forest(), 4));
@@ -241,6 +387,20 @@ pub fn create_green_deck() -> Vec<Card> {
     deck
 }
 
+pub fn create_green_sideboard() -> Vec<Card> {
+    let mut sideboard: Vec<Card> = vec![];
+    sideboard.append(&mut duplicate_card(create_priest_of_titania(), 2));
+    sideboard.append(&mut duplicate_card(create_leaf_crowned_visionary(), 2));
+    sideboard.append(&mut duplicate_card(create_nyleas_chosen(), 2));
+    sideboard.append(&mut duplicate_card(create_bonesplitter(), 2));
+    sideboard.append(&mut duplicate_card(create_durkwood_baloth(), 2));
+    sideboard.append(&mut duplicate_card(create_albino_troll(), 2));
+    sideboard.append(&mut duplicate_card(create_elvish_harbinger(), 2));
+    sideboard.append(&mut duplicate_card(create_giant_growth(), 1));
+
+    sideboard
+}
+
 mod test {
     use std::sync::Arc;
 
@@ -248,15 +408,23 @@ mod test {
 
     use crate::{
         game::{
+            action::CardActionWrapper,
             card::{Card, CardPhase},
             decks::{
-                green::{create_forest, create_leaf_crowned_visionary, create_wirewood},
+                blue::create_simic_guildgate,
+                green::{
+                    create_albino_troll, create_bonesplitter, create_durkwood_baloth,
+                    create_forest, create_giant_growth, create_leaf_crowned_visionary,
+                    create_nyleas_chosen, create_priest_of_titania, create_wirewood,
+                },
                 Deck,
             },
             effects::EffectTarget,
-            mana,
+            mana::{self, ManaType},
             player::Player,
+            stat::{StatType, Stats},
             turn::TurnPhase,
+            zone::Zone,
             CardWithDetails, Game,
         },
         lobby::manager::LobbyManager,
@@ -397,4 +565,324 @@ mod test {
             .expect("oh no?");
         ga.lock().await.print().await;
     }
+
+    #[tokio::test]
+    async fn test_nyleas_chosen_power_equals_devotion_to_green() {
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new(
+                "test",
+                20,
+                vec![
+                    create_forest(),
+                    create_priest_of_titania(),
+                    create_nyleas_chosen(),
+                ],
+            ))
+            .await;
+        game.start_turn(0).await;
+
+        let mut in_play = vec![];
+        for _ in 0..3 {
+            let mut player_lock = player.lock().await;
+            player_lock.draw_card();
+            let card = player_lock.cards_in_hand.pop().unwrap();
+            card.lock().await.controller = Some(player.clone());
+            player_lock.cards_in_play.push(card.clone());
+            in_play.push(card);
+        }
+        let (chosen, priest) = (&in_play[0], &in_play[1]);
+
+        // Its own {G}{G} plus the priest's {G}, the forest has no cost
+        game.execute_actions(&mut vec![]).await;
+        assert_eq!(chosen.lock().await.get_stat_value(StatType::Power), 3);
+
+        game.destroy_card(priest).await.unwrap();
+        assert_eq!(chosen.lock().await.get_stat_value(StatType::Power), 2);
+    }
+
+    #[tokio::test]
+    async fn test_equipment_moves_between_creatures() {
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new(
+                "test",
+                20,
+                vec![create_wirewood(), create_wirewood(), create_bonesplitter()],
+            ))
+            .await;
+        game.start_turn(0).await;
+
+        let mut in_play = vec![];
+        for _ in 0..3 {
+            let mut player_lock = player.lock().await;
+            player_lock.draw_card();
+            let card = player_lock.cards_in_hand.pop().unwrap();
+            card.lock().await.controller = Some(player.clone());
+            player_lock.cards_in_play.push(card.clone());
+            in_play.push(card);
+        }
+        let (bonesplitter, first, second) = (&in_play[0], &in_play[1], &in_play[2]);
+        for _ in 0..2 {
+            player.lock().await.mana_pool.add_mana(ManaType::Colorless);
+        }
+
+        // Only as a sorcery
+        let target = Some(EffectTarget::Card(first.clone()));
+        assert!(game
+            .equip_card_action(&player, 0, target.clone())
+            .await
+            .is_err());
+
+        game.current_turn.as_mut().unwrap().phase = TurnPhase::Main;
+        game.equip_card_action(&player, 0, target).await.unwrap();
+        assert_eq!(first.lock().await.get_stat_value(StatType::Power), 3);
+
+        game.equip_card_action(&player, 0, Some(EffectTarget::Card(second.clone())))
+            .await
+            .unwrap();
+        assert_eq!(first.lock().await.get_stat_value(StatType::Power), 1);
+        assert_eq!(second.lock().await.get_stat_value(StatType::Power), 3);
+        assert_eq!(player.lock().await.mana_pool.colorless, 0);
+
+        // Unlike an aura it stays behind when the creature dies
+        game.destroy_card(second).await.unwrap();
+        assert!(bonesplitter.lock().await.attached.is_none());
+        assert!(player
+            .lock()
+            .await
+            .cards_in_play
+            .iter()
+            .any(|card| Arc::ptr_eq(card, bonesplitter)));
+    }
+
+    #[tokio::test]
+    async fn test_equip_without_a_target_does_nothing() {
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new("test", 20, vec![create_bonesplitter()]))
+            .await;
+        game.start_turn(0).await;
+        let bonesplitter = player.lock().await.draw_card().unwrap();
+        let equip = bonesplitter.lock().await.triggers[0].action.clone();
+
+        game.add_to_stack(Arc::new(CardActionWrapper {
+            card: bonesplitter,
+            action: equip,
+            target: None,
+        }));
+        game.resolve_stack().await;
+
+        assert!(game.effect_manager.effects.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_auto_pay_taps_exactly_two_forests() {
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new(
+                "test",
+                20,
+                vec![
+                    create_leaf_crowned_visionary(),
+                    create_simic_guildgate(),
+                    create_forest(),
+                    create_forest(),
+                    create_forest(),
+                ],
+            ))
+            .await;
+        game.start_turn(0).await;
+        game.current_turn.as_mut().unwrap().phase = TurnPhase::Main;
+
+        let mut lands = vec![];
+        for _ in 0..4 {
+            let mut player_lock = player.lock().await;
+            player_lock.draw_card();
+            let card = player_lock.cards_in_hand.pop().unwrap();
+            player_lock.cards_in_play.push(card.clone());
+            lands.push(card);
+        }
+        player.lock().await.draw_card();
+
+        game.auto_pay(&player, 0).await.unwrap();
+
+        let mut tapped = vec![];
+        for land in &lands {
+            tapped.push(land.lock().await.tapped);
+        }
+        // The guildgate could make either color, so it's left for later
+        assert_eq!(tapped.iter().filter(|tapped| **tapped).count(), 2);
+        assert!(!tapped[3]);
+        assert_eq!(player.lock().await.mana_pool.green, 2);
+
+        game.play_card(&player, 0, None).await.unwrap();
+        assert_eq!(player.lock().await.mana_pool.green, 0);
+    }
+
+    #[tokio::test]
+    async fn test_lord_bonus_doesnt_stack_across_actions() {
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new(
+                "test",
+                20,
+                vec![create_wirewood(), create_leaf_crowned_visionary()],
+            ))
+            .await;
+        game.start_turn(0).await;
+
+        for _ in 0..2 {
+            let mut player_lock = player.lock().await;
+            player_lock.draw_card();
+            let card = player_lock.cards_in_hand.pop().unwrap();
+            card.lock().await.controller = Some(player.clone());
+            player_lock.cards_in_play.push(card);
+        }
+        let wirewood = player.lock().await.cards_in_play[1].clone();
+
+        for _ in 0..4 {
+            game.execute_actions(&mut vec![]).await;
+        }
+        assert_eq!(wirewood.lock().await.get_stat_value(StatType::Power), 2);
+        assert_eq!(wirewood.lock().await.get_stat_value(StatType::Toughness), 2);
+    }
+
+    #[tokio::test]
+    async fn test_suspended_creature_is_cast_after_its_time_counters() {
+        let mut game = Game::new();
+        let mut deck: Vec<Card> = (0..10).map(|_| create_forest()).collect();
+        deck.push(create_durkwood_baloth());
+        let player = game.add_player(Player::new("test", 20, deck)).await;
+        game.start_turn(0).await;
+
+        let baloth = player.lock().await.draw_card().unwrap();
+        player.lock().await.mana_pool.add_mana(ManaType::Green);
+        game.suspend_card(&player, 0).await.unwrap();
+        assert!(player.lock().await.zone_contains(Zone::Exile, &baloth));
+        assert_eq!(baloth.lock().await.current_phase, CardPhase::Charging(5));
+        assert!(player.lock().await.mana_pool.to_vec().is_empty());
+
+        for upkeep in 1..=5 {
+            game.advance_turn().await;
+            game.skip_to_phase(TurnPhase::Upkeep).await;
+            if upkeep < 5 {
+                assert_eq!(
+                    baloth.lock().await.current_phase,
+                    CardPhase::Charging(5 - upkeep)
+                );
+            }
+        }
+        game.resolve_stack().await;
+
+        let mut player_lock = player.lock().await;
+        assert!(!player_lock.zone_contains(Zone::Exile, &baloth));
+        assert!(player_lock.zone_contains(Zone::Battlefield, &baloth));
+        assert_eq!(baloth.lock().await.current_phase, CardPhase::Ready);
+    }
+
+    // Plays an Albino Troll in the main phase, then moves on to the next
+    // upkeep where its echo is due.
+    async fn troll_at_echo_upkeep() -> (Arc<Mutex<Game>>, Arc<Mutex<Player>>, Arc<Mutex<Card>>) {
+        let mut game = Game::new();
+        let mut deck: Vec<Card> = (0..10).map(|_| create_forest()).collect();
+        deck.push(create_albino_troll());
+        let player = game.add_player(Player::new("test", 20, deck)).await;
+        game.start_turn(0).await;
+        game.skip_to_phase(TurnPhase::Main).await;
+
+        player.lock().await.draw_card();
+        player.lock().await.mana_pool.add_mana(ManaType::Green);
+        player.lock().await.mana_pool.add_mana(ManaType::Colorless);
+        let troll = game.play_card(&player, 0, None).await.unwrap();
+        game.resolve_stack().await;
+        assert!(player.lock().await.zone_contains(Zone::Battlefield, &troll));
+        assert!(game.abilities.is_empty());
+
+        game.advance_turn().await;
+        game.skip_to_phase(TurnPhase::Upkeep).await;
+        assert_eq!(game.abilities.len(), 1);
+
+        (Arc::new(Mutex::new(game)), player, troll)
+    }
+
+    #[tokio::test]
+    async fn test_unpaid_echo_sacrifices_the_creature() {
+        let (game, player, troll) = troll_at_echo_upkeep().await;
+        assert!(player.lock().await.zone_contains(Zone::Battlefield, &troll));
+
+        // Letting the upkeep go by without paying
+        game.lock().await.advance_turn().await;
+
+        assert!(game.lock().await.abilities.is_empty());
+        assert!(player.lock().await.zone_contains(Zone::Graveyard, &troll));
+    }
+
+    #[tokio::test]
+    async fn test_declining_echo_sacrifices_the_creature() {
+        let (game, player, troll) = troll_at_echo_upkeep().await;
+        let ability_id = game.lock().await.abilities.keys().next().unwrap().clone();
+
+        Game::respond_player_ability(game.clone(), &player, ability_id, false, None)
+            .await
+            .unwrap();
+
+        assert!(player.lock().await.zone_contains(Zone::Graveyard, &troll));
+    }
+
+    #[tokio::test]
+    async fn test_paid_echo_keeps_the_creature() {
+        let (game, player, troll) = troll_at_echo_upkeep().await;
+        let ability_id = game.lock().await.abilities.keys().next().unwrap().clone();
+        player.lock().await.mana_pool.add_mana(ManaType::Green);
+        player.lock().await.mana_pool.add_mana(ManaType::Colorless);
+
+        Game::respond_player_ability(game.clone(), &player, ability_id, true, None)
+            .await
+            .unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        assert!(game.lock().await.abilities.is_empty());
+
+        // It's only paid the once
+        let mut game = game.lock().await;
+        game.advance_turn().await;
+        game.skip_to_phase(TurnPhase::Upkeep).await;
+        assert!(game.abilities.is_empty());
+        assert!(player.lock().await.zone_contains(Zone::Battlefield, &troll));
+    }
+
+    #[tokio::test]
+    async fn test_giant_growth_pumps_until_end_of_turn() {
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new(
+                "test",
+                20,
+                vec![create_albino_troll(), create_giant_growth()],
+            ))
+            .await;
+        game.add_player(Player::new("opponent", 20, vec![])).await;
+        game.start_turn(0).await;
+        player.lock().await.draw_card();
+        let troll = player.lock().await.draw_card().unwrap();
+        game.move_card(&troll, Zone::Hand, Zone::Battlefield)
+            .await
+            .unwrap();
+        player.lock().await.mana_pool.add_mana(ManaType::Green);
+
+        game.play_card(&player, 0, Some(EffectTarget::Card(troll.clone())))
+            .await
+            .unwrap();
+        game.resolve_stack().await;
+        {
+            let troll = troll.lock().await;
+            assert_eq!(troll.get_stat_value(StatType::Power), 6);
+            assert_eq!(troll.get_stat_value(StatType::Toughness), 6);
+        }
+
+        for _ in 0..12 {
+            game.advance_turn().await;
+        }
+        assert_eq!(troll.lock().await.get_stat_value(StatType::Power), 3);
+    }
 }
@@ -5,7 +5,9 @@ pub mod red;
 pub mod white;
 
 use rand::seq::SliceRandom;
-use rand::thread_rng;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use specta::Type;
 use std::borrow::BorrowMut;
 use std::future::Future;
 use std::pin::Pin;
@@ -36,11 +38,128 @@ use super::player::Player;
 #[derive(Debug, Default)]
 pub struct Deck {
     pub draw_pile: Vec<Arc<Mutex<Card>>>,
-    pub discard_pile: Vec<Arc<Mutex<Card>>>,
     pub destroyed_pile: Vec<Arc<Mutex<Card>>>,
+    pub exiled: Vec<Arc<Mutex<Card>>>,
     pub in_game: Vec<Arc<Mutex<Card>>>,
 }
 
+pub const MAX_SIDEBOARD_SIZE: usize = 15;
+
+// The cards a player brings to a match. The main deck can never shrink below
+// the size it was registered at, sideboarding only trades cards in and out.
+#[derive(Type, Serialize, Deserialize, Debug, Clone)]
+pub struct Decklist {
+    pub main: Vec<Card>,
+    pub sideboard: Vec<Card>,
+    pub min_size: u32,
+}
+
+impl Decklist {
+    pub fn new(main: Vec<Card>, sideboard: Vec<Card>) -> Decklist {
+        Decklist {
+            min_size: main.len() as u32,
+            main,
+            sideboard,
+        }
+    }
+
+    // Moves the `remove` card ids from the main deck to the sideboard and the
+    // `add` ids the other way.
+    pub fn swap(&mut self, remove: &[String], add: &[String]) -> Result<(), String> {
+        let find_all = |cards: &[Card], ids: &[String]| -> Result<Vec<usize>, String> {
+            let mut indexes = vec![];
+            for id in ids {
+                let index = cards
+                    .iter()
+                    .position(|card| &card.id == id)
+                    .ok_or_else(|| format!("No card {} to swap", id))?;
+                if indexes.contains(&index) {
+                    return Err(format!("{} is listed twice", id));
+                }
+                indexes.push(index);
+            }
+            Ok(indexes)
+        };
+        let mut removing = find_all(&self.main, remove)?;
+        let mut adding = find_all(&self.sideboard, add)?;
+
+        let main_size = self.main.len() - removing.len() + adding.len();
+        if main_size < self.min_size as usize {
+            return Err(format!("Your deck needs at least {} cards", self.min_size));
+        }
+        let sideboard_size = self.sideboard.len() - adding.len() + removing.len();
+        if sideboard_size > MAX_SIDEBOARD_SIZE {
+            return Err(format!(
+                "Your sideboard can't have more than {} cards",
+                MAX_SIDEBOARD_SIZE
+            ));
+        }
+
+        // Take from the back so the remaining indexes stay put
+        removing.sort_unstable();
+        adding.sort_unstable();
+        let removed: Vec<Card> = removing
+            .into_iter()
+            .rev()
+            .map(|index| self.main.remove(index))
+            .collect();
+        let added: Vec<Card> = adding
+            .into_iter()
+            .rev()
+            .map(|index| self.sideboard.remove(index))
+            .collect();
+        self.main.extend(added);
+        self.sideboard.extend(removed);
+
+        Ok(())
+    }
+
+    // Highlander: at most one of each card in the main deck, basic lands aside.
+    // Copies get their own ids, so they're matched by name.
+    pub fn validate_singleton(&self) -> Result<(), Vec<String>> {
+        let mut seen: Vec<&str> = vec![];
+        let mut duplicates: Vec<String> = vec![];
+        for card in &self.main {
+            if matches!(card.card_type, CardType::BasicLand(_)) {
+                continue;
+            }
+            if !seen.contains(&card.name.as_str()) {
+                seen.push(&card.name);
+            } else if !duplicates.contains(&card.name) {
+                duplicates.push(card.name.clone());
+            }
+        }
+
+        if duplicates.is_empty() {
+            Ok(())
+        } else {
+            Err(duplicates)
+        }
+    }
+
+    // Rejects a list with any card, main deck or sideboard, whose cost has a
+    // color outside `identity`. Generic mana fits every identity.
+    pub fn validate_color_identity(&self, identity: &[ManaType]) -> Result<(), Vec<Card>> {
+        let offending: Vec<Card> = self
+            .main
+            .iter()
+            .chain(self.sideboard.iter())
+            .filter(|card| {
+                card.cost
+                    .iter()
+                    .any(|mana| *mana != ManaType::Colorless && !identity.contains(mana))
+            })
+            .cloned()
+            .collect();
+
+        if offending.is_empty() {
+            Ok(())
+        } else {
+            Err(offending)
+        }
+    }
+}
+
 fn duplicate_card(base_card: Card, count: usize) -> Vec<Card> {
     let mut cards = Vec::new();
     for i in 0..count {
@@ -51,22 +170,41 @@ fn duplicate_card(base_card: Card, count: usize) -> Vec<Card> {
     cards
 }
 
+// A fresh copy of any card that can be put in a deck, by name.
+pub fn card_named(name: &str) -> Option<Card> {
+    [
+        green::create_green_deck(),
+        green::create_green_sideboard(),
+        blue::create_blue_deck(),
+        blue::create_blue_sideboard(),
+        black::create_black_deck(),
+        black::create_black_sideboard(),
+        white::create_angels_deck(),
+        white::create_angels_sideboard(),
+        red::create_red_deck(),
+        red::create_red_sideboard(),
+    ]
+    .into_iter()
+    .flatten()
+    .find(|card| card.name == name)
+}
+
 impl Deck {
     pub fn new(cards: Vec<Card>) -> Self {
         Self {
             draw_pile: cards.into_iter().map(|c| Arc::new(Mutex::new(c))).collect(),
-            discard_pile: vec![],
             destroyed_pile: vec![],
+            exiled: vec![],
             in_game: vec![],
         }
     }
 
-    // Shuffle the draw pile
-    pub fn shuffle(&mut self) {
-        self.draw_pile.shuffle(&mut thread_rng());
+    // Shuffle the draw pile, normally with the game's rng so seeded games repeat
+    pub fn shuffle<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        self.draw_pile.shuffle(rng);
     }
 
-    // Draw a card from the draw pile, or shuffle the discard pile back in
+    // Draw a card from the draw pile, if there's anything left in it
     pub fn draw(&mut self) -> Option<Arc<Mutex<Card>>> {
         if let Some(card) = self.draw_pile.pop() {
             self.in_game.push(card.clone()); // Add to in-game pile
@@ -76,11 +214,6 @@ impl Deck {
         }
     }
 
-    // Discard a card
-    pub fn discard(&mut self, card: Arc<Mutex<Card>>) {
-        self.discard_pile.push(card);
-    }
-
     // Destroy a card
     pub fn destroy(&mut self, card: Arc<Mutex<Card>>) {
         self.destroyed_pile.push(card);
@@ -90,12 +223,6 @@ impl Deck {
         self.in_game.push(card);
     }
 
-    // Reshuffle discard pile back into the draw pile
-    fn reshuffle_discard_pile(&mut self) {
-        self.draw_pile.append(&mut self.discard_pile);
-        self.shuffle();
-    }
-
     pub async fn set_owner(&self, player: &Arc<Mutex<Player>>) {
         for card in self.draw_pile.iter() {
             let mut d = card.lock().await;
@@ -103,3 +230,42 @@ impl Deck {
         }
     }
 }
+
+mod test {
+    use crate::game::{
+        decks::{
+            black::create_hydra,
+            duplicate_card,
+            green::{create_green_deck, create_green_sideboard},
+            red::{create_burn, create_red_deck},
+            Decklist,
+        },
+        mana::ManaType,
+    };
+
+    #[test]
+    fn test_color_identity_rejects_off_color_cards() {
+        let mut main = create_green_deck();
+        let decklist = Decklist::new(main.clone(), create_green_sideboard());
+        assert!(decklist.validate_color_identity(&[ManaType::Green]).is_ok());
+
+        main.push(create_burn());
+        let decklist = Decklist::new(main, create_green_sideboard());
+        let offending = decklist
+            .validate_color_identity(&[ManaType::Green])
+            .unwrap_err();
+        assert_eq!(offending.len(), 1);
+        assert_eq!(offending[0].name, "Burn");
+    }
+
+    #[test]
+    fn test_singleton_rejects_duplicate_nonlands() {
+        // Eight Fire are fine, four Burn aren't
+        let decklist = Decklist::new(create_red_deck(), vec![]);
+        assert_eq!(decklist.validate_singleton(), Err(vec!["Burn".to_string()]));
+
+        let mut main = duplicate_card(create_hydra(), 1);
+        main.push(create_burn());
+        assert!(Decklist::new(main, vec![]).validate_singleton().is_ok());
+    }
+}
@@ -1,8 +1,11 @@
 use crate::game::{
     action::{
-        generate_mana::GenerateManaAction, ActionTriggerType, AsyncClosureAction,
-        AsyncClosureWithCardAction, CardActionTrigger, CardRequiredTarget, DeclareAttackerAction,
-        DeclareBlockerAction, PlayerActionTarget, TriggerTarget,
+        chance::{FlipCoinAction, RollDieAction},
+        delayed::{DelayedTriggerAction, SacrificeSelfAction},
+        generate_mana::GenerateManaAction,
+        ActionTriggerType, AsyncClosureAction, AsyncClosureWithCardAction, CardActionTrigger,
+        CardRequiredTarget, CardTargetTeam, DamageTargetAction, DeclareAttackerAction,
+        DeclareBlockerAction, GoadAction, PlayerActionTarget, TriggerTarget,
     },
     card::{
         card::{create_creature_card, create_multiple_cards},
@@ -23,7 +26,7 @@ use std::{f32::consts::E, future::Future, mem::zeroed, pin::Pin, sync::Arc};
 use tokio::sync::Mutex;
 use ulid::Ulid;
 
-fn create_fire() -> Card {
+pub fn create_fire() -> Card {
     Card::new(
         "Fire",
         "",
@@ -47,22 +50,311 @@ fn create_fire() -> Card {
     )
 }
 
+pub fn create_burn() -> Card {
+    Card::new(
+        "Burn",
+        "Burn deals 2 damage to target creature or player.",
+        vec![CardActionTrigger::new(
+            ActionTriggerType::CardPlayedFromHand,
+            CardRequiredTarget::EnemyCardOrPlayer,
+            Arc::new(DamageTargetAction { amount: 2 }),
+        )],
+        CardPhase::Ready,
+        CardType::Instant,
+        vec![],
+        vec![ManaType::Red],
+    )
+}
+
+pub fn create_grapeshot() -> Card {
+    Card::new(
+        "Grapeshot",
+        "Grapeshot deals 1 damage to target creature or player. Storm",
+        vec![CardActionTrigger::new(
+            ActionTriggerType::CardPlayedFromHand,
+            CardRequiredTarget::EnemyCardOrPlayer,
+            Arc::new(DamageTargetAction { amount: 1 }),
+        )],
+        CardPhase::Ready,
+        CardType::Sorcery,
+        vec![Stat::new(StatType::Storm, 1)],
+        vec![ManaType::Red],
+    )
+}
+
+pub fn create_sudden_shock() -> Card {
+    Card::new(
+        "Sudden Shock",
+        "Split second. Sudden Shock deals 2 damage to target creature or player.",
+        vec![CardActionTrigger::new(
+            ActionTriggerType::CardPlayedFromHand,
+            CardRequiredTarget::EnemyCardOrPlayer,
+            Arc::new(DamageTargetAction { amount: 2 }),
+        )],
+        CardPhase::Ready,
+        CardType::Instant,
+        vec![Stat::new(StatType::SplitSecond, 1)],
+        vec![ManaType::Colorless, ManaType::Red],
+    )
+}
+
 pub fn create_red_deck() -> Vec<Card> {
     let mut deck: Vec<Card> = vec![];
     deck.append(&mut duplicate_card(create_fire(), 8));
+    deck.append(&mut duplicate_card(create_burn(), 4));
 
     deck
 }
 
+pub fn create_wild_bolt() -> Card {
+    Card::new(
+        "Wild Bolt",
+        "Roll a six-sided die. On a 4 or higher, Wild Bolt deals 4 damage to target creature or player. Otherwise it deals 1 damage.",
+        vec![CardActionTrigger::new(
+            ActionTriggerType::CardPlayedFromHand,
+            CardRequiredTarget::EnemyCardOrPlayer,
+            Arc::new(RollDieAction {
+                sides: 6,
+                succeeds_at: 4,
+                on_success: Arc::new(DamageTargetAction { amount: 4 }),
+                on_failure: Some(Arc::new(DamageTargetAction { amount: 1 })),
+            }),
+        )],
+        CardPhase::Ready,
+        CardType::Instant,
+        vec![],
+        vec![ManaType::Red],
+    )
+}
+
+pub fn create_reckless_bolt() -> Card {
+    Card::new(
+        "Reckless Bolt",
+        "Flip a coin. If you win the flip, Reckless Bolt deals 3 damage to target creature or player.",
+        vec![CardActionTrigger::new(
+            ActionTriggerType::CardPlayedFromHand,
+            CardRequiredTarget::EnemyCardOrPlayer,
+            Arc::new(FlipCoinAction {
+                on_heads: Arc::new(DamageTargetAction { amount: 3 }),
+                on_tails: None,
+            }),
+        )],
+        CardPhase::Ready,
+        CardType::Instant,
+        vec![],
+        vec![ManaType::Red],
+    )
+}
+
+pub fn create_goading_jeer() -> Card {
+    Card::new(
+        "Goading Jeer",
+        "Goad target creature. Until the turn ends, it attacks if able and attacks a player other than you if able.",
+        vec![CardActionTrigger::new(
+            ActionTriggerType::CardPlayedFromHand,
+            CardRequiredTarget::CardOfType(CardType::Creature, CardTargetTeam::Opponent),
+            Arc::new(GoadAction {}),
+        )],
+        CardPhase::Ready,
+        CardType::Instant,
+        vec![],
+        vec![ManaType::Red],
+    )
+}
+
+pub fn create_spark_elemental() -> Card {
+    create_creature_card!(
+        "Spark Elemental",
+        CreatureType::None,
+        "Trample. At the beginning of the end step, sacrifice Spark Elemental.",
+        3,
+        1,
+        [ManaType::Red],
+        [StatType::Trample],
+        CardActionTrigger::new(
+            ActionTriggerType::CardPlayedFromHand,
+            CardRequiredTarget::None,
+            Arc::new(DelayedTriggerAction {
+                phase: TurnPhase::End,
+                action: Arc::new(SacrificeSelfAction {}),
+            })
+        )
+    )
+}
+
+pub fn create_red_sideboard() -> Vec<Card> {
+    let mut sideboard: Vec<Card> = vec![];
+    sideboard.append(&mut duplicate_card(create_fire(), 4));
+    sideboard.append(&mut duplicate_card(create_grapeshot(), 2));
+    sideboard.append(&mut duplicate_card(create_sudden_shock(), 2));
+    sideboard.append(&mut duplicate_card(create_wild_bolt(), 2));
+    sideboard.append(&mut duplicate_card(create_reckless_bolt(), 2));
+    sideboard.append(&mut duplicate_card(create_goading_jeer(), 1));
+    sideboard.append(&mut duplicate_card(create_spark_elemental(), 2));
+
+    sideboard
+}
+
 mod test {
     use std::sync::Arc;
 
     use tokio::sync::{Mutex, RwLock};
 
+    use rand::Rng;
+
     use crate::game::{
-        card::Card, decks::red::create_fire, effects::EffectTarget, mana, player::Player, Game,
+        action::CardActionWrapper,
+        card::{Card, CardPhase},
+        decks::{
+            black::create_hydra,
+            red::{
+                create_fire, create_goading_jeer, create_reckless_bolt, create_spark_elemental,
+                create_wild_bolt,
+            },
+        },
+        effects::EffectTarget,
+        error::GameError,
+        mana,
+        player::Player,
+        rng::GameRng,
+        stat::{StatType, Stats},
+        turn::TurnPhase,
+        zone::Zone,
+        Game,
     };
 
+    #[tokio::test]
+    async fn test_seeded_wild_bolt_rolls_the_same_every_time() {
+        let seed = 11;
+        let roll = GameRng::seeded(seed).gen_range(1..=6);
+
+        let mut game = Game::new().with_seed(seed);
+        let player = game
+            .add_player(Player::new("caster", 20, vec![create_wild_bolt()]))
+            .await;
+        let opponent = game.add_player(Player::new("opponent", 20, vec![])).await;
+        let bolt = player.lock().await.draw_card().unwrap();
+        game.start_turn(0).await;
+
+        let action = bolt.lock().await.triggers[0].action.clone();
+        game.add_to_stack(Arc::new(CardActionWrapper {
+            card: bolt,
+            action,
+            target: Some(EffectTarget::Player(opponent.clone())),
+        }));
+        game.resolve_stack().await;
+
+        let damage = if roll >= 4 { 4 } else { 1 };
+        assert_eq!(
+            opponent.lock().await.get_stat_value(StatType::Health),
+            20 - damage
+        );
+        let message = format!("Wild Bolt rolled a {} on a d6", roll);
+        assert!(game.turn_messages.contains(&message));
+    }
+
+    #[tokio::test]
+    async fn test_seeded_reckless_bolt_only_hits_on_heads() {
+        let seed = 5;
+        let heads = GameRng::seeded(seed).gen_bool(0.5);
+
+        let mut game = Game::new().with_seed(seed);
+        let player = game
+            .add_player(Player::new("caster", 20, vec![create_reckless_bolt()]))
+            .await;
+        let opponent = game.add_player(Player::new("opponent", 20, vec![])).await;
+        game.start_turn(0).await;
+        player.lock().await.draw_card();
+        player.lock().await.mana_pool.add_mana(mana::ManaType::Red);
+
+        game.play_card(&player, 0, Some(EffectTarget::Player(opponent.clone())))
+            .await
+            .unwrap();
+        game.resolve_stack().await;
+
+        let damage = if heads { 3 } else { 0 };
+        assert_eq!(
+            opponent.lock().await.get_stat_value(StatType::Health),
+            20 - damage
+        );
+        let side = if heads { "heads" } else { "tails" };
+        let message = format!("Reckless Bolt flipped {}", side);
+        assert!(game.turn_messages.contains(&message));
+    }
+
+    #[tokio::test]
+    async fn test_goading_jeer_makes_the_creature_attack() {
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new("caster", 20, vec![create_goading_jeer()]))
+            .await;
+        let opponent = game
+            .add_player(Player::new("opponent", 20, vec![create_hydra()]))
+            .await;
+        game.start_turn(1).await;
+        game.skip_to_phase(TurnPhase::Main).await;
+        // Drawn during the draw step
+        let hydra = opponent.lock().await.cards_in_hand[0].clone();
+        game.move_card(&hydra, Zone::Hand, Zone::Battlefield)
+            .await
+            .unwrap();
+        hydra.lock().await.current_phase = CardPhase::Ready;
+        player.lock().await.draw_card();
+        player.lock().await.mana_pool.add_mana(mana::ManaType::Red);
+
+        game.play_card(&player, 0, Some(EffectTarget::Card(hydra.clone())))
+            .await
+            .unwrap();
+        game.resolve_stack().await;
+
+        assert!(Arc::ptr_eq(
+            hydra.lock().await.goaded_by.as_ref().unwrap(),
+            &player
+        ));
+        game.skip_to_phase(TurnPhase::DeclareAttackers).await;
+        assert_eq!(
+            game.try_advance_turn().await.unwrap_err(),
+            GameError::MustAttack {
+                creature: "Voracious Hydra".to_string()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_spark_elemental_is_sacrificed_at_the_end_step() {
+        let mut game = Game::new();
+        let mut deck: Vec<Card> = (0..4).map(|_| create_fire()).collect();
+        deck.push(create_spark_elemental());
+        let player = game.add_player(Player::new("caster", 20, deck)).await;
+        let opponent_deck: Vec<Card> = (0..4).map(|_| create_fire()).collect();
+        game.add_player(Player::new("opponent", 20, opponent_deck))
+            .await;
+        game.start_turn(0).await;
+        game.skip_to_phase(TurnPhase::Main).await;
+        let elemental = player.lock().await.cards_in_hand[0].clone();
+        player.lock().await.mana_pool.add_mana(mana::ManaType::Red);
+
+        game.play_card(&player, 0, None).await.unwrap();
+        game.resolve_stack().await;
+        assert!(player
+            .lock()
+            .await
+            .zone_contains(Zone::Battlefield, &elemental));
+
+        // Sticks around until the end step
+        game.skip_to_phase(TurnPhase::Main2).await;
+        assert!(player
+            .lock()
+            .await
+            .zone_contains(Zone::Battlefield, &elemental));
+
+        game.skip_to_phase(TurnPhase::End).await;
+        assert!(player
+            .lock()
+            .await
+            .zone_contains(Zone::Graveyard, &elemental));
+    }
+
     #[tokio::test]
     async fn test_green_1() {
         // DeclareAttackerAction
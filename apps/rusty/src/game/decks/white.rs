@@ -1,9 +1,10 @@
 use crate::game::{
     action::{
-        generate_mana::GenerateManaAction, ActionTriggerType, ApplyDynamicEffectToCard,
-        ApplyEffectToCardBasedOnTotalCardType, AsyncClosureAction, AsyncClosureWithCardAction,
-        CardActionTrigger, CardRequiredTarget, CardTargetTeam, DeclareAttackerAction,
-        DeclareBlockerAction, PlayerActionTarget, TriggerTarget,
+        generate_mana::GenerateManaAction, morph::ManifestAction, ActionTriggerType,
+        ApplyDynamicEffectToCard, ApplyEffectToCardBasedOnTotalCardType, AsyncClosureAction,
+        AsyncClosureWithCardAction, BecomeMonarchAction, CardActionTrigger, CardRequiredTarget,
+        CardTargetTeam, DeclareAttackerAction, DeclareBlockerAction, PlayerActionTarget,
+        PreventFirstCombatDamageAction, TapTargetAction, TriggerTarget, WrathAction,
     },
     card::{
         card::{create_creature_card, create_multiple_cards},
@@ -19,11 +20,11 @@ use crate::game::{
 use std::{f32::consts::E, future::Future, mem::zeroed, pin::Pin, sync::Arc};
 
 use tokio::sync::Mutex;
-use ulid::Ulid;
+use tracing::debug;
 
 use super::duplicate_card;
 
-fn create_plains() -> Card {
+pub fn create_plains() -> Card {
     Card::new(
         "Plains",
         "",
@@ -47,6 +48,132 @@ fn create_plains() -> Card {
     )
 }
 
+pub fn create_azorius_guildgate() -> Card {
+    let add_mana = |mana: ManaType, label: &str| {
+        CardActionTrigger::new(
+            ActionTriggerType::AbilityWithinPhases(
+                format!("Add 1 {{{}}} to your pool", label),
+                vec![],
+                None,
+                true,
+            ),
+            CardRequiredTarget::None,
+            Arc::new(GenerateManaAction {
+                mana_to_add: vec![mana],
+                target: PlayerActionTarget::Owner,
+            }),
+        )
+    };
+
+    Card::new(
+        "Azorius Guildgate",
+        "Azorius Guildgate enters the battlefield tapped.",
+        vec![
+            add_mana(ManaType::White, "W"),
+            add_mana(ManaType::Blue, "U"),
+        ],
+        CardPhase::Ready,
+        CardType::DualLand(ManaType::White, ManaType::Blue),
+        vec![],
+        vec![],
+    )
+    .with_enters_tapped()
+}
+
+pub fn create_wrath() -> Card {
+    Card::new(
+        "Wrath",
+        "Destroy all creatures.",
+        vec![CardActionTrigger::new(
+            ActionTriggerType::CardPlayedFromHand,
+            CardRequiredTarget::None,
+            Arc::new(WrathAction {
+                filter: CardRequiredTarget::CardOfType(CardType::Creature, CardTargetTeam::Any),
+            }),
+        )],
+        CardPhase::Ready,
+        CardType::Sorcery,
+        vec![],
+        vec![
+            ManaType::White,
+            ManaType::White,
+            ManaType::Black,
+            ManaType::Black,
+        ],
+    )
+}
+
+pub fn create_palace_sentinels() -> Card {
+    create_creature_card!(
+        "Palace Sentinels",
+        CreatureType::None,
+        "When Palace Sentinels enters the battlefield, you become the monarch.",
+        2,
+        4,
+        [ManaType::White],
+        [],
+        CardActionTrigger::new(
+            ActionTriggerType::CardPlayedFromHand,
+            CardRequiredTarget::None,
+            Arc::new(BecomeMonarchAction {})
+        )
+    )
+}
+
+pub fn create_master_decoy() -> Card {
+    create_creature_card!(
+        "Master Decoy",
+        CreatureType::None,
+        "",
+        1,
+        2,
+        [ManaType::Colorless, ManaType::White],
+        [],
+        CardActionTrigger::new(
+            ActionTriggerType::AbilityWithinPhases(
+                "{W}, {T}: Tap target creature.".to_string(),
+                vec![ManaType::White],
+                None,
+                true
+            ),
+            CardRequiredTarget::CardOfType(CardType::Creature, CardTargetTeam::Any),
+            Arc::new(TapTargetAction {})
+        )
+    )
+}
+
+pub fn create_sheltering_light() -> Card {
+    Card::new(
+        "Sheltering Light",
+        "Prevent the first combat damage that would be dealt to target creature this turn.",
+        vec![CardActionTrigger::new(
+            ActionTriggerType::CardPlayedFromHand,
+            CardRequiredTarget::CardOfType(CardType::Creature, CardTargetTeam::Any),
+            Arc::new(PreventFirstCombatDamageAction {}),
+        )],
+        CardPhase::Ready,
+        CardType::Instant,
+        vec![],
+        vec![ManaType::White],
+    )
+}
+
+pub fn create_soul_summons() -> Card {
+    Card::new(
+        "Soul Summons",
+        "Manifest the top card of your library.",
+        vec![CardActionTrigger::new(
+            ActionTriggerType::CardPlayedFromHand,
+            CardRequiredTarget::None,
+            Arc::new(ManifestAction {}),
+        )],
+        CardPhase::Ready,
+        CardType::Sorcery,
+        vec![],
+        vec![ManaType::Colorless, ManaType::White],
+    )
+}
+
 pub fn create_angels_deck() -> Vec<Card> {
     let mut deck: Vec<Card> = vec![];
     // deck.push();
@@ -57,6 +184,19 @@ pub fn create_angels_deck() -> Vec<Card> {
     deck
 }
 
+pub fn create_angels_sideboard() -> Vec<Card> {
+    let mut sideboard: Vec<Card> = vec![];
+    sideboard.append(&mut duplicate_card(create_righteous_valkyrie(), 2));
+    sideboard.append(&mut duplicate_card(create_palace_sentinels(), 2));
+    sideboard.append(&mut duplicate_card(create_azorius_guildgate(), 2));
+    sideboard.append(&mut duplicate_card(create_soul_summons(), 2));
+    sideboard.append(&mut duplicate_card(create_wrath(), 1));
+    sideboard.append(&mut duplicate_card(create_master_decoy(), 1));
+    sideboard.append(&mut duplicate_card(create_sheltering_light(), 2));
+
+    sideboard
+}
+
 fn create_angelic_accord() -> Card {
     Card::new(
         "Angelic Accord",
@@ -68,22 +208,23 @@ fn create_angelic_accord() -> Card {
                 Arc::new(AsyncClosureAction::new(Arc::new(
                     |game: Arc<Mutex<Game>>, card: Arc<Mutex<Card>>| -> Pin<Box<dyn Future<Output = ()> + Send>> {
                         Box::pin(async move {
-                            let (difference, owner) = {
-
-                            let card = card.lock().await;
-                            let owner_arc = card.owner.clone().unwrap();
-                            let owner = owner_arc.lock().await;
-                            let health = owner.stat_manager.get_stat_value(StatType::Health);
-                                (health - owner.health_at_start_of_round, owner_arc.clone())
-                            };
-                            println!("difference: {}", difference);
-                            if difference > 3 {
-                                Game::play_token(&game, &owner, create_creature_card!("Token", CreatureType::Angel, "", 4,4, [], [])).await.ok();
-                            }
+                            let owner = card.lock().await.controller().unwrap();
+                            Game::play_token(&game, &owner, create_creature_card!("Token", CreatureType::Angel, "", 4,4, [], [])).await.ok();
                         })
                     }
                 )))
-            ),
+            )
+            .with_condition(Arc::new(
+                |card: Arc<Mutex<Card>>| -> Pin<Box<dyn Future<Output = bool> + Send>> {
+                    Box::pin(async move {
+                        let owner = card.lock().await.controller();
+                        match owner {
+                            Some(owner) => owner.lock().await.life_gained_this_turn >= 4,
+                            None => false,
+                        }
+                    })
+                },
+            )),
             CardActionTrigger::new(
                 ActionTriggerType::Attached,
                 CardRequiredTarget::CardOfType(CardType::Creature, CardTargetTeam::Any),
@@ -128,7 +269,7 @@ fn create_righteous_valkyrie() -> Card {
                     move |card_arc: Arc<Mutex<Card>>| -> Pin<Box<dyn Future<Output = i8> + Send>> {
                         Box::pin(async move {
                             let owner = {
-                                card_arc.lock().await.owner.clone()
+                                card_arc.lock().await.controller()
                             };
 
                             if let Some(owner_arc) = owner {
@@ -152,7 +293,7 @@ fn create_righteous_valkyrie() -> Card {
                             let (owner,name,id) = {
                                 let card = source_card.clone();
                                 let card = card.lock().await;
-                                (card.owner.clone(), card.name.clone(), card.id.clone())
+                                (card.controller(), card.name.clone(), card.id.clone())
                             };
 
                             if let Some(owner_arc) = owner {
@@ -185,7 +326,7 @@ fn create_righteous_valkyrie() -> Card {
                                         effect.id = EffectID(id.clone());
                                         effects.push(Arc::new(Mutex::new(effect)));
                                     } else {
-                                        println!("{} is skipping {}, not right type", name, card.lock().await.name);
+                                        debug!("{} is skipping {}, not right type", name, card.lock().await.name);
                                     }
                                 }
                             }
@@ -204,19 +345,17 @@ fn create_righteous_valkyrie() -> Card {
             Arc::new(AsyncClosureWithCardAction::new(Arc::new(
                 |game: Arc<Mutex<Game>>, source: Arc<Mutex<Card>>, card_played: Arc<Mutex<Card>>| -> Pin<Box<dyn Future<Output = ()> + Send>> {
                     Box::pin(async move {
-                        let card = card_played.lock().await;
-                        let owner_arc = card.owner.clone().unwrap();
-
-                        if card.creature_type == Some(CreatureType::Angel) {
-                            let mut owner = owner_arc.lock().await;
-                            println!("{} is an angel!", card.name);
-                            let toughness = card.get_stat_value(StatType::Toughness);
-                            let id = format!("{}-{}", card.name, Ulid::new().to_string());
-
-                            println!("Adding health {}", toughness);
+                        let (owner_arc, toughness) = {
+                            let card = card_played.lock().await;
+                            if card.creature_type != Some(CreatureType::Angel) {
+                                return;
+                            }
+                            debug!("{} is an angel!", card.name);
+                            (card.controller().unwrap(), card.get_stat_value(StatType::Toughness))
+                        };
 
-                            owner.stat_manager.add_stat(id, Stat::new(StatType::Health, toughness));
-                        }
+                        debug!("Adding health {}", toughness);
+                        game.lock().await.gain_life(&owner_arc, toughness).await;
                     })
                 }
             )))
@@ -231,15 +370,25 @@ mod test {
     use tokio::sync::{Mutex, RwLock};
 
     use crate::game::{
+        action::{ActionTriggerType, TriggerTarget},
+        card::{Card, CardPhase},
         decks::{
+            black::create_hydra,
             duplicate_card,
-            white::{create_angelic_accord, create_righteous_valkyrie},
+            white::{
+                create_angelic_accord, create_azorius_guildgate, create_master_decoy,
+                create_palace_sentinels, create_plains, create_righteous_valkyrie,
+                create_sheltering_light,
+            },
             Deck,
         },
         effects::EffectTarget,
-        mana,
+        mana::{self, ManaType},
         player::Player,
-        Game,
+        stat::{Stat, StatType, Stats},
+        turn::TurnPhase,
+        zone::Zone,
+        Game, PaymentChoice,
     };
 
     #[tokio::test]
@@ -397,4 +546,219 @@ mod test {
         }
         ga.lock().await.print().await;
     }
+
+    #[tokio::test]
+    async fn test_angelic_accord_counts_life_gained() {
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new("test", 20, vec![create_angelic_accord()]))
+            .await;
+        game.add_player(Player::new("opponent", 20, vec![])).await;
+        game.start_turn(0).await;
+
+        let accord = player.lock().await.draw_card().unwrap();
+        accord.lock().await.owner = Some(player.clone());
+        player.lock().await.cards_in_play.push(accord);
+
+        // Net life is only up by one, but four was gained
+        game.gain_life(&player, 4).await;
+        player
+            .lock()
+            .await
+            .add_stat("damage".to_string(), Stat::new(StatType::Health, -3));
+
+        while game.current_turn.as_ref().unwrap().phase != TurnPhase::End {
+            game.advance_turn().await;
+        }
+
+        let player = player.lock().await;
+        assert_eq!(player.life_gained_this_turn, 4);
+        assert_eq!(player.cards_in_play.len(), 2);
+        assert_eq!(player.cards_in_play[1].lock().await.name, "Token");
+    }
+
+    #[tokio::test]
+    async fn test_angelic_accord_rechecks_life_gained_on_resolution() {
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new("test", 20, vec![create_angelic_accord()]))
+            .await;
+        game.start_turn(0).await;
+        let accord = {
+            let mut player_lock = player.lock().await;
+            player_lock.draw_card();
+            let accord = player_lock.cards_in_hand.pop().unwrap();
+            player_lock.cards_in_play.push(accord.clone());
+            accord
+        };
+        accord.lock().await.controller = Some(player.clone());
+        let mut turn = game.current_turn.clone().unwrap();
+        turn.phase = TurnPhase::End;
+        let end_step = ActionTriggerType::PhaseStarted(vec![TurnPhase::End], TriggerTarget::Any);
+
+        // Not enough life gained, so it never goes on the stack
+        player.lock().await.life_gained_this_turn = 3;
+        assert!(
+            Card::collect_phase_based_actions(&accord, &turn, end_step.clone())
+                .await
+                .is_empty()
+        );
+
+        player.lock().await.life_gained_this_turn = 4;
+        let actions = Card::collect_phase_based_actions(&accord, &turn, end_step).await;
+        assert_eq!(actions.len(), 1);
+
+        // The condition stops holding before the trigger resolves
+        player.lock().await.life_gained_this_turn = 0;
+        for action in actions {
+            game.add_to_stack(action);
+        }
+        game.resolve_stack().await;
+
+        assert_eq!(player.lock().await.cards_in_play.len(), 1);
+        assert!(game
+            .turn_messages
+            .iter()
+            .any(|message| message == "Angelic Accord's trigger no longer applies."));
+    }
+
+    #[tokio::test]
+    async fn test_paying_white_from_a_dual_instead_of_a_plains() {
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new(
+                "test",
+                20,
+                vec![
+                    create_palace_sentinels(),
+                    create_plains(),
+                    create_azorius_guildgate(),
+                ],
+            ))
+            .await;
+        game.start_turn(0).await;
+        game.current_turn.as_mut().unwrap().phase = TurnPhase::Main;
+
+        let mut lands = vec![];
+        for _ in 0..2 {
+            let mut player_lock = player.lock().await;
+            player_lock.draw_card();
+            let card = player_lock.cards_in_hand.pop().unwrap();
+            player_lock.cards_in_play.push(card.clone());
+            lands.push(card);
+        }
+        player.lock().await.draw_card();
+        let (guildgate, plains) = (&lands[0], &lands[1]);
+        let guildgate_id = guildgate.lock().await.id.clone();
+
+        // Blue doesn't pay for {W}
+        let blue = vec![PaymentChoice {
+            card_id: guildgate_id.clone(),
+            mana: ManaType::Blue,
+        }];
+        assert!(game.pay_with(&player, 0, &blue).await.is_err());
+        assert!(!guildgate.lock().await.tapped);
+
+        let white = vec![PaymentChoice {
+            card_id: guildgate_id,
+            mana: ManaType::White,
+        }];
+        game.pay_with(&player, 0, &white).await.unwrap();
+        assert!(guildgate.lock().await.tapped);
+        assert!(!plains.lock().await.tapped);
+
+        game.play_card(&player, 0, None).await.unwrap();
+        assert_eq!(player.lock().await.mana_pool.white, 0);
+    }
+
+    #[tokio::test]
+    async fn test_master_decoy_taps_target_creature() {
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new("test", 20, vec![create_master_decoy()]))
+            .await;
+        let opponent = game
+            .add_player(Player::new("opponent", 20, vec![create_hydra()]))
+            .await;
+        game.start_turn(0).await;
+        let decoy = player.lock().await.draw_card().unwrap();
+        let hydra = opponent.lock().await.draw_card().unwrap();
+        game.move_card(&decoy, Zone::Hand, Zone::Battlefield)
+            .await
+            .unwrap();
+        game.move_card(&hydra, Zone::Hand, Zone::Battlefield)
+            .await
+            .unwrap();
+        decoy.lock().await.current_phase = CardPhase::Ready;
+        let trigger_id = decoy
+            .lock()
+            .await
+            .triggers
+            .iter()
+            .find(|trigger| {
+                matches!(
+                    &trigger.trigger_type,
+                    ActionTriggerType::AbilityWithinPhases(description, _, _, _)
+                        if description.starts_with("{W}, {T}")
+                )
+            })
+            .unwrap()
+            .id
+            .clone();
+
+        game.activate_card_action(
+            &player,
+            0,
+            Some(EffectTarget::Card(hydra.clone())),
+            trigger_id,
+        )
+        .await
+        .unwrap();
+
+        assert!(hydra.lock().await.tapped);
+        assert!(decoy.lock().await.tapped);
+    }
+
+    #[tokio::test]
+    async fn test_sheltering_light_saves_a_blocked_attacker() {
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new(
+                "test",
+                20,
+                vec![create_sheltering_light(), create_master_decoy()],
+            ))
+            .await;
+        let opponent = game
+            .add_player(Player::new("opponent", 20, vec![create_palace_sentinels()]))
+            .await;
+        game.start_turn(0).await;
+        let decoy = player.lock().await.draw_card().unwrap();
+        player.lock().await.draw_card();
+        let sentinels = opponent.lock().await.draw_card().unwrap();
+        game.move_card(&decoy, Zone::Hand, Zone::Battlefield)
+            .await
+            .unwrap();
+        game.move_card(&sentinels, Zone::Hand, Zone::Battlefield)
+            .await
+            .unwrap();
+        player.lock().await.mana_pool.add_mana(ManaType::White);
+
+        game.play_card(&player, 0, Some(EffectTarget::Card(decoy.clone())))
+            .await
+            .unwrap();
+        game.resolve_stack().await;
+
+        // The 2 damage from the blocker would otherwise kill the 1/2
+        game.combat
+            .declare_attacker(decoy.clone(), EffectTarget::Player(opponent.clone()))
+            .await;
+        game.combat
+            .declare_blocker(sentinels.clone(), decoy.clone())
+            .await;
+        let destroyed = game.combat.resolve_combat().await;
+
+        assert!(!destroyed.iter().any(|card| Arc::ptr_eq(card, &decoy)));
+        assert!(!decoy.lock().await.prevent_next_combat_damage);
+    }
 }
@@ -1,12 +1,13 @@
 use fmt::Debug;
 use std::{any::Any, collections::HashMap, fmt, future::Future, pin::Pin, sync::Arc};
 use tokio::sync::Mutex;
-use ulid::Ulid;
+use tracing::debug;
 use uuid::Uuid;
 
 use super::{
     action::{ActionTriggerType, CardAction, CardActionTarget},
     card::{Card, CardType, CreatureType},
+    mana::ManaType,
     player::Player,
     stat::{Stat, StatType, Stats},
     turn::{Turn, TurnPhase},
@@ -24,6 +25,8 @@ pub enum ModifyStatTarget {
 pub enum EffectTarget {
     Player(Arc<Mutex<Player>>),
     Card(Arc<Mutex<Card>>),
+    // Several cards at once, for spells that target "up to N" of something
+    Cards(Vec<Arc<Mutex<Card>>>),
 }
 
 // Define a unique identifier for each effect
@@ -52,6 +55,8 @@ pub trait Effect: Send + Sync + Debug {
     }
     async fn cleanup(&mut self) {}
     fn get_id(&self) -> &EffectID;
+    // Lets a caller key an effect so re-applying it replaces instead of stacking
+    fn set_id(&mut self, _id: EffectID) {}
     fn get_final_id(&self) -> EffectID {
         self.get_id().clone()
     }
@@ -88,21 +93,28 @@ impl EffectManager {
     }
 
     pub async fn apply_effects(&mut self, turn: Turn) {
-        println!("Apply effects called!");
         let effect_ids: Vec<EffectID> = self.effects.keys().cloned().collect();
         for effect_id in effect_ids {
             if let Some(effect_arc) = self.effects.clone().get(&effect_id) {
                 let mut effect = effect_arc.lock().await;
-                println!(
-                    "Applying effect {:?} for card {}",
-                    effect,
-                    effect.get_source_card().clone().unwrap().lock().await.name
-                );
+                let source = effect.get_source_card().unwrap();
+                let (source, phased_out) = {
+                    let source = source.lock().await;
+                    (source.name.clone(), source.phased_out)
+                };
+                // A phased out source counts as gone, same as leaving play
+                if phased_out {
+                    debug!(card = %source, "source phased out, cleaning up effect");
+                    effect.cleanup().await;
+                    self.effects.remove(&effect_id);
+                    continue;
+                }
+                debug!(card = %source, "applying effect {:?}", effect);
                 {
                     effect.apply(turn.clone()).await;
                 }
                 if effect.is_expired() {
-                    println!("cleaning up effect.");
+                    debug!("cleaning up effect");
                     effect.cleanup().await;
                     self.effects.remove(&effect_id);
                 }
@@ -150,17 +162,14 @@ impl EffectManager {
             }
         }
 
-        println!(
-            "removing effects: {:?}\n\nbefore:\n{:?}",
-            effect_ids_to_remove, self.effects
-        );
+        debug!(removing = ?effect_ids_to_remove, "removing effects by source");
 
         // Remove effects after iteration
         for effect_id in effect_ids_to_remove {
             self.effects.remove(&effect_id);
         }
 
-        println!("\n\nafter:\n{:?}\n\n", self.effects);
+        debug!(remaining = self.effects.len(), "removed effects");
         self.apply_effects(turn).await;
     }
 }
@@ -230,6 +239,13 @@ impl Effect for StatModifierEffect {
                         .stat_manager
                         .add_stat(id, Stat::new(self.stat_type, self.amount));
                 }
+                EffectTarget::Cards(cards) => {
+                    for card_arc in cards {
+                        let mut card = card_arc.lock().await;
+                        card.stats
+                            .add_stat(id.clone(), Stat::new(self.stat_type, self.amount));
+                    }
+                }
             }
             self.applied = true;
         }
@@ -240,7 +256,7 @@ impl Effect for StatModifierEffect {
                 if let Some(prev) = self.previous_turn {
                     if prev != turn.turn_number && *remaining > 0 {
                         *remaining -= 1;
-                        println!("remaining {:?}", remaining);
+                        debug!("remaining {:?}", remaining);
                     }
                 } else {
                     self.previous_turn = Some(turn.turn_number);
@@ -269,19 +285,84 @@ impl Effect for StatModifierEffect {
                 let mut player = player_arc.lock().await;
                 player.stat_manager.remove_stat(id_str);
             }
+            EffectTarget::Cards(cards) => {
+                for card_arc in cards {
+                    card_arc.lock().await.stats.remove_stat(id_str.clone());
+                }
+            }
         }
     }
 
     fn get_id(&self) -> &EffectID {
         &self.id
     }
+
+    fn set_id(&mut self, id: EffectID) {
+        self.id = id;
+    }
+}
+
+pub type AmountCalculator =
+    Arc<dyn Fn(Arc<Mutex<Card>>) -> Pin<Box<dyn Future<Output = i8> + Send>> + Send + Sync>;
+
+// What a dynamic effect's generator hands back
+pub type GeneratedEffects =
+    Pin<Box<dyn Future<Output = Vec<Arc<Mutex<dyn Effect + Send + Sync>>>> + Send>>;
+
+// Something an effect can scale on, counted from the point of view of the
+// source card's controller.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DynamicValue {
+    PermanentsOfType(CardType),
+    // Devotion: mana symbols of the color among the costs of your permanents
+    ManaSymbols(ManaType),
+    CardsInHand,
+    CardsInGraveyard,
+}
+
+impl DynamicValue {
+    pub async fn calculate(&self, card: &Arc<Mutex<Card>>) -> i8 {
+        let Some(controller) = card.lock().await.controller() else {
+            return 0;
+        };
+        let controller = controller.lock().await;
+
+        let total = match self {
+            DynamicValue::PermanentsOfType(card_type) => {
+                let mut total = 0;
+                for card_in_play in &controller.cards_in_play {
+                    if card_in_play.lock().await.card_type == *card_type {
+                        total += 1;
+                    }
+                }
+                total
+            }
+            DynamicValue::ManaSymbols(mana_type) => {
+                let mut total = 0;
+                for card_in_play in &controller.cards_in_play {
+                    total += card_in_play.lock().await.colored_pips(*mana_type) as usize;
+                }
+                total
+            }
+            DynamicValue::CardsInHand => controller.cards_in_hand.len(),
+            DynamicValue::CardsInGraveyard => controller.deck.destroyed_pile.len(),
+        };
+
+        i8::try_from(total).unwrap_or(i8::MAX)
+    }
+
+    pub fn calculator(self) -> AmountCalculator {
+        Arc::new(move |card: Arc<Mutex<Card>>| {
+            let value = self.clone();
+            Box::pin(async move { value.calculate(&card).await })
+        })
+    }
 }
 
 pub struct DynamicStatModifierEffect {
     pub target: EffectTarget,
     pub stat_type: StatType,
-    pub amount_calculator:
-        Arc<dyn Fn(Arc<Mutex<Card>>) -> Pin<Box<dyn Future<Output = i8> + Send>> + Send + Sync>,
+    pub amount_calculator: AmountCalculator,
     pub expires: ExpireContract,
     pub id: EffectID,
     pub applied: bool,
@@ -306,9 +387,7 @@ impl DynamicStatModifierEffect {
     pub fn new(
         target: EffectTarget,
         stat_type: StatType,
-        amount_calculator: Arc<
-            dyn Fn(Arc<Mutex<Card>>) -> Pin<Box<dyn Future<Output = i8> + Send>> + Send + Sync,
-        >,
+        amount_calculator: AmountCalculator,
         expires: ExpireContract,
         source_card: Option<Arc<Mutex<Card>>>,
         permanent_change: bool,
@@ -336,6 +415,10 @@ impl Effect for DynamicStatModifierEffect {
         &self.id
     }
 
+    fn set_id(&mut self, id: EffectID) {
+        self.id = id;
+    }
+
     // fn get_final_id(&self) -> EffectID {
     //     if self.permanent_change {
     //         println!("it's a permenant change");
@@ -362,6 +445,11 @@ impl Effect for DynamicStatModifierEffect {
                     let mut player = player_arc.lock().await;
                     player.stat_manager.remove_stat(id_str);
                 }
+                EffectTarget::Cards(cards) => {
+                    for card_arc in cards {
+                        card_arc.lock().await.stats.remove_stat(id_str.clone());
+                    }
+                }
             }
         }
     }
@@ -393,6 +481,17 @@ impl Effect for DynamicStatModifierEffect {
                             .add_stat(id, Stat::new(self.stat_type, amount));
                     }
                 }
+                EffectTarget::Cards(cards) => {
+                    for card_arc in cards {
+                        let mut card = card_arc.lock().await;
+                        if self.permanent_change {
+                            card.stats.modify_stat(self.stat_type, amount);
+                        } else {
+                            card.stats
+                                .add_stat(id.clone(), Stat::new(self.stat_type, amount));
+                        }
+                    }
+                }
             }
             self.applied = true;
         }
@@ -495,16 +594,12 @@ impl CardAction for LifeLinkAction {
     async fn apply(&self, game: &mut Game, card_arc: Arc<Mutex<Card>>, target: EffectTarget) {
         let (owner, amount) = {
             let lock = card_arc.lock().await;
-            let owner = lock.owner.clone();
+            let owner = lock.controller();
             let amount = lock.damage_dealt_to_players.clone();
             (owner, amount)
         };
         if let Some(owner) = owner {
-            owner
-                .lock()
-                .await
-                .stat_manager
-                .add_stat(Ulid::new().to_string(), Stat::new(StatType::Health, amount));
+            game.gain_life(&owner, amount).await;
         }
     }
 }
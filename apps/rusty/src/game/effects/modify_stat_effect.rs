@@ -1,6 +1,7 @@
 use std::{cell::RefCell, rc::Rc, sync::Arc};
 
 use tokio::sync::Mutex;
+use tracing::warn;
 
 use crate::game::{
     action::{Action, CardAction},
@@ -43,10 +44,7 @@ impl CardAction for ModifyStatAction {
                     let mut owner = owner.lock().await;
                     owner.modify_stat(self.stat, self.amount);
                 } else {
-                    println!(
-                        "no owner of card {}",
-                        card.lock().await.render(30).join("\n")
-                    );
+                    warn!("card has no owner");
                 }
                 // let mut owner = card.lock().await.owner.and_then(|f| f)owner.lock().await;
             }
@@ -0,0 +1,68 @@
+use std::fmt;
+
+use super::turn::TurnPhase;
+
+// Errors the frontend may want to tell apart. Most of the game still reports
+// plain strings, so these turn into one wherever they meet that.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameError {
+    // The ability exists, it just can't be used in this phase
+    WrongPhase {
+        ability: String,
+        phase: TurnPhase,
+        allowed: Vec<TurnPhase>,
+    },
+    // The stack kept refilling itself, named by what was left on it
+    LoopDetected {
+        triggers: Vec<String>,
+    },
+    // A creature that attacks each combat if able wasn't declared
+    MustAttack {
+        creature: String,
+    },
+    // A goaded creature went after the player who goaded it when it had a choice
+    GoadedAttack {
+        creature: String,
+        player: String,
+    },
+    // Someone is still deciding whether to mulligan
+    OpeningHands,
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameError::WrongPhase {
+                ability,
+                phase,
+                allowed,
+            } => write!(
+                f,
+                "{} can only be used during {:?}, not {:?}",
+                ability, allowed, phase
+            ),
+            GameError::LoopDetected { triggers } => {
+                write!(f, "Stopped an endless loop between {}", triggers.join(", "))
+            }
+            GameError::MustAttack { creature } => {
+                write!(f, "{} must attack this combat", creature)
+            }
+            GameError::GoadedAttack { creature, player } => {
+                write!(
+                    f,
+                    "{} was goaded and must attack someone other than {}",
+                    creature, player
+                )
+            }
+            GameError::OpeningHands => {
+                write!(f, "Waiting for everyone to keep their opening hand")
+            }
+        }
+    }
+}
+
+impl From<GameError> for String {
+    fn from(err: GameError) -> String {
+        err.to_string()
+    }
+}
@@ -32,7 +32,6 @@ pub struct ManaPool {
     pub red: u8,
     pub green: u8,
     pub colorless: u8,
-    pub played_card: bool,
 }
 
 impl ManaPool {
@@ -69,7 +68,6 @@ impl ManaPool {
             red: 0,
             green: 0,
             colorless: 0,
-            played_card: false,
         }
     }
 
@@ -89,9 +87,7 @@ impl ManaPool {
     }
 
     pub fn empty_pool(&mut self) {
-        let played_card = self.played_card;
         *self = ManaPool::new();
-        self.played_card = played_card;
     }
 
     pub fn format_mana(&self) -> String {
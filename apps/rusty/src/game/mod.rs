@@ -1,7 +1,7 @@
 use std::{
     borrow::{Borrow, BorrowMut},
     cell::RefCell,
-    collections::{HashMap, HashSet},
+    collections::{HashMap, HashSet, VecDeque},
     fmt,
     future::Future,
     pin::Pin,
@@ -12,40 +12,59 @@ use std::{
 };
 
 use action::{
+    delayed::{DelayedTrigger, EchoAction},
+    draw::{self, DrawReplacement, DrawSource},
     Action, ActionTriggerType, AsyncClosureAction, CardAction, CardActionTarget, CardActionTrigger,
-    CardActionWrapper, CardRequiredTarget, CombatDamageAction, DestroyTargetCAction, TriggerTarget,
+    CardActionWrapper, CardRequiredTarget, CombatDamageAction, DestroyTargetCAction,
+    OrderBlockersAction, PlayerActionTarget, StackItem, StackItemKind, TriggerTarget,
 };
 use card::{Card, CardPhase, CardType};
 use combat::Combat;
+use cost::Cost;
+use decks::Decklist;
 use effects::{EffectID, EffectManager, EffectTarget};
+use error::GameError;
 use mana::{ManaPool, ManaType};
-use player::Player;
+use player::{Player, OPENING_HAND_SIZE};
+use rand::Rng;
 use redis::Pipeline;
+use replay::{replay_target, Replay, ReplayInput};
+use rng::GameRng;
 use serde::{Deserialize, Serialize};
 use specta::Type;
-use stat::{StatManager, StatType, Stats};
+use stat::{Stat, StatType, Stats};
 use tokio::{
     select,
     sync::{broadcast, mpsc, Mutex, Notify, RwLock},
     time::{sleep, timeout, Instant},
 };
-use turn::{Turn, TurnPhase};
+use tracing::{debug, info, info_span, warn, Instrument};
+use turn::{Turn, TurnPhase, PHASE_ORDER};
 use ulid::Ulid;
+use zone::Zone;
 
 use crate::lobby::{
     lobby::DeckSelector,
-    manager::{AbilityDetails, ExecuteAbility, LobbyCommand, LobbyTurnMessage},
+    manager::{
+        AbilityDetails, ExecuteAbility, LobbyCommand, LobbyTurnMessage, ManaAdded, ManaPoolUpdate,
+    },
 };
+use crate::metrics::METRICS;
 
 pub mod action;
 pub mod card;
 pub mod combat;
+pub mod cost;
 pub mod decks;
 pub mod effects;
+pub mod error;
 pub mod mana;
 pub mod player;
+pub mod replay;
+pub mod rng;
 pub mod stat;
 pub mod turn;
+pub mod zone;
 
 enum PhaseAction {
     Untap(usize),
@@ -62,6 +81,9 @@ pub enum GameStatus {
     NeedsPlayers,
     InGame,
     WaitingForStart(i32),
+    // Between the games of a best-of match
+    Sideboarding,
+    GameOver,
 }
 
 impl Default for GameStatus {
@@ -84,12 +106,22 @@ pub enum PlayerStatus {
     InGame,
 }
 
+// Something a player could do at instant speed right now.
+#[derive(Type, Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub enum LegalAction {
+    CastSpell { card_id: String },
+    FlashbackSpell { card_id: String },
+    ActivateAbility { card_id: String, trigger_id: String },
+    TurnFaceUp { card_id: String },
+}
+
 #[derive(Type, Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub enum ActionType {
     Tap,
     None,
     Instant,
     Attach,
+    Equip,
     PlayedCard,
 }
 
@@ -119,6 +151,18 @@ impl CardWithDetails {
 
         let mut abilities = vec![];
         for trigger in card.triggers.iter() {
+            let has_target = async {
+                match (&game_arc, card.controller()) {
+                    (Some(game_arc), Some(chooser)) => {
+                        game_arc
+                            .lock()
+                            .await
+                            .has_legal_target(&trigger.card_required_target, &chooser)
+                            .await
+                    }
+                    _ => true,
+                }
+            };
             match &trigger.trigger_type {
                 // action::ActionTriggerType::CardTapped => {
                 //     return (trigger.card_required_target.clone(), ActionType::Tap)
@@ -130,32 +174,53 @@ impl CardWithDetails {
                 // }
                 action::ActionTriggerType::CardPlayedFromHand => {
                     if &trigger.card_required_target != &CardRequiredTarget::None {
+                        let has_target = has_target.await;
                         abilities.push(AbilityDetails {
                             id: trigger.id.clone(),
                             action_type: ActionType::PlayedCard,
                             mana_cost: vec![],
                             required_target: trigger.card_required_target.clone(),
                             description: "Play".to_string(),
-                            show: true,
-                            meets_requirements: true,
+                            show: has_target,
+                            meets_requirements: has_target,
                         });
                     }
                 }
 
                 action::ActionTriggerType::Attached => {
                     if &turn_phase == &TurnPhase::Main {
+                        let has_target = has_target.await;
                         abilities.push(AbilityDetails {
                             id: trigger.id.clone(),
                             action_type: ActionType::Attach,
                             mana_cost: vec![],
                             required_target: trigger.card_required_target.clone(),
                             description: "Attach".to_string(),
-                            show: in_play,
-                            meets_requirements: true,
+                            show: in_play && has_target,
+                            meets_requirements: has_target,
                         });
                     }
                 }
 
+                action::ActionTriggerType::Equip(required_mana)
+                    if turn_phase == TurnPhase::Main || turn_phase == TurnPhase::Main2 =>
+                {
+                    let can_pay_mana = match &card.controller() {
+                        Some(owner) => owner.lock().await.can_pay_mana(required_mana).await,
+                        None => false,
+                    };
+                    let has_target = has_target.await;
+                    abilities.push(AbilityDetails {
+                        id: trigger.id.clone(),
+                        action_type: ActionType::Equip,
+                        mana_cost: required_mana.clone(),
+                        required_target: trigger.card_required_target.clone(),
+                        description: "Equip".to_string(),
+                        show: in_play && has_target,
+                        meets_requirements: can_pay_mana && has_target,
+                    });
+                }
+
                 action::ActionTriggerType::AbilityWithinPhases(
                     description,
                     required_mana,
@@ -167,7 +232,7 @@ impl CardWithDetails {
                         .and_then(|phase| Some(phase.contains(&turn_phase)))
                         .unwrap_or(true);
 
-                    if let Some(owner) = &card.owner {
+                    if let Some(owner) = &card.controller() {
                         let can_pay_mana = { owner.lock().await.can_pay_mana(required_mana).await };
                         if can_pay_mana && within_phase {
                             let mut meets_requirements = true;
@@ -180,6 +245,7 @@ impl CardWithDetails {
                                     .await;
                                 }
                             }
+                            let has_target = has_target.await;
 
                             abilities.push(AbilityDetails {
                                 id: trigger.id.clone(),
@@ -191,8 +257,8 @@ impl CardWithDetails {
                                 } else {
                                     ActionType::None
                                 },
-                                show: in_play || required_with_phases.is_none(),
-                                meets_requirements,
+                                show: (in_play || required_with_phases.is_none()) && has_target,
+                                meets_requirements: meets_requirements && has_target,
                             });
                             // return (trigger.card_required_target.clone(), action_type);
                         }
@@ -223,11 +289,31 @@ impl CardWithDetails {
         CardWithDetails { card, abilities }
     }
 
+    // Sent to every player, so face down cards only show as a 2/2
     pub async fn from_card(card: Card, turn_phase: TurnPhase, in_play: bool) -> CardWithDetails {
+        if card.face_down {
+            return CardWithDetails::face_down(&card);
+        }
         let abilities =
             CardWithDetails::get_abilities(&card, turn_phase, in_play, None, None).await;
         CardWithDetails { card, abilities }
     }
+
+    // A face down card as everyone but its controller sees it
+    pub fn face_down(card: &Card) -> CardWithDetails {
+        CardWithDetails {
+            card: card.face_down_view(),
+            abilities: vec![],
+        }
+    }
+
+    // A face down card as its controller sees it. Its real abilities can't be
+    // used until it's face up, so none are shown as usable.
+    pub async fn hidden_face(card: &Card, turn_phase: TurnPhase) -> CardWithDetails {
+        let card = card.hidden_face_view();
+        let abilities = CardWithDetails::get_abilities(&card, turn_phase, false, None, None).await;
+        CardWithDetails { card, abilities }
+    }
 }
 
 #[derive(Type, Deserialize, Serialize, Debug, Clone)]
@@ -240,12 +326,18 @@ pub struct PriorityQueue {
 pub struct PlayerState {
     pub public_info: PublicPlayerInfo,
     pub hand: Vec<CardWithDetails>,
+    // The top of their library, while an effect reveals it to them
+    pub top_card: Option<CardWithDetails>,
+    // What their face down cards in play really are
+    pub face_down_cards: Vec<CardWithDetails>,
     pub discard_pile: Vec<CardWithDetails>,
     pub status: PlayerStatus,
     pub is_leader: bool,
     pub player_index: i32,
     pub priority_queue: Option<PriorityQueue>,
     pub deck: DeckSelector,
+    // Registered when the player readies up for the first game of a match
+    pub decklist: Option<Decklist>,
 
     #[serde(skip_serializing, skip_deserializing)]
     pub player: Arc<Mutex<Player>>,
@@ -259,9 +351,13 @@ impl PlayerState {
                 hand_size: 0,
                 mana_pool: ManaPool::new(),
                 health: 10,
+                revealed_top_card: None,
             },
             deck: DeckSelector::Green,
+            decklist: None,
             hand: vec![],
+            top_card: None,
+            face_down_cards: vec![],
             discard_pile: vec![],
             status: PlayerStatus::Spectator,
             player,
@@ -277,12 +373,14 @@ pub enum FrontendPileName {
     Hand,
     Play,
     Spell,
+    Library,
 }
 
 #[derive(Type, Deserialize, Serialize, Debug, Clone)]
 pub enum FrontendTarget {
     Card(FrontendCardTarget),
     Player(i32),
+    Cards(Vec<FrontendCardTarget>),
 }
 
 #[derive(Type, Deserialize, Serialize, Debug, Clone)]
@@ -310,6 +408,27 @@ pub struct PublicGameInfo {
     pub priority_queue: Option<PriorityQueue>,
     pub attacks: Vec<Attack>,
     pub blocks: Vec<Block>,
+    // Id of the player who is the monarch, if anyone
+    pub monarch: Option<String>,
+    pub stack: Vec<StackItemView>,
+    // Ids of the players who still have to mulligan or keep their hand
+    pub deciding_hands: Vec<String>,
+}
+
+// One thing waiting on the stack. Name is the card's, or a description for
+// the few items with no card behind them.
+#[derive(Type, Deserialize, Serialize, Debug, Clone)]
+pub struct StackItemView {
+    pub kind: StackItemKind,
+    pub card_id: Option<String>,
+    pub name: String,
+}
+
+// One land a player picked to pay with, and the color it should make.
+#[derive(Type, Deserialize, Serialize, Debug, Clone)]
+pub struct PaymentChoice {
+    pub card_id: String,
+    pub mana: ManaType,
 }
 
 #[derive(Type, Deserialize, Serialize, Debug, Clone)]
@@ -319,6 +438,8 @@ pub struct PublicPlayerInfo {
     pub spells: Vec<CardWithDetails>,
     pub mana_pool: ManaPool,
     pub health: i8,
+    // The top of their library, while an effect reveals it to everyone
+    pub revealed_top_card: Option<CardWithDetails>,
 }
 
 enum PriorityActionResult {
@@ -348,8 +469,53 @@ pub struct Game {
     pub turn_messages: Vec<String>,
     #[serde(skip_serializing, skip_deserializing)]
     pub abilities: HashMap<String, Ability>,
+    #[serde(skip_serializing, skip_deserializing)]
+    pub state_notify: Arc<Notify>,
+    // Every player in seating order, including those who have been eliminated
+    #[serde(skip_serializing, skip_deserializing)]
+    pub seats: Vec<Arc<Mutex<Player>>>,
+    #[serde(skip_serializing, skip_deserializing)]
+    pub extra_turns: VecDeque<Arc<Mutex<Player>>>,
+    #[serde(skip_serializing, skip_deserializing)]
+    pub skipped_turns: Vec<Arc<Mutex<Player>>>,
+    #[serde(skip_serializing, skip_deserializing)]
+    pub rng: GameRng,
+    // Draws an extra card at their end step until someone deals them combat damage
+    #[serde(skip_serializing, skip_deserializing)]
+    pub monarch: Option<Arc<Mutex<Player>>>,
+    // Fast mode for teaching games: spells resolve without anyone getting priority
+    #[serde(skip_serializing, skip_deserializing)]
+    pub auto_pass_priority: bool,
+    // Only opens a priority window when someone could respond at instant speed
+    #[serde(skip_serializing, skip_deserializing)]
+    pub skip_empty_priority_windows: bool,
+    // Every spell cast by anyone this turn, copies aside
+    pub spells_cast_this_turn: u8,
+    // What's needed to play this game back, once it has started
+    #[serde(skip_serializing, skip_deserializing)]
+    pub replay: Option<Replay>,
+    // How many items one resolve_stack may resolve, STACK_RESOLUTION_LIMIT if unset
+    #[serde(skip_serializing, skip_deserializing)]
+    pub stack_resolution_limit: Option<usize>,
+    // Effects waiting to swap a player's draw for something else
+    #[serde(skip_serializing, skip_deserializing)]
+    pub draw_replacements: Vec<DrawReplacement>,
+    // Triggers waiting on a step later in the game
+    #[serde(skip_serializing, skip_deserializing)]
+    pub delayed_triggers: Vec<DelayedTrigger>,
+    // Set from the start of the game until every player has kept their hand
+    #[serde(skip_serializing, skip_deserializing)]
+    pub awaiting_opening_hands: bool,
+    #[serde(skip_serializing, skip_deserializing)]
+    pub abilities_offered: usize,
+    // The stack item each ability-countering spell was cast at, by the spell's card id
+    #[serde(skip_serializing, skip_deserializing)]
+    pub ability_targets: HashMap<String, Arc<dyn Action + Send + Sync>>,
 }
 
+// Well past anything a real game does in one go, so hitting it means a loop
+pub const STACK_RESOLUTION_LIMIT: usize = 1000;
+
 impl fmt::Debug for Game {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Game")
@@ -371,11 +537,24 @@ impl fmt::Debug for Game {
 pub struct Ability {
     id: String,
     card_arc: Arc<Mutex<Card>>,
-    mana_cost: Vec<ManaType>,
+    costs: Vec<Cost>,
     target: CardRequiredTarget,
     description: String,
     ability: Arc<dyn Fn(Arc<Mutex<Card>>) -> Arc<dyn CardAction + Send + Sync> + Send + Sync>,
     action_type: ActionType,
+    choices: Vec<Arc<Mutex<Card>>>,
+    chooser: Option<Arc<Mutex<Player>>>,
+    // Turn number and phase it was offered in; it lapses once that phase ends
+    asked_in: Option<(usize, TurnPhase)>,
+    // Runs instead when the player turns it down
+    declined: Option<Arc<dyn CardAction + Send + Sync>>,
+    // Not answering before the phase ends counts as turning it down
+    declines_on_lapse: bool,
+    // Has to be answered, so it outlasts the phase it was asked in
+    mandatory: bool,
+    // How many abilities were offered before it this game. Ids are made up
+    // per game, so replays find it again by this.
+    offered: usize,
 }
 
 impl fmt::Debug for Ability {
@@ -383,7 +562,7 @@ impl fmt::Debug for Ability {
         f.debug_struct("Ability")
             .field("id", &self.id)
             .field("card_arc", &self.card_arc)
-            .field("mana_cost", &self.mana_cost)
+            .field("costs", &self.costs)
             .field("target", &self.target)
             .finish()
     }
@@ -400,13 +579,57 @@ impl Ability {
         Self {
             id: Ulid::new().to_string(),
             card_arc,
-            mana_cost,
+            costs: if mana_cost.is_empty() {
+                vec![]
+            } else {
+                vec![Cost::Mana(mana_cost)]
+            },
             target,
             ability,
             action_type,
             description,
+            choices: vec![],
+            chooser: None,
+            asked_in: None,
+            declined: None,
+            declines_on_lapse: false,
+            mandatory: false,
+            offered: 0,
         }
     }
+
+    // Cards the player has to pick their target from, for abilities that
+    // target cards outside of play (e.g. a library search).
+    pub fn with_choices(mut self, choices: Vec<Arc<Mutex<Card>>>) -> Self {
+        self.choices = choices;
+        self
+    }
+
+    // Costs paid on top of the mana, such as life or tapping this card.
+    pub fn with_costs(mut self, costs: Vec<Cost>) -> Self {
+        self.costs.extend(costs);
+        self
+    }
+
+    // Asks this player instead of the card's controller.
+    pub fn with_chooser(mut self, chooser: Arc<Mutex<Player>>) -> Self {
+        self.chooser = Some(chooser);
+        self
+    }
+
+    // What happens if they say no, for abilities offered in place of
+    // something that still has to happen otherwise.
+    pub fn with_declined(mut self, declined: Arc<dyn CardAction + Send + Sync>) -> Self {
+        self.declined = Some(declined);
+        self
+    }
+
+    // For something that has to be dealt with, like an upkeep cost, so
+    // letting the phase go by doesn't get the player out of it.
+    pub fn with_declines_on_lapse(mut self) -> Self {
+        self.declines_on_lapse = true;
+        self
+    }
 }
 
 impl Game {
@@ -425,23 +648,152 @@ impl Game {
             broadcast_sender: Some(sender),
             turn_messages: vec![],
             abilities: HashMap::new(),
+            state_notify: Arc::new(Notify::new()),
+            seats: vec![],
+            extra_turns: VecDeque::new(),
+            skipped_turns: vec![],
+            rng: GameRng::default(),
+            monarch: None,
+            auto_pass_priority: false,
+            skip_empty_priority_windows: false,
+            spells_cast_this_turn: 0,
+            replay: None,
+            stack_resolution_limit: None,
+            draw_replacements: vec![],
+            delayed_triggers: vec![],
+            awaiting_opening_hands: false,
+            abilities_offered: 0,
+            ability_targets: HashMap::new(),
+        }
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = GameRng::seeded(seed);
+        self
+    }
+
+    pub fn with_stack_resolution_limit(mut self, limit: usize) -> Self {
+        self.stack_resolution_limit = Some(limit);
+        self
+    }
+
+    // Like CardRequiredTarget::matches_card, but also checks the board for
+    // requirements that depend on it, such as being in combat.
+    pub fn card_target_matches(
+        &self,
+        required: &CardRequiredTarget,
+        card_arc: &Arc<Mutex<Card>>,
+        card: &Card,
+        owned: bool,
+    ) -> bool {
+        if card.phased_out || !required.matches_card(card, owned) {
+            return false;
+        }
+
+        match required {
+            CardRequiredTarget::EnemyCardInCombat => self.combat.is_in_combat(card_arc),
+            CardRequiredTarget::Ability => self.is_ability_on_stack(card_arc),
+            _ => true,
+        }
+    }
+
+    // Whether `chooser` has anything at all they could point `required` at.
+    pub async fn has_legal_target(
+        &self,
+        required: &CardRequiredTarget,
+        chooser: &Arc<Mutex<Player>>,
+    ) -> bool {
+        required == &CardRequiredTarget::None
+            || !self.targets_for(required, chooser).await.is_empty()
+    }
+
+    // Everything `chooser` could point `required` at, in the shape the
+    // frontend sends targets back in.
+    pub async fn targets_for(
+        &self,
+        required: &CardRequiredTarget,
+        chooser: &Arc<Mutex<Player>>,
+    ) -> Vec<FrontendTarget> {
+        let mut targets = vec![];
+        for (player_index, player_arc) in self.players.iter().enumerate() {
+            let owned = Arc::ptr_eq(player_arc, chooser);
+            let player_targetable = match required {
+                CardRequiredTarget::AnyPlayer => true,
+                CardRequiredTarget::EnemyPlayer | CardRequiredTarget::EnemyCardOrPlayer => !owned,
+                _ => false,
+            };
+            if player_targetable {
+                targets.push(FrontendTarget::Player(player_index as i32));
+            }
+
+            let (pile, cards) = {
+                let player = player_arc.lock().await;
+                if required == &CardRequiredTarget::Spell {
+                    (FrontendPileName::Spell, player.spells.clone())
+                } else {
+                    (FrontendPileName::Play, player.cards_in_play.clone())
+                }
+            };
+            for (card_index, card_arc) in cards.iter().enumerate() {
+                let card = card_arc.lock().await;
+                if self.card_target_matches(required, card_arc, &card, owned) {
+                    targets.push(FrontendTarget::Card(FrontendCardTarget {
+                        player_index: player_index as i32,
+                        pile: pile.clone(),
+                        card_index: card_index as i32,
+                    }));
+                }
+            }
+        }
+
+        targets
+    }
+
+    // What the frontend should highlight once `chooser` picks an ability,
+    // either one they're being asked to respond to or one of the triggers on
+    // the cards in their hand or in play.
+    pub async fn legal_targets(
+        &self,
+        chooser: &Arc<Mutex<Player>>,
+        ability_id: &str,
+    ) -> Vec<FrontendTarget> {
+        let required = match self.abilities.get(ability_id) {
+            Some(ability) => Some(ability.target.clone()),
+            None => {
+                let cards = {
+                    let player = chooser.lock().await;
+                    let mut cards = player.cards_in_hand.clone();
+                    cards.extend(player.cards_in_play.iter().cloned());
+                    cards
+                };
+                let mut required = None;
+                for card in cards {
+                    let card = card.lock().await;
+                    if let Some(trigger) = card
+                        .triggers
+                        .iter()
+                        .find(|trigger| trigger.id == ability_id)
+                    {
+                        required = Some(trigger.card_required_target.clone());
+                        break;
+                    }
+                }
+                required
+            }
+        };
+
+        match required {
+            Some(required) => self.targets_for(&required, chooser).await,
+            None => vec![],
         }
     }
 
-    pub async fn ask_mandatory_player_ability(&mut self, ability: Ability) {
-        self.abilities.insert(ability.id.clone(), ability.clone());
+    pub async fn ask_mandatory_player_ability(&mut self, mut ability: Ability) {
+        ability.mandatory = true;
+        self.track_ability(&ability);
         if let Some(ref sender) = self.broadcast_sender {
-            let player = ability
-                .card_arc
-                .lock()
-                .await
-                .owner
-                .as_ref()
-                .unwrap()
-                .lock()
-                .await
-                .name
-                .clone();
+            let player = Self::chooser_name(&ability).await;
+            let choices = self.choice_details(&ability).await;
             let _ = sender.send(Some(LobbyCommand::MandatoryExecuteAbility(
                 ExecuteAbility::new(
                     player,
@@ -452,45 +804,127 @@ impl Game {
                     )
                     .await,
                     ability.action_type,
-                    ability.mana_cost,
+                    Cost::total_mana(&ability.costs),
                     ability.target,
                     ability.description,
                     ability.id,
                     true,
-                ),
+                )
+                .with_costs(ability.costs)
+                .with_choices(choices),
             )));
         }
     }
 
-    pub async fn request_player_ability(&mut self, ability: Ability) {
-        self.abilities.insert(ability.id.clone(), ability.clone());
-        if let Some(ref sender) = self.broadcast_sender {
-            let player = ability
-                .card_arc
-                .lock()
-                .await
-                .owner
-                .as_ref()
-                .unwrap()
-                .lock()
-                .await
-                .name
-                .clone();
-            let _ = sender.send(Some(LobbyCommand::AskExecuteAbility(ExecuteAbility::new(
-                player,
+    async fn choice_details(&self, ability: &Ability) -> Vec<CardWithDetails> {
+        let mut choices = vec![];
+        for choice in &ability.choices {
+            choices.push(
                 CardWithDetails::from_card(
-                    ability.card_arc.lock().await.clone(),
+                    choice.lock().await.clone(),
                     self.current_phase(),
-                    true,
+                    false,
                 )
                 .await,
-                ability.action_type,
-                ability.mana_cost,
-                ability.target,
-                ability.description,
-                ability.id,
-                true,
-            ))));
+            );
+        }
+        choices
+    }
+
+    // Who answers the ability. Players are locked before cards everywhere
+    // else, so the card's lock has to be let go before taking the player's.
+    async fn chooser(ability: &Ability) -> Option<Arc<Mutex<Player>>> {
+        match &ability.chooser {
+            Some(chooser) => Some(chooser.clone()),
+            None => ability.card_arc.lock().await.controller(),
+        }
+    }
+
+    async fn chooser_name(ability: &Ability) -> String {
+        let chooser = Self::chooser(ability).await.unwrap();
+        let name = chooser.lock().await.name.clone();
+        name
+    }
+
+    // The `index`th card an ability offered to choose from
+    pub fn ability_choice(&self, ability_id: &str, index: usize) -> Option<Arc<Mutex<Card>>> {
+        self.abilities.get(ability_id)?.choices.get(index).cloned()
+    }
+
+    fn track_ability(&mut self, ability: &Ability) {
+        let mut ability = ability.clone();
+        ability.asked_in = self
+            .current_turn
+            .as_ref()
+            .map(|turn| (self.turn_number, turn.phase));
+        ability.offered = self.abilities_offered;
+        self.abilities_offered += 1;
+        self.abilities.insert(ability.id.clone(), ability);
+    }
+
+    // Id of the ability that was the `offered`th offered this game, if it's
+    // still waiting on an answer.
+    pub(crate) fn offered_ability(&self, offered: usize) -> Option<String> {
+        self.abilities
+            .values()
+            .find(|ability| ability.offered == offered)
+            .map(|ability| ability.id.clone())
+    }
+
+    // Drops every optional ability offered before the current phase, answered
+    // or not. Ones that decline on lapse are turned down on the player's
+    // behalf. Mandatory ones stay until they're answered.
+    async fn sweep_stale_abilities(&mut self) {
+        let now = self
+            .current_turn
+            .as_ref()
+            .map(|turn| (self.turn_number, turn.phase));
+        let (current, lapsed): (HashMap<String, Ability>, HashMap<String, Ability>) =
+            std::mem::take(&mut self.abilities)
+                .into_iter()
+                .partition(|(_, ability)| ability.mandatory || ability.asked_in == now);
+        self.abilities = current;
+
+        for ability in lapsed.into_values() {
+            let Some(declined) = ability.declined.filter(|_| ability.declines_on_lapse) else {
+                continue;
+            };
+            let chooser = match ability.chooser {
+                Some(chooser) => Some(chooser),
+                None => ability.card_arc.lock().await.controller(),
+            };
+            if let Some(chooser) = chooser {
+                declined
+                    .apply(self, ability.card_arc, EffectTarget::Player(chooser))
+                    .await;
+            }
+        }
+    }
+
+    pub async fn request_player_ability(&mut self, ability: Ability) {
+        self.track_ability(&ability);
+        if let Some(ref sender) = self.broadcast_sender {
+            let player = Self::chooser_name(&ability).await;
+            let choices = self.choice_details(&ability).await;
+            let _ = sender.send(Some(LobbyCommand::AskExecuteAbility(
+                ExecuteAbility::new(
+                    player,
+                    CardWithDetails::from_card(
+                        ability.card_arc.lock().await.clone(),
+                        self.current_phase(),
+                        true,
+                    )
+                    .await,
+                    ability.action_type,
+                    Cost::total_mana(&ability.costs),
+                    ability.target,
+                    ability.description,
+                    ability.id,
+                    true,
+                )
+                .with_costs(ability.costs)
+                .with_choices(choices),
+            )));
         }
     }
 
@@ -534,6 +968,12 @@ impl Game {
                 .ok_or_else(|| "No ability with that id".to_string())?
                 .clone()
         };
+        let is_chooser = Self::chooser(&ability)
+            .await
+            .is_some_and(|chooser| Arc::ptr_eq(&chooser, player));
+        if !is_chooser {
+            return Err("It's not your choice to make".to_string());
+        }
 
         let phase = {
             let game = game_arc.lock().await;
@@ -542,13 +982,28 @@ impl Game {
         let player = Arc::clone(player);
 
         if response {
-            println!("Processing response for ability: {:?}", ability);
-            if !ability.mana_cost.is_empty() {
-                println!("it has a cost!");
+            debug!("Processing response for ability: {:?}", ability);
+            // Everything but the mana has to be payable right away
+            game_arc
+                .lock()
+                .await
+                .check_costs(&ability.card_arc, &player, &ability.costs)
+                .await?;
+
+            let mana_cost = Cost::total_mana(&ability.costs);
+            if !mana_cost.is_empty() {
+                debug!("it has a cost!");
                 let cloned_ability_id = ability_id.clone();
                 tokio::spawn(async move {
-                    println!("Starting async task for ability...");
+                    debug!("Starting async task for ability...");
+                    let state_notify = game_arc.lock().await.state_notify.clone();
                     loop {
+                        // Register interest before checking, so a change that lands
+                        // between the check and the await is not missed.
+                        let notified = state_notify.notified();
+                        tokio::pin!(notified);
+                        notified.as_mut().enable();
+
                         let current_phase = {
                             let game = game_arc.lock().await;
                             game.current_turn.as_ref().unwrap().phase
@@ -556,39 +1011,222 @@ impl Game {
 
                         // Exit if we're no longer in the correct phase
                         if current_phase != phase {
-                            println!("wrong phase, guess they did not really want to");
+                            debug!("wrong phase, guess they did not really want to");
                             return;
                         }
-                        println!("still in phase...");
+                        debug!("still in phase...");
 
                         // Check if the player can pay the mana cost
-                        let can_pay_mana = player
-                            .lock()
-                            .await
-                            .has_required_mana(&ability.mana_cost)
-                            .await;
+                        let can_pay_mana = player.lock().await.has_required_mana(&mana_cost).await;
 
                         if can_pay_mana {
-                            println!("can pay mana");
-                            player.lock().await.pay_mana(&ability.mana_cost).await;
+                            debug!("can pay mana");
                             let mut game = game_arc.lock().await;
-                            println!("executing");
-                            game.execute_ability(cloned_ability_id, target).await.ok();
+                            if let Err(err) = game
+                                .answer_ability(&player, cloned_ability_id, true, target)
+                                .await
+                            {
+                                warn!("could not pay for the ability: {}", err);
+                            }
                             return;
                         }
 
-                        tokio::time::sleep(tokio::time::Duration::from_micros(100)).await;
+                        notified.await;
                     }
                 });
             } else {
                 let mut game = game_arc.lock().await;
-                game.execute_ability(ability_id, target).await?;
+                game.answer_ability(&player, ability_id, true, target)
+                    .await?;
+            }
+        } else {
+            let mut game = game_arc.lock().await;
+            game.answer_ability(&player, ability_id, false, None)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    // Pays for and runs the ability, or turns it down, once the player is
+    // able to. Recorded here so replays don't have to wait on mana.
+    pub(crate) async fn answer_ability(
+        &mut self,
+        player: &Arc<Mutex<Player>>,
+        ability_id: String,
+        response: bool,
+        target: Option<EffectTarget>,
+    ) -> Result<(), String> {
+        let ability = self
+            .abilities
+            .get(&ability_id)
+            .ok_or_else(|| "No ability with that id".to_string())?
+            .clone();
+        if response {
+            self.pay_costs(&ability.card_arc, player, &ability.costs)
+                .await?;
+        }
+        self.record(ReplayInput::RespondAbility {
+            seat: self.seat_index(player).unwrap_or_default(),
+            ability: ability.offered,
+            response,
+            target: replay_target(self, &target).await,
+        });
+
+        if response {
+            self.execute_ability(ability_id, target).await
+        } else {
+            self.abilities.remove(&ability_id);
+            if let Some(declined) = ability.declined {
+                declined
+                    .apply(
+                        self,
+                        ability.card_arc,
+                        EffectTarget::Player(Arc::clone(player)),
+                    )
+                    .await;
+            }
+            Ok(())
+        }
+    }
+
+    // Checks every cost except mana, which the player may still be tapping
+    // lands for.
+    pub async fn check_costs(
+        &self,
+        card_arc: &Arc<Mutex<Card>>,
+        player: &Arc<Mutex<Player>>,
+        costs: &[Cost],
+    ) -> Result<(), String> {
+        for cost in costs {
+            match cost {
+                Cost::Mana(_) => {}
+                Cost::Life(amount) => {
+                    if player.lock().await.get_stat_value(StatType::Health) < *amount {
+                        return Err("Not enough life to pay".to_string());
+                    }
+                }
+                Cost::Tap => {
+                    let card = card_arc.lock().await;
+                    if card.tapped || card.current_phase != CardPhase::Ready {
+                        return Err(format!("{} can't be tapped", card.name));
+                    }
+                }
+                Cost::TapOther(filter) => {
+                    if self
+                        .find_card_for_cost(card_arc, player, filter, true)
+                        .await
+                        .is_none()
+                    {
+                        return Err("Nothing untapped to tap".to_string());
+                    }
+                }
+                Cost::Sacrifice(filter) => {
+                    if self
+                        .find_card_for_cost(card_arc, player, filter, false)
+                        .await
+                        .is_none()
+                    {
+                        return Err("Nothing to sacrifice".to_string());
+                    }
+                }
+                Cost::Discard(count) => {
+                    if player.lock().await.cards_in_hand.len() < *count as usize {
+                        return Err("Not enough cards in hand to discard".to_string());
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Pays every cost at once, or nothing if any of it can't be paid.
+    pub async fn pay_costs(
+        &mut self,
+        card_arc: &Arc<Mutex<Card>>,
+        player: &Arc<Mutex<Player>>,
+        costs: &[Cost],
+    ) -> Result<(), String> {
+        self.check_costs(card_arc, player, costs).await?;
+        let mana_cost = Cost::total_mana(costs);
+        if !player.lock().await.has_required_mana(&mana_cost).await {
+            return Err("Not enough mana".to_string());
+        }
+
+        for cost in costs {
+            match cost {
+                Cost::Mana(mana) => player.lock().await.pay_mana(mana).await,
+                Cost::Life(amount) => {
+                    player.lock().await.stat_manager.add_stat(
+                        Ulid::new().to_string(),
+                        Stat::new(StatType::Health, -amount),
+                    );
+                }
+                Cost::Tap => {
+                    card_arc.lock().await.tap()?;
+                }
+                Cost::TapOther(filter) => {
+                    let other = self
+                        .find_card_for_cost(card_arc, player, filter, true)
+                        .await
+                        .ok_or("Nothing untapped to tap")?;
+                    other.lock().await.tap()?;
+                }
+                Cost::Sacrifice(filter) => {
+                    let sacrificed = self
+                        .find_card_for_cost(card_arc, player, filter, false)
+                        .await
+                        .ok_or("Nothing to sacrifice")?;
+                    self.sacrifice_card(&sacrificed).await?;
+                }
+                Cost::Discard(count) => {
+                    for _ in 0..*count {
+                        let hand_size = player.lock().await.cards_in_hand.len();
+                        if hand_size == 0 {
+                            break;
+                        }
+                        let index = self.rng.gen_range(0..hand_size);
+                        let discarded = player.lock().await.cards_in_hand[index].clone();
+                        action::discard::discard_card(self, player, discarded).await?;
+                    }
+                }
             }
         }
+        if !mana_cost.is_empty() {
+            self.mana_pool_updated(player).await;
+        }
 
         Ok(())
     }
 
+    // Until players get to pick, the first matching card pays for the cost.
+    async fn find_card_for_cost(
+        &self,
+        card_arc: &Arc<Mutex<Card>>,
+        player: &Arc<Mutex<Player>>,
+        filter: &CardRequiredTarget,
+        untapped_other: bool,
+    ) -> Option<Arc<Mutex<Card>>> {
+        let cards_in_play = player.lock().await.cards_in_play.clone();
+        for card_in_play in cards_in_play {
+            if untapped_other && Arc::ptr_eq(&card_in_play, card_arc) {
+                continue;
+            }
+
+            let card = card_in_play.lock().await;
+            if untapped_other && (card.tapped || card.current_phase != CardPhase::Ready) {
+                continue;
+            }
+            if self.card_target_matches(filter, &card_in_play, &card, true) {
+                drop(card);
+                return Some(card_in_play);
+            }
+        }
+
+        None
+    }
+
     pub async fn execute_ability(
         &mut self,
         ability_id: String,
@@ -601,11 +1239,14 @@ impl Game {
 
         let card_arc = ability.card_arc.clone();
         let action = (ability.ability)(card_arc.clone());
-        self.add_to_stack(Arc::new(CardActionWrapper {
-            card: card_arc,
-            action,
-            target,
-        }));
+        self.add_to_stack_as(
+            StackItemKind::Ability,
+            Arc::new(CardActionWrapper {
+                card: card_arc,
+                action,
+                target,
+            }),
+        );
         self.resolve_stack().await;
 
         Ok(())
@@ -654,10 +1295,19 @@ impl Game {
         target: &EffectTarget,
     ) -> FrontendTarget {
         match target {
-            EffectTarget::Player(arc) => FrontendTarget::Player(0),
+            EffectTarget::Player(arc) => {
+                FrontendTarget::Player(self.player_index(arc).unwrap_or_default() as i32)
+            }
             EffectTarget::Card(arc) => {
                 FrontendTarget::Card(self.frontend_target_from_card(arc).await)
             }
+            EffectTarget::Cards(arcs) => {
+                let mut targets = vec![];
+                for arc in arcs {
+                    targets.push(self.frontend_target_from_card(arc).await);
+                }
+                FrontendTarget::Cards(targets)
+            }
         }
     }
 
@@ -665,41 +1315,351 @@ impl Game {
         self.event_stack.push(action);
     }
 
-    pub async fn reset_creature_damage(&mut self) {
-        for player_arc in &self.players {
-            let mut player = player_arc.lock().await;
-            for card_arc in &player.cards_in_play {
-                let mut card = card_arc.lock().await;
-                if card.card_type == CardType::Creature {
-                    card.damage_taken = 0;
-                    card.damage_dealt_to_players = 0;
-                }
-            }
-        }
+    // For actions that don't know on their own whether they're a spell, an
+    // activated ability or a trigger.
+    pub fn add_to_stack_as(&mut self, kind: StackItemKind, action: Arc<dyn Action + Send + Sync>) {
+        self.add_to_stack(Arc::new(StackItem { kind, action }));
     }
 
-    pub async fn resolve_stack(&mut self) {
-        while let Some(action) = self.event_stack.pop() {
-            action.apply(self).await;
-        }
+    pub fn is_on_stack_as(&self, card: &Arc<Mutex<Card>>, kind: StackItemKind) -> bool {
+        self.event_stack.iter().any(|action| {
+            action.stack_kind() == kind
+                && action
+                    .stack_card()
+                    .is_some_and(|stack_card| Arc::ptr_eq(stack_card, card))
+        })
+    }
 
-        for player_arc in &self.players {
+    pub fn is_ability_on_stack(&self, card: &Arc<Mutex<Card>>) -> bool {
+        self.is_on_stack_as(card, StackItemKind::Ability)
+            || self.is_on_stack_as(card, StackItemKind::Trigger)
+    }
+
+    // The topmost activated or triggered ability from `source` on the stack
+    fn topmost_ability(&self, source: &Arc<Mutex<Card>>) -> Option<Arc<dyn Action + Send + Sync>> {
+        self.event_stack
+            .iter()
+            .rev()
+            .find(|action| {
+                action.stack_kind() != StackItemKind::Spell
+                    && action
+                        .stack_card()
+                        .is_some_and(|stack_card| Arc::ptr_eq(stack_card, source))
+            })
+            .cloned()
+    }
+
+    // Where on the stack the ability `spell_id` was cast at is, if it's still there
+    pub fn targeted_ability_index(&self, spell_id: &str) -> Option<usize> {
+        let ability = self.ability_targets.get(spell_id)?;
+        self.event_stack
+            .iter()
+            .position(|action| Arc::ptr_eq(action, ability))
+    }
+
+    // Takes the ability `spell` was cast at off the stack before it resolves.
+    pub async fn counter_ability(&mut self, spell: &Arc<Mutex<Card>>) -> Result<(), String> {
+        let spell_id = spell.lock().await.id.clone();
+        let index = self
+            .targeted_ability_index(&spell_id)
+            .ok_or_else(|| "Target ability is not on the stack.".to_string())?;
+        self.ability_targets.remove(&spell_id);
+        let ability = self.event_stack.remove(index);
+
+        let name = match ability.stack_card() {
+            Some(source) => source.lock().await.public_name(),
+            None => format!("{:?}", ability),
+        };
+        self.add_turn_message(format!("{}'s ability was countered.", name));
+        self.stack_updated().await;
+
+        Ok(())
+    }
+
+    // The name of a split second spell on the stack, if there is one. Until it
+    // resolves nothing else can be put on the stack.
+    pub async fn split_second_spell(&self) -> Option<String> {
+        for action in &self.event_stack {
+            if action.stack_kind() != StackItemKind::Spell {
+                continue;
+            }
+            if let Some(card) = action.stack_card() {
+                let card = card.lock().await;
+                if card.get_stat_value(StatType::SplitSecond) > 0 {
+                    return Some(card.name.clone());
+                }
+            }
+        }
+        None
+    }
+
+    fn check_opening_hands(&self) -> Result<(), GameError> {
+        if self.awaiting_opening_hands {
+            return Err(GameError::OpeningHands);
+        }
+        Ok(())
+    }
+
+    async fn check_split_second(&self) -> Result<(), String> {
+        match self.split_second_spell().await {
+            Some(name) => Err(format!("Nobody can respond to {}", name)),
+            None => Ok(()),
+        }
+    }
+
+    // The stack from the bottom up, as the players should see it.
+    pub async fn stack_view(&self) -> Vec<StackItemView> {
+        let mut view = vec![];
+        for action in &self.event_stack {
+            let (card_id, name) = match action.stack_card() {
+                Some(card) => {
+                    let card = card.lock().await;
+                    (Some(card.id.clone()), card.public_name())
+                }
+                None => (None, format!("{:?}", action)),
+            };
+            view.push(StackItemView {
+                kind: action.stack_kind(),
+                card_id,
+                name,
+            });
+        }
+        view
+    }
+
+    // Puts a copy of `spell` on the stack, at `new_target` or else whatever the
+    // original was cast at. Only the spell's effect is copied; the card and what
+    // was paid for it stay with the original.
+    pub async fn copy_spell(
+        &mut self,
+        spell: &Arc<Mutex<Card>>,
+        new_target: Option<EffectTarget>,
+    ) -> Result<(), String> {
+        let original_target = self
+            .event_stack
+            .iter()
+            .filter_map(|action| action.as_spell())
+            .find(|action| Arc::ptr_eq(&action.card_arc, spell))
+            .ok_or_else(|| "That spell isn't on the stack".to_string())?
+            .target
+            .clone();
+        let target = new_target.or(original_target);
+
+        let (name, triggers) = {
+            let spell = spell.lock().await;
+            (spell.name.clone(), spell.triggers.clone())
+        };
+        let copies: Vec<CardActionWrapper> = triggers
+            .into_iter()
+            .filter(|trigger| trigger.trigger_type == ActionTriggerType::CardPlayedFromHand)
+            .map(|trigger| CardActionWrapper {
+                action: trigger.action,
+                card: Arc::clone(spell),
+                target: target.clone(),
+            })
+            .collect();
+        if copies.is_empty() {
+            return Err(format!("{} has nothing to copy", name));
+        }
+
+        // A copy of a spell is still a spell
+        for copy in copies {
+            self.add_to_stack_as(StackItemKind::Spell, Arc::new(copy));
+        }
+        self.add_turn_message(format!("{} was copied.", name));
+
+        Ok(())
+    }
+
+    pub async fn reset_creature_damage(&mut self) {
+        for player_arc in &self.players {
             let mut player = player_arc.lock().await;
-            player.reset_spells();
+            for card_arc in &player.cards_in_play {
+                let mut card = card_arc.lock().await;
+                if card.card_type == CardType::Creature {
+                    card.damage_taken = 0;
+                    card.damage_dealt_to_players = 0;
+                    card.prevent_next_combat_damage = false;
+                    card.goaded_by = None;
+                }
+            }
+        }
+    }
+
+    pub async fn resolve_stack(&mut self) {
+        if let Err(err) = self.try_resolve_stack().await {
+            self.debug(&err.to_string());
+        }
+    }
+
+    // Resolves the stack, giving up once it has resolved more than the limit.
+    // Whatever is left then is thrown away, since it would only go on forever.
+    pub async fn try_resolve_stack(&mut self) -> Result<(), GameError> {
+        let had_items = !self.event_stack.is_empty();
+        let limit = self
+            .stack_resolution_limit
+            .unwrap_or(STACK_RESOLUTION_LIMIT);
+        let mut resolved = 0;
+        let mut result = Ok(());
+        while let Some(action) = self.event_stack.pop() {
+            if resolved >= limit {
+                self.event_stack.push(action);
+                let mut triggers = vec![];
+                for item in self.stack_view().await {
+                    if !triggers.contains(&item.name) {
+                        triggers.push(item.name);
+                    }
+                }
+                self.event_stack.clear();
+                result = Err(GameError::LoopDetected { triggers });
+                break;
+            }
+            action.apply(self).await;
+            resolved += 1;
         }
 
         self.effect_manager
             .apply_effects(self.current_turn.clone().unwrap())
             .await;
+
+        // Tell the frontend outright, rather than leaving it to notice on the next update
+        if had_items {
+            self.stack_updated().await;
+        }
+        self.notify_state_changed();
+
+        result
+    }
+
+    pub async fn stack_updated(&self) {
+        if let Some(ref sender) = self.broadcast_sender {
+            let _ = sender.send(Some(LobbyCommand::StackUpdated(self.stack_view().await)));
+        }
+    }
+
+    pub fn add_draw_replacement(&mut self, replacement: DrawReplacement) {
+        self.draw_replacements.push(replacement);
+    }
+
+    // Drops replacements whose permanent has left play
+    async fn prune_draw_replacements(&mut self) {
+        let mut active = vec![];
+        for replacement in std::mem::take(&mut self.draw_replacements) {
+            if replacement.is_active().await {
+                active.push(replacement);
+            }
+        }
+        self.draw_replacements = active;
+    }
+
+    // Takes the first replacement that matches, using it up if it only
+    // applies once.
+    async fn take_draw_replacement(
+        &mut self,
+        matches: impl Fn(&DrawReplacement) -> bool,
+    ) -> Option<DrawReplacement> {
+        self.prune_draw_replacements().await;
+        let index = self.draw_replacements.iter().position(matches)?;
+
+        if self.draw_replacements[index].once {
+            Some(self.draw_replacements.remove(index))
+        } else {
+            Some(self.draw_replacements[index].clone())
+        }
+    }
+
+    // The draw step checks this before drawing anything. A replacement made
+    // for the draw step skips it, doing whatever it does instead once.
+    pub async fn skip_draw_step(&mut self, player: &Arc<Mutex<Player>>) -> bool {
+        let Some(replacement) = self
+            .take_draw_replacement(|replacement| {
+                Arc::ptr_eq(&replacement.player, player)
+                    && replacement.from == Some(DrawSource::DrawStep)
+            })
+            .await
+        else {
+            return false;
+        };
+
+        let name = player.lock().await.name.clone();
+        self.add_turn_message(format!("{} skipped their draw", name));
+        if let (Some(instead), Some(player_index)) =
+            (replacement.instead, self.player_index(player))
+        {
+            instead.apply(self, player_index).await;
+        }
+
+        true
+    }
+
+    // Every draw in the game goes through here so replacement effects get a
+    // chance to swap it for something else, then dredge gets its chance.
+    // Opening hands aren't draws. Draws after one waiting on a dredge answer
+    // wait with it.
+    pub async fn player_draws(
+        &mut self,
+        player: &Arc<Mutex<Player>>,
+        count: u8,
+        source: DrawSource,
+    ) {
+        for drawn in 1..=count {
+            let Some(replacement) = self
+                .take_draw_replacement(|replacement| replacement.replaces(player, source))
+                .await
+            else {
+                if draw::offer_dredge(self, player, count - drawn, source).await {
+                    return;
+                }
+                player.lock().await.draw_card();
+                continue;
+            };
+
+            if let (Some(instead), Some(player_index)) =
+                (replacement.instead, self.player_index(player))
+            {
+                instead.apply(self, player_index).await;
+            }
+        }
+    }
+
+    pub async fn gain_life(&mut self, player: &Arc<Mutex<Player>>, amount: i8) {
+        if amount <= 0 {
+            return;
+        }
+
+        {
+            let mut player = player.lock().await;
+            player
+                .stat_manager
+                .add_stat(Ulid::new().to_string(), Stat::new(StatType::Health, amount));
+            player.life_gained_this_turn += amount;
+        }
+
+        let mut actions = vec![];
+        for player_arc in self.players.clone() {
+            let cards_in_play = player_arc.lock().await.cards_in_play.clone();
+            for card in cards_in_play {
+                actions.extend(card.lock().await.collect_life_gained_actions(
+                    Arc::clone(&card),
+                    player,
+                    amount,
+                ));
+            }
+        }
+
+        for action in actions {
+            action.apply(self).await;
+        }
     }
 
     pub async fn remove_references_to(&mut self, card: &Arc<Mutex<Card>>) {
         let mut actions: Vec<Arc<dyn Action + Send + Sync>> = vec![];
 
         for player_arc in self.players.clone() {
-            let player = player_arc.lock().await;
+            // Detaching re-applies effects, which may need the owner, so don't hold it here
+            let cards_in_play = player_arc.lock().await.cards_in_play.clone();
 
-            for (index, card_in_play_arc) in player.cards_in_play.iter().enumerate() {
+            for card_in_play_arc in cards_in_play.iter() {
                 let should_detach = {
                     let card_in_play = card_in_play_arc.lock().await;
 
@@ -726,60 +1686,425 @@ impl Game {
     pub async fn detach_card(&mut self, card_arc: &Arc<Mutex<Card>>) {
         let mut card = card_arc.lock().await;
         if let Some(attached_card) = card.attached.take() {
-            println!("Detached card {} from \n\n{:?}", card.name, attached_card);
+            debug!("Detached card {} from \n\n{:?}", card.name, attached_card);
             self.effect_manager
                 .remove_effects_by_source(card_arc, self.current_turn.clone().unwrap())
                 .await;
         }
     }
 
-    pub async fn destroy_card(&mut self, card: &Arc<Mutex<Card>>) {
-        self.remove_references_to(card).await;
-        let mut actions = vec![];
-        for player in self.players.iter() {
-            let cards_to_destroy = {
-                let player_locked = player.lock().await;
-
-                let mut cards_to_destroy = Vec::new();
-                for (card_index, card_in_play) in player_locked.cards_in_play.iter().enumerate() {
-                    if Arc::ptr_eq(card, card_in_play) {
-                        cards_to_destroy.push(card_index);
+    // Resolves combat damage and everything it sets off.
+    pub async fn deal_combat_damage(&mut self) {
+        let destroyed_cards = self.combat.resolve_combat().await;
+
+        // Damage triggers go off even if the source died in the exchange
+        let damage_dealt = std::mem::take(&mut self.combat.damage_dealt);
+        for (source, target, amount) in damage_dealt {
+            // Hitting the monarch steals it
+            if let EffectTarget::Player(damaged) = &target {
+                if self.is_monarch(damaged) {
+                    if let Some(controller) = source.lock().await.controller() {
+                        self.become_monarch(&controller).await;
                     }
                 }
+            }
+
+            let actions =
+                source
+                    .lock()
+                    .await
+                    .collect_damage_actions(Arc::clone(&source), target, amount);
+            for action in actions {
+                action.apply(self).await;
+            }
+        }
+
+        for card in destroyed_cards {
+            if let Err(err) = self.destroy_card(&card).await {
+                debug!("{}", err);
+            }
+        }
+        self.handle_deaths().await;
+    }
+
+    // Asks for the order each attacker with more than one blocker deals its
+    // damage in. Returns whether combat damage has to wait on an answer.
+    pub async fn ask_for_damage_orders(&mut self) -> bool {
+        let attackers = self.combat.needing_damage_order();
+        self.combat.awaiting_order = attackers.clone();
+        for attacker in attackers {
+            self.ask_damage_order(attacker, vec![]).await;
+        }
+        !self.combat.awaiting_order.is_empty()
+    }
+
+    // Asks for the attacker's next blocker in damage order. The defending
+    // player puts one blocker in front to take the damage first, then the
+    // attacking player orders the rest, unless banding lets the defending
+    // player order them all. Once one blocker is left the order is set, and
+    // combat damage goes ahead when no other attacker is still waiting.
+    pub async fn ask_damage_order(
+        &mut self,
+        attacker: Arc<Mutex<Card>>,
+        chosen: Vec<Arc<Mutex<Card>>>,
+    ) {
+        let blockers = self.combat.blockers_of(&attacker);
+        let remaining: Vec<Arc<Mutex<Card>>> = blockers
+            .iter()
+            .filter(|blocker| !chosen.iter().any(|c| Arc::ptr_eq(c, blocker)))
+            .map(Arc::clone)
+            .collect();
+
+        if remaining.len() <= 1 {
+            let mut order = chosen;
+            order.extend(remaining);
+            self.combat.set_damage_order(&attacker, order);
+            if self.combat.awaiting_order.is_empty() {
+                self.deal_combat_damage().await;
+            }
+            return;
+        }
 
-                cards_to_destroy
+        let mut defender = None;
+        let mut banding = false;
+        for blocker in &blockers {
+            let blocker = blocker.lock().await;
+            defender = defender.or_else(|| blocker.controller());
+            banding |= blocker.get_stat_value(StatType::Banding) > 0;
+        }
+        let defender_picks = chosen.is_empty() || banding;
+        let (chooser, name) = {
+            let attacker = attacker.lock().await;
+            let chooser = if defender_picks {
+                defender
+            } else {
+                attacker.controller()
             };
+            (chooser, attacker.name.clone())
+        };
+        let description = if chosen.is_empty() {
+            format!("Choose which blocker takes {}'s damage first", name)
+        } else {
+            format!("Choose which blocker {} deals damage to next", name)
+        };
+        let Some(chooser) = chooser else {
+            let mut order = chosen;
+            order.extend(remaining);
+            self.combat.set_damage_order(&attacker, order);
+            return;
+        };
+
+        let ordering = Arc::clone(&attacker);
+        self.ask_mandatory_player_ability(
+            Ability::new(
+                Arc::clone(&attacker),
+                vec![],
+                CardRequiredTarget::AnyCard,
+                Arc::new(move |_| {
+                    Arc::new(OrderBlockersAction {
+                        attacker: ordering.clone(),
+                        chosen: chosen.clone(),
+                    })
+                }),
+                description,
+                ActionType::None,
+            )
+            .with_choices(remaining)
+            .with_chooser(chooser),
+        )
+        .await;
+    }
+
+    pub async fn destroy_card(&mut self, card: &Arc<Mutex<Card>>) -> Result<(), String> {
+        {
+            let card = card.lock().await;
+            if card.get_stat_value(StatType::Indestructible) > 0 {
+                debug!("{} is indestructible.", card.name);
+                return Ok(());
+            }
+        }
+
+        self.put_into_graveyard(card).await
+    }
+
+    // Sacrificing isn't destroying, so indestructible doesn't help.
+    pub async fn sacrifice_card(&mut self, card: &Arc<Mutex<Card>>) -> Result<(), String> {
+        self.put_into_graveyard(card).await
+    }
+
+    async fn put_into_graveyard(&mut self, card: &Arc<Mutex<Card>>) -> Result<(), String> {
+        // Auras go down with what they enchant, equipment just falls off
+        let mut auras = vec![];
+        for player in self.players.iter() {
+            for card_in_play in player.lock().await.cards_in_play.iter() {
+                let card_in_play_locked = card_in_play.lock().await;
+                if card_in_play_locked.card_type == CardType::Enchantment
+                    && card_in_play_locked
+                        .attached
+                        .as_ref()
+                        .is_some_and(|attached| Arc::ptr_eq(attached, card))
+                {
+                    auras.push(Arc::clone(card_in_play));
+                }
+            }
+        }
+
+        self.move_card(card, Zone::Battlefield, Zone::Graveyard)
+            .await?;
+        for aura in auras {
+            Box::pin(self.put_into_graveyard(&aura)).await?;
+        }
 
-            for &card_index in &cards_to_destroy {
-                let mut player_locked = player.lock().await;
-                actions.append(
-                    &mut player_locked
-                        .destroy_card_in_play(card_index, self.current_turn.as_ref().unwrap())
-                        .await,
+        Ok(())
+    }
+
+    // Whoever has the card in that zone right now.
+    async fn zone_holder(&self, card: &Arc<Mutex<Card>>, zone: Zone) -> Option<Arc<Mutex<Player>>> {
+        for player in self.players.iter() {
+            if player.lock().await.zone_contains(zone, card) {
+                return Some(Arc::clone(player));
+            }
+        }
+        None
+    }
+
+    // Moves a card from one zone to another, firing whatever leaving or
+    // entering them triggers. Dying fires death watchers and the card's own
+    // destroyed triggers, entering the battlefield fires its enter triggers.
+    pub async fn move_card(
+        &mut self,
+        card: &Arc<Mutex<Card>>,
+        from: Zone,
+        to: Zone,
+    ) -> Result<(), String> {
+        // A flashed back spell is exiled instead of going anywhere else. It
+        // still resolves onto the battlefield, and is exiled from there.
+        let to = {
+            let mut card = card.lock().await;
+            if from == Zone::Stack && to != Zone::Battlefield && card.flashed_back {
+                card.flashed_back = false;
+                Zone::Exile
+            } else {
+                to
+            }
+        };
+        let Some(holder) = self.zone_holder(card, from).await else {
+            return Err(format!(
+                "{} isn't in the {:?}",
+                card.lock().await.name,
+                from
+            ));
+        };
+
+        let mut actions = vec![];
+        if from == Zone::Battlefield {
+            // Watchers see the creature die, including the creature itself
+            if to == Zone::Graveyard {
+                actions.extend(self.collect_creature_died_actions(card).await);
+            }
+            self.remove_references_to(card).await;
+            self.combat.remove(card);
+        }
+
+        holder
+            .lock()
+            .await
+            .zone_mut(from)
+            .retain(|c| !Arc::ptr_eq(c, card));
+        if from == Zone::Battlefield && to == Zone::Graveyard {
+            actions.extend(
+                Card::collect_phase_based_actions(
+                    card,
+                    self.current_turn.as_ref().unwrap(),
+                    ActionTriggerType::CardDestroyed,
+                )
+                .await,
+            );
+        }
+
+        let destination = {
+            let mut card = card.lock().await;
+            if from == Zone::Battlefield {
+                card.controller = None;
+                // Face down cards are revealed as they leave
+                card.turn_face_up();
+            }
+            if to.is_owners() {
+                card.owner.clone().unwrap_or_else(|| Arc::clone(&holder))
+            } else {
+                Arc::clone(&holder)
+            }
+        };
+        destination.lock().await.zone_mut(to).push(Arc::clone(card));
+
+        if to == Zone::Battlefield {
+            let echo = {
+                let mut card = card.lock().await;
+                card.owner.get_or_insert(Arc::clone(&destination));
+                card.controller = Some(Arc::clone(&destination));
+                card.enter_battlefield();
+                card.echo.clone().filter(|_| !card.face_down)
+            };
+            if let Some(cost) = echo {
+                self.schedule_delayed_trigger(
+                    Arc::clone(&destination),
+                    TurnPhase::Upkeep,
+                    Arc::clone(card),
+                    Arc::new(EchoAction { cost }),
                 );
             }
+            actions.extend(self.collect_card_played_actions(card).await);
         }
 
         self.execute_actions(&mut actions).await;
+
+        Ok(())
+    }
+
+    async fn collect_creature_died_actions(
+        &self,
+        card: &Arc<Mutex<Card>>,
+    ) -> Vec<Arc<dyn Action + Send + Sync>> {
+        let mut actions = vec![];
+        if card.lock().await.card_type != CardType::Creature {
+            return actions;
+        }
+
+        let mut died_controller = None;
+        for player in self.players.iter() {
+            let player_locked = player.lock().await;
+            if player_locked
+                .cards_in_play
+                .iter()
+                .any(|card_in_play| Arc::ptr_eq(card_in_play, card))
+            {
+                died_controller = Some(Arc::clone(player));
+            }
+        }
+        let Some(died_controller) = died_controller else {
+            return actions;
+        };
+
+        for player in self.players.iter() {
+            let cards_in_play = player.lock().await.cards_in_play.clone();
+            for watcher in cards_in_play {
+                actions.extend(
+                    Card::collect_creature_died_actions(&watcher, card, &died_controller).await,
+                );
+            }
+        }
+
+        actions
+    }
+
+    // Treats the card as gone until its controller's next untap step. It
+    // keeps its place on the battlefield, attachments and all.
+    pub async fn phase_out(&mut self, card_arc: &Arc<Mutex<Card>>) {
+        card_arc.lock().await.phased_out = true;
+        self.combat.remove(card_arc);
+    }
+
+    // Destroys every card in play matching `filter` at once, judged from the
+    // point of view of `controller`, then checks whether anyone died of it.
+    pub async fn destroy_all(
+        &mut self,
+        filter: &CardRequiredTarget,
+        controller: Option<&Arc<Mutex<Player>>>,
+    ) -> usize {
+        let mut doomed = vec![];
+        for player in &self.players {
+            let owned = controller.is_some_and(|controller| Arc::ptr_eq(controller, player));
+            for card_arc in player.lock().await.cards_in_play.iter() {
+                let card = card_arc.lock().await;
+                if !card.phased_out && filter.matches_card(&card, owned) {
+                    doomed.push(Arc::clone(card_arc));
+                }
+            }
+        }
+
+        for card in &doomed {
+            // Auras can already have gone down with what they enchanted
+            if let Err(err) = self.destroy_card(card).await {
+                debug!("{}", err);
+            }
+        }
+        self.handle_deaths().await;
+
+        doomed.len()
+    }
+
+    // Takes a card off the battlefield and puts it into its owner's hand, even
+    // if someone else controls it.
+    pub async fn return_card_to_owner_hand(
+        &mut self,
+        card_arc: &Arc<Mutex<Card>>,
+    ) -> Result<(), String> {
+        self.move_card(card_arc, Zone::Battlefield, Zone::Hand)
+            .await
+    }
+
+    // Moves a permanent onto `new_controller`'s side of the battlefield.
+    pub async fn gain_control(
+        &mut self,
+        card_arc: &Arc<Mutex<Card>>,
+        new_controller: &Arc<Mutex<Player>>,
+    ) {
+        let previous_controller = card_arc.lock().await.controller();
+        if let Some(previous_controller) = &previous_controller {
+            if Arc::ptr_eq(previous_controller, new_controller) {
+                return;
+            }
+
+            previous_controller
+                .lock()
+                .await
+                .cards_in_play
+                .retain(|c| !Arc::ptr_eq(c, card_arc));
+        }
+
+        new_controller
+            .lock()
+            .await
+            .cards_in_play
+            .push(Arc::clone(card_arc));
+        card_arc.lock().await.controller = Some(Arc::clone(new_controller));
     }
 
     pub async fn add_player(&mut self, player: Player) -> Arc<Mutex<Player>> {
         let player_arc = Arc::new(Mutex::new(player));
         player_arc.lock().await.deck.set_owner(&player_arc).await;
         self.players.push(Arc::clone(&player_arc));
+        self.seats.push(Arc::clone(&player_arc));
 
         player_arc
     }
 
+    // Only meant for before the game starts, e.g. a player leaving a lobby.
+    pub fn remove_player(&mut self, player: &Arc<Mutex<Player>>) {
+        self.players.retain(|p| !Arc::ptr_eq(p, player));
+        self.seats.retain(|p| !Arc::ptr_eq(p, player));
+    }
+
     pub async fn attach_card_action(
         &mut self,
         player: &Arc<Mutex<Player>>,
         in_play_index: usize,
         target: Option<EffectTarget>,
     ) -> Result<(), String> {
-        target
-            .clone()
-            .ok_or_else(|| "Choose a target".to_string())?;
+        let host = match &target {
+            Some(EffectTarget::Card(host)) => Arc::clone(host),
+            Some(_) => return Err("Can only attach to a card".to_string()),
+            None => return Err("Choose a target".to_string()),
+        };
+        self.check_attach_target(player, in_play_index, &host, |trigger_type| {
+            trigger_type == &ActionTriggerType::Attached
+        })
+        .await?;
+        let input = ReplayInput::AttachCard {
+            seat: self.seat_index(player).unwrap_or_default(),
+            in_play_index,
+            target: replay_target(self, &target).await,
+        };
 
         let mut actions = {
             let mut player_locked = player.lock().await;
@@ -787,39 +2112,219 @@ impl Game {
                 .attach_card(in_play_index, target, self)
                 .await?
         };
+        self.record(input);
 
         self.execute_actions(&mut actions).await;
 
         Ok(())
     }
 
-    pub async fn activate_card_action(
+    // Moves equipment onto another creature its controller controls. Unlike
+    // attaching an aura this costs mana and can only be done as a sorcery.
+    pub async fn equip_card_action(
         &mut self,
         player: &Arc<Mutex<Player>>,
         in_play_index: usize,
         target: Option<EffectTarget>,
-        trigger_id: String,
     ) -> Result<(), String> {
-        if let Some((current_player, _, action_taken)) = &mut self.current_priority_player {
-            if !Arc::ptr_eq(&player, current_player) {
-                return Err("Not your turn".to_string());
-            } else {
-                *action_taken = ActionType::Tap;
-            }
+        let host = match &target {
+            Some(EffectTarget::Card(host)) => Arc::clone(host),
+            Some(_) => return Err("Can only equip a creature".to_string()),
+            None => return Err("Choose a target".to_string()),
+        };
+        if !self.is_sorcery_speed(player).await {
+            return Err("You can only equip as a sorcery".to_string());
         }
-        let game_arc = Arc::new(Mutex::new(std::mem::take(self)));
+        self.check_attach_target(player, in_play_index, &host, |trigger_type| {
+            matches!(trigger_type, ActionTriggerType::Equip(_))
+        })
+        .await?;
+
+        let card_arc = Arc::clone(&player.lock().await.cards_in_play[in_play_index]);
+        let cost = {
+            let card = card_arc.lock().await;
+            if card
+                .attached
+                .as_ref()
+                .is_some_and(|attached| Arc::ptr_eq(attached, &host))
+            {
+                return Err(format!("{} is already equipped there", card.name));
+            }
+            card.triggers
+                .iter()
+                .find_map(|trigger| match &trigger.trigger_type {
+                    ActionTriggerType::Equip(cost) => Some(cost.clone()),
+                    _ => None,
+                })
+                .unwrap_or_default()
+        };
+        {
+            let mut player = player.lock().await;
+            if !player.has_required_mana(&cost).await {
+                return Err("Not enough mana to equip".to_string());
+            }
+            player.pay_mana(&cost).await;
+        }
+        self.mana_pool_updated(player).await;
+        let input = ReplayInput::EquipCard {
+            seat: self.seat_index(player).unwrap_or_default(),
+            in_play_index,
+            target: replay_target(self, &target).await,
+        };
+
+        self.detach_card(&card_arc).await;
         let mut actions = {
-            Player::execute_action(
-                Arc::clone(player),
-                in_play_index,
-                target,
-                game_arc.clone(),
-                trigger_id,
-            )
-            .await?
+            let mut card = card_arc.lock().await;
+            card.attached = Some(host);
+            card.collect_equip_actions(Arc::clone(&card_arc), target)
+                .await
+        };
+        self.record(input);
+
+        self.execute_actions(&mut actions).await;
+
+        Ok(())
+    }
+
+    // Main phase of your own turn with nothing waiting to resolve.
+    pub async fn is_sorcery_speed(&self, player: &Arc<Mutex<Player>>) -> bool {
+        let Some(turn) = &self.current_turn else {
+            return false;
+        };
+        if !Arc::ptr_eq(&turn.current_player, player)
+            || !matches!(turn.phase, TurnPhase::Main | TurnPhase::Main2)
+            || !self.event_stack.is_empty()
+        {
+            return false;
+        }
+        for player in &self.players {
+            if !player.lock().await.spells.is_empty() {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    // Checked before anything is detached, so a rejected attach leaves the
+    // card where it was.
+    async fn check_attach_target(
+        &self,
+        player: &Arc<Mutex<Player>>,
+        in_play_index: usize,
+        host: &Arc<Mutex<Card>>,
+        attaches_with: impl Fn(&ActionTriggerType) -> bool,
+    ) -> Result<(), String> {
+        let card_arc = {
+            let player = player.lock().await;
+            player
+                .cards_in_play
+                .get(in_play_index)
+                .cloned()
+                .ok_or_else(|| "No such card in play".to_string())?
+        };
+        if Arc::ptr_eq(&card_arc, host) {
+            return Err("Cannot attach to self".to_string());
+        }
+
+        let mut host_controller = None;
+        for player_arc in &self.players {
+            let player = player_arc.lock().await;
+            if player.cards_in_play.iter().any(|c| Arc::ptr_eq(c, host)) {
+                host_controller = Some(Arc::clone(player_arc));
+                break;
+            }
+        }
+        let host_controller =
+            host_controller.ok_or_else(|| "Can only attach to a card in play".to_string())?;
+        let owned = Arc::ptr_eq(&host_controller, player);
+
+        let card = card_arc.lock().await;
+        let host_card = host.lock().await;
+        if !card
+            .triggers
+            .iter()
+            .any(|trigger| attaches_with(&trigger.trigger_type))
+        {
+            return Err(format!("{} can't be attached that way", card.name));
+        }
+        for trigger in &card.triggers {
+            if attaches_with(&trigger.trigger_type)
+                && !self.card_target_matches(&trigger.card_required_target, host, &host_card, owned)
+            {
+                return Err(format!(
+                    "{} can't be attached to {}",
+                    card.name, host_card.name
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn activate_card_action(
+        &mut self,
+        player: &Arc<Mutex<Player>>,
+        in_play_index: usize,
+        target: Option<EffectTarget>,
+        trigger_id: String,
+    ) -> Result<(), String> {
+        let trigger_index = {
+            let player = player.lock().await;
+            match player.cards_in_play.get(in_play_index) {
+                Some(card) => card
+                    .lock()
+                    .await
+                    .triggers
+                    .iter()
+                    .position(|trigger| trigger.id == trigger_id),
+                None => None,
+            }
         };
+        let is_mana_ability = match trigger_index {
+            Some(trigger_index) => {
+                let card = Arc::clone(&player.lock().await.cards_in_play[in_play_index]);
+                let card = card.lock().await;
+                card.triggers[trigger_index].is_mana_ability()
+            }
+            None => false,
+        };
+
+        // Mana can be made mid-payment whoever holds priority, and doing so
+        // doesn't count as acting on it
+        self.check_opening_hands()?;
+        if !is_mana_ability {
+            self.check_split_second().await?;
+            if let Some((current_player, _, action_taken)) = &mut self.current_priority_player {
+                if !Arc::ptr_eq(player, current_player) {
+                    return Err("Not your turn".to_string());
+                } else {
+                    *action_taken = ActionType::Tap;
+                }
+            }
+        }
+        let recorded_target = replay_target(self, &target).await;
+        let game_arc = Arc::new(Mutex::new(std::mem::take(self)));
+        let result = Player::execute_action(
+            Arc::clone(player),
+            in_play_index,
+            target,
+            game_arc.clone(),
+            trigger_id,
+        )
+        .await;
+        // Put the game back before bailing so a failed activation (e.g. a tapped land) doesn't wipe it
         let mut game_unlocked = game_arc.lock().await;
         *self = std::mem::take(&mut *game_unlocked);
+        let mut actions = result?;
+        if let Some(trigger_index) = trigger_index {
+            self.record(ReplayInput::ActivateCard {
+                seat: self.seat_index(player).unwrap_or_default(),
+                in_play_index,
+                trigger_index,
+                target: recorded_target,
+            });
+        }
 
         self.execute_actions(&mut actions).await;
 
@@ -832,6 +2337,7 @@ impl Game {
         in_play_index: usize,
         target: Option<EffectTarget>,
     ) -> Result<(), String> {
+        self.check_opening_hands()?;
         if let Some((current_player, _, action_taken)) = &mut self.current_priority_player {
             if !Arc::ptr_eq(&player, current_player) {
                 return Err("Not your turn".to_string());
@@ -866,7 +2372,9 @@ impl Game {
         };
 
         let mut game = game_arc.lock().await; // Lock the game for write access
-        let result = game.execute_card(player_arc, index, None).await?;
+        let result = game
+            .execute_card(player_arc, Zone::Hand, index, None)
+            .await?;
         game.resolve_stack().await;
 
         // Now pass the game Arc to process the action queue
@@ -881,6 +2389,49 @@ impl Game {
         index: usize,
         target: Option<EffectTarget>,
     ) -> Result<Arc<Mutex<Card>>, String> {
+        self.cast_from(player, Zone::Hand, index, target).await
+    }
+
+    // Casts the card at that index of the graveyard for its flashback cost.
+    // It's exiled once it leaves the stack, however that happens.
+    pub async fn flashback_card(
+        &mut self,
+        player: &Arc<Mutex<Player>>,
+        index: usize,
+        target: Option<EffectTarget>,
+    ) -> Result<Arc<Mutex<Card>>, String> {
+        self.cast_from(player, Zone::Graveyard, index, target).await
+    }
+
+    async fn cast_from(
+        &mut self,
+        player: &Arc<Mutex<Player>>,
+        from: Zone,
+        index: usize,
+        target: Option<EffectTarget>,
+    ) -> Result<Arc<Mutex<Card>>, String> {
+        let name = player.lock().await.name.clone();
+        let span = info_span!(
+            "play_card",
+            player = %name,
+            phase = ?self.current_turn.as_ref().map(|turn| turn.phase),
+            ?from,
+            index,
+        );
+        self.cast(player, from, index, target)
+            .instrument(span)
+            .await
+    }
+
+    async fn cast(
+        &mut self,
+        player: &Arc<Mutex<Player>>,
+        from: Zone,
+        index: usize,
+        target: Option<EffectTarget>,
+    ) -> Result<Arc<Mutex<Card>>, String> {
+        self.check_opening_hands()?;
+        self.check_split_second().await?;
         if let Some((current_player, _, action_taken)) = &mut self.current_priority_player {
             if !Arc::ptr_eq(&player, current_player) {
                 return Err("Not your turn".to_string());
@@ -889,135 +2440,304 @@ impl Game {
             }
         }
 
-        Ok(self.execute_card(player, index, target).await?)
+        // Nobody gets to respond in fast mode, so only the active player casts
+        if self.auto_pass_priority
+            && !self
+                .current_turn
+                .as_ref()
+                .is_some_and(|turn| Arc::ptr_eq(&turn.current_player, player))
+        {
+            return Err("You can only cast spells on your own turn".to_string());
+        }
+
+        if let Some(EffectTarget::Cards(targets)) = &target {
+            self.check_multiple_targets(player, from, index, targets)
+                .await?;
+        }
+        let mut ability_target = None;
+        if let Some(EffectTarget::Card(target_card)) = &target {
+            let target = target_card.lock().await;
+            if target.phased_out {
+                return Err(format!("{} is phased out", target.name));
+            }
+            drop(target);
+            ability_target = self
+                .check_spell_target(player, from, index, target_card)
+                .await?;
+        }
+
+        let seat = self.seat_index(player).unwrap_or_default();
+        let replay_target = replay_target(self, &target).await;
+        let input = match from {
+            Zone::Graveyard => ReplayInput::FlashbackCard {
+                seat,
+                index,
+                target: replay_target,
+            },
+            _ => ReplayInput::PlayCard {
+                seat,
+                index,
+                target: replay_target,
+            },
+        };
+        let card = self.execute_card(player, from, index, target).await?;
+        if let Some(ability) = ability_target {
+            let id = card.lock().await.id.clone();
+            self.ability_targets.insert(id, ability);
+        }
+        self.record(input);
+
+        Ok(card)
     }
 
-    async fn execute_card(
+    // Exiles a card from hand with its time counters instead of casting it.
+    // SuspendUpkeepAction takes them off and casts it once they're gone.
+    pub async fn suspend_card(
         &mut self,
         player: &Arc<Mutex<Player>>,
         index: usize,
-        target: Option<EffectTarget>,
-    ) -> Result<Arc<Mutex<Card>>, String> {
-        let (action, card) = {
-            Player::play_card(
-                player,
-                index,
-                target.clone(),
-                self.current_turn.clone().unwrap(),
-            )
-            .await?
+    ) -> Result<(), String> {
+        self.check_opening_hands()?;
+        self.check_split_second().await?;
+        if let Some((current_player, _, action_taken)) = &mut self.current_priority_player {
+            if !Arc::ptr_eq(player, current_player) {
+                return Err("Not your turn".to_string());
+            }
+            *action_taken = ActionType::PlayedCard;
+        }
+
+        let card = player
+            .lock()
+            .await
+            .cards_in_hand
+            .get(index)
+            .cloned()
+            .ok_or("Invalid card index")?;
+        let (name, suspend) = {
+            let card = card.lock().await;
+            (card.name.clone(), card.suspend.clone())
+        };
+        let Some(suspend) = suspend else {
+            return Err(format!("{} doesn't have suspend", name));
         };
 
-        self.add_to_stack(action);
-        self.effect_manager
-            .apply_effects(self.current_turn.clone().unwrap())
-            .await;
+        {
+            let mut player = player.lock().await;
+            if !player.can_play(&card, true).await {
+                return Err(format!("You cannot suspend {} right now", name));
+            }
+            if !player.has_required_mana(&suspend.cost).await {
+                return Err(format!("Not enough mana to suspend {}", name));
+            }
+            player.pay_mana(&suspend.cost).await;
+        }
+        self.mana_pool_updated(player).await;
+
+        card.lock().await.current_phase = CardPhase::Charging(suspend.time_counters);
+        self.move_card(&card, Zone::Hand, Zone::Exile).await?;
+        let player_name = player.lock().await.name.clone();
+        self.add_turn_message(format!(
+            "{} suspended {} with {} time counters",
+            player_name, name, suspend.time_counters
+        ));
+        self.record(ReplayInput::SuspendCard {
+            seat: self.seat_index(player).unwrap_or_default(),
+            index,
+        });
 
-        Ok(card)
+        Ok(())
     }
 
-    pub async fn next_priority_queue(&mut self) {}
+    // Morph: pays to turn a face down creature face up. It's a special action,
+    // so it doesn't use the stack and nobody can respond to it.
+    pub async fn turn_face_up(
+        &mut self,
+        player: &Arc<Mutex<Player>>,
+        in_play_index: usize,
+    ) -> Result<(), String> {
+        self.check_opening_hands()?;
+        if let Some((current_player, _, action_taken)) = &mut self.current_priority_player {
+            if !Arc::ptr_eq(player, current_player) {
+                return Err("Not your turn".to_string());
+            }
+            *action_taken = ActionType::PlayedCard;
+        }
 
-    pub fn send_command(&mut self, command: LobbyCommand) {
-        if let Some(ref sender) = self.broadcast_sender {
-            let _ = sender.send(Some(command));
+        let card = player
+            .lock()
+            .await
+            .cards_in_play
+            .get(in_play_index)
+            .cloned()
+            .ok_or("Invalid card index")?;
+        let cost = {
+            let card = card.lock().await;
+            card.morph
+                .clone()
+                .filter(|_| card.face_down && !card.phased_out)
+                .ok_or("That card can't be turned face up")?
+        };
+
+        {
+            let mut player = player.lock().await;
+            if !player.has_required_mana(&cost).await {
+                return Err("Not enough mana to turn it face up".to_string());
+            }
+            player.pay_mana(&cost).await;
         }
+        self.mana_pool_updated(player).await;
+
+        let name = {
+            let mut card = card.lock().await;
+            card.turn_face_up();
+            card.name.clone()
+        };
+        self.add_turn_message(format!("{} was turned face up", name));
+        self.record(ReplayInput::TurnFaceUp {
+            seat: self.seat_index(player).unwrap_or_default(),
+            in_play_index,
+        });
+
+        Ok(())
     }
 
-    pub fn debug(&mut self, message: &str) {
-        println!("DEBUG: {}", message);
-        if let Some(ref sender) = self.broadcast_sender {
-            let _ = sender.send(Some(LobbyCommand::DebugMessage(message.to_string())));
+    // The colored pips the floating mana doesn't cover, and how much generic
+    // is still owed after that.
+    async fn unpaid_cost(player: &Arc<Mutex<Player>>, cost: &[ManaType]) -> (Vec<ManaType>, usize) {
+        let mut floating = player.lock().await.mana_pool.to_vec();
+        let mut colored_needed = vec![];
+        for mana in cost.iter().filter(|mana| **mana != ManaType::Colorless) {
+            match floating.iter().position(|floating| floating == mana) {
+                Some(pos) => {
+                    floating.remove(pos);
+                }
+                None => colored_needed.push(*mana),
+            }
         }
+        let generic = cost
+            .iter()
+            .filter(|mana| **mana == ManaType::Colorless)
+            .count();
+        (colored_needed, generic.saturating_sub(floating.len()))
     }
 
-    pub fn add_turn_message(&mut self, message: String) {
-        self.turn_messages.push(message);
-        self.messages_updated();
+    // Every untapped land that can tap for mana, with what each ability makes
+    async fn mana_sources(player: &Arc<Mutex<Player>>) -> Vec<(usize, Vec<(String, ManaType)>)> {
+        let mut sources = vec![];
+        let cards_in_play = player.lock().await.cards_in_play.clone();
+        for (in_play_index, card_arc) in cards_in_play.iter().enumerate() {
+            let card = card_arc.lock().await;
+            if card.tapped || card.current_phase != CardPhase::Ready {
+                continue;
+            }
+            let options: Vec<(String, ManaType)> = card
+                .triggers
+                .iter()
+                .filter(|trigger| trigger.is_mana_ability())
+                .filter_map(|trigger| {
+                    let action = trigger
+                        .action
+                        .as_any()
+                        .downcast_ref::<action::generate_mana::GenerateManaAction>()?;
+                    match action.mana_to_add.as_slice() {
+                        [mana] => Some((trigger.id.clone(), *mana)),
+                        _ => None,
+                    }
+                })
+                .collect();
+            if !options.is_empty() {
+                sources.push((in_play_index, options));
+            }
+        }
+        sources
     }
 
-    pub async fn collect_card_played_actions(
-        &self,
-        card_arc: &Arc<Mutex<Card>>,
-    ) -> Vec<Arc<dyn Action + Send + Sync>> {
-        let mut actions: Vec<Arc<dyn Action + Send + Sync>> = Vec::new();
-        let owner = card_arc.lock().await.owner.clone();
-        if let Some(owner) = &owner {
-            for player in &self.players {
-                let cards = player.lock().await.cards_in_play.clone();
+    // What the player could do at instant speed right now: cast an instant
+    // from their hand or with flashback from their graveyard, activate an
+    // ability that isn't a mana ability or turn a morph face up, as long as
+    // their pool and untapped lands can pay for it.
+    pub async fn legal_actions(&self, player: &Arc<Mutex<Player>>) -> Vec<LegalAction> {
+        let mut actions = vec![];
+        if self.split_second_spell().await.is_some() {
+            return actions;
+        }
 
-                for card in &cards {
-                    let triggers = card.lock().await.triggers.clone();
-                    let target = Some(EffectTarget::Card(Arc::clone(card_arc)));
-                    for trigger in triggers {
-                        if &trigger.trigger_type == &ActionTriggerType::CardPlayedFromHand
-                            && Arc::ptr_eq(card, card_arc)
-                        {
-                            actions.push(Arc::new(CardActionWrapper {
-                                action: trigger.action.clone(),
-                                card: Arc::clone(card),
-                                target: target.clone(),
-                            }));
-                        }
+        let (hand, graveyard, in_play) = {
+            let mut player = player.lock().await;
+            (
+                player.cards_in_hand.clone(),
+                player.zone_mut(Zone::Graveyard).clone(),
+                player.cards_in_play.clone(),
+            )
+        };
+        for card_arc in &hand {
+            let (is_instant, card_id) = {
+                let card = card_arc.lock().await;
+                (card.card_type == CardType::Instant, card.id.clone())
+            };
+            if !is_instant {
+                continue;
+            }
+            let cost = Card::cost_to_cast(card_arc).await;
+            if Self::payment_plan(player, &cost).await.is_ok() {
+                actions.push(LegalAction::CastSpell { card_id });
+            }
+        }
 
-                        if let ActionTriggerType::CreatureTypeCardPlayed(
-                            trigger_target,
-                            creature_type,
-                        ) = &trigger.trigger_type
-                        {
-                            match trigger_target {
-                                TriggerTarget::Target => todo!(),
-                                TriggerTarget::Owner => {
-                                    if Arc::ptr_eq(card, card_arc) {
-                                        continue;
-                                    }
-                                    let card_creature_type = { card.lock().await.creature_type };
-                                    if card_creature_type == Some(*creature_type) {
-                                        if self
-                                            .current_turn
-                                            .as_ref()
-                                            .and_then(|x| {
-                                                Some(Arc::ptr_eq(&x.current_player, owner))
-                                            })
-                                            .unwrap_or(false)
-                                        {
-                                            actions.push(Arc::new(CardActionWrapper {
-                                                action: trigger.action.clone(),
-                                                card: Arc::clone(card),
-                                                target: target.clone(),
-                                            }));
-                                        }
-                                    }
-                                }
-                                TriggerTarget::Any => todo!(),
-                            }
-                        }
-                        if let ActionTriggerType::OtherCardPlayed(trigger_target) =
-                            &trigger.trigger_type
+        for card_arc in &graveyard {
+            let (card_id, cost) = {
+                let card = card_arc.lock().await;
+                if card.card_type != CardType::Instant {
+                    continue;
+                }
+                let Some(cost) = card.flashback.clone() else {
+                    continue;
+                };
+                (card.id.clone(), cost)
+            };
+            if Self::payment_plan(player, &cost).await.is_ok() {
+                actions.push(LegalAction::FlashbackSpell { card_id });
+            }
+        }
+
+        let phase = self.current_phase();
+        for card_arc in &in_play {
+            let (abilities, morph): (Vec<(String, String, Vec<ManaType>)>, _) = {
+                let card = card_arc.lock().await;
+                if card.phased_out {
+                    continue;
+                }
+                let untapped = !card.tapped && card.current_phase == CardPhase::Ready;
+                let abilities = card
+                    .triggers
+                    .iter()
+                    .filter(|trigger| !trigger.is_mana_ability())
+                    .filter_map(|trigger| match &trigger.trigger_type {
+                        ActionTriggerType::AbilityWithinPhases(_, cost, allowed, tap_required)
+                            if allowed
+                                .as_ref()
+                                .is_none_or(|allowed| allowed.contains(&phase))
+                                && (untapped || !tap_required) =>
                         {
-                            match trigger_target {
-                                TriggerTarget::Target => todo!(),
-                                TriggerTarget::Owner => {
-                                    if Arc::ptr_eq(card, card_arc) {
-                                        continue;
-                                    }
-                                    if self
-                                        .current_turn
-                                        .as_ref()
-                                        .and_then(|x| Some(Arc::ptr_eq(&x.current_player, owner)))
-                                        .unwrap_or(false)
-                                    {
-                                        actions.push(Arc::new(CardActionWrapper {
-                                            action: trigger.action.clone(),
-                                            card: Arc::clone(card),
-                                            target: target.clone(),
-                                        }));
-                                    }
-                                }
-                                TriggerTarget::Any => todo!(),
-                            }
+                            Some((card.id.clone(), trigger.id.clone(), cost.clone()))
                         }
-                    }
+                        _ => None,
+                    })
+                    .collect();
+                let morph = card.morph.clone().filter(|_| card.face_down);
+                (abilities, morph.map(|cost| (card.id.clone(), cost)))
+            };
+            if let Some((card_id, cost)) = morph {
+                if Self::payment_plan(player, &cost).await.is_ok() {
+                    actions.push(LegalAction::TurnFaceUp { card_id });
+                }
+            }
+            for (card_id, trigger_id, cost) in abilities {
+                if Self::payment_plan(player, &cost).await.is_ok() {
+                    actions.push(LegalAction::ActivateAbility {
+                        card_id,
+                        trigger_id,
+                    });
                 }
             }
         }
@@ -1025,24 +2745,438 @@ impl Game {
         actions
     }
 
-    pub async fn collect_actions_for_phase(&mut self) -> Vec<Arc<dyn Action + Send + Sync>> {
-        let mut actions = Vec::new();
+    async fn tap_for_mana(
+        &mut self,
+        player: &Arc<Mutex<Player>>,
+        taps: Vec<(usize, String)>,
+    ) -> Result<(), String> {
+        for (in_play_index, trigger_id) in taps {
+            self.activate_card_action(player, in_play_index, None, trigger_id)
+                .await?;
+        }
+        Ok(())
+    }
 
-        for (player_index, player) in self.players.iter().enumerate() {
-            let mut a = Player::collection_actions_for_phase(
-                Arc::clone(player),
-                player_index,
-                self.current_turn.clone().unwrap(),
-            )
-            .await;
-            actions.append(&mut a);
+    // Taps just enough lands to cover what the pool doesn't for the card in
+    // hand at `index`. Lands that only make one color go first, so ones with a
+    // choice are kept back. Nothing is tapped if the lands can't cover it.
+    pub async fn auto_pay(
+        &mut self,
+        player: &Arc<Mutex<Player>>,
+        index: usize,
+    ) -> Result<(), String> {
+        let card_arc = player
+            .lock()
+            .await
+            .cards_in_hand
+            .get(index)
+            .cloned()
+            .ok_or("Invalid card index")?;
+        let cost = Card::cost_to_cast(&card_arc).await;
+        let taps = Self::payment_plan(player, &cost).await?;
+
+        self.tap_for_mana(player, taps).await
+    }
 
-            for card_rc in &player.lock().await.cards_in_play {
-                let collected_actions: Vec<Arc<dyn Action + Send + Sync>> =
-                    Card::collect_phase_based_actions(
-                        card_rc,
-                        &self.current_turn.clone().unwrap(),
-                        action::ActionTriggerType::PhaseStarted(
+    // The lands auto_pay would tap to cover `cost`, on top of the floating
+    // mana, if they can cover it at all.
+    async fn payment_plan(
+        player: &Arc<Mutex<Player>>,
+        cost: &[ManaType],
+    ) -> Result<Vec<(usize, String)>, String> {
+        let (mut colored_needed, generic_needed) = Self::unpaid_cost(player, cost).await;
+
+        let mut sources = Self::mana_sources(player).await;
+        sources.sort_by_key(|(_, options)| options.len());
+
+        let mut taps = vec![];
+        colored_needed.sort_by_key(|mana| {
+            sources
+                .iter()
+                .filter(|(_, options)| options.iter().any(|(_, made)| made == mana))
+                .count()
+        });
+        for mana in colored_needed {
+            let pos = sources
+                .iter()
+                .position(|(_, options)| options.iter().any(|(_, made)| *made == mana))
+                .ok_or("Not enough lands to pay for it")?;
+            let (in_play_index, options) = sources.remove(pos);
+            let (trigger_id, _) = options.into_iter().find(|(_, made)| *made == mana).unwrap();
+            taps.push((in_play_index, trigger_id));
+        }
+        if sources.len() < generic_needed {
+            return Err("Not enough lands to pay for it".to_string());
+        }
+        for (in_play_index, mut options) in sources.into_iter().take(generic_needed) {
+            taps.push((in_play_index, options.remove(0).0));
+        }
+
+        Ok(taps)
+    }
+
+    // Taps the lands the player picked for the card in hand at `index`. The
+    // plan has to cover the cost without making mana that would go unused,
+    // otherwise nothing is tapped.
+    pub async fn pay_with(
+        &mut self,
+        player: &Arc<Mutex<Player>>,
+        index: usize,
+        plan: &[PaymentChoice],
+    ) -> Result<(), String> {
+        let card_arc = player
+            .lock()
+            .await
+            .cards_in_hand
+            .get(index)
+            .cloned()
+            .ok_or("Invalid card index")?;
+        let card_name = card_arc.lock().await.name.clone();
+        let cost = Card::cost_to_cast(&card_arc).await;
+        let (colored_needed, generic_needed) = Self::unpaid_cost(player, &cost).await;
+
+        let sources = Self::mana_sources(player).await;
+        let mut in_play_ids = vec![];
+        for card in player.lock().await.cards_in_play.iter() {
+            in_play_ids.push(card.lock().await.id.clone());
+        }
+        let mut taps: Vec<(usize, String)> = vec![];
+        let mut paying = vec![];
+        for choice in plan {
+            let in_play_index = in_play_ids
+                .iter()
+                .position(|id| *id == choice.card_id)
+                .ok_or_else(|| format!("No card {} in play", choice.card_id))?;
+            if taps.iter().any(|(index, _)| *index == in_play_index) {
+                return Err(format!("{} is listed twice", choice.card_id));
+            }
+            let (trigger_id, _) = sources
+                .iter()
+                .find(|(index, _)| *index == in_play_index)
+                .and_then(|(_, options)| options.iter().find(|(_, made)| *made == choice.mana))
+                .ok_or_else(|| format!("{} can't make {}", choice.card_id, choice.mana.format()))?;
+            taps.push((in_play_index, trigger_id.clone()));
+            paying.push(choice.mana);
+        }
+
+        for mana in colored_needed {
+            let pos = paying
+                .iter()
+                .position(|paid| *paid == mana)
+                .ok_or_else(|| format!("That doesn't pay for {}", card_name))?;
+            paying.remove(pos);
+        }
+        if paying.len() < generic_needed {
+            return Err(format!("That doesn't pay for {}", card_name));
+        }
+        if paying.len() > generic_needed {
+            return Err(format!("That pays more than {} costs", card_name));
+        }
+
+        self.tap_for_mana(player, taps).await
+    }
+
+    // Spells that target a spell, like counterspells, can't be aimed at an
+    // ability or trigger from the same card, and ones that target an ability
+    // can't be aimed at a spell. For those, gives back the ability they'll
+    // counter: the topmost one from the target.
+    async fn check_spell_target(
+        &self,
+        player: &Arc<Mutex<Player>>,
+        from: Zone,
+        index: usize,
+        target: &Arc<Mutex<Card>>,
+    ) -> Result<Option<Arc<dyn Action + Send + Sync>>, String> {
+        let card_arc = player
+            .lock()
+            .await
+            .zone_mut(from)
+            .get(index)
+            .cloned()
+            .ok_or("Invalid card index")?;
+        let card = card_arc.lock().await;
+        let targets = |required: CardRequiredTarget| {
+            card.triggers.iter().any(|trigger| {
+                trigger.trigger_type == ActionTriggerType::CardPlayedFromHand
+                    && trigger.card_required_target == required
+            })
+        };
+        if targets(CardRequiredTarget::Spell) && !self.is_on_stack_as(target, StackItemKind::Spell)
+        {
+            return Err(format!("{} can only target a spell", card.name));
+        }
+        if targets(CardRequiredTarget::Ability) {
+            return match self.topmost_ability(target) {
+                Some(ability) => Ok(Some(ability)),
+                None => Err(format!("{} can only target an ability", card.name)),
+            };
+        }
+
+        Ok(None)
+    }
+
+    // Several targets are only for spells that take "up to N" cards of a type,
+    // and each has to be a different card of that type in play.
+    async fn check_multiple_targets(
+        &self,
+        player: &Arc<Mutex<Player>>,
+        from: Zone,
+        index: usize,
+        targets: &[Arc<Mutex<Card>>],
+    ) -> Result<(), String> {
+        let card_arc = player
+            .lock()
+            .await
+            .zone_mut(from)
+            .get(index)
+            .cloned()
+            .ok_or("Invalid card index")?;
+        let (name, required) = {
+            let card = card_arc.lock().await;
+            let required =
+                card.triggers
+                    .iter()
+                    .find_map(|trigger| match &trigger.card_required_target {
+                        required @ CardRequiredTarget::MultipleCardsOfType(_, max_targets)
+                            if trigger.trigger_type == ActionTriggerType::CardPlayedFromHand =>
+                        {
+                            Some((required.clone(), *max_targets))
+                        }
+                        _ => None,
+                    });
+            (card.name.clone(), required)
+        };
+        let Some((required, max_targets)) = required else {
+            return Err(format!("{} only takes one target", name));
+        };
+
+        if targets.is_empty() || targets.len() > max_targets as usize {
+            return Err(format!(
+                "{} needs between 1 and {} targets",
+                name, max_targets
+            ));
+        }
+        for (i, target) in targets.iter().enumerate() {
+            if targets[..i].iter().any(|other| Arc::ptr_eq(other, target)) {
+                return Err("Each target has to be a different card".to_string());
+            }
+
+            let mut controller = None;
+            for player_arc in &self.players {
+                if player_arc
+                    .lock()
+                    .await
+                    .cards_in_play
+                    .iter()
+                    .any(|card| Arc::ptr_eq(card, target))
+                {
+                    controller = Some(Arc::clone(player_arc));
+                }
+            }
+            let Some(controller) = controller else {
+                return Err("You can only target cards in play".to_string());
+            };
+            let owned = Arc::ptr_eq(&controller, player);
+            let target_card = target.lock().await;
+            if !self.card_target_matches(&required, target, &target_card, owned) {
+                return Err(format!("{} can't target {}", name, target_card.name));
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn execute_card(
+        &mut self,
+        player: &Arc<Mutex<Player>>,
+        from: Zone,
+        index: usize,
+        target: Option<EffectTarget>,
+    ) -> Result<Arc<Mutex<Card>>, String> {
+        let (action, card) = {
+            Player::play_card(
+                player,
+                from,
+                index,
+                target.clone(),
+                self.current_turn.clone().unwrap(),
+            )
+            .await?
+        };
+        self.mana_pool_updated(player).await;
+
+        self.add_to_stack(action);
+
+        let (is_spell, storm) = {
+            let card = card.lock().await;
+            (
+                card.card_type.is_spell(),
+                card.get_stat_value(StatType::Storm) > 0,
+            )
+        };
+        if is_spell {
+            let spells_before = self.spells_cast_this_turn;
+            self.spells_cast_this_turn = self.spells_cast_this_turn.saturating_add(1);
+            if storm {
+                for _ in 0..spells_before {
+                    self.copy_spell(&card, None).await?;
+                }
+            }
+        }
+
+        self.effect_manager
+            .apply_effects(self.current_turn.clone().unwrap())
+            .await;
+
+        Ok(card)
+    }
+
+    pub async fn next_priority_queue(&mut self) {}
+
+    pub fn send_command(&mut self, command: LobbyCommand) {
+        if let Some(ref sender) = self.broadcast_sender {
+            let _ = sender.send(Some(command));
+        }
+    }
+
+    pub fn debug(&mut self, message: &str) {
+        debug!("{}", message);
+        if let Some(ref sender) = self.broadcast_sender {
+            let _ = sender.send(Some(LobbyCommand::DebugMessage(message.to_string())));
+        }
+    }
+
+    pub async fn become_monarch(&mut self, player: &Arc<Mutex<Player>>) {
+        if self
+            .monarch
+            .as_ref()
+            .is_some_and(|monarch| Arc::ptr_eq(monarch, player))
+        {
+            return;
+        }
+
+        self.monarch = Some(Arc::clone(player));
+        let name = player.lock().await.name.clone();
+        self.add_turn_message(format!("{} becomes the monarch.", name));
+        self.notify_state_changed();
+    }
+
+    pub fn is_monarch(&self, player: &Arc<Mutex<Player>>) -> bool {
+        self.monarch
+            .as_ref()
+            .is_some_and(|monarch| Arc::ptr_eq(monarch, player))
+    }
+
+    pub fn add_turn_message(&mut self, message: String) {
+        self.turn_messages.push(message);
+        self.messages_updated();
+    }
+
+    pub async fn collect_card_played_actions(
+        &self,
+        card_arc: &Arc<Mutex<Card>>,
+    ) -> Vec<Arc<dyn Action + Send + Sync>> {
+        let mut actions: Vec<Arc<dyn Action + Send + Sync>> = Vec::new();
+        let owner = card_arc.lock().await.controller();
+        if let Some(owner) = &owner {
+            for player in &self.players {
+                let cards = player.lock().await.cards_in_play.clone();
+
+                for card in &cards {
+                    let (triggers, spell_target) = {
+                        let card = card.lock().await;
+                        if card.phased_out {
+                            continue;
+                        }
+                        (card.triggers.clone(), card.target.clone())
+                    };
+                    let target = Some(EffectTarget::Card(Arc::clone(card_arc)));
+                    for trigger in triggers {
+                        // An intervening if keeps the trigger off the stack entirely
+                        if !trigger.condition_met(card).await {
+                            continue;
+                        }
+                        if trigger.trigger_type == ActionTriggerType::CardPlayedFromHand
+                            && Arc::ptr_eq(card, card_arc)
+                        {
+                            // The spell's own effect goes to whatever it was cast at
+                            actions.push(Arc::new(CardActionWrapper {
+                                action: trigger.action.clone(),
+                                card: Arc::clone(card),
+                                target: spell_target.clone().or(target.clone()),
+                            }));
+                        }
+
+                        let watched_target = match &trigger.trigger_type {
+                            ActionTriggerType::CreatureTypeCardPlayed(
+                                trigger_target,
+                                creature_type,
+                            ) => {
+                                let played_type = { card_arc.lock().await.creature_type };
+                                if played_type != Some(*creature_type) {
+                                    continue;
+                                }
+                                trigger_target
+                            }
+                            ActionTriggerType::OtherCardPlayed(trigger_target) => trigger_target,
+                            _ => continue,
+                        };
+                        if Arc::ptr_eq(card, card_arc) {
+                            continue;
+                        }
+                        let cast_by_controller = card
+                            .lock()
+                            .await
+                            .controller()
+                            .is_some_and(|controller| Arc::ptr_eq(&controller, owner));
+                        let applies = match watched_target {
+                            TriggerTarget::Owner => cast_by_controller,
+                            // Cast by one of the watcher's opponents
+                            TriggerTarget::Target => !cast_by_controller,
+                            TriggerTarget::Any => true,
+                        };
+                        if applies {
+                            actions.push(Arc::new(CardActionWrapper {
+                                action: trigger.action.clone(),
+                                card: Arc::clone(card),
+                                target: target.clone(),
+                            }));
+                        }
+                    }
+                }
+            }
+        }
+
+        actions
+    }
+
+    pub async fn collect_actions_for_phase(&mut self) -> Vec<Arc<dyn Action + Send + Sync>> {
+        let mut actions = self.take_due_delayed_triggers();
+
+        for (player_index, player) in self.players.iter().enumerate() {
+            let mut a = Player::collection_actions_for_phase(
+                Arc::clone(player),
+                player_index,
+                self.current_turn.clone().unwrap(),
+            )
+            .await;
+            actions.append(&mut a);
+
+            // Cloned so trigger conditions are free to look at the player
+            let cards_in_play = player.lock().await.cards_in_play.clone();
+            for card_rc in &cards_in_play {
+                // A spell only passes through play while it resolves, e.g. one that ends the turn
+                if matches!(
+                    card_rc.lock().await.card_type,
+                    CardType::Instant | CardType::Sorcery
+                ) {
+                    continue;
+                }
+                let collected_actions: Vec<Arc<dyn Action + Send + Sync>> =
+                    Card::collect_phase_based_actions(
+                        card_rc,
+                        &self.current_turn.clone().unwrap(),
+                        action::ActionTriggerType::PhaseStarted(
                             vec![self.current_phase()],
                             TriggerTarget::Any,
                         ),
@@ -1052,7 +3186,7 @@ impl Game {
 
                 let has_effects = self.effect_manager.has_effects(card_rc).await;
                 if card_rc.lock().await.is_useless(has_effects) {
-                    println!("card is considered useless, let's get rid of it");
+                    debug!("card is considered useless, let's get rid of it");
                     actions.push(Arc::new(CardActionWrapper {
                         action: Arc::new(DestroyTargetCAction {}),
                         card: Arc::clone(card_rc),
@@ -1065,6 +3199,51 @@ impl Game {
         actions
     }
 
+    pub fn schedule_delayed_trigger(
+        &mut self,
+        player: Arc<Mutex<Player>>,
+        phase: TurnPhase,
+        card: Arc<Mutex<Card>>,
+        action: Arc<dyn CardAction + Send + Sync>,
+    ) {
+        let scheduled = self
+            .current_turn
+            .as_ref()
+            .map_or((-1, TurnPhase::Untap), |turn| {
+                (turn.turn_number, turn.phase)
+            });
+        self.delayed_triggers.push(DelayedTrigger {
+            player,
+            phase,
+            card,
+            action,
+            scheduled,
+        });
+    }
+
+    // Delayed triggers for the step that just started, which are done with
+    // once they fire.
+    fn take_due_delayed_triggers(&mut self) -> Vec<Arc<dyn Action + Send + Sync>> {
+        let Some(turn) = self.current_turn.clone() else {
+            return vec![];
+        };
+        let (due, waiting): (Vec<DelayedTrigger>, Vec<DelayedTrigger>) =
+            std::mem::take(&mut self.delayed_triggers)
+                .into_iter()
+                .partition(|trigger| trigger.is_due(&turn));
+        self.delayed_triggers = waiting;
+
+        due.into_iter()
+            .map(|trigger| -> Arc<dyn Action + Send + Sync> {
+                Arc::new(CardActionWrapper {
+                    target: Some(EffectTarget::Card(Arc::clone(&trigger.card))),
+                    card: trigger.card,
+                    action: trigger.action,
+                })
+            })
+            .collect()
+    }
+
     pub async fn collect_omnipresent_actions(&mut self) -> Vec<Arc<dyn Action + Send + Sync>> {
         let mut actions: Vec<Arc<dyn Action + Send + Sync>> = Vec::new();
 
@@ -1072,7 +3251,13 @@ impl Game {
             let cards = player.lock().await.cards_in_play.clone();
 
             for card in &cards {
-                let triggers = card.lock().await.triggers.clone();
+                let triggers = {
+                    let card = card.lock().await;
+                    if card.phased_out {
+                        continue;
+                    }
+                    card.triggers.clone()
+                };
                 let target = Some(EffectTarget::Card(Arc::clone(&card)));
                 for trigger in triggers {
                     match trigger.trigger_type {
@@ -1096,18 +3281,26 @@ impl Game {
         let actions_to_execute = std::mem::take(actions);
 
         for action in actions_to_execute {
-            println!("Applying action {:?}", action);
+            debug!("Applying action {:?}", action);
             action.apply(self).await;
         }
         let actions_to_execute = self.collect_omnipresent_actions().await;
         for action in actions_to_execute {
-            println!("Applying action {:?}", action);
+            debug!("Applying action {:?}", action);
             action.apply(self).await;
         }
 
         self.effect_manager
             .apply_effects(self.current_turn.clone().unwrap())
             .await;
+
+        self.notify_state_changed();
+    }
+
+    /// Wakes anything waiting on a mana pool or phase change, such as a paid
+    /// ability that is waiting for its owner to tap enough mana.
+    pub fn notify_state_changed(&self) {
+        self.state_notify.notify_waiters();
     }
 
     pub fn reset_turn_messages(&mut self) {
@@ -1124,18 +3317,23 @@ impl Game {
             self.turn_number,
         ));
         self.turn_number += 1;
+        self.spells_cast_this_turn = 0;
 
         self.reset_creature_damage().await;
 
         for player in &self.players {
             let mut player = player.lock().await;
             player.health_at_start_of_round = player.stat_manager.get_stat_value(StatType::Health);
+            player.life_gained_this_turn = 0;
+            player.lands_played_this_turn = 0;
+            player.prevent_next_combat_damage = false;
             player.triggers_played_this_turn = HashSet::new();
         }
 
         let player_arc = self.players[player_index].clone();
         {
             let mut player = player_arc.lock().await;
+            player.passing_until_my_turn = false;
             player.advance_card_phases().await;
         }
     }
@@ -1143,11 +3341,14 @@ impl Game {
         let player = self.players[self.current_turn.clone().unwrap().current_player_index as usize]
             .lock()
             .await;
+        #[cfg(feature = "cli")]
         println!(
             "{}'s turn: ------\n{}",
             player.name,
             player.render(30, 10, 30).await
         );
+        #[cfg(not(feature = "cli"))]
+        debug!("{}'s turn: ------\n{:#?}", player.name, player.view().await);
     }
 
     pub async fn handle_deaths(&mut self) {
@@ -1158,13 +3359,43 @@ impl Game {
             let health = player.get_stat_value(StatType::Health);
             if health <= 0 {
                 player.is_alive = false;
-                println!("{} has been defeated.", player.name);
+                info!(player = %player.name, "player defeated");
             } else {
                 alive_players.push(player_arc.clone());
             }
         }
 
         self.players = alive_players;
+
+        // A monarch leaving the game hands it to whoever's turn it is
+        let monarch_left = self.monarch.as_ref().is_some_and(|monarch| {
+            !self
+                .players
+                .iter()
+                .any(|player| Arc::ptr_eq(player, monarch))
+        });
+        if monarch_left {
+            self.monarch = None;
+            let active = self
+                .current_turn
+                .as_ref()
+                .map(|turn| Arc::clone(&turn.current_player))
+                .filter(|active| self.players.iter().any(|p| Arc::ptr_eq(p, active)));
+            if let Some(active) = active {
+                self.become_monarch(&active).await;
+            }
+        }
+
+        // Indexes shift when players are removed, so re-resolve the active player
+        if let Some(turn) = self.current_turn.as_mut() {
+            if let Some(index) = self
+                .players
+                .iter()
+                .position(|p| Arc::ptr_eq(p, &turn.current_player))
+            {
+                turn.current_player_index = index as i32;
+            }
+        }
     }
 
     pub async fn execute_player_action(
@@ -1228,6 +3459,11 @@ impl Game {
                 return PriorityActionResult::Timeout;
             }
 
+            // There's nothing anyone could do, so don't make them wait
+            if game_arc.lock().await.split_second_spell().await.is_some() {
+                return PriorityActionResult::NoAction;
+            }
+
             // Remove the check for current_priority_player change
             // The player remains the same during their priority turn
 
@@ -1235,7 +3471,7 @@ impl Game {
                 let mut game = game_arc.lock().await;
                 match game.performed_action() {
                     ActionType::PlayedCard => {
-                        println!("Player performed an action requiring priority loop restart.");
+                        debug!("Player performed an action requiring priority loop restart.");
                         // Reset the action performed flag
                         // {
                         //     let mut game = game_arc.write().await;
@@ -1251,7 +3487,7 @@ impl Game {
                         // No action performed
                     }
                     x => {
-                        println!("Player performed an action {:?}, resetting timer.", x);
+                        debug!("Player performed an action {:?}, resetting timer.", x);
                         if let Some((_, _, action)) = &mut game.current_priority_player {
                             *action = ActionType::None;
                         }
@@ -1288,6 +3524,48 @@ impl Game {
         }
     }
 
+    // Lets a player pass on everything until their own next turn, so nobody
+    // waits on them in the meantime. Something aimed at them takes it back.
+    pub async fn pass_until_my_turn(&mut self, player: &Arc<Mutex<Player>>, passing: bool) {
+        let name = {
+            let mut player = player.lock().await;
+            player.passing_until_my_turn = passing;
+            player.name.clone()
+        };
+        if passing {
+            self.add_turn_message(format!("{} is passing until their turn", name));
+        }
+    }
+
+    // Whether anything waiting on the stack targets the player themselves.
+    fn is_targeted_on_stack(&self, player: &Arc<Mutex<Player>>) -> bool {
+        self.event_stack.iter().any(|action| {
+            matches!(
+                action.stack_target(),
+                Some(EffectTarget::Player(target)) if Arc::ptr_eq(target, player)
+            )
+        })
+    }
+
+    // Whether the player has said they're passing and nothing has since made
+    // them want to look. Being targeted ends their passing for the turn.
+    async fn is_auto_passing(game_arc: &Arc<Mutex<Game>>, player: &Arc<Mutex<Player>>) -> bool {
+        let mut game = game_arc.lock().await;
+        if !player.lock().await.passing_until_my_turn {
+            return false;
+        }
+        if game.is_targeted_on_stack(player) {
+            let name = {
+                let mut player = player.lock().await;
+                player.passing_until_my_turn = false;
+                player.name.clone()
+            };
+            game.add_turn_message(format!("{} is targeted and stops passing", name));
+            return false;
+        }
+        true
+    }
+
     pub fn performed_action(&self) -> ActionType {
         if let Some((_, _, action)) = &self.current_priority_player {
             return action.clone();
@@ -1298,8 +3576,23 @@ impl Game {
     pub async fn priority_loop(game_arc: Arc<Mutex<Game>>, source_card_arc: Arc<Mutex<Card>>) {
         let mut players_in_order = {
             let mut game = game_arc.lock().await;
+            let players = game
+                .get_players_in_priority_order(&source_card_arc.lock().await.controller().unwrap());
+            if game.skip_empty_priority_windows {
+                let mut anyone_can_respond = false;
+                for player in &players {
+                    if !game.legal_actions(player).await.is_empty() {
+                        anyone_can_respond = true;
+                        break;
+                    }
+                }
+                if !anyone_can_respond {
+                    game.debug("Nobody can respond, skipping priority");
+                    return;
+                }
+            }
             game.debug("Entering priority loop");
-            game.get_players_in_priority_order(&source_card_arc.lock().await.owner.clone().unwrap())
+            players
         };
 
         loop {
@@ -1311,11 +3604,15 @@ impl Game {
                 if passed_players[i] {
                     continue;
                 }
+                if Game::is_auto_passing(&game_arc, player_arc).await {
+                    passed_players[i] = true;
+                    continue;
+                }
 
                 {
                     let mut player = player_arc.lock().await;
                     player.priority_turn_start().await;
-                    println!("Player {}'s priority turn has started.", player.name);
+                    debug!(player = %player.name, "priority turn started");
                 }
                 let time_limit = if i == 0 { 3 } else { 3 };
 
@@ -1330,11 +3627,13 @@ impl Game {
                 }
 
                 let game_arc_clone = Arc::clone(&game_arc);
+                let timer = METRICS.priority_window_seconds.start_timer();
                 let result = Game::wait_for_player_action_async(game_arc_clone, time_limit).await;
+                timer.observe_duration();
 
                 match result {
                     PriorityActionResult::ActionRequiresRestart => {
-                        println!("Player performed an action requiring priority loop restart.");
+                        debug!("Player performed an action requiring priority loop restart.");
                         // Restart the priority loop from the player who performed the action
                         players_in_order = {
                             let game = game_arc.lock().await;
@@ -1346,14 +3645,13 @@ impl Game {
                         break;
                     }
                     PriorityActionResult::Timeout => {
-                        println!(
-                            "{} did not act in time, passing.",
-                            player_arc.lock().await.name
-                        );
+                        let name = player_arc.lock().await.name.clone();
+                        debug!(player = %name, "did not act in time, passing");
                         passed_players[i] = true;
                     }
                     PriorityActionResult::NoAction => {
-                        println!("{} passed without action.", player_arc.lock().await.name);
+                        let name = player_arc.lock().await.name.clone();
+                        debug!(player = %name, "passed without action");
                         passed_players[i] = true;
                     }
                 }
@@ -1365,7 +3663,7 @@ impl Game {
             }
 
             if passed_players.iter().all(|&passed| passed) {
-                println!("All players have passed. Exiting priority loop.");
+                debug!("All players have passed. Exiting priority loop.");
 
                 {
                     let game = game_arc.lock().await;
@@ -1392,16 +3690,20 @@ impl Game {
                 let mut game = game_arc.lock().await;
                 game.add_turn_message(format!(
                     "{} is casting {}",
-                    card.owner.unwrap().lock().await.name,
+                    card.controller().unwrap().lock().await.name,
                     card.name
                 ));
             }
 
-            Self::priority_loop(Arc::clone(&game_arc), card_arc).await;
+            let auto_pass_priority = { game_arc.lock().await.auto_pass_priority };
+            if !auto_pass_priority {
+                Self::priority_loop(Arc::clone(&game_arc), card_arc).await;
+            }
         }
 
         let mut game = game_arc.lock().await;
 
+        game.record(ReplayInput::ResolveStack);
         game.resolve_stack().await;
 
         if let Some(ref sender) = game.broadcast_sender {
@@ -1417,44 +3719,3161 @@ impl Game {
         }
     }
 
+    pub fn mana_added(&self, player_id: String, card_id: String, mana: Vec<ManaType>) {
+        if let Some(ref sender) = self.broadcast_sender {
+            let _ = sender.send(Some(LobbyCommand::ManaAdded(ManaAdded {
+                player_id,
+                card_id,
+                mana,
+            })));
+        }
+    }
+
+    pub async fn mana_pool_updated(&self, player: &Arc<Mutex<Player>>) {
+        if let Some(ref sender) = self.broadcast_sender {
+            let (player_id, mana_pool) = {
+                let player = player.lock().await;
+                (player.name.clone(), player.mana_pool.clone())
+            };
+            let _ = sender.send(Some(LobbyCommand::ManaPoolUpdated(ManaPoolUpdate {
+                player_id,
+                mana_pool,
+            })));
+        }
+    }
+
     pub async fn advance_turn(&mut self) {
         if let Some(ref mut turn) = self.current_turn {
             if let Some((current_player, _, action_taken)) = &mut self.current_priority_player {
                 if !Arc::ptr_eq(&turn.current_player, current_player) {
-                    println!("cannot advance turn while waiting for priority queue.");
+                    warn!("cannot advance turn while waiting for priority queue");
                     return;
                 }
             }
-
             turn.next_phase();
+            self.state_notify.notify_waiters();
             if turn.phase == TurnPhase::Untap {
-                let next_player_index = (turn.current_player_index + 1) % self.players.len() as i32;
-                println!("advancing player? {}", next_player_index);
-                self.start_turn(next_player_index as usize).await;
+                let current_player = Arc::clone(&turn.current_player);
+                let next_player_index = self.next_turn_player_index(&current_player);
+                info!(player_index = next_player_index, "starting turn");
+                self.start_turn(next_player_index).await;
             }
 
+            self.record(ReplayInput::AdvanceTurn);
+            self.sweep_stale_abilities().await;
+
             let mut actions = self.collect_actions_for_phase().await;
             self.execute_actions(&mut actions).await;
 
-            println!(
-                ":: TURN ADVANCED :: {:?} effects: {:?}",
-                self.current_turn.clone().unwrap().phase,
-                ""
-            );
+            debug!(phase = ?self.current_turn.as_ref().unwrap().phase, "turn advanced");
         }
     }
 
-    fn current_phase(&self) -> TurnPhase {
-        self.current_turn.as_ref().unwrap().phase
+    // Steps through every phase up to the given one, running their triggers
+    // as usual. Gives up after a full turn if priority keeps it from moving.
+    pub async fn skip_to_phase(&mut self, phase: TurnPhase) {
+        for _ in 0..PHASE_ORDER.len() {
+            if self.current_turn.is_none() || self.current_phase() == phase {
+                return;
+            }
+            self.advance_turn().await;
+        }
     }
 
-    pub(crate) async fn start(&mut self) {
-        for player in &self.players {
-            let mut player = player.lock().await;
-            for _ in 0..6 {
+    // "End the turn": everything on the stack is exiled, combat ends, and
+    // the turn jumps straight to cleanup without the phases in between.
+    pub async fn end_turn_now(&mut self) {
+        if self.current_turn.is_none() {
+            return;
+        }
+
+        self.event_stack.clear();
+        for player in self.players.clone() {
+            let spells = player.lock().await.spells.clone();
+            for spell in spells {
+                self.move_card(&spell, Zone::Stack, Zone::Exile).await.ok();
+            }
+        }
+        self.combat.clear();
+
+        if let Some(turn) = &mut self.current_turn {
+            turn.phase = TurnPhase::Cleanup;
+        }
+        self.state_notify.notify_waiters();
+        self.add_turn_message("The turn ends.".to_string());
+
+        let mut actions = self.collect_actions_for_phase().await;
+        self.execute_actions(&mut actions).await;
+    }
+
+    // Advances like advance_turn, but won't leave declare attackers while a
+    // creature that must attack is able to and hasn't, or a goaded creature
+    // is attacking the player who goaded it.
+    pub async fn try_advance_turn(&mut self) -> Result<(), GameError> {
+        self.check_opening_hands()?;
+        self.check_must_attack().await?;
+        self.advance_turn().await;
+        Ok(())
+    }
+
+    async fn check_must_attack(&self) -> Result<(), GameError> {
+        let Some(turn) = &self.current_turn else {
+            return Ok(());
+        };
+        if turn.phase != TurnPhase::DeclareAttackers {
+            return Ok(());
+        }
+
+        let active = Arc::clone(&turn.current_player);
+        let cards_in_play = active.lock().await.cards_in_play.clone();
+        for card_arc in cards_in_play {
+            let card = card_arc.lock().await;
+            let able = card.card_type == CardType::Creature
+                && !card.tapped
+                && !card.phased_out
+                && card.current_phase == CardPhase::Ready;
+            if !able {
+                continue;
+            }
+
+            let attacking = self
+                .combat
+                .attackers
+                .iter()
+                .find(|(attacker, _)| Arc::ptr_eq(attacker, &card_arc))
+                .map(|(_, target)| target);
+            let Some(target) = attacking else {
+                if card.must_attack || card.goaded_by.is_some() {
+                    return Err(GameError::MustAttack {
+                        creature: card.name.clone(),
+                    });
+                }
+                continue;
+            };
+
+            // Goaded creatures only go after the goading player if nobody else is left
+            if let (Some(goader), EffectTarget::Player(defender)) = (&card.goaded_by, target) {
+                let someone_else = self
+                    .players
+                    .iter()
+                    .any(|player| !Arc::ptr_eq(player, &active) && !Arc::ptr_eq(player, goader));
+                if Arc::ptr_eq(defender, goader) && someone_else {
+                    return Err(GameError::GoadedAttack {
+                        creature: card.name.clone(),
+                        player: goader.lock().await.name.clone(),
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn queue_extra_turn(&mut self, player: &Arc<Mutex<Player>>) {
+        self.extra_turns.push_back(Arc::clone(player));
+    }
+
+    pub fn skip_next_turn(&mut self, player: &Arc<Mutex<Player>>) {
+        self.skipped_turns.push(Arc::clone(player));
+    }
+
+    pub fn player_index(&self, player: &Arc<Mutex<Player>>) -> Option<usize> {
+        self.players.iter().position(|p| Arc::ptr_eq(p, player))
+    }
+
+    pub fn seat_index(&self, player: &Arc<Mutex<Player>>) -> Option<usize> {
+        self.seats.iter().position(|p| Arc::ptr_eq(p, player))
+    }
+
+    pub(crate) fn record(&mut self, input: ReplayInput) {
+        if let Some(replay) = &mut self.replay {
+            replay.inputs.push(input);
+        }
+    }
+
+    // Only before the game begins, so nobody can have played a card yet.
+    pub async fn mulligan(&mut self, player: &Arc<Mutex<Player>>) -> Result<(), String> {
+        if !self.awaiting_opening_hands {
+            return Err("Mulligans are only allowed at the start of the game".to_string());
+        }
+        player.lock().await.mulligan(&mut self.rng)?;
+        self.record(ReplayInput::Mulligan {
+            seat: self.seat_index(player).unwrap_or_default(),
+        });
+
+        Ok(())
+    }
+
+    pub async fn keep_hand(
+        &mut self,
+        player: &Arc<Mutex<Player>>,
+        hand_indexes: Vec<usize>,
+    ) -> Result<(), String> {
+        player.lock().await.keep_hand(hand_indexes.clone())?;
+        self.record(ReplayInput::KeepHand {
+            seat: self.seat_index(player).unwrap_or_default(),
+            hand_indexes,
+        });
+
+        let mut everyone_kept = true;
+        for player in &self.players {
+            everyone_kept &= player.lock().await.kept_hand;
+        }
+        if everyone_kept {
+            self.awaiting_opening_hands = false;
+            self.add_turn_message("Everyone has kept their hand".to_string());
+        }
+
+        Ok(())
+    }
+
+    pub fn player_for_target(
+        &self,
+        owner: &Arc<Mutex<Player>>,
+        target: &PlayerActionTarget,
+    ) -> Option<Arc<Mutex<Player>>> {
+        match target {
+            PlayerActionTarget::Owner => Some(Arc::clone(owner)),
+            PlayerActionTarget::Opponent => {
+                let owner_index = self.player_index(owner)?;
+                let opponent_index = (owner_index + 1) % self.players.len();
+                Some(Arc::clone(&self.players[opponent_index]))
+            }
+        }
+    }
+
+    // Picks who takes the next turn: queued extra turns go first, otherwise
+    // play passes to the next surviving seat that isn't skipping their turn.
+    fn next_turn_player_index(&mut self, current_player: &Arc<Mutex<Player>>) -> usize {
+        while let Some(player) = self.extra_turns.pop_front() {
+            if let Some(index) = self.player_index(&player) {
+                return index;
+            }
+        }
+
+        let current_seat = self
+            .seats
+            .iter()
+            .position(|p| Arc::ptr_eq(p, current_player))
+            .unwrap_or(0);
+        let mut skipped = None;
+        for offset in 1..=self.seats.len() {
+            let seat = Arc::clone(&self.seats[(current_seat + offset) % self.seats.len()]);
+            let Some(index) = self.player_index(&seat) else {
+                // No longer in the game
+                continue;
+            };
+
+            if let Some(position) = self
+                .skipped_turns
+                .iter()
+                .position(|p| Arc::ptr_eq(p, &seat))
+            {
+                self.skipped_turns.remove(position);
+                skipped.get_or_insert(index);
+                continue;
+            }
+
+            return index;
+        }
+
+        skipped.unwrap_or(0)
+    }
+
+    fn current_phase(&self) -> TurnPhase {
+        self.current_turn.as_ref().unwrap().phase
+    }
+
+    pub(crate) async fn start(&mut self) {
+        let seed: u64 = self.rng.gen();
+        self.start_with_seed(seed).await;
+    }
+
+    // Reseeds the rng for this game so the replay only needs the one seed,
+    // whatever was drawn from it while the lobby was shuffling.
+    pub(crate) async fn start_with_seed(&mut self, seed: u64) {
+        self.rng = GameRng::seeded(seed);
+        self.replay = Some(Replay::new(self, seed).await);
+        for player in &self.players {
+            let mut player = player.lock().await;
+            for _ in 0..OPENING_HAND_SIZE {
                 player.draw_card();
             }
         }
+        self.awaiting_opening_hands = true;
         self.start_turn(0).await;
     }
 }
+
+mod test {
+    use std::{any::Any, sync::Arc};
+
+    use tokio::{sync::Mutex, time::Duration};
+
+    use crate::game::{
+        action::{
+            delayed::{DelayedTriggerAction, SacrificeSelfAction},
+            draw::{DrawReplacement, DrawSource, SkipDrawStepAction},
+            morph::ManifestAction,
+            ActionTriggerType, CardAction, CardActionTarget, CardActionTrigger, CardActionWrapper,
+            CardRequiredTarget, CardTargetTeam, CombatAction, CounterSpellAction,
+            DeclareAttackerAction, DeclareBlockerAction, DestroyTargetCAction, DrawCardAction,
+            DrawCardCardAction, EndTheTurnAction, ExtraTurnAction, GainControlAction, GoadAction,
+            ModifyStatUntilEndOfTurnAction, MonarchDrawAction, PlayerAction, PlayerActionTarget,
+            PreventFirstCombatDamageAction, SkipNextDrawAction, SkipTurnAction, TapTargetAction,
+            TriggerCondition, TriggerTarget, WrathAction,
+        },
+        card::{CardPhase, CardType, CreatureType},
+        decks::{
+            black::create_hydra,
+            blue::{create_island, create_twincast, create_unsummon},
+            green::create_leaf_crowned_visionary,
+            red::{create_burn, create_fire, create_grapeshot, create_sudden_shock},
+            white::create_wrath,
+        },
+        effects::{DynamicValue, Effect, EffectTarget, ExpireContract, StatModifierEffect},
+        error::GameError,
+        mana::ManaType,
+        player::{Player, OPENING_HAND_SIZE},
+        rng::GameRng,
+        stat::{Stat, StatType, Stats},
+        turn::TurnPhase,
+        zone::Zone,
+        Ability, ActionType, Card, CardWithDetails, FrontendPileName, FrontendTarget, Game,
+        LegalAction, PriorityActionResult,
+    };
+    use crate::lobby::manager::LobbyCommand;
+
+    async fn pending_ability(game: &mut Game, player: &Arc<Mutex<Player>>) -> String {
+        let card = player.lock().await.cards_in_hand[0].clone();
+        let ability = Ability::new(
+            card,
+            vec![ManaType::Black],
+            CardRequiredTarget::None,
+            Arc::new(|_| Arc::new(DrawCardCardAction::one(CardActionTarget::SelfOwner))),
+            "draw a card".to_string(),
+            ActionType::Instant,
+        );
+        let id = ability.id.clone();
+        game.track_ability(&ability);
+
+        id
+    }
+
+    #[tokio::test]
+    async fn test_only_the_chooser_can_answer_an_ability() {
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new("player", 20, vec![create_hydra()]))
+            .await;
+        let opponent = game.add_player(Player::new("opponent", 20, vec![])).await;
+        game.start_turn(0).await;
+        player.lock().await.draw_card();
+        let id = pending_ability(&mut game, &player).await;
+
+        let game = Arc::new(Mutex::new(game));
+        let err = Game::respond_player_ability(game.clone(), &opponent, id.clone(), false, None)
+            .await
+            .unwrap_err();
+        assert_eq!(err, "It's not your choice to make");
+        assert!(game.lock().await.abilities.contains_key(&id));
+
+        Game::respond_player_ability(game.clone(), &player, id.clone(), false, None)
+            .await
+            .unwrap();
+        assert!(!game.lock().await.abilities.contains_key(&id));
+    }
+
+    #[tokio::test]
+    async fn test_paid_ability_waits_for_notify() {
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new(
+                "test",
+                20,
+                vec![create_hydra(), create_hydra(), create_hydra()],
+            ))
+            .await;
+        player.lock().await.draw_card();
+        game.start_turn(0).await;
+        let ability_id = pending_ability(&mut game, &player).await;
+
+        let ga = Arc::new(Mutex::new(game));
+        Game::respond_player_ability(ga.clone(), &player, ability_id.clone(), true, None)
+            .await
+            .expect("ability should be pending");
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(ga.lock().await.abilities.contains_key(&ability_id));
+
+        // Mana that shows up without a notification is not noticed, so the
+        // task is parked rather than polling.
+        player.lock().await.mana_pool.add_mana(ManaType::Black);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(ga.lock().await.abilities.contains_key(&ability_id));
+
+        ga.lock().await.notify_state_changed();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!ga.lock().await.abilities.contains_key(&ability_id));
+        assert_eq!(player.lock().await.cards_in_hand.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_paid_ability_gives_up_on_phase_change() {
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new(
+                "test",
+                20,
+                vec![create_hydra(), create_hydra(), create_hydra()],
+            ))
+            .await;
+        player.lock().await.draw_card();
+        game.start_turn(0).await;
+        let ability_id = pending_ability(&mut game, &player).await;
+
+        let ga = Arc::new(Mutex::new(game));
+        Game::respond_player_ability(ga.clone(), &player, ability_id.clone(), true, None)
+            .await
+            .expect("ability should be pending");
+
+        ga.lock().await.advance_turn().await;
+        assert_ne!(ga.lock().await.current_phase(), TurnPhase::Untap);
+
+        // The ability lapsed with its phase, so it's gone rather than paid for
+        assert!(!ga.lock().await.abilities.contains_key(&ability_id));
+        player.lock().await.mana_pool.add_mana(ManaType::Black);
+        ga.lock().await.notify_state_changed();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(player.lock().await.cards_in_hand.len(), 1);
+        assert_eq!(player.lock().await.mana_pool.black, 1);
+    }
+
+    #[tokio::test]
+    async fn test_mandatory_ability_outlasts_its_phase() {
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new(
+                "test",
+                20,
+                vec![create_hydra(), create_hydra()],
+            ))
+            .await;
+        player.lock().await.draw_card();
+        game.start_turn(0).await;
+        let optional = pending_ability(&mut game, &player).await;
+        let card = player.lock().await.cards_in_hand[0].clone();
+        game.ask_mandatory_player_ability(
+            Ability::new(
+                card,
+                vec![],
+                CardRequiredTarget::None,
+                Arc::new(|_| Arc::new(DrawCardCardAction::one(CardActionTarget::SelfOwner))),
+                "draw a card".to_string(),
+                ActionType::Instant,
+            )
+            .with_chooser(player.clone()),
+        )
+        .await;
+
+        game.advance_turn().await;
+        assert!(!game.abilities.contains_key(&optional));
+        assert_eq!(game.abilities.len(), 1);
+        let mandatory = game.abilities.keys().next().unwrap().clone();
+
+        let ga = Arc::new(Mutex::new(game));
+        Game::respond_player_ability(ga.clone(), &player, mandatory, true, None)
+            .await
+            .unwrap();
+        assert!(ga.lock().await.abilities.is_empty());
+        assert_eq!(player.lock().await.cards_in_hand.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_declined_ability_is_forgotten() {
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new("test", 20, vec![create_hydra()]))
+            .await;
+        player.lock().await.draw_card();
+        game.start_turn(0).await;
+        let ability_id = pending_ability(&mut game, &player).await;
+
+        let ga = Arc::new(Mutex::new(game));
+        Game::respond_player_ability(ga.clone(), &player, ability_id.clone(), false, None)
+            .await
+            .unwrap();
+        assert!(ga.lock().await.abilities.is_empty());
+    }
+
+    async fn two_player_game() -> (Game, Arc<Mutex<Card>>) {
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new("first", 20, vec![create_hydra()]))
+            .await;
+        game.add_player(Player::new("second", 20, vec![])).await;
+        let card = player.lock().await.draw_card().unwrap();
+        game.start_turn(0).await;
+
+        (game, card)
+    }
+
+    async fn play_out_turn(game: &mut Game) -> i32 {
+        for _ in 0..12 {
+            game.advance_turn().await;
+        }
+
+        game.current_turn.as_ref().unwrap().current_player_index
+    }
+
+    // Counts how many times it has been applied
+    #[derive(Debug, Default)]
+    struct CountingAction(std::sync::atomic::AtomicUsize);
+
+    #[async_trait::async_trait]
+    impl CardAction for CountingAction {
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+        async fn apply(&self, _game: &mut Game, _card: Arc<Mutex<Card>>, _target: EffectTarget) {
+            self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_delayed_sacrifice_fires_at_next_upkeep_only() {
+        let (mut game, card) = two_player_game().await;
+        let player = game.players[0].clone();
+        card.lock().await.owner = Some(player.clone());
+        player.lock().await.cards_in_hand.clear();
+        player.lock().await.cards_in_play.push(card.clone());
+        while game.current_phase() != TurnPhase::Main {
+            game.advance_turn().await;
+        }
+
+        let counter = Arc::new(CountingAction::default());
+        for action in [
+            Arc::new(SacrificeSelfAction {}) as Arc<dyn CardAction + Send + Sync>,
+            counter.clone(),
+        ] {
+            game.add_to_stack(Arc::new(CardActionWrapper {
+                card: card.clone(),
+                action: Arc::new(DelayedTriggerAction {
+                    phase: TurnPhase::Upkeep,
+                    action,
+                }),
+                target: None,
+            }));
+        }
+        game.resolve_stack().await;
+        assert_eq!(game.delayed_triggers.len(), 2);
+
+        // Not on the opponent's upkeep
+        game.skip_to_phase(TurnPhase::Upkeep).await;
+        assert_eq!(game.current_turn.as_ref().unwrap().current_player_index, 1);
+        assert!(player.lock().await.zone_contains(Zone::Battlefield, &card));
+        assert_eq!(counter.0.load(std::sync::atomic::Ordering::SeqCst), 0);
+
+        game.advance_turn().await;
+        game.skip_to_phase(TurnPhase::Upkeep).await;
+        assert_eq!(game.current_turn.as_ref().unwrap().current_player_index, 0);
+        assert!(player.lock().await.zone_contains(Zone::Graveyard, &card));
+        assert_eq!(counter.0.load(std::sync::atomic::Ordering::SeqCst), 1);
+        assert!(game.delayed_triggers.is_empty());
+
+        // And only the once
+        play_out_turn(&mut game).await;
+        play_out_turn(&mut game).await;
+        assert_eq!(counter.0.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_extra_turn() {
+        let (mut game, card) = two_player_game().await;
+        game.add_to_stack(Arc::new(CardActionWrapper {
+            card,
+            action: Arc::new(ExtraTurnAction {
+                target: PlayerActionTarget::Owner,
+            }),
+            target: None,
+        }));
+        game.resolve_stack().await;
+
+        assert_eq!(play_out_turn(&mut game).await, 0);
+        assert_eq!(play_out_turn(&mut game).await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_skip_turn() {
+        let (mut game, card) = two_player_game().await;
+        game.add_to_stack(Arc::new(CardActionWrapper {
+            card,
+            action: Arc::new(SkipTurnAction {
+                target: PlayerActionTarget::Opponent,
+            }),
+            target: None,
+        }));
+        game.resolve_stack().await;
+
+        assert_eq!(play_out_turn(&mut game).await, 0);
+        assert_eq!(play_out_turn(&mut game).await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_turn_order_survives_eliminations() {
+        let mut game = Game::new();
+        let first = game.add_player(Player::new("first", 20, vec![])).await;
+        let second = game.add_player(Player::new("second", 20, vec![])).await;
+        let third = game.add_player(Player::new("third", 20, vec![])).await;
+        game.start_turn(1).await;
+
+        // The player seated before the active player is eliminated, shifting
+        // everyone after them down a slot.
+        first
+            .lock()
+            .await
+            .stat_manager
+            .add_stat("damage".to_string(), Stat::new(StatType::Health, -20));
+        game.handle_deaths().await;
+        assert_eq!(game.players.len(), 2);
+
+        play_out_turn(&mut game).await;
+        let turn = game.current_turn.as_ref().unwrap();
+        assert!(Arc::ptr_eq(&turn.current_player, &third));
+
+        play_out_turn(&mut game).await;
+        let turn = game.current_turn.as_ref().unwrap();
+        assert!(Arc::ptr_eq(&turn.current_player, &second));
+    }
+
+    #[tokio::test]
+    async fn test_turn_skips_eliminated_neighbor() {
+        let mut game = Game::new();
+        let first = game.add_player(Player::new("first", 20, vec![])).await;
+        let second = game.add_player(Player::new("second", 20, vec![])).await;
+        game.add_player(Player::new("third", 20, vec![])).await;
+        game.start_turn(0).await;
+
+        second
+            .lock()
+            .await
+            .stat_manager
+            .add_stat("damage".to_string(), Stat::new(StatType::Health, -20));
+        game.handle_deaths().await;
+
+        play_out_turn(&mut game).await;
+        let turn = game.current_turn.as_ref().unwrap();
+        assert_eq!(turn.current_player.lock().await.name, "third");
+
+        play_out_turn(&mut game).await;
+        let turn = game.current_turn.as_ref().unwrap();
+        assert!(Arc::ptr_eq(&turn.current_player, &first));
+    }
+
+    #[tokio::test]
+    async fn test_stolen_creature_dies_to_owner_graveyard() {
+        let mut game = Game::new();
+        let owner = game
+            .add_player(Player::new("owner", 20, vec![create_hydra()]))
+            .await;
+        let thief = game
+            .add_player(Player::new("thief", 20, vec![create_hydra()]))
+            .await;
+        let creature = owner.lock().await.draw_card().unwrap();
+        let source = thief.lock().await.draw_card().unwrap();
+        owner.lock().await.cards_in_play.push(creature.clone());
+        game.start_turn(1).await;
+
+        game.add_to_stack(Arc::new(CardActionWrapper {
+            card: source,
+            action: Arc::new(GainControlAction {}),
+            target: Some(EffectTarget::Card(creature.clone())),
+        }));
+        game.resolve_stack().await;
+
+        {
+            let card = creature.lock().await;
+            assert!(Arc::ptr_eq(&card.controller().unwrap(), &thief));
+            assert!(Arc::ptr_eq(card.owner.as_ref().unwrap(), &owner));
+        }
+        assert!(owner.lock().await.cards_in_play.is_empty());
+        assert_eq!(thief.lock().await.cards_in_play.len(), 1);
+
+        game.destroy_card(&creature).await.unwrap();
+        assert!(thief.lock().await.cards_in_play.is_empty());
+        assert!(thief.lock().await.deck.destroyed_pile.is_empty());
+        let owner = owner.lock().await;
+        assert!(Arc::ptr_eq(&owner.deck.destroyed_pile[0], &creature));
+        assert!(creature.lock().await.controller.is_none());
+    }
+
+    fn create_frost_hydra() -> Card {
+        let mut card = create_hydra();
+        card.triggers.push(CardActionTrigger::new(
+            ActionTriggerType::Attacks(TriggerTarget::Target),
+            CardRequiredTarget::None,
+            Arc::new(TapTargetAction {}),
+        ));
+        card
+    }
+
+    #[tokio::test]
+    async fn test_attack_trigger_taps_defender() {
+        let mut game = Game::new();
+        let attacker = game
+            .add_player(Player::new(
+                "attacker",
+                20,
+                vec![create_frost_hydra(), create_frost_hydra()],
+            ))
+            .await;
+        let defender = game
+            .add_player(Player::new(
+                "defender",
+                20,
+                vec![create_hydra(), create_hydra()],
+            ))
+            .await;
+        game.start_turn(0).await;
+
+        let mut attackers = vec![];
+        let mut defenders = vec![];
+        for _ in 0..2 {
+            let card = attacker.lock().await.draw_card().unwrap();
+            attacker.lock().await.cards_in_play.push(card.clone());
+            attackers.push(card);
+            let card = defender.lock().await.draw_card().unwrap();
+            defender.lock().await.cards_in_play.push(card.clone());
+            defenders.push(card);
+        }
+
+        for (card, target) in attackers.iter().zip(defenders.iter()) {
+            game.add_to_stack(Arc::new(CardActionWrapper {
+                card: card.clone(),
+                action: Arc::new(DeclareAttackerAction {}),
+                target: Some(EffectTarget::Card(target.clone())),
+            }));
+        }
+        game.resolve_stack().await;
+
+        assert_eq!(game.combat.attackers.len(), 2);
+        for card in &defenders {
+            assert!(card.lock().await.tapped);
+        }
+        for card in &attackers {
+            assert!(!card.lock().await.tapped);
+        }
+    }
+
+    fn create_creature(name: &str, power: i8, toughness: i8) -> Card {
+        Card::new(
+            name,
+            "",
+            vec![],
+            CardPhase::Ready,
+            CardType::Creature,
+            vec![
+                Stat::new(StatType::Power, power),
+                Stat::new(StatType::Toughness, toughness),
+            ],
+            vec![],
+        )
+    }
+
+    #[tokio::test]
+    async fn test_affinity_reduces_generic_cost_per_artifact() {
+        let artifact = || {
+            Card::new(
+                "Trinket",
+                "",
+                vec![],
+                CardPhase::Ready,
+                CardType::Artifact,
+                vec![],
+                vec![],
+            )
+        };
+        // {4}{B}, affinity for artifacts
+        let mut cost = vec![ManaType::Colorless; 4];
+        cost.push(ManaType::Black);
+        let affinity = Card::new(
+            "Affinity",
+            "",
+            vec![],
+            CardPhase::Ready,
+            CardType::Artifact,
+            vec![],
+            cost,
+        )
+        .with_cost_reduction(DynamicValue::PermanentsOfType(CardType::Artifact));
+
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new(
+                "test",
+                20,
+                vec![affinity, artifact(), artifact()],
+            ))
+            .await;
+        game.start_turn(0).await;
+        for _ in 0..3 {
+            player.lock().await.draw_card();
+        }
+        let trinket = player.lock().await.cards_in_hand.remove(0);
+        player.lock().await.cards_in_play.push(trinket);
+        {
+            let mut player = player.lock().await;
+            player.mana_pool.add_mana(ManaType::Colorless);
+            player.mana_pool.add_mana(ManaType::Colorless);
+            player.mana_pool.add_mana(ManaType::Black);
+        }
+
+        // One artifact only takes off {1}
+        let affinity = player.lock().await.cards_in_hand[1].clone();
+        assert_eq!(Card::cost_to_cast(&affinity).await.len(), 4);
+        assert!(game.play_card(&player, 1, None).await.is_err());
+
+        let trinket = player.lock().await.cards_in_hand.remove(0);
+        player.lock().await.cards_in_play.push(trinket);
+        assert_eq!(
+            Card::cost_to_cast(&affinity).await,
+            vec![ManaType::Colorless, ManaType::Colorless, ManaType::Black]
+        );
+        game.play_card(&player, 0, None).await.unwrap();
+        let pool = player.lock().await.mana_pool.clone();
+        assert_eq!((pool.colorless, pool.black), (0, 0));
+    }
+
+    #[tokio::test]
+    async fn test_block_trigger_saves_blocker() {
+        let mut wall = create_creature("Wall", 0, 1);
+        wall.triggers.push(CardActionTrigger::new(
+            ActionTriggerType::Blocks,
+            CardRequiredTarget::None,
+            Arc::new(ModifyStatUntilEndOfTurnAction {
+                stat_type: StatType::Toughness,
+                amount: 2,
+            }),
+        ));
+
+        let mut game = Game::new();
+        let attacker = game
+            .add_player(Player::new(
+                "attacker",
+                20,
+                vec![create_creature("Brute", 2, 2)],
+            ))
+            .await;
+        let defender = game
+            .add_player(Player::new("defender", 20, vec![wall]))
+            .await;
+        game.start_turn(0).await;
+
+        let brute = attacker.lock().await.draw_card().unwrap();
+        attacker.lock().await.cards_in_play.push(brute.clone());
+        let wall = defender.lock().await.draw_card().unwrap();
+        defender.lock().await.cards_in_play.push(wall.clone());
+
+        game.add_to_stack(Arc::new(CardActionWrapper {
+            card: brute.clone(),
+            action: Arc::new(DeclareAttackerAction {}),
+            target: Some(EffectTarget::Player(defender.clone())),
+        }));
+        game.resolve_stack().await;
+        game.add_to_stack(Arc::new(CardActionWrapper {
+            card: wall.clone(),
+            action: Arc::new(DeclareBlockerAction {}),
+            target: Some(EffectTarget::Card(brute.clone())),
+        }));
+        game.resolve_stack().await;
+
+        assert_eq!(wall.lock().await.get_stat_value(StatType::Toughness), 3);
+        let destroyed = game.combat.resolve_combat().await;
+        assert!(destroyed.is_empty());
+        assert_eq!(wall.lock().await.damage_taken, 2);
+    }
+
+    #[derive(Debug, Default)]
+    struct RecordAmountAction {
+        amounts: std::sync::Mutex<Vec<i8>>,
+    }
+
+    #[async_trait::async_trait]
+    impl CardAction for RecordAmountAction {
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+        async fn apply(&self, game: &mut Game, card: Arc<Mutex<Card>>, target: EffectTarget) {}
+        async fn apply_with_amount(
+            &self,
+            game: &mut Game,
+            card: Arc<Mutex<Card>>,
+            target: EffectTarget,
+            amount: i8,
+        ) {
+            self.amounts.lock().unwrap().push(amount);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_combat_damage_trigger_draws_card() {
+        let recorder = Arc::new(RecordAmountAction::default());
+        let mut brute = create_creature("Brute", 3, 3);
+        brute.triggers.push(CardActionTrigger::new(
+            ActionTriggerType::DamageApplied,
+            CardRequiredTarget::None,
+            Arc::new(DrawCardCardAction::one(CardActionTarget::SelfOwner)),
+        ));
+        brute.triggers.push(CardActionTrigger::new(
+            ActionTriggerType::DamageApplied,
+            CardRequiredTarget::None,
+            recorder.clone(),
+        ));
+
+        let mut game = Game::new();
+        let attacker = game
+            .add_player(Player::new(
+                "attacker",
+                20,
+                vec![create_hydra(), create_hydra()],
+            ))
+            .await;
+        let defender = game
+            .add_player(Player::new("defender", 20, vec![create_hydra()]))
+            .await;
+        game.start_turn(0).await;
+
+        brute.owner = Some(attacker.clone());
+        let brute = Arc::new(Mutex::new(brute));
+        attacker.lock().await.cards_in_play.push(brute.clone());
+        let hand_size = attacker.lock().await.cards_in_hand.len();
+
+        game.add_to_stack(Arc::new(CardActionWrapper {
+            card: brute.clone(),
+            action: Arc::new(DeclareAttackerAction {}),
+            target: Some(EffectTarget::Player(defender.clone())),
+        }));
+        game.resolve_stack().await;
+        CombatAction {}.apply(&mut game, 0).await;
+
+        assert_eq!(defender.lock().await.get_stat_value(StatType::Health), 17);
+        assert_eq!(attacker.lock().await.cards_in_hand.len(), hand_size + 1);
+        assert_eq!(*recorder.amounts.lock().unwrap(), vec![3]);
+    }
+
+    #[tokio::test]
+    async fn test_life_gained_trigger_gets_amount() {
+        let recorder = Arc::new(RecordAmountAction::default());
+        let mut watcher = create_hydra();
+        watcher.triggers.push(CardActionTrigger::new(
+            ActionTriggerType::LifeGained(TriggerTarget::Owner),
+            CardRequiredTarget::None,
+            recorder.clone(),
+        ));
+
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new("player", 20, vec![watcher]))
+            .await;
+        let opponent = game.add_player(Player::new("opponent", 20, vec![])).await;
+        game.start_turn(0).await;
+
+        let watcher = player.lock().await.draw_card().unwrap();
+        player.lock().await.cards_in_play.push(watcher);
+
+        game.gain_life(&player, 3).await;
+        game.gain_life(&opponent, 5).await;
+        game.gain_life(&player, 0).await;
+
+        assert_eq!(*recorder.amounts.lock().unwrap(), vec![3]);
+        assert_eq!(player.lock().await.get_stat_value(StatType::Health), 23);
+        assert_eq!(player.lock().await.life_gained_this_turn, 3);
+    }
+
+    #[tokio::test]
+    async fn test_countered_spell_goes_to_graveyard() {
+        let mut game = Game::new();
+        let caster = game
+            .add_player(Player::new(
+                "caster",
+                20,
+                vec![create_creature("Bear", 2, 2)],
+            ))
+            .await;
+        let opponent = game
+            .add_player(Player::new("opponent", 20, vec![create_hydra()]))
+            .await;
+        game.start_turn(0).await;
+        caster.lock().await.draw_card();
+        let counterspell = opponent.lock().await.draw_card().unwrap();
+        counterspell.lock().await.owner = Some(opponent.clone());
+
+        let bear = game.play_card(&caster, 0, None).await.unwrap();
+        assert_eq!(caster.lock().await.spells.len(), 1);
+
+        game.add_to_stack(Arc::new(CardActionWrapper {
+            card: counterspell,
+            action: Arc::new(CounterSpellAction {}),
+            target: Some(EffectTarget::Card(bear.clone())),
+        }));
+        game.resolve_stack().await;
+
+        let caster = caster.lock().await;
+        assert!(caster.spells.is_empty());
+        assert!(caster.cards_in_play.is_empty());
+        assert_eq!(caster.deck.destroyed_pile.len(), 1);
+        assert!(Arc::ptr_eq(&caster.deck.destroyed_pile[0], &bear));
+        assert!(!bear.lock().await.is_countered);
+    }
+
+    #[tokio::test]
+    async fn test_legal_targets_for_destroy_target_enemy_creature() {
+        let murder = Card::new(
+            "Murder",
+            "Destroy target enemy creature.",
+            vec![CardActionTrigger::new(
+                ActionTriggerType::CardPlayedFromHand,
+                CardRequiredTarget::CardOfType(CardType::Creature, CardTargetTeam::Opponent),
+                Arc::new(DestroyTargetCAction {}),
+            )],
+            CardPhase::Ready,
+            CardType::Instant,
+            vec![],
+            vec![],
+        );
+        let ability_id = murder.triggers[0].id.clone();
+
+        let mut game = Game::new();
+        let caster = game
+            .add_player(Player::new(
+                "caster",
+                20,
+                vec![murder, create_creature("Own Bear", 2, 2)],
+            ))
+            .await;
+        let defender = game
+            .add_player(Player::new(
+                "defender",
+                20,
+                vec![
+                    create_creature("Bear", 2, 2),
+                    create_island(),
+                    create_creature("Wolf", 3, 3),
+                ],
+            ))
+            .await;
+        game.start_turn(0).await;
+
+        for (player, count) in [(&caster, 1), (&defender, 3)] {
+            let mut player = player.lock().await;
+            for _ in 0..count {
+                let card = player.draw_card().unwrap();
+                player.cards_in_hand.pop();
+                player.cards_in_play.push(card);
+            }
+        }
+        caster.lock().await.draw_card();
+
+        let targets = game.legal_targets(&caster, &ability_id).await;
+        let card_indexes: Vec<(i32, i32)> = targets
+            .iter()
+            .map(|target| match target {
+                FrontendTarget::Card(card) => {
+                    assert!(matches!(card.pile, FrontendPileName::Play));
+                    (card.player_index, card.card_index)
+                }
+                other => panic!("unexpected target {:?}", other),
+            })
+            .collect();
+        // The wolf and the bear, but not the island or the caster's own bear
+        assert_eq!(card_indexes, vec![(1, 0), (1, 2)]);
+
+        assert!(game
+            .legal_targets(&caster, "no such ability")
+            .await
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_removal_fizzles_when_target_is_bounced() {
+        let murder = Card::new(
+            "Murder",
+            "Destroy target creature.",
+            vec![CardActionTrigger::new(
+                ActionTriggerType::CardPlayedFromHand,
+                CardRequiredTarget::CardOfType(CardType::Creature, CardTargetTeam::Opponent),
+                Arc::new(DestroyTargetCAction {}),
+            )],
+            CardPhase::Ready,
+            CardType::Instant,
+            vec![],
+            vec![],
+        );
+
+        let mut game = Game::new();
+        let caster = game
+            .add_player(Player::new("caster", 20, vec![murder]))
+            .await;
+        let defender = game
+            .add_player(Player::new(
+                "defender",
+                20,
+                vec![create_unsummon(), create_creature("Bear", 2, 2)],
+            ))
+            .await;
+        game.start_turn(0).await;
+
+        caster.lock().await.draw_card();
+        let bear = defender.lock().await.draw_card().unwrap();
+        defender.lock().await.draw_card();
+        defender.lock().await.mana_pool.add_mana(ManaType::Blue);
+        game.play_card(&defender, 0, None).await.unwrap();
+        game.resolve_stack().await;
+        assert!(Arc::ptr_eq(&defender.lock().await.cards_in_play[0], &bear));
+
+        let murder = game
+            .play_card(&caster, 0, Some(EffectTarget::Card(bear.clone())))
+            .await
+            .unwrap();
+        game.play_card(&defender, 0, Some(EffectTarget::Card(bear.clone())))
+            .await
+            .unwrap();
+        game.resolve_stack().await;
+
+        let defender = defender.lock().await;
+        assert!(defender
+            .cards_in_hand
+            .iter()
+            .any(|card| Arc::ptr_eq(card, &bear)));
+        assert!(!defender
+            .deck
+            .destroyed_pile
+            .iter()
+            .any(|card| Arc::ptr_eq(card, &bear)));
+        let caster = caster.lock().await;
+        assert!(caster.spells.is_empty());
+        assert!(Arc::ptr_eq(&caster.deck.destroyed_pile[0], &murder));
+        assert!(game
+            .turn_messages
+            .iter()
+            .any(|message| message == "Spell Murder fizzled and was moved to graveyard."));
+    }
+
+    #[tokio::test]
+    async fn test_frontend_target_reports_player_index() {
+        let (game, _) = two_player_game().await;
+        let second = game.players[1].clone();
+
+        let target = game
+            .frontend_target_from_effect_target(&EffectTarget::Player(second))
+            .await;
+
+        assert!(matches!(target, FrontendTarget::Player(1)));
+    }
+
+    #[tokio::test]
+    async fn test_creature_enters_tapped() {
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new(
+                "player",
+                20,
+                vec![create_creature("Sleepy", 1, 1).with_enters_tapped()],
+            ))
+            .await;
+        game.start_turn(0).await;
+        player.lock().await.draw_card();
+
+        let card = game.play_card(&player, 0, None).await.unwrap();
+        game.resolve_stack().await;
+
+        assert!(card.lock().await.tapped);
+    }
+
+    #[tokio::test]
+    async fn test_creature_enters_with_counters() {
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new(
+                "player",
+                20,
+                vec![create_creature("Grower", 1, 1).with_counters(2)],
+            ))
+            .await;
+        game.start_turn(0).await;
+        player.lock().await.draw_card();
+
+        let card = game.play_card(&player, 0, None).await.unwrap();
+        game.resolve_stack().await;
+
+        let card = card.lock().await;
+        assert!(!card.tapped);
+        assert_eq!(card.get_stat_value(StatType::Power), 3);
+        assert_eq!(card.get_stat_value(StatType::Toughness), 3);
+    }
+
+    #[tokio::test]
+    async fn test_effect_grants_additional_land_drop() {
+        let swamp = || {
+            Card::new(
+                "Swamp",
+                "",
+                vec![],
+                CardPhase::Ready,
+                CardType::BasicLand(ManaType::Black),
+                vec![],
+                vec![],
+            )
+        };
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new("player", 20, vec![swamp(), swamp(), swamp()]))
+            .await;
+        game.start_turn(0).await;
+        for _ in 0..3 {
+            player.lock().await.draw_card();
+        }
+
+        game.play_card(&player, 0, None).await.unwrap();
+        game.resolve_stack().await;
+        assert!(game.play_card(&player, 0, None).await.is_err());
+
+        let explorer = Arc::new(Mutex::new(create_creature("Explorer", 0, 1)));
+        let effect = StatModifierEffect::new(
+            EffectTarget::Player(player.clone()),
+            StatType::LandsAllowed,
+            1,
+            ExpireContract::Turns(1),
+            Some(explorer),
+        );
+        game.effect_manager
+            .add_effect(effect.get_id().clone(), Arc::new(Mutex::new(effect)));
+        game.effect_manager
+            .apply_effects(game.current_turn.clone().unwrap())
+            .await;
+
+        game.play_card(&player, 0, None).await.unwrap();
+        game.resolve_stack().await;
+        assert!(game.play_card(&player, 0, None).await.is_err());
+        assert_eq!(player.lock().await.lands_played_this_turn, 2);
+    }
+
+    #[tokio::test]
+    async fn test_same_seed_draws_same_order() {
+        async fn draw_order(seed: u64) -> Vec<String> {
+            let mut game = Game::new().with_seed(seed);
+            let deck = (0..10)
+                .map(|i| create_creature(&format!("Creature {}", i), 1, 1))
+                .collect();
+            let player = game.add_player(Player::new("player", 20, deck)).await;
+            player.lock().await.deck.shuffle(&mut game.rng);
+
+            let mut names = vec![];
+            while let Some(card) = player.lock().await.draw_card() {
+                names.push(card.lock().await.name.clone());
+            }
+            names
+        }
+
+        let order = draw_order(42).await;
+        assert_eq!(order.len(), 10);
+        assert_eq!(order, draw_order(42).await);
+    }
+
+    #[tokio::test]
+    async fn test_end_of_combat_takes_creatures_out_of_combat() {
+        let mut game = Game::new();
+        let attacker = game
+            .add_player(Player::new(
+                "attacker",
+                20,
+                vec![create_creature("Attacker", 1, 3)],
+            ))
+            .await;
+        let defender = game
+            .add_player(Player::new(
+                "defender",
+                20,
+                vec![create_creature("Blocker", 0, 3)],
+            ))
+            .await;
+        game.start_turn(0).await;
+
+        let attacking = attacker.lock().await.draw_card().unwrap();
+        attacker.lock().await.cards_in_play.push(attacking.clone());
+        let blocking = defender.lock().await.draw_card().unwrap();
+        defender.lock().await.cards_in_play.push(blocking.clone());
+
+        while game.current_phase() != TurnPhase::DeclareAttackers {
+            game.advance_turn().await;
+        }
+        game.add_to_stack(Arc::new(CardActionWrapper {
+            card: attacking.clone(),
+            action: Arc::new(DeclareAttackerAction {}),
+            target: Some(EffectTarget::Player(defender.clone())),
+        }));
+        game.add_to_stack(Arc::new(CardActionWrapper {
+            card: blocking.clone(),
+            action: Arc::new(DeclareBlockerAction {}),
+            target: Some(EffectTarget::Card(attacking.clone())),
+        }));
+        game.resolve_stack().await;
+        assert!(game.combat.is_in_combat(&attacking));
+        assert!(game.combat.is_in_combat(&blocking));
+
+        while game.current_phase() != TurnPhase::EndOfCombat {
+            game.advance_turn().await;
+        }
+
+        // Nothing the defender controls is a legal EnemyCardInCombat target any more
+        let mut targets = vec![];
+        for card in defender.lock().await.cards_in_play.iter() {
+            if CardRequiredTarget::EnemyCardInCombat.matches_card(&*card.lock().await, false)
+                && game.combat.is_in_combat(card)
+            {
+                targets.push(card.clone());
+            }
+        }
+        assert!(targets.is_empty());
+        assert!(!game.combat.is_in_combat(&attacking));
+    }
+
+    #[tokio::test]
+    async fn test_only_declared_attackers_can_be_blocked() {
+        let mut game = Game::new();
+        let attacker = game
+            .add_player(Player::new(
+                "attacker",
+                20,
+                vec![create_hydra(), create_hydra()],
+            ))
+            .await;
+        let defender = game
+            .add_player(Player::new("defender", 20, vec![create_hydra()]))
+            .await;
+        game.start_turn(0).await;
+
+        let attacking = attacker.lock().await.draw_card().unwrap();
+        let idle = attacker.lock().await.draw_card().unwrap();
+        attacker.lock().await.cards_in_play = vec![attacking.clone(), idle.clone()];
+        let blocker = defender.lock().await.draw_card().unwrap();
+        defender.lock().await.cards_in_play.push(blocker.clone());
+
+        while game.current_phase() != TurnPhase::DeclareAttackers {
+            game.advance_turn().await;
+        }
+        game.add_to_stack(Arc::new(CardActionWrapper {
+            card: attacking.clone(),
+            action: Arc::new(DeclareAttackerAction {}),
+            target: Some(EffectTarget::Player(defender.clone())),
+        }));
+        game.resolve_stack().await;
+        while game.current_phase() != TurnPhase::DeclareBlockers {
+            game.advance_turn().await;
+        }
+
+        let block_id = blocker
+            .lock()
+            .await
+            .triggers
+            .iter()
+            .find(|trigger| {
+                trigger.trigger_type
+                    == ActionTriggerType::AbilityWithinPhases(
+                        "Block".to_string(),
+                        vec![],
+                        Some(vec![TurnPhase::DeclareBlockers]),
+                        false,
+                    )
+            })
+            .unwrap()
+            .id
+            .clone();
+
+        game.activate_card_action(
+            &defender,
+            0,
+            Some(EffectTarget::Card(idle.clone())),
+            block_id.clone(),
+        )
+        .await
+        .unwrap();
+        assert!(!game.combat.is_attacking(&idle));
+        assert!(!game.combat.is_blocking(&blocker));
+
+        game.activate_card_action(
+            &defender,
+            0,
+            Some(EffectTarget::Card(attacking.clone())),
+            block_id,
+        )
+        .await
+        .unwrap();
+        assert!(game.combat.is_attacking(&attacking));
+        assert!(game.combat.is_blocking(&blocker));
+    }
+
+    async fn advance_to_draw_step(game: &mut Game) {
+        while game.current_phase() != TurnPhase::Draw {
+            game.advance_turn().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_extra_draw_each_turn() {
+        let mut game = Game::new();
+        let deck = (0..4).map(|_| create_creature("Bear", 2, 2)).collect();
+        let player = game.add_player(Player::new("player", 20, deck)).await;
+        game.start_turn(0).await;
+
+        let source = Arc::new(Mutex::new(create_creature("Library", 0, 4)));
+        let effect = StatModifierEffect::new(
+            EffectTarget::Player(player.clone()),
+            StatType::DrawsPerTurn,
+            1,
+            ExpireContract::Never,
+            Some(source),
+        );
+        game.effect_manager
+            .add_effect(effect.get_id().clone(), Arc::new(Mutex::new(effect)));
+        game.effect_manager
+            .apply_effects(game.current_turn.clone().unwrap())
+            .await;
+
+        advance_to_draw_step(&mut game).await;
+        assert_eq!(player.lock().await.cards_in_hand.len(), 2);
+    }
+
+    #[derive(Debug)]
+    struct GainLifeInsteadAction {}
+
+    #[async_trait::async_trait]
+    impl PlayerAction for GainLifeInsteadAction {
+        async fn apply(&self, game: &mut Game, player_index: usize) {
+            let player = Arc::clone(&game.players[player_index]);
+            game.gain_life(&player, 3).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replacing_a_draw_with_gaining_life() {
+        let mut game = Game::new();
+        let deck = (0..4).map(|_| create_creature("Bear", 2, 2)).collect();
+        let player = game.add_player(Player::new("player", 20, deck)).await;
+        game.start_turn(0).await;
+
+        game.add_draw_replacement(DrawReplacement {
+            player: player.clone(),
+            from: Some(DrawSource::Effect),
+            instead: Some(Arc::new(GainLifeInsteadAction {})),
+            once: true,
+            source: None,
+        });
+
+        // The draw step's draw isn't the one being replaced
+        advance_to_draw_step(&mut game).await;
+        assert_eq!(player.lock().await.cards_in_hand.len(), 1);
+
+        let draw = DrawCardAction {
+            target: PlayerActionTarget::Owner,
+        };
+        draw.apply(&mut game, 0).await;
+        assert_eq!(player.lock().await.cards_in_hand.len(), 1);
+        assert_eq!(player.lock().await.get_stat_value(StatType::Health), 23);
+
+        // It only replaced the one draw
+        draw.apply(&mut game, 0).await;
+        assert_eq!(player.lock().await.cards_in_hand.len(), 2);
+        assert!(game.draw_replacements.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_permanent_skips_draw_step_and_gains_life_instead() {
+        let mut game = Game::new();
+        let mut deck: Vec<Card> = (0..4).map(|_| create_creature("Bear", 2, 2)).collect();
+        let mut monk = create_creature("Fasting Monk", 1, 1);
+        monk.triggers.push(CardActionTrigger::new(
+            ActionTriggerType::CardPlayedFromHand,
+            CardRequiredTarget::None,
+            Arc::new(SkipDrawStepAction {
+                instead: Some(Arc::new(GainLifeInsteadAction {})),
+            }),
+        ));
+        deck.push(monk);
+        let player = game.add_player(Player::new("player", 20, deck)).await;
+        game.start_turn(0).await;
+        player.lock().await.draw_card();
+
+        let monk = game.play_card(&player, 0, None).await.unwrap();
+        game.resolve_stack().await;
+        advance_to_draw_step(&mut game).await;
+        assert_eq!(player.lock().await.cards_in_hand.len(), 0);
+        assert_eq!(player.lock().await.get_stat_value(StatType::Health), 23);
+
+        // Every draw step while it's in play
+        play_out_turn(&mut game).await;
+        advance_to_draw_step(&mut game).await;
+        assert_eq!(player.lock().await.cards_in_hand.len(), 0);
+        assert_eq!(player.lock().await.get_stat_value(StatType::Health), 26);
+
+        // Draws are back to normal once it's gone
+        game.destroy_card(&monk).await.unwrap();
+        play_out_turn(&mut game).await;
+        advance_to_draw_step(&mut game).await;
+        assert_eq!(player.lock().await.cards_in_hand.len(), 1);
+        assert_eq!(player.lock().await.get_stat_value(StatType::Health), 26);
+        assert!(game.draw_replacements.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_skip_next_draw() {
+        let mut game = Game::new();
+        let deck = (0..4).map(|_| create_creature("Bear", 2, 2)).collect();
+        let player = game.add_player(Player::new("player", 20, deck)).await;
+        game.start_turn(0).await;
+
+        let source = Arc::new(Mutex::new(create_creature("Sleeper", 0, 1)));
+        source.lock().await.owner = Some(player.clone());
+        game.add_to_stack(Arc::new(CardActionWrapper {
+            card: source,
+            action: Arc::new(SkipNextDrawAction {}),
+            target: None,
+        }));
+        game.resolve_stack().await;
+
+        advance_to_draw_step(&mut game).await;
+        assert_eq!(player.lock().await.cards_in_hand.len(), 0);
+        assert!(!player.lock().await.skip_next_draw);
+
+        // Only the next draw is skipped
+        play_out_turn(&mut game).await;
+        advance_to_draw_step(&mut game).await;
+        assert_eq!(player.lock().await.cards_in_hand.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_player_view_has_stat_values() {
+        let mut player = Player::new("viewer", 20, vec![create_creature("Bear", 2, 3)]);
+        player
+            .stat_manager
+            .add_stat("damage".to_string(), Stat::new(StatType::Health, -5));
+        player.draw_card();
+        let bear = player.cards_in_hand.remove(0);
+        player.cards_in_play.push(bear);
+
+        let view = player.view().await;
+        assert_eq!(view.name, "viewer");
+        let health = view
+            .stats
+            .iter()
+            .find(|stat| stat.stat_type == StatType::Health)
+            .unwrap();
+        assert_eq!(health.value, 15);
+        assert!(view.hand.is_empty());
+        assert_eq!(view.cards_in_play[0].name, "Bear");
+        assert_eq!(
+            (view.cards_in_play[0].power, view.cards_in_play[0].toughness),
+            (2, 3)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_two_mulligans_bottom_two_cards() {
+        let deck = (0..20)
+            .map(|i| create_creature(&format!("Bear {}", i), 2, 2))
+            .collect();
+        let mut player = Player::new("mulliganer", 20, deck);
+        let mut rng = GameRng::seeded(7);
+        for _ in 0..OPENING_HAND_SIZE {
+            player.draw_card();
+        }
+        assert_eq!(player.cards_to_bottom(), 0);
+
+        player.mulligan(&mut rng).unwrap();
+        player.mulligan(&mut rng).unwrap();
+        assert_eq!(player.cards_in_hand.len(), OPENING_HAND_SIZE);
+        assert_eq!(player.deck.draw_pile.len(), 20 - OPENING_HAND_SIZE);
+        assert_eq!(player.cards_to_bottom(), 2);
+
+        assert!(player.keep_hand(vec![0]).is_err());
+        assert!(player.keep_hand(vec![0, 0]).is_err());
+        assert!(player.keep_hand(vec![0, 1, 2]).is_err());
+        assert!(player.keep_hand(vec![0, OPENING_HAND_SIZE]).is_err());
+
+        let bottomed = player.cards_in_hand[1].clone();
+        player.keep_hand(vec![4, 1]).unwrap();
+        assert_eq!(player.cards_in_hand.len(), OPENING_HAND_SIZE - 2);
+        assert!(
+            Arc::ptr_eq(&player.deck.draw_pile[0], &bottomed)
+                || Arc::ptr_eq(&player.deck.draw_pile[1], &bottomed)
+        );
+        assert_eq!(player.cards_to_bottom(), 0);
+        assert!(player.mulligan(&mut rng).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_nothing_happens_until_every_hand_is_kept() {
+        let mut game = Game::new().with_seed(3);
+        let bears = |name: &str| {
+            (0..20)
+                .map(|i| create_creature(&format!("{} Bear {}", name, i), 2, 2))
+                .collect()
+        };
+        let first = game
+            .add_player(Player::new("first", 20, bears("first")))
+            .await;
+        let second = game
+            .add_player(Player::new("second", 20, bears("second")))
+            .await;
+        game.start().await;
+
+        let waiting = GameError::OpeningHands.to_string();
+        game.mulligan(&first).await.unwrap();
+        game.keep_hand(&first, vec![0]).await.unwrap();
+        assert_eq!(game.play_card(&first, 0, None).await.unwrap_err(), waiting);
+        assert_eq!(game.try_advance_turn().await, Err(GameError::OpeningHands));
+        assert!(game.mulligan(&first).await.is_err());
+
+        // A player can't mulligan away more cards than a hand holds
+        for _ in 0..OPENING_HAND_SIZE {
+            game.mulligan(&second).await.unwrap();
+        }
+        assert!(game.mulligan(&second).await.is_err());
+        game.keep_hand(&second, (0..OPENING_HAND_SIZE).collect())
+            .await
+            .unwrap();
+        assert!(second.lock().await.cards_in_hand.is_empty());
+
+        assert!(!game.awaiting_opening_hands);
+        assert_ne!(
+            game.play_card(&first, 0, None).await.map(|_| ()),
+            Err(waiting)
+        );
+        assert!(game.mulligan(&second).await.is_err());
+        game.try_advance_turn().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_copied_burn_deals_damage_twice() {
+        let mut game = Game::new();
+        let caster = game
+            .add_player(Player::new(
+                "caster",
+                20,
+                vec![create_twincast(), create_burn()],
+            ))
+            .await;
+        let opponent = game.add_player(Player::new("opponent", 20, vec![])).await;
+        game.start_turn(0).await;
+
+        let burn = caster.lock().await.draw_card().unwrap();
+        caster.lock().await.draw_card();
+        {
+            let mut caster = caster.lock().await;
+            caster.mana_pool.add_mana(ManaType::Red);
+            caster.mana_pool.add_mana(ManaType::Blue);
+            caster.mana_pool.add_mana(ManaType::Blue);
+        }
+
+        game.play_card(&caster, 0, Some(EffectTarget::Player(opponent.clone())))
+            .await
+            .unwrap();
+        game.play_card(&caster, 0, Some(EffectTarget::Card(burn.clone())))
+            .await
+            .unwrap();
+        game.resolve_stack().await;
+
+        assert_eq!(opponent.lock().await.get_stat_value(StatType::Health), 16);
+        // Only the original Burn was cast, and only paid for once
+        let caster = caster.lock().await;
+        assert!(caster.spells.is_empty());
+        assert!(caster.cards_in_hand.is_empty());
+        assert_eq!(
+            caster
+                .deck
+                .destroyed_pile
+                .iter()
+                .filter(|card| Arc::ptr_eq(card, &burn))
+                .count(),
+            1
+        );
+        assert_eq!(caster.mana_pool.total(), 0);
+        assert!(game.copy_spell(&burn, None).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_storm_copies_once_per_earlier_spell() {
+        let mut game = Game::new();
+        let caster = game
+            .add_player(Player::new(
+                "caster",
+                20,
+                vec![create_grapeshot(), create_burn(), create_burn()],
+            ))
+            .await;
+        let opponent = game.add_player(Player::new("opponent", 20, vec![])).await;
+        game.start_turn(0).await;
+
+        {
+            let mut caster = caster.lock().await;
+            for _ in 0..3 {
+                caster.draw_card();
+                caster.mana_pool.add_mana(ManaType::Red);
+            }
+        }
+
+        for _ in 0..2 {
+            game.play_card(&caster, 0, Some(EffectTarget::Player(opponent.clone())))
+                .await
+                .unwrap();
+            game.resolve_stack().await;
+        }
+        assert_eq!(game.spells_cast_this_turn, 2);
+
+        game.play_card(&caster, 0, Some(EffectTarget::Player(opponent.clone())))
+            .await
+            .unwrap();
+        // The Grapeshot plus a copy for each Burn
+        assert_eq!(game.event_stack.len(), 3);
+        game.resolve_stack().await;
+
+        assert_eq!(opponent.lock().await.get_stat_value(StatType::Health), 13);
+        assert_eq!(game.spells_cast_this_turn, 3);
+
+        game.start_turn(1).await;
+        assert_eq!(game.spells_cast_this_turn, 0);
+    }
+
+    #[tokio::test]
+    async fn test_wrath_spares_indestructible_creatures() {
+        let mut bear = create_creature("Bear", 2, 2);
+        bear.triggers.push(CardActionTrigger::new(
+            ActionTriggerType::CardDestroyed,
+            CardRequiredTarget::None,
+            Arc::new(DrawCardCardAction::one(CardActionTarget::SelfOwner)),
+        ));
+        let mut golem = create_creature("Golem", 3, 3);
+        golem
+            .stats
+            .add_stat("golem".to_string(), Stat::new(StatType::Indestructible, 1));
+
+        let mut game = Game::new();
+        let caster = game
+            .add_player(Player::new(
+                "caster",
+                20,
+                vec![create_creature("Spare", 1, 1), bear, create_wrath()],
+            ))
+            .await;
+        let opponent = game
+            .add_player(Player::new(
+                "opponent",
+                20,
+                vec![golem, create_creature("Wolf", 2, 2)],
+            ))
+            .await;
+        game.start_turn(0).await;
+
+        let wrath = caster.lock().await.draw_card().unwrap();
+        wrath.lock().await.controller = Some(caster.clone());
+        for (player, count) in [(&caster, 1), (&opponent, 2)] {
+            for _ in 0..count {
+                let mut player_lock = player.lock().await;
+                player_lock.draw_card();
+                let card = player_lock.cards_in_hand.pop().unwrap();
+                card.lock().await.controller = Some(player.clone());
+                player_lock.cards_in_play.push(card);
+            }
+        }
+
+        game.add_to_stack(Arc::new(CardActionWrapper {
+            card: wrath,
+            action: Arc::new(WrathAction {
+                filter: CardRequiredTarget::CardOfType(CardType::Creature, CardTargetTeam::Any),
+            }),
+            target: None,
+        }));
+        game.resolve_stack().await;
+
+        let caster = caster.lock().await;
+        assert!(caster.cards_in_play.is_empty());
+        assert_eq!(caster.deck.destroyed_pile.len(), 1);
+        // The bear's dies trigger drew the last card
+        assert_eq!(caster.cards_in_hand.len(), 2);
+        let opponent = opponent.lock().await;
+        assert_eq!(opponent.cards_in_play.len(), 1);
+        assert_eq!(opponent.cards_in_play[0].lock().await.name, "Golem");
+        assert_eq!(opponent.deck.destroyed_pile.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_combat_damage_steals_the_monarch() {
+        let mut game = Game::new();
+        let attacker = game
+            .add_player(Player::new(
+                "attacker",
+                20,
+                vec![
+                    create_creature("Extra", 1, 1),
+                    create_creature("Bear", 2, 2),
+                ],
+            ))
+            .await;
+        let monarch = game
+            .add_player(Player::new(
+                "monarch",
+                20,
+                vec![create_creature("Extra", 1, 1)],
+            ))
+            .await;
+        game.start_turn(0).await;
+        game.become_monarch(&monarch).await;
+
+        let bear = {
+            let mut attacker_lock = attacker.lock().await;
+            attacker_lock.draw_card();
+            let bear = attacker_lock.cards_in_hand.pop().unwrap();
+            bear.lock().await.controller = Some(attacker.clone());
+            attacker_lock.cards_in_play.push(bear.clone());
+            bear
+        };
+
+        game.combat
+            .declare_attacker(bear, EffectTarget::Player(monarch.clone()))
+            .await;
+        CombatAction {}.apply(&mut game, 0).await;
+
+        assert!(game.is_monarch(&attacker));
+        assert_eq!(monarch.lock().await.get_stat_value(StatType::Health), 18);
+
+        // Only the monarch gets the extra card at their end step
+        MonarchDrawAction {}.apply(&mut game, 1).await;
+        assert!(monarch.lock().await.cards_in_hand.is_empty());
+        MonarchDrawAction {}.apply(&mut game, 0).await;
+        assert_eq!(attacker.lock().await.cards_in_hand.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_passing_until_my_turn_skips_priority_on_opponents_turn() {
+        let mut game = Game::new();
+        let active = game
+            .add_player(Player::new(
+                "active",
+                20,
+                vec![create_creature("Bear", 2, 2)],
+            ))
+            .await;
+        let passer = game
+            .add_player(Player::new("passer", 20, vec![create_burn()]))
+            .await;
+        game.start_turn(0).await;
+        active.lock().await.draw_card();
+        game.pass_until_my_turn(&passer, true).await;
+        let bear = game.play_card(&active, 0, None).await.unwrap();
+
+        let ga = Arc::new(Mutex::new(game));
+        let watcher = {
+            let ga = ga.clone();
+            let passer = passer.clone();
+            tokio::spawn(async move {
+                loop {
+                    if let Some((player, _, _)) = &ga.lock().await.current_priority_player {
+                        if Arc::ptr_eq(player, &passer) {
+                            return true;
+                        }
+                    }
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                }
+            })
+        };
+        // The passer is the only one who could respond, so nobody is waited on
+        tokio::time::timeout(
+            Duration::from_secs(1),
+            Game::process_action_queue(ga.clone(), bear.clone()),
+        )
+        .await
+        .expect("the passing player should not be waited on");
+        watcher.abort();
+        assert!(watcher.await.is_err());
+        assert!(active
+            .lock()
+            .await
+            .cards_in_play
+            .iter()
+            .any(|card| Arc::ptr_eq(card, &bear)));
+
+        // Through the rest of the turn, then it's over once theirs starts
+        let mut game = ga.lock().await;
+        game.skip_to_phase(TurnPhase::Cleanup).await;
+        assert!(passer.lock().await.passing_until_my_turn);
+        game.advance_turn().await;
+        assert!(Arc::ptr_eq(
+            &game.current_turn.as_ref().unwrap().current_player,
+            &passer
+        ));
+        assert!(!passer.lock().await.passing_until_my_turn);
+    }
+
+    #[tokio::test]
+    async fn test_being_targeted_stops_passing_until_my_turn() {
+        let mut game = Game::new();
+        let caster = game
+            .add_player(Player::new("caster", 20, vec![create_burn()]))
+            .await;
+        let passer = game.add_player(Player::new("passer", 20, vec![])).await;
+        game.start_turn(0).await;
+        caster.lock().await.draw_card();
+        caster.lock().await.mana_pool.add_mana(ManaType::Red);
+        game.pass_until_my_turn(&passer, true).await;
+
+        let ga = Arc::new(Mutex::new(game));
+        assert!(Game::is_auto_passing(&ga, &passer).await);
+        ga.lock()
+            .await
+            .play_card(&caster, 0, Some(EffectTarget::Player(passer.clone())))
+            .await
+            .unwrap();
+        assert!(!Game::is_auto_passing(&ga, &passer).await);
+        assert!(!passer.lock().await.passing_until_my_turn);
+    }
+
+    #[tokio::test]
+    async fn test_empty_board_skips_the_priority_window() {
+        let mut game = Game::new();
+        game.skip_empty_priority_windows = true;
+        let player = game
+            .add_player(Player::new(
+                "active",
+                20,
+                vec![create_creature("Bear", 2, 2)],
+            ))
+            .await;
+        let opponent = game.add_player(Player::new("opponent", 20, vec![])).await;
+        game.start_turn(0).await;
+        player.lock().await.draw_card();
+        assert!(game.legal_actions(&opponent).await.is_empty());
+        let bear = game.play_card(&player, 0, None).await.unwrap();
+        let mut updates = game.broadcast_sender.as_ref().unwrap().subscribe();
+
+        let ga = Arc::new(Mutex::new(game));
+        tokio::time::timeout(
+            Duration::from_millis(500),
+            Game::process_action_queue(ga.clone(), bear.clone()),
+        )
+        .await
+        .expect("nobody could respond, so nobody should be waited on");
+
+        assert!(player
+            .lock()
+            .await
+            .cards_in_play
+            .iter()
+            .any(|card| Arc::ptr_eq(card, &bear)));
+        while let Ok(update) = updates.try_recv() {
+            assert!(!matches!(
+                update,
+                Some(LobbyCommand::DebugMessage(message)) if message == "Entering priority loop"
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_legal_actions_only_lists_what_can_be_paid_for() {
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new(
+                "test",
+                20,
+                vec![create_burn(), create_grapeshot(), create_fire()],
+            ))
+            .await;
+        game.start_turn(0).await;
+        let fire = player.lock().await.draw_card().unwrap();
+        player.lock().await.draw_card();
+        let burn = player.lock().await.draw_card().unwrap();
+
+        // Nothing to pay for the burn with yet, and grapeshot is a sorcery
+        assert!(game.legal_actions(&player).await.is_empty());
+
+        {
+            let mut player = player.lock().await;
+            player
+                .cards_in_hand
+                .retain(|card| !Arc::ptr_eq(card, &fire));
+            player.cards_in_play.push(fire.clone());
+        }
+        let burn_id = burn.lock().await.id.clone();
+        assert_eq!(
+            game.legal_actions(&player).await,
+            vec![LegalAction::CastSpell { card_id: burn_id }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_legal_actions_include_flashback_and_turning_face_up() {
+        let (game, player, giant) = manifest_giant().await;
+        let burn = Arc::new(Mutex::new(
+            create_burn().with_flashback(vec![ManaType::Green]),
+        ));
+        player
+            .lock()
+            .await
+            .zone_mut(Zone::Graveyard)
+            .push(burn.clone());
+        assert!(game.legal_actions(&player).await.is_empty());
+
+        player.lock().await.mana_pool.add_mana(ManaType::Green);
+        let burn_id = burn.lock().await.id.clone();
+        let giant_id = giant.lock().await.id.clone();
+        assert_eq!(
+            game.legal_actions(&player).await,
+            vec![
+                LegalAction::FlashbackSpell { card_id: burn_id },
+                LegalAction::TurnFaceUp { card_id: giant_id },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fast_mode_resolves_spells_without_priority() {
+        let mut game = Game::new();
+        game.auto_pass_priority = true;
+        let player = game
+            .add_player(Player::new(
+                "active",
+                20,
+                vec![create_creature("Bear", 2, 2)],
+            ))
+            .await;
+        let opponent = game
+            .add_player(Player::new(
+                "opponent",
+                20,
+                vec![create_creature("Wolf", 2, 2)],
+            ))
+            .await;
+        game.start_turn(0).await;
+        player.lock().await.draw_card();
+        opponent.lock().await.draw_card();
+        let mut updates = game.broadcast_sender.as_ref().unwrap().subscribe();
+
+        // Nobody is given the chance to respond, so it's the active player's turn only
+        assert!(game.play_card(&opponent, 0, None).await.is_err());
+        let bear = game.play_card(&player, 0, None).await.unwrap();
+
+        let ga = Arc::new(Mutex::new(game));
+        tokio::time::timeout(
+            Duration::from_secs(1),
+            Game::process_action_queue(ga.clone(), bear.clone()),
+        )
+        .await
+        .expect("the spell should not wait on priority");
+
+        assert!(ga.lock().await.current_priority_player.is_none());
+        assert!(player
+            .lock()
+            .await
+            .cards_in_play
+            .iter()
+            .any(|card| Arc::ptr_eq(card, &bear)));
+        while let Ok(update) = updates.try_recv() {
+            assert!(!matches!(
+                update,
+                Some(LobbyCommand::DebugMessage(message)) if message == "Entering priority loop"
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mana_abilities_skip_the_stack() {
+        let mut game = Game::new();
+        let caster = game
+            .add_player(Player::new("caster", 20, vec![create_burn()]))
+            .await;
+        let responder = game
+            .add_player(Player::new("responder", 20, vec![create_island()]))
+            .await;
+        game.start_turn(0).await;
+        caster.lock().await.draw_card();
+        caster.lock().await.mana_pool.add_mana(ManaType::Red);
+        let island = responder.lock().await.draw_card().unwrap();
+        {
+            let mut responder_lock = responder.lock().await;
+            island.lock().await.controller = Some(responder.clone());
+            responder_lock.cards_in_hand.pop();
+            responder_lock.cards_in_play.push(island.clone());
+        }
+        let burn = game
+            .play_card(&caster, 0, Some(EffectTarget::Player(responder.clone())))
+            .await
+            .unwrap();
+        game.current_priority_player = Some((responder.clone(), 3, ActionType::None));
+        let mut updates = game.broadcast_sender.as_ref().unwrap().subscribe();
+
+        let trigger_id = island.lock().await.triggers[0].id.clone();
+        game.activate_card_action(&responder, 0, None, trigger_id)
+            .await
+            .unwrap();
+
+        // The mana is there straight away, the burn is still waiting and the
+        // responder still hasn't done anything with their priority
+        assert_eq!(responder.lock().await.mana_pool.blue, 1);
+        assert!(Arc::ptr_eq(&caster.lock().await.spells[0], &burn));
+        assert_eq!(game.performed_action(), ActionType::None);
+        let mut mana_added = None;
+        while let Ok(update) = updates.try_recv() {
+            if let Some(LobbyCommand::ManaAdded(added)) = update {
+                mana_added = Some(added);
+            }
+        }
+        let mana_added = mana_added.expect("no ManaAdded event");
+        assert_eq!(mana_added.player_id, "responder");
+        assert_eq!(mana_added.mana, vec![ManaType::Blue]);
+    }
+
+    #[tokio::test]
+    async fn test_tapping_a_land_broadcasts_the_pool() {
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new("test", 20, vec![create_island()]))
+            .await;
+        game.start_turn(0).await;
+        let island = player.lock().await.draw_card().unwrap();
+        player.lock().await.cards_in_hand.pop();
+        player.lock().await.cards_in_play.push(island.clone());
+        player.lock().await.mana_pool.add_mana(ManaType::Blue);
+        let mut updates = game.broadcast_sender.as_ref().unwrap().subscribe();
+
+        let trigger_id = island.lock().await.triggers[0].id.clone();
+        game.activate_card_action(&player, 0, None, trigger_id)
+            .await
+            .unwrap();
+
+        let mut pools = vec![];
+        while let Ok(update) = updates.try_recv() {
+            if let Some(LobbyCommand::ManaPoolUpdated(update)) = update {
+                pools.push(update);
+            }
+        }
+        assert_eq!(pools.len(), 1);
+        assert_eq!(pools[0].player_id, "test");
+        // Floating mana from before is included
+        assert_eq!(pools[0].mana_pool.blue, 2);
+    }
+
+    #[tokio::test]
+    async fn test_emptying_the_stack_is_broadcast() {
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new("test", 20, vec![create_creature("Bear", 2, 2)]))
+            .await;
+        game.start_turn(0).await;
+        let bear = player.lock().await.draw_card().unwrap();
+        game.add_to_stack(Arc::new(CardActionWrapper {
+            action: Arc::new(DrawCardCardAction::one(CardActionTarget::SelfOwner)),
+            card: bear,
+            target: None,
+        }));
+        let mut updates = game.broadcast_sender.as_ref().unwrap().subscribe();
+
+        game.resolve_stack().await;
+
+        let mut stack_updates = vec![];
+        while let Ok(update) = updates.try_recv() {
+            if let Some(LobbyCommand::StackUpdated(stack)) = update {
+                stack_updates.push(stack);
+            }
+        }
+        assert_eq!(stack_updates.len(), 1);
+        assert!(stack_updates[0].is_empty());
+
+        // Nothing to clear the second time round
+        game.resolve_stack().await;
+        assert!(updates.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_draws_whenever_an_opponent_casts_a_spell() {
+        let mut watcher = create_creature("Watcher", 1, 1);
+        watcher.triggers.push(CardActionTrigger::new(
+            ActionTriggerType::OtherCardPlayed(TriggerTarget::Target),
+            CardRequiredTarget::None,
+            Arc::new(DrawCardCardAction::one(CardActionTarget::SelfOwner)),
+        ));
+
+        let mut game = Game::new();
+        let watching = game
+            .add_player(Player::new(
+                "watching",
+                20,
+                vec![create_burn(), create_creature("Extra", 1, 1), watcher],
+            ))
+            .await;
+        let caster = game
+            .add_player(Player::new("caster", 20, vec![create_burn()]))
+            .await;
+        game.start_turn(1).await;
+
+        {
+            let mut watching_lock = watching.lock().await;
+            watching_lock.draw_card();
+            let watcher = watching_lock.cards_in_hand.pop().unwrap();
+            watcher.lock().await.controller = Some(watching.clone());
+            watching_lock.cards_in_play.push(watcher);
+        }
+        {
+            let mut caster = caster.lock().await;
+            caster.draw_card();
+            caster.mana_pool.add_mana(ManaType::Red);
+        }
+
+        game.play_card(&caster, 0, Some(EffectTarget::Player(watching.clone())))
+            .await
+            .unwrap();
+        game.resolve_stack().await;
+        assert_eq!(watching.lock().await.cards_in_hand.len(), 1);
+
+        // Its controller's own spells don't count
+        game.start_turn(0).await;
+        {
+            let mut watching = watching.lock().await;
+            watching.cards_in_hand.clear();
+            watching.draw_card();
+            watching.mana_pool.add_mana(ManaType::Red);
+        }
+        game.play_card(&watching, 0, Some(EffectTarget::Player(caster.clone())))
+            .await
+            .unwrap();
+        game.resolve_stack().await;
+        assert!(watching.lock().await.cards_in_hand.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_cast_triggers_check_who_cast_the_spell() {
+        // (trigger target, fires for its controller's spell, fires for an opponent's spell)
+        let arms = [
+            (TriggerTarget::Owner, true, false),
+            (TriggerTarget::Target, false, true),
+            (TriggerTarget::Any, true, true),
+        ];
+        for (trigger_target, for_controller, for_opponent) in arms {
+            let mut game = Game::new();
+            let watching = game.add_player(Player::new("watching", 20, vec![])).await;
+            let opponent = game.add_player(Player::new("opponent", 20, vec![])).await;
+            game.start_turn(0).await;
+
+            for trigger_type in [
+                ActionTriggerType::OtherCardPlayed(trigger_target.clone()),
+                ActionTriggerType::CreatureTypeCardPlayed(
+                    trigger_target.clone(),
+                    CreatureType::Elf,
+                ),
+            ] {
+                let mut watcher = create_creature("Watcher", 1, 1);
+                watcher.controller = Some(watching.clone());
+                watcher.triggers.push(CardActionTrigger::new(
+                    trigger_type,
+                    CardRequiredTarget::None,
+                    Arc::new(DrawCardCardAction::one(CardActionTarget::SelfOwner)),
+                ));
+                watching
+                    .lock()
+                    .await
+                    .cards_in_play
+                    .push(Arc::new(Mutex::new(watcher)));
+            }
+
+            for (caster, expected) in [(&watching, for_controller), (&opponent, for_opponent)] {
+                let mut elf = create_creature("Elf", 1, 1);
+                elf.creature_type = Some(CreatureType::Elf);
+                elf.controller = Some(caster.clone());
+                let actions = game
+                    .collect_card_played_actions(&Arc::new(Mutex::new(elf)))
+                    .await;
+                assert_eq!(actions.len(), if expected { 2 } else { 0 });
+
+                // Only the creature type watcher cares what kind of spell it is
+                let mut other = create_creature("Other", 1, 1);
+                other.controller = Some(caster.clone());
+                let actions = game
+                    .collect_card_played_actions(&Arc::new(Mutex::new(other)))
+                    .await;
+                assert_eq!(actions.len(), if expected { 1 } else { 0 });
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_intervening_if_is_checked_on_every_trigger_path() {
+        let untapped: TriggerCondition =
+            Arc::new(|card| Box::pin(async move { !card.lock().await.tapped }));
+        let mut game = Game::new();
+        let player = game.add_player(Player::new("test", 20, vec![])).await;
+        game.start_turn(0).await;
+
+        let mut watcher = create_creature("Watcher", 1, 1);
+        watcher.controller = Some(player.clone());
+        for trigger_type in [
+            ActionTriggerType::CreatureDied(TriggerTarget::Any),
+            ActionTriggerType::Blocks,
+            ActionTriggerType::OtherCardPlayed(TriggerTarget::Any),
+        ] {
+            watcher.triggers.push(
+                CardActionTrigger::new(
+                    trigger_type,
+                    CardRequiredTarget::None,
+                    Arc::new(DrawCardCardAction::one(CardActionTarget::SelfOwner)),
+                )
+                .with_condition(untapped.clone()),
+            );
+        }
+        let watcher = Arc::new(Mutex::new(watcher));
+        player.lock().await.cards_in_play.push(watcher.clone());
+        let mut bear = create_creature("Bear", 2, 2);
+        bear.controller = Some(player.clone());
+        let bear = Arc::new(Mutex::new(bear));
+
+        for (tapped, expected) in [(true, 0), (false, 1)] {
+            watcher.lock().await.tapped = tapped;
+            let died = Card::collect_creature_died_actions(&watcher, &bear, &player).await;
+            assert_eq!(died.len(), expected);
+            let blocked =
+                Card::collect_triggered_actions(&watcher, &ActionTriggerType::Blocks).await;
+            assert_eq!(blocked.len(), expected);
+            let played = game.collect_card_played_actions(&bear).await;
+            assert_eq!(played.len(), expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ability_without_a_legal_target_is_hidden() {
+        let mut assassin = create_creature("Assassin", 1, 1);
+        assassin.triggers.push(CardActionTrigger::new(
+            ActionTriggerType::AbilityWithinPhases(
+                "Destroy target enemy creature".to_string(),
+                vec![],
+                None,
+                true,
+            ),
+            CardRequiredTarget::CardOfType(CardType::Creature, CardTargetTeam::Opponent),
+            Arc::new(DestroyTargetCAction {}),
+        ));
+
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new("player", 20, vec![assassin]))
+            .await;
+        let opponent = game
+            .add_player(Player::new(
+                "opponent",
+                20,
+                vec![create_creature("Bear", 2, 2)],
+            ))
+            .await;
+        game.start_turn(0).await;
+        let assassin = {
+            let mut player_lock = player.lock().await;
+            player_lock.draw_card();
+            let assassin = player_lock.cards_in_hand.pop().unwrap();
+            assassin.lock().await.controller = Some(player.clone());
+            player_lock.cards_in_play.push(assassin.clone());
+            assassin
+        };
+
+        let game = Arc::new(Mutex::new(game));
+        let details = CardWithDetails::from_card_arc(&assassin, TurnPhase::Main, true, &game).await;
+        assert_eq!(details.abilities.len(), 1);
+        assert!(!details.abilities[0].show);
+        assert!(!details.abilities[0].meets_requirements);
+
+        {
+            let mut opponent_lock = opponent.lock().await;
+            opponent_lock.draw_card();
+            let bear = opponent_lock.cards_in_hand.pop().unwrap();
+            bear.lock().await.controller = Some(opponent.clone());
+            opponent_lock.cards_in_play.push(bear);
+        }
+        let details = CardWithDetails::from_card_arc(&assassin, TurnPhase::Main, true, &game).await;
+        assert!(details.abilities[0].show);
+        assert!(details.abilities[0].meets_requirements);
+    }
+
+    #[tokio::test]
+    async fn test_nothing_can_respond_to_split_second() {
+        let mut game = Game::new();
+        let caster = game
+            .add_player(Player::new("caster", 20, vec![create_sudden_shock()]))
+            .await;
+        let opponent = game
+            .add_player(Player::new(
+                "opponent",
+                20,
+                vec![create_burn(), create_fire()],
+            ))
+            .await;
+        game.start_turn(0).await;
+        caster.lock().await.draw_card();
+        {
+            let mut caster = caster.lock().await;
+            caster.mana_pool.add_mana(ManaType::Red);
+            caster.mana_pool.add_mana(ManaType::Colorless);
+        }
+        let fire = {
+            let mut opponent = opponent.lock().await;
+            opponent.draw_card();
+            let fire = opponent.cards_in_hand.pop().unwrap();
+            opponent.cards_in_play.push(fire.clone());
+            opponent.draw_card();
+            opponent.mana_pool.add_mana(ManaType::Red);
+            fire
+        };
+
+        game.play_card(&caster, 0, Some(EffectTarget::Player(opponent.clone())))
+            .await
+            .unwrap();
+
+        let err = game
+            .play_card(&opponent, 0, Some(EffectTarget::Player(caster.clone())))
+            .await
+            .unwrap_err();
+        assert_eq!(err, "Nobody can respond to Sudden Shock");
+        assert_eq!(opponent.lock().await.cards_in_hand.len(), 1);
+
+        // Mana abilities don't use the stack, so they're still fine
+        let mana_ability = fire.lock().await.triggers[0].id.clone();
+        game.activate_card_action(&opponent, 0, None, mana_ability)
+            .await
+            .unwrap();
+
+        // Priority passes straight through
+        game.current_priority_player = Some((opponent.clone(), 3, ActionType::None));
+        let game_arc = Arc::new(Mutex::new(game));
+        let result = Game::wait_for_player_action_async(game_arc.clone(), 3).await;
+        assert!(matches!(result, PriorityActionResult::NoAction));
+
+        let mut game = game_arc.lock().await;
+        game.current_priority_player = None;
+        game.resolve_stack().await;
+        assert_eq!(opponent.lock().await.get_stat_value(StatType::Health), 18);
+        game.play_card(&opponent, 0, Some(EffectTarget::Player(caster.clone())))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_phased_out_permanent_has_no_effects_or_triggers() {
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new("player", 20, vec![create_island()]))
+            .await;
+        game.start_turn(0).await;
+        let mut visionary = create_leaf_crowned_visionary();
+        visionary.controller = Some(player.clone());
+        visionary.triggers.push(CardActionTrigger::new(
+            ActionTriggerType::Blocks,
+            CardRequiredTarget::None,
+            Arc::new(DrawCardCardAction::one(CardActionTarget::SelfOwner)),
+        ));
+        let visionary = Arc::new(Mutex::new(visionary));
+        let mut bear = create_creature("Bear", 2, 2);
+        bear.controller = Some(player.clone());
+        let bear = Arc::new(Mutex::new(bear));
+        player
+            .lock()
+            .await
+            .cards_in_play
+            .extend([visionary.clone(), bear.clone()]);
+
+        game.execute_actions(&mut vec![]).await;
+        assert_eq!(bear.lock().await.get_stat_value(StatType::Power), 3);
+
+        game.phase_out(&visionary).await;
+        game.execute_actions(&mut vec![]).await;
+        assert_eq!(bear.lock().await.get_stat_value(StatType::Power), 2);
+        let blocks = Card::collect_triggered_actions(&visionary, &ActionTriggerType::Blocks).await;
+        assert!(blocks.is_empty());
+
+        visionary.lock().await.phased_out = false;
+        game.execute_actions(&mut vec![]).await;
+        assert_eq!(bear.lock().await.get_stat_value(StatType::Power), 3);
+    }
+
+    #[tokio::test]
+    async fn test_phased_out_creature_cant_be_targeted_until_it_returns() {
+        let mut game = Game::new();
+        let caster = game
+            .add_player(Player::new(
+                "caster",
+                20,
+                vec![create_island(), create_unsummon()],
+            ))
+            .await;
+        let opponent = game
+            .add_player(Player::new(
+                "opponent",
+                20,
+                vec![create_island(), create_hydra()],
+            ))
+            .await;
+        game.start_turn(0).await;
+        game.current_turn.as_mut().unwrap().phase = TurnPhase::Main;
+        {
+            let mut caster = caster.lock().await;
+            caster.draw_card();
+            caster.mana_pool.add_mana(ManaType::Blue);
+        }
+        let hydra = {
+            let mut opponent_lock = opponent.lock().await;
+            opponent_lock.draw_card();
+            let hydra = opponent_lock.cards_in_hand.pop().unwrap();
+            hydra.lock().await.controller = Some(opponent.clone());
+            opponent_lock.cards_in_play.push(hydra.clone());
+            hydra
+        };
+
+        game.phase_out(&hydra).await;
+        let err = game
+            .play_card(&caster, 0, Some(EffectTarget::Card(hydra.clone())))
+            .await
+            .unwrap_err();
+        assert_eq!(err, "Voracious Hydra is phased out");
+
+        // Back at the start of the opponent's turn
+        while !Arc::ptr_eq(
+            &game.current_turn.as_ref().unwrap().current_player,
+            &opponent,
+        ) {
+            game.advance_turn().await;
+        }
+        assert!(!hydra.lock().await.phased_out);
+        assert!(opponent
+            .lock()
+            .await
+            .cards_in_play
+            .iter()
+            .any(|card| Arc::ptr_eq(card, &hydra)));
+    }
+
+    #[tokio::test]
+    async fn test_only_the_first_attacker_is_prevented() {
+        let mut game = Game::new();
+        let attacker = game
+            .add_player(Player::new(
+                "attacker",
+                20,
+                vec![create_creature("Bear", 2, 2), create_creature("Bear", 2, 2)],
+            ))
+            .await;
+        let defender = game.add_player(Player::new("defender", 20, vec![])).await;
+        game.start_turn(0).await;
+
+        let mut bears = vec![];
+        for _ in 0..2 {
+            let bear = attacker.lock().await.draw_card().unwrap();
+            attacker.lock().await.cards_in_play.push(bear.clone());
+            bears.push(bear);
+        }
+
+        game.add_to_stack(Arc::new(CardActionWrapper {
+            card: bears[0].clone(),
+            action: Arc::new(PreventFirstCombatDamageAction {}),
+            target: Some(EffectTarget::Player(defender.clone())),
+        }));
+        game.resolve_stack().await;
+        for bear in &bears {
+            game.combat
+                .declare_attacker(bear.clone(), EffectTarget::Player(defender.clone()))
+                .await;
+        }
+        game.combat.resolve_combat().await;
+        assert_eq!(defender.lock().await.get_stat_value(StatType::Health), 18);
+        assert!(!defender.lock().await.prevent_next_combat_damage);
+
+        // An unused shield doesn't carry over to the next turn
+        defender.lock().await.prevent_next_combat_damage = true;
+        game.start_turn(1).await;
+        assert!(!defender.lock().await.prevent_next_combat_damage);
+    }
+
+    #[tokio::test]
+    async fn test_move_card_from_hand_to_graveyard() {
+        let mut bear = create_creature("Bear", 2, 2);
+        bear.triggers.push(CardActionTrigger::new(
+            ActionTriggerType::CardPlayedFromHand,
+            CardRequiredTarget::None,
+            Arc::new(DrawCardCardAction::one(CardActionTarget::SelfOwner)),
+        ));
+        let mut watcher = create_creature("Watcher", 1, 1);
+        watcher.triggers.push(CardActionTrigger::new(
+            ActionTriggerType::CreatureDied(TriggerTarget::Owner),
+            CardRequiredTarget::None,
+            Arc::new(DrawCardCardAction::one(CardActionTarget::SelfOwner)),
+        ));
+
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new(
+                "test",
+                20,
+                vec![
+                    create_creature("Filler", 1, 1),
+                    create_creature("Filler", 1, 1),
+                    watcher,
+                    bear,
+                ],
+            ))
+            .await;
+        game.start_turn(0).await;
+        let bear = player.lock().await.draw_card().unwrap();
+        let watcher = player.lock().await.draw_card().unwrap();
+        game.move_card(&watcher, Zone::Hand, Zone::Battlefield)
+            .await
+            .unwrap();
+        assert_eq!(player.lock().await.cards_in_hand.len(), 1);
+
+        game.move_card(&bear, Zone::Hand, Zone::Stack)
+            .await
+            .unwrap();
+        assert!(player.lock().await.zone_contains(Zone::Stack, &bear));
+        assert!(player.lock().await.cards_in_hand.is_empty());
+
+        // Entering the battlefield draws a card
+        game.move_card(&bear, Zone::Stack, Zone::Battlefield)
+            .await
+            .unwrap();
+        assert!(player.lock().await.spells.is_empty());
+        assert!(player.lock().await.zone_contains(Zone::Battlefield, &bear));
+        assert!(Arc::ptr_eq(
+            &bear.lock().await.controller().unwrap(),
+            &player
+        ));
+        assert_eq!(player.lock().await.cards_in_hand.len(), 1);
+
+        // And dying makes the watcher draw one
+        game.move_card(&bear, Zone::Battlefield, Zone::Graveyard)
+            .await
+            .unwrap();
+        assert!(player.lock().await.zone_contains(Zone::Graveyard, &bear));
+        assert!(!player.lock().await.zone_contains(Zone::Battlefield, &bear));
+        assert!(bear.lock().await.controller.is_none());
+        assert_eq!(player.lock().await.cards_in_hand.len(), 2);
+
+        assert!(game
+            .move_card(&bear, Zone::Battlefield, Zone::Graveyard)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_asking_for_an_ability_while_the_player_is_locked() {
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new("test", 20, vec![create_creature("Bear", 2, 2)]))
+            .await;
+        game.start_turn(0).await;
+        let bear = player.lock().await.draw_card().unwrap();
+        bear.lock().await.controller = Some(player.clone());
+
+        for mandatory in [true, false] {
+            // Someone else holds the player, then wants the card, the usual order
+            let (locked_tx, locked_rx) = tokio::sync::oneshot::channel();
+            let other = tokio::spawn({
+                let (player, bear) = (player.clone(), bear.clone());
+                async move {
+                    let _player = player.lock().await;
+                    locked_tx.send(()).unwrap();
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    let _bear = bear.lock().await;
+                }
+            });
+            locked_rx.await.unwrap();
+
+            let ability = Ability::new(
+                bear.clone(),
+                vec![],
+                CardRequiredTarget::None,
+                Arc::new(|_| Arc::new(DrawCardCardAction::one(CardActionTarget::SelfOwner))),
+                "draw a card".to_string(),
+                ActionType::Instant,
+            );
+            let asked = async {
+                if mandatory {
+                    game.ask_mandatory_player_ability(ability).await;
+                } else {
+                    game.request_player_ability(ability).await;
+                }
+            };
+            tokio::time::timeout(Duration::from_secs(1), asked)
+                .await
+                .expect("asking for the ability deadlocked");
+            other.await.unwrap();
+        }
+    }
+
+    // Sets off the same trigger on every other card in play
+    #[derive(Debug)]
+    struct TriggerOthersAction {}
+
+    #[async_trait::async_trait]
+    impl CardAction for TriggerOthersAction {
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+        async fn apply(&self, game: &mut Game, card: Arc<Mutex<Card>>, target: EffectTarget) {
+            for player in game.players.clone() {
+                let cards_in_play = player.lock().await.cards_in_play.clone();
+                for other in cards_in_play
+                    .iter()
+                    .filter(|other| !Arc::ptr_eq(other, &card))
+                {
+                    let actions =
+                        Card::collect_triggered_actions(other, &ActionTriggerType::Blocks).await;
+                    for action in actions {
+                        game.add_to_stack(action);
+                    }
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_creatures_triggering_each_other_are_stopped() {
+        let echo = |name: &str| {
+            let mut card = create_creature(name, 1, 1);
+            card.triggers.push(CardActionTrigger::new(
+                ActionTriggerType::Blocks,
+                CardRequiredTarget::None,
+                Arc::new(TriggerOthersAction {}),
+            ));
+            card
+        };
+        let mut game = Game::new().with_stack_resolution_limit(50);
+        let player = game
+            .add_player(Player::new("test", 20, vec![echo("Ping"), echo("Pong")]))
+            .await;
+        game.start_turn(0).await;
+        let pong = player.lock().await.draw_card().unwrap();
+        let ping = player.lock().await.draw_card().unwrap();
+        player.lock().await.cards_in_play.push(pong.clone());
+        player.lock().await.cards_in_play.push(ping.clone());
+
+        let actions = Card::collect_triggered_actions(&ping, &ActionTriggerType::Blocks).await;
+        for action in actions {
+            game.add_to_stack(action);
+        }
+
+        let GameError::LoopDetected { triggers } = game.try_resolve_stack().await.unwrap_err()
+        else {
+            panic!("expected the loop to be caught");
+        };
+        assert!(!triggers.is_empty());
+        assert!(triggers.iter().all(|name| name == "Ping" || name == "Pong"));
+        assert!(game.event_stack.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_ending_the_turn_exiles_the_stack_and_skips_to_cleanup() {
+        let mut game = Game::new();
+        let caster = game
+            .add_player(Player::new("caster", 20, vec![create_sudden_shock()]))
+            .await;
+        let opponent = game.add_player(Player::new("opponent", 20, vec![])).await;
+        game.start_turn(0).await;
+        game.skip_to_phase(TurnPhase::Main).await;
+        assert_eq!(game.current_phase(), TurnPhase::Main);
+
+        let shock = {
+            let mut caster = caster.lock().await;
+            caster.draw_card();
+            caster.mana_pool.add_mana(ManaType::Red);
+            caster.mana_pool.add_mana(ManaType::Colorless);
+            caster.cards_in_hand[0].clone()
+        };
+        game.play_card(&caster, 0, Some(EffectTarget::Player(opponent.clone())))
+            .await
+            .unwrap();
+        assert!(!game.event_stack.is_empty());
+
+        let source = Arc::new(Mutex::new(create_creature("Time Stop", 0, 0)));
+        EndTheTurnAction {}
+            .apply(&mut game, source, EffectTarget::Player(caster.clone()))
+            .await;
+
+        assert_eq!(game.current_phase(), TurnPhase::Cleanup);
+        assert!(game.event_stack.is_empty());
+        assert!(caster.lock().await.zone_contains(Zone::Exile, &shock));
+        assert!(caster.lock().await.spells.is_empty());
+        assert_eq!(opponent.lock().await.get_stat_value(StatType::Health), 20);
+    }
+
+    #[tokio::test]
+    async fn test_banding_lets_the_defender_pick_which_blocker_dies() {
+        let mut game = Game::new();
+        let mut wall = create_creature("Wall", 0, 2);
+        wall.stats
+            .add_stat("banding".to_string(), Stat::new(StatType::Banding, 1));
+        let attacker = game
+            .add_player(Player::new(
+                "attacker",
+                20,
+                vec![create_creature("Brute", 2, 2)],
+            ))
+            .await;
+        let defender = game
+            .add_player(Player::new(
+                "defender",
+                20,
+                vec![create_creature("Bear", 0, 2), wall],
+            ))
+            .await;
+        game.start_turn(0).await;
+
+        let brute = attacker.lock().await.draw_card().unwrap();
+        brute.lock().await.controller = Some(attacker.clone());
+        attacker.lock().await.cards_in_play.push(brute.clone());
+        let mut blockers = vec![];
+        for _ in 0..2 {
+            let card = defender.lock().await.draw_card().unwrap();
+            card.lock().await.controller = Some(defender.clone());
+            defender.lock().await.cards_in_play.push(card.clone());
+            blockers.push(card);
+        }
+        let (wall, bear) = (blockers[0].clone(), blockers[1].clone());
+
+        game.combat
+            .declare_attacker(brute.clone(), EffectTarget::Player(defender.clone()))
+            .await;
+        game.combat
+            .declare_blocker(wall.clone(), brute.clone())
+            .await;
+        game.combat
+            .declare_blocker(bear.clone(), brute.clone())
+            .await;
+
+        // The wall was declared first, but the defender puts the bear first
+        CombatAction {}.apply(&mut game, 0).await;
+        let (ability_id, chooser) = {
+            let ability = game.abilities.values().next().unwrap();
+            (ability.id.clone(), ability.chooser.clone().unwrap())
+        };
+        assert!(Arc::ptr_eq(&chooser, &defender));
+        assert_eq!(wall.lock().await.damage_taken, 0);
+
+        let game_arc = Arc::new(Mutex::new(game));
+        Game::respond_player_ability(
+            game_arc.clone(),
+            &defender,
+            ability_id,
+            true,
+            Some(EffectTarget::Card(bear.clone())),
+        )
+        .await
+        .unwrap();
+
+        let mut defender = defender.lock().await;
+        assert!(defender.zone_contains(Zone::Graveyard, &bear));
+        assert!(defender.zone_contains(Zone::Battlefield, &wall));
+        assert_eq!(wall.lock().await.damage_taken, 0);
+    }
+
+    #[tokio::test]
+    async fn test_defender_puts_a_blocker_in_front_and_attacker_orders_the_rest() {
+        let mut game = Game::new();
+        let attacker = game
+            .add_player(Player::new(
+                "attacker",
+                20,
+                vec![create_creature("Brute", 3, 3)],
+            ))
+            .await;
+        let defender = game
+            .add_player(Player::new(
+                "defender",
+                20,
+                vec![
+                    create_creature("Wall", 0, 2),
+                    create_creature("Bear", 0, 2),
+                    create_creature("Elk", 0, 2),
+                ],
+            ))
+            .await;
+        game.start_turn(0).await;
+
+        let brute = attacker.lock().await.draw_card().unwrap();
+        brute.lock().await.controller = Some(attacker.clone());
+        attacker.lock().await.cards_in_play.push(brute.clone());
+        game.combat
+            .declare_attacker(brute.clone(), EffectTarget::Player(defender.clone()))
+            .await;
+        let mut blockers = vec![];
+        for _ in 0..3 {
+            let card = defender.lock().await.draw_card().unwrap();
+            card.lock().await.controller = Some(defender.clone());
+            defender.lock().await.cards_in_play.push(card.clone());
+            game.combat
+                .declare_blocker(card.clone(), brute.clone())
+                .await;
+            blockers.push(card);
+        }
+        let (elk, bear, wall) = (
+            blockers[0].clone(),
+            blockers[1].clone(),
+            blockers[2].clone(),
+        );
+
+        CombatAction {}.apply(&mut game, 0).await;
+        let game_arc = Arc::new(Mutex::new(game));
+        // The defender sends the wall in first, then the attacker picks the bear
+        for (player, pick) in [(&defender, &wall), (&attacker, &bear)] {
+            let (ability_id, chooser) = {
+                let game = game_arc.lock().await;
+                let ability = game.abilities.values().next().unwrap();
+                (ability.id.clone(), ability.chooser.clone().unwrap())
+            };
+            assert!(Arc::ptr_eq(&chooser, player));
+            Game::respond_player_ability(
+                game_arc.clone(),
+                player,
+                ability_id,
+                true,
+                Some(EffectTarget::Card(pick.clone())),
+            )
+            .await
+            .unwrap();
+        }
+
+        let mut defender = defender.lock().await;
+        assert!(defender.zone_contains(Zone::Graveyard, &wall));
+        assert_eq!(bear.lock().await.damage_taken, 1);
+        assert_eq!(elk.lock().await.damage_taken, 0);
+        assert!(defender.zone_contains(Zone::Battlefield, &elk));
+    }
+
+    #[tokio::test]
+    async fn test_skipping_a_must_attack_creature_is_rejected() {
+        let mut game = Game::new();
+        let attacker = game
+            .add_player(Player::new(
+                "attacker",
+                20,
+                vec![create_creature("Berserker", 2, 2).with_must_attack()],
+            ))
+            .await;
+        let defender = game.add_player(Player::new("defender", 20, vec![])).await;
+        game.start_turn(0).await;
+        // Drawn during the draw step
+        game.skip_to_phase(TurnPhase::DeclareAttackers).await;
+
+        let berserker = attacker.lock().await.cards_in_hand.pop().unwrap();
+        {
+            let mut card = berserker.lock().await;
+            card.controller = Some(attacker.clone());
+            card.current_phase = CardPhase::Ready;
+        }
+        attacker.lock().await.cards_in_play.push(berserker.clone());
+
+        let err = game.try_advance_turn().await.unwrap_err();
+        assert_eq!(
+            err,
+            GameError::MustAttack {
+                creature: "Berserker".to_string()
+            }
+        );
+        assert_eq!(game.current_phase(), TurnPhase::DeclareAttackers);
+
+        game.combat
+            .declare_attacker(berserker, EffectTarget::Player(defender))
+            .await;
+        game.try_advance_turn().await.unwrap();
+        assert_eq!(game.current_phase(), TurnPhase::DeclareBlockers);
+    }
+
+    #[tokio::test]
+    async fn test_goaded_creature_must_attack_someone_else() {
+        let mut game = Game::new();
+        let attacker = game
+            .add_player(Player::new(
+                "attacker",
+                20,
+                vec![create_creature("Brute", 2, 2)],
+            ))
+            .await;
+        let goader = game
+            .add_player(Player::new(
+                "goader",
+                20,
+                vec![create_creature("Instigator", 1, 1)],
+            ))
+            .await;
+        let bystander = game.add_player(Player::new("bystander", 20, vec![])).await;
+        game.start_turn(0).await;
+        // Drawn during the draw step
+        game.skip_to_phase(TurnPhase::DeclareAttackers).await;
+
+        let brute = attacker.lock().await.cards_in_hand.pop().unwrap();
+        {
+            let mut card = brute.lock().await;
+            card.controller = Some(attacker.clone());
+            card.current_phase = CardPhase::Ready;
+        }
+        attacker.lock().await.cards_in_play.push(brute.clone());
+        let instigator = goader.lock().await.draw_card().unwrap();
+        instigator.lock().await.controller = Some(goader.clone());
+
+        GoadAction {}
+            .apply(&mut game, instigator, EffectTarget::Card(brute.clone()))
+            .await;
+
+        // Goaded, so it has to attack
+        let err = game.try_advance_turn().await.unwrap_err();
+        assert_eq!(
+            err,
+            GameError::MustAttack {
+                creature: "Brute".to_string()
+            }
+        );
+
+        // But not the player who goaded it while the bystander is around
+        game.combat
+            .declare_attacker(brute.clone(), EffectTarget::Player(goader.clone()))
+            .await;
+        let err = game.try_advance_turn().await.unwrap_err();
+        assert_eq!(
+            err,
+            GameError::GoadedAttack {
+                creature: "Brute".to_string(),
+                player: "goader".to_string()
+            }
+        );
+
+        game.combat.remove(&brute);
+        game.combat
+            .declare_attacker(brute.clone(), EffectTarget::Player(bystander.clone()))
+            .await;
+        game.try_advance_turn().await.unwrap();
+        assert_eq!(game.current_phase(), TurnPhase::DeclareBlockers);
+    }
+
+    // Manifests a morph creature off the top of the player's library
+    async fn manifest_giant() -> (Game, Arc<Mutex<Player>>, Arc<Mutex<Card>>) {
+        let mut game = Game::new();
+        let giant = create_creature("Hill Giant", 3, 3).with_morph(vec![ManaType::Green]);
+        let player = game
+            .add_player(Player::new("player", 20, vec![giant]))
+            .await;
+        game.start_turn(0).await;
+        let source = Arc::new(Mutex::new(create_creature("Summoner", 1, 1)));
+        source.lock().await.owner = Some(player.clone());
+        ManifestAction {}
+            .apply(&mut game, source.clone(), EffectTarget::Card(source))
+            .await;
+        let giant = player.lock().await.cards_in_play[0].clone();
+        (game, player, giant)
+    }
+
+    #[tokio::test]
+    async fn test_face_down_creature_serializes_as_a_nameless_2_2() {
+        let (game, _, giant) = manifest_giant().await;
+        assert!(giant.lock().await.face_down);
+
+        let details =
+            CardWithDetails::from_card(giant.lock().await.clone(), game.current_phase(), true)
+                .await;
+        assert_eq!(details.card.get_stat_value(StatType::Power), 2);
+        assert_eq!(details.card.get_stat_value(StatType::Toughness), 2);
+        assert_eq!(details.card.card_type, CardType::Creature);
+        assert!(details.card.morph.is_none());
+        assert!(details.abilities.is_empty());
+
+        let json = serde_json::to_value(&details).unwrap();
+        assert_eq!(json["card"]["name"], "");
+        assert_eq!(json["card"]["face_down"], true);
+        assert!(!json.to_string().contains("Hill Giant"));
+    }
+
+    #[tokio::test]
+    async fn test_morph_turns_face_up_keeping_its_counters() {
+        let (mut game, player, giant) = manifest_giant().await;
+        {
+            let mut giant = giant.lock().await;
+            giant
+                .stats
+                .add_stat("counter".to_string(), Stat::new(StatType::Power, 1));
+            assert_eq!(giant.get_stat_value(StatType::Power), 3);
+        }
+
+        assert!(game.turn_face_up(&player, 0).await.is_err());
+        player.lock().await.mana_pool.add_mana(ManaType::Green);
+        game.turn_face_up(&player, 0).await.unwrap();
+        // Nothing went on the stack for anyone to respond to
+        assert!(game.event_stack.is_empty());
+
+        let giant = giant.lock().await;
+        assert!(!giant.face_down);
+        assert_eq!(giant.name, "Hill Giant");
+        assert_eq!(giant.get_stat_value(StatType::Power), 4);
+        assert_eq!(giant.get_stat_value(StatType::Toughness), 3);
+    }
+
+    #[tokio::test]
+    async fn test_stack_hides_the_name_of_a_face_down_source() {
+        let (mut game, _, giant) = manifest_giant().await;
+        game.add_to_stack(Arc::new(CardActionWrapper {
+            action: Arc::new(DrawCardCardAction::one(CardActionTarget::SelfOwner)),
+            card: giant.clone(),
+            target: None,
+        }));
+
+        let view = game.stack_view().await;
+        assert_eq!(view[0].card_id, Some(giant.lock().await.id.clone()));
+        assert_eq!(view[0].name, "");
+    }
+
+    #[tokio::test]
+    async fn test_play_card_emits_a_span() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        // Keeps the name and fields of every span opened while it's the default.
+        #[derive(Clone, Default)]
+        struct RecordedSpans(Arc<std::sync::Mutex<Vec<(String, String)>>>);
+
+        impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for RecordedSpans {
+            fn on_new_span(
+                &self,
+                attrs: &tracing::span::Attributes<'_>,
+                _: &tracing::span::Id,
+                _: tracing_subscriber::layer::Context<'_, S>,
+            ) {
+                let name = attrs.metadata().name().to_string();
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push((name, format!("{:?}", attrs.values())));
+            }
+        }
+
+        let spans = RecordedSpans::default();
+        let _guard =
+            tracing::subscriber::set_default(tracing_subscriber::registry().with(spans.clone()));
+
+        let mut game = Game::new();
+        let player = game
+            .add_player(Player::new(
+                "caster",
+                20,
+                vec![create_creature("Bear", 2, 2)],
+            ))
+            .await;
+        game.start_turn(0).await;
+        player.lock().await.draw_card();
+        game.play_card(&player, 0, None).await.unwrap();
+        let phase = format!("{:?}", game.current_phase());
+
+        let spans = spans.0.lock().unwrap();
+        let (_, fields) = spans
+            .iter()
+            .find(|(name, _)| name == "play_card")
+            .expect("play_card should open a span");
+        assert!(fields.contains("caster"));
+        assert!(fields.contains(&phase));
+    }
+}
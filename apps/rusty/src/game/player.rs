@@ -10,9 +10,11 @@ use std::{
 
 use axum::response::sse::KeepAlive;
 use futures::future::join_all;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
-use textwrap::fill;
+use specta::Type;
 use tokio::{sync::Mutex, time::sleep};
+use tracing::debug;
 
 use crate::{
     error::{AppError, AppResult},
@@ -22,16 +24,18 @@ use crate::{
 use super::{
     action::{
         generate_mana::GenerateManaAction, Action, ActionTriggerType, Attachable,
-        CardActionTrigger, CardActionWrapper, CombatAction, DrawCardAction, PlayCardAction,
-        PlayerAction, PlayerActionTarget, PlayerActionTrigger, PlayerActionWrapper,
-        ResetManaPoolAction, TriggerTarget, UntapAllAction,
+        CardActionTrigger, CardActionWrapper, CleanupSpellsAction, CombatAction, DrawStepAction,
+        EndOfCombatAction, MonarchDrawAction, PlayCardAction, PlayerAction, PlayerActionTarget,
+        PlayerActionTrigger, PlayerActionWrapper, ResetManaPoolAction, SuspendUpkeepAction,
+        TriggerTarget, UntapAllAction,
     },
-    card::{Card, CardPhase, CreatureType},
+    card::{Card, CardPhase, CardView, CreatureType},
     decks::Deck,
     effects::{Effect, EffectID, EffectManager, EffectTarget},
     mana::ManaPool,
     stat::{Stat, StatManager, StatType, Stats},
-    turn::{Turn, TurnPhase},
+    turn::{Turn, TurnPhase, PHASE_ORDER},
+    zone::Zone,
     Game,
 };
 
@@ -55,6 +59,13 @@ impl Hash for PlayerKey {
     }
 }
 
+// Who can see the top of a player's library while it's revealed.
+#[derive(Type, Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub enum TopCardReveal {
+    Owner,
+    Everyone,
+}
+
 #[derive(Deserialize, Serialize, Default)]
 pub struct Player {
     pub name: String,
@@ -79,9 +90,47 @@ pub struct Player {
     #[serde(skip_serializing, skip_deserializing)]
     pub health_at_start_of_round: i8,
     #[serde(skip_serializing, skip_deserializing)]
+    pub life_gained_this_turn: i8,
+    #[serde(skip_serializing, skip_deserializing)]
+    pub lands_played_this_turn: u8,
+    #[serde(skip_serializing, skip_deserializing)]
+    pub skip_next_draw: bool,
+    // Passes priority on everything until their own next turn starts
+    #[serde(skip_serializing, skip_deserializing)]
+    pub passing_until_my_turn: bool,
+    // The next combat damage dealt to this player this turn is prevented
+    #[serde(skip_serializing, skip_deserializing)]
+    pub prevent_next_combat_damage: bool,
+    #[serde(skip_serializing, skip_deserializing)]
     pub spells: Vec<Arc<Mutex<Card>>>,
     #[serde(skip_serializing, skip_deserializing)]
     pub triggers_played_this_turn: HashSet<String>,
+    #[serde(skip_serializing, skip_deserializing)]
+    pub mulligans_taken: u8,
+    #[serde(skip_serializing, skip_deserializing)]
+    pub kept_hand: bool,
+    // Set while an effect has them playing with the top of their library revealed
+    #[serde(skip_serializing, skip_deserializing)]
+    pub reveal_top_card: Option<TopCardReveal>,
+}
+
+pub const OPENING_HAND_SIZE: usize = 6;
+
+// What `render` draws, as data for a frontend to lay out itself.
+#[derive(Type, Debug, Serialize, Clone)]
+pub struct PlayerView {
+    pub name: String,
+    pub is_alive: bool,
+    pub stats: Vec<StatView>,
+    pub mana_pool: ManaPool,
+    pub hand: Vec<CardView>,
+    pub cards_in_play: Vec<CardView>,
+}
+
+#[derive(Type, Debug, Serialize, Clone, PartialEq)]
+pub struct StatView {
+    pub stat_type: StatType,
+    pub value: i8,
 }
 
 impl fmt::Display for Player {
@@ -92,19 +141,51 @@ impl fmt::Display for Player {
 }
 
 impl Player {
-    pub fn reset_spells(&mut self) {
-        self.spells = vec![];
-        println!("reset spells");
+    // The pile backing each zone. The graveyard is the destroyed pile.
+    pub fn zone_mut(&mut self, zone: Zone) -> &mut Vec<Arc<Mutex<Card>>> {
+        match zone {
+            Zone::Library => &mut self.deck.draw_pile,
+            Zone::Hand => &mut self.cards_in_hand,
+            Zone::Stack => &mut self.spells,
+            Zone::Battlefield => &mut self.cards_in_play,
+            Zone::Graveyard => &mut self.deck.destroyed_pile,
+            Zone::Exile => &mut self.deck.exiled,
+        }
+    }
+
+    pub fn zone_contains(&mut self, zone: Zone, card: &Arc<Mutex<Card>>) -> bool {
+        self.zone_mut(zone).iter().any(|c| Arc::ptr_eq(c, card))
+    }
+
+    // Moves a card between two of this player's zones, without any triggers.
+    // Game::move_card is the one to use when the rules care about the move.
+    pub fn move_card(&mut self, card: &Arc<Mutex<Card>>, from: Zone, to: Zone) -> bool {
+        let pile = self.zone_mut(from);
+        let Some(position) = pile.iter().position(|c| Arc::ptr_eq(c, card)) else {
+            return false;
+        };
+        let card = pile.remove(position);
+        self.zone_mut(to).push(card);
+        true
     }
 
     pub fn new(name: &str, health: i8, deck: Vec<Card>) -> Self {
         let mut player = Self {
             triggers_played_this_turn: HashSet::new(),
             name: name.to_string(),
-            stat_manager: StatManager::new(vec![Stat::new(StatType::Health, health)]),
+            stat_manager: StatManager::new(vec![
+                Stat::new(StatType::Health, health),
+                Stat::new(StatType::LandsAllowed, 1),
+                Stat::new(StatType::DrawsPerTurn, 1),
+            ]),
             is_alive: true,
             cards_in_hand: vec![],
             health_at_start_of_round: health.clone(),
+            life_gained_this_turn: 0,
+            lands_played_this_turn: 0,
+            skip_next_draw: false,
+            passing_until_my_turn: false,
+            prevent_next_combat_damage: false,
             cards_in_play: vec![],
             game: None,
             deck: Deck::new(deck),
@@ -115,30 +196,16 @@ impl Player {
                     Arc::new(UntapAllAction {}),
                 ),
                 PlayerActionTrigger::new(
-                    ActionTriggerType::PhaseStarted(
-                        vec![
-                            TurnPhase::Untap,
-                            TurnPhase::Upkeep,
-                            TurnPhase::Draw,
-                            TurnPhase::Main,
-                            TurnPhase::BeginningOfCombat,
-                            TurnPhase::DeclareAttackers,
-                            TurnPhase::DeclareBlockers,
-                            TurnPhase::CombatDamage,
-                            TurnPhase::EndOfCombat,
-                            TurnPhase::Main2,
-                            TurnPhase::End,
-                            TurnPhase::Cleanup,
-                        ],
-                        TriggerTarget::Owner,
-                    ),
+                    ActionTriggerType::PhaseStarted(PHASE_ORDER.to_vec(), TriggerTarget::Owner),
                     Arc::new(ResetManaPoolAction {}),
                 ),
+                PlayerActionTrigger::new(
+                    ActionTriggerType::PhaseStarted(vec![TurnPhase::Upkeep], TriggerTarget::Owner),
+                    Arc::new(SuspendUpkeepAction {}),
+                ),
                 PlayerActionTrigger::new(
                     ActionTriggerType::PhaseStarted(vec![TurnPhase::Draw], TriggerTarget::Owner),
-                    Arc::new(DrawCardAction {
-                        target: PlayerActionTarget::Owner,
-                    }),
+                    Arc::new(DrawStepAction {}),
                 ),
                 PlayerActionTrigger::new(
                     ActionTriggerType::PhaseStarted(
@@ -147,10 +214,28 @@ impl Player {
                     ),
                     Arc::new(CombatAction {}),
                 ),
+                PlayerActionTrigger::new(
+                    ActionTriggerType::PhaseStarted(
+                        vec![TurnPhase::EndOfCombat],
+                        TriggerTarget::Owner,
+                    ),
+                    Arc::new(EndOfCombatAction {}),
+                ),
+                PlayerActionTrigger::new(
+                    ActionTriggerType::PhaseStarted(vec![TurnPhase::End], TriggerTarget::Owner),
+                    Arc::new(MonarchDrawAction {}),
+                ),
+                PlayerActionTrigger::new(
+                    ActionTriggerType::PhaseStarted(vec![TurnPhase::Cleanup], TriggerTarget::Any),
+                    Arc::new(CleanupSpellsAction {}),
+                ),
             ],
             rendered_output: "".to_string(),
             mana_pool: ManaPool::new(),
             effect_ids: vec![],
+            mulligans_taken: 0,
+            kept_hand: false,
+            reveal_top_card: None,
         };
 
         player
@@ -158,14 +243,69 @@ impl Player {
 
     pub fn set_deck(&mut self, card: Vec<Card>) {}
 
-    pub async fn return_card_to_hand(&mut self, card_arc: &Arc<Mutex<Card>>) {
-        self.cards_in_play.retain(|c| !Arc::ptr_eq(c, card_arc));
-        self.cards_in_hand.push(Arc::clone(card_arc));
+    // London mulligan: the hand goes back into the deck for a fresh one, and
+    // every mulligan is one more card to bottom once the hand is kept.
+    pub fn mulligan<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Result<(), String> {
+        if self.kept_hand {
+            return Err("You've already kept your hand".to_string());
+        }
+        // Past this there'd be nothing left to keep
+        if self.mulligans_taken as usize >= OPENING_HAND_SIZE {
+            return Err("You can't mulligan any further".to_string());
+        }
+
+        for card in std::mem::take(&mut self.cards_in_hand) {
+            self.deck.in_game.retain(|c| !Arc::ptr_eq(c, &card));
+            self.deck.draw_pile.push(card);
+        }
+        self.deck.shuffle(rng);
+        for _ in 0..OPENING_HAND_SIZE {
+            self.draw_card();
+        }
+        self.mulligans_taken += 1;
+
+        Ok(())
     }
 
-    pub async fn remove_card_from_play(&mut self, card_arc: Arc<Mutex<Card>>) {
-        self.cards_in_play.retain(|c| !Arc::ptr_eq(c, &card_arc));
-        self.deck.destroy(card_arc);
+    pub fn cards_to_bottom(&self) -> usize {
+        if self.kept_hand {
+            0
+        } else {
+            self.mulligans_taken as usize
+        }
+    }
+
+    // Keeps the hand, putting the cards at `hand_indexes` on the bottom of the
+    // deck. Exactly one card has to go per mulligan taken.
+    pub fn keep_hand(&mut self, mut hand_indexes: Vec<usize>) -> Result<(), String> {
+        if self.kept_hand {
+            return Err("You've already kept your hand".to_string());
+        }
+
+        hand_indexes.sort_unstable();
+        hand_indexes.dedup();
+        if hand_indexes.len() != self.cards_to_bottom() {
+            return Err(format!(
+                "You need to put {} cards on the bottom",
+                self.cards_to_bottom()
+            ));
+        }
+        if hand_indexes
+            .iter()
+            .any(|index| *index >= self.cards_in_hand.len())
+        {
+            return Err("No such card in hand".to_string());
+        }
+
+        for index in hand_indexes.into_iter().rev() {
+            let card = self.cards_in_hand.remove(index);
+            self.deck.in_game.retain(|c| !Arc::ptr_eq(c, &card));
+            // Cards are drawn off the end, so the bottom is the front
+            self.deck.draw_pile.insert(0, card);
+        }
+        self.kept_hand = true;
+
+        Ok(())
     }
 
     // pub async fn
@@ -271,6 +411,7 @@ impl Player {
     //     }
     // }
 
+    #[cfg(feature = "cli")]
     pub async fn render_cards(
         &self,
         cards: &Vec<Arc<Mutex<Card>>>,
@@ -312,7 +453,42 @@ impl Player {
         lines
     }
 
+    pub async fn view(&self) -> PlayerView {
+        let mut stat_types: Vec<StatType> = vec![];
+        for stat in self.stat_manager.stats.values() {
+            if !stat_types.contains(&stat.stat_type) {
+                stat_types.push(stat.stat_type);
+            }
+        }
+        stat_types.sort_by_key(|stat_type| stat_type.to_string());
+
+        let mut hand = vec![];
+        for card in &self.cards_in_hand {
+            hand.push(card.lock().await.view());
+        }
+        let mut cards_in_play = vec![];
+        for card in &self.cards_in_play {
+            cards_in_play.push(card.lock().await.view());
+        }
+
+        PlayerView {
+            name: self.name.clone(),
+            is_alive: self.is_alive,
+            stats: stat_types
+                .into_iter()
+                .map(|stat_type| StatView {
+                    stat_type,
+                    value: self.get_stat_value(stat_type),
+                })
+                .collect(),
+            mana_pool: self.mana_pool.clone(),
+            hand,
+            cards_in_play,
+        }
+    }
+
     // Renders player information (e.g., stats, name, status) for display
+    #[cfg(feature = "cli")]
     fn render_player_info(&self, height: usize, width: usize) -> Vec<String> {
         let mut lines = Vec::new();
 
@@ -357,36 +533,25 @@ impl Player {
     }
 
     pub async fn advance_card_phases(&mut self) {
-        println!("advancing card phases for {}", self.name);
+        debug!("advancing card phases for {}", self.name);
         // Collect indices and card arcs to avoid holding locks across awaits
         let card_arcs: Vec<Arc<Mutex<Card>>> = self.cards_in_play.clone();
 
         for card_arc in card_arcs {
             // Advance the card's phase
             let mut card = card_arc.lock().await;
-
-            match &mut card.current_phase {
-                CardPhase::Charging(turns_remaining) => {
-                    if *turns_remaining > 0 {
-                        *turns_remaining -= 1;
-                        if *turns_remaining == 0 {
-                            card.current_phase = CardPhase::Ready;
-                            println!("Card '{}' is now Ready.", card.name);
-                        }
-                    }
-                }
-                // Handle other phases if necessary
-                _ => {}
+            if card.count_down() {
+                debug!("Card '{}' is now Ready.", card.name);
             }
         }
     }
 
     pub async fn priority_turn_start(&self) {
-        println!("{}'s priority turn starts.", self.name);
+        debug!("{}'s priority turn starts.", self.name);
     }
 
     pub async fn priority_turn_end(&self) {
-        println!("{}'s priority turn ends.", self.name);
+        debug!("{}'s priority turn ends.", self.name);
     }
 
     // pub async fn choose_action(&self, game: &Game) -> Option<Arc<dyn Action + Send + Sync>> {
@@ -489,7 +654,7 @@ impl Player {
             }
             actions
         };
-        println!("{:?}", actions);
+        debug!("{:?}", actions);
 
         Ok(actions)
     }
@@ -503,6 +668,9 @@ impl Player {
     ) -> Result<Vec<Arc<dyn Action + Send + Sync>>, String> {
         let actions = {
             let card = Arc::clone(&player.lock().await.cards_in_play[in_play_index]);
+            if card.lock().await.phased_out {
+                return Err(format!("{} is phased out", card.lock().await.name));
+            }
             let phase = game.lock().await.current_turn.as_ref().unwrap().phase;
             // card_l.action_target = target.clone();
 
@@ -513,14 +681,14 @@ impl Player {
                 trigger_id,
                 game.clone(),
             )
-            .await;
+            .await?;
 
             if requires_tap {
                 card.lock().await.tap()?;
             }
             actions
         };
-        println!("{:?}", actions);
+        debug!("{:?}", actions);
 
         Ok(actions)
     }
@@ -624,29 +792,44 @@ impl Player {
             for card in &self.cards_in_play {
                 if let Ok(card) = card.try_lock() {
                     if !card.tapped {
-                        for trigger in &card.triggers {
-                            if let Some(generate_mana_action) =
-                                trigger.action.as_any().downcast_ref::<GenerateManaAction>()
-                            {
-                                available_lands.push((
-                                    card.card_type.clone(),
-                                    generate_mana_action.mana_to_add.clone(),
-                                ));
-                            }
+                        let options: Vec<Vec<ManaType>> = card
+                            .triggers
+                            .iter()
+                            .filter_map(|trigger| {
+                                trigger
+                                    .action
+                                    .as_any()
+                                    .downcast_ref::<GenerateManaAction>()
+                                    .map(|action| action.mana_to_add.clone())
+                            })
+                            .collect();
+
+                        // A land with several mana abilities (e.g. a dual land) only taps for one of them
+                        match options.len() {
+                            0 => {}
+                            1 => available_lands.push((false, options[0].clone())),
+                            _ => available_lands.push((true, options.concat())),
                         }
                     }
                 } else {
-                    println!("hmm card locked: {:?}", card);
+                    debug!("hmm card locked: {:?}", card);
                 }
             }
         }
 
+        // Spend single-color lands first so lands with a choice are kept for what they cover
+        available_lands.sort_by_key(|(is_choice, _)| *is_choice);
+
         // Check if the available lands can satisfy the required mana
         for mana_type in &required_mana.to_vec() {
             let mut found = false;
-            for (index, (_, available_mana)) in available_lands.iter_mut().enumerate() {
+            for (is_choice, available_mana) in available_lands.iter_mut() {
                 if let Some(pos) = available_mana.iter().position(|m| m == mana_type) {
-                    available_mana.remove(pos);
+                    if *is_choice {
+                        available_mana.clear();
+                    } else {
+                        available_mana.remove(pos);
+                    }
                     found = true;
                     break;
                 }
@@ -659,22 +842,41 @@ impl Player {
         true
     }
 
+    // Casts the card at that index of the hand, or of the graveyard for a card
+    // with flashback, which is paid for with its flashback cost instead.
     pub async fn play_card(
         player_arc: &Arc<Mutex<Player>>,
+        from: Zone,
         index: usize,
         target: Option<EffectTarget>,
         current_turn: Turn,
     ) -> Result<(Arc<dyn Action + Send + Sync>, Arc<Mutex<Card>>), String> {
+        let card = player_arc
+            .lock()
+            .await
+            .zone_mut(from)
+            .get(index)
+            .ok_or("Invalid card index")?
+            .clone();
+        let cost = match from {
+            Zone::Hand => {
+                // Worked out before locking the player, since it can count their permanents
+                Card::cost_to_cast(&card).await
+            }
+            Zone::Graveyard => {
+                let card = card.lock().await;
+                card.flashback
+                    .clone()
+                    .ok_or_else(|| format!("{} doesn't have flashback", card.name))?
+            }
+            _ => return Err(format!("Cards can't be cast from the {:?}", from)),
+        };
+
         // Lock the player to mutate state
         let card_arc = {
             let mut player = player_arc.lock().await;
-            let card = player
-                .cards_in_hand
-                .get(index)
-                .ok_or("Invalid card index")?
-                .clone();
 
-            let can_pay_to_cast = player.pool_has_cost_for_card(&card).await;
+            let can_pay_to_cast = player.has_required_mana(&cost).await;
             let can_play = player
                 .can_play(&card, Arc::ptr_eq(&current_turn.current_player, player_arc))
                 .await;
@@ -696,17 +898,17 @@ impl Player {
                 ));
             }
 
-            // Remove the card from hand
-            player.cards_in_hand.remove(index);
-
-            player.spells.push(card.clone());
-            println!("Added to spells list");
+            player.move_card(&card, from, Zone::Stack);
+            debug!("Added to spells list");
 
             // Pay mana
-            player.pay_mana_for_card(&card).await;
+            player.pay_mana(&cost).await;
 
             card
         }; // Lock is released here
+        if from == Zone::Graveyard {
+            card_arc.lock().await.flashed_back = true;
+        }
 
         // Create the action
         let action = Arc::new(PlayCardAction::new(
@@ -719,7 +921,7 @@ impl Player {
     }
 
     pub fn draw_card(&mut self) -> Option<Arc<Mutex<Card>>> {
-        println!("{} draws a card.", self.name);
+        debug!("{} draws a card.", self.name);
         if let Some(card) = self.deck.draw() {
             self.cards_in_hand.push(card.clone());
             Some(card)
@@ -728,22 +930,6 @@ impl Player {
         }
     }
 
-    pub async fn destroy_card_in_play(
-        &mut self,
-        card_index: usize,
-        turn: &Turn,
-        // game: &mut Game,
-    ) -> Vec<Arc<dyn Action + Send + Sync>> {
-        let card = &self.cards_in_play.remove(card_index);
-        self.deck.destroy(Arc::clone(card));
-
-        // let card = card.lock().await;
-        let actions =
-            Card::collect_phase_based_actions(card, turn, ActionTriggerType::CardDestroyed).await;
-
-        actions
-    }
-
     // pub async fn collect_available_actions(
     //     &self,
     //     turn: Turn,
@@ -791,6 +977,7 @@ impl Player {
         actions_for_phase
     }
 
+    #[cfg(feature = "cli")]
     pub async fn render(
         &self,
         card_width: usize,
@@ -834,6 +1021,7 @@ impl Player {
         output
     }
 
+    #[cfg(feature = "cli")]
     pub async fn render_card_list(
         &self,
         cards: &Vec<Arc<Mutex<Card>>>,
@@ -876,6 +1064,7 @@ impl Player {
     }
 
     // Call this method whenever the player's state changes
+    #[cfg(feature = "cli")]
     pub async fn on_state_change(&mut self) {
         let card_width = 30;
         let card_height = 10;
@@ -929,27 +1118,30 @@ impl Player {
         remaining_mana >= required_mana.colorless
     }
 
+    // Cards drawn in the draw step, one plus whatever effects add through DrawsPerTurn
+    pub fn draws_per_turn(&self) -> u8 {
+        self.stat_manager
+            .get_stat_value(StatType::DrawsPerTurn)
+            .max(0) as u8
+    }
+
+    // One land drop a turn, plus whatever effects grant through LandsAllowed
+    pub fn lands_allowed(&self) -> u8 {
+        self.stat_manager
+            .get_stat_value(StatType::LandsAllowed)
+            .max(0) as u8
+    }
+
     pub async fn can_play(&self, card: &Arc<Mutex<Card>>, is_my_turn: bool) -> bool {
         let card = card.lock().await;
 
-        if let CardType::BasicLand(mana) = card.card_type {
-            self.mana_pool.played_card == false && is_my_turn
+        if !card.card_type.is_spell() {
+            self.lands_played_this_turn < self.lands_allowed() && is_my_turn
         } else {
             true
         }
     }
 
-    pub async fn pool_has_cost_for_card(&self, card: &Arc<Mutex<Card>>) -> bool {
-        let card = card.lock().await;
-
-        self.has_required_mana(&card.cost).await
-    }
-
-    pub async fn pay_mana_for_card(&mut self, card: &Arc<Mutex<Card>>) {
-        let cost = { card.lock().await.cost.clone() };
-        self.pay_mana(&cost).await;
-    }
-
     pub async fn pay_mana(&mut self, cost: &Vec<ManaType>) {
         // Counts of required mana
         let mut white_required = 0;
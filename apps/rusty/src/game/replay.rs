@@ -0,0 +1,563 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use tokio::sync::Mutex;
+
+use super::{
+    card::Card,
+    decks::card_named,
+    effects::EffectTarget,
+    player::Player,
+    stat::{StatType, Stats},
+    Game,
+};
+
+// Everything needed to play a finished game back: the seed, the decks as they
+// were when it started and every input that moved it along, in order.
+#[derive(Type, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Replay {
+    pub seed: u64,
+    pub auto_pass_priority: bool,
+    pub players: Vec<ReplayPlayer>,
+    pub inputs: Vec<ReplayInput>,
+}
+
+#[derive(Type, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ReplayPlayer {
+    pub name: String,
+    pub health: i8,
+    // Draw pile from the bottom up, so the last card is drawn first
+    pub deck: Vec<ReplayCard>,
+}
+
+#[derive(Type, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ReplayCard {
+    pub id: String,
+    pub name: String,
+}
+
+// Players are referred to by seat, which doesn't shift when someone is
+// eliminated, and cards by id.
+#[derive(Type, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum ReplayTarget {
+    Player(usize),
+    Card(String),
+    Cards(Vec<String>),
+}
+
+#[derive(Type, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum ReplayInput {
+    Mulligan {
+        seat: usize,
+    },
+    KeepHand {
+        seat: usize,
+        hand_indexes: Vec<usize>,
+    },
+    PlayCard {
+        seat: usize,
+        index: usize,
+        target: Option<ReplayTarget>,
+    },
+    FlashbackCard {
+        seat: usize,
+        index: usize,
+        target: Option<ReplayTarget>,
+    },
+    SuspendCard {
+        seat: usize,
+        index: usize,
+    },
+    TurnFaceUp {
+        seat: usize,
+        in_play_index: usize,
+    },
+    // Trigger ids are made up per game, so activations go by position
+    ActivateCard {
+        seat: usize,
+        in_play_index: usize,
+        trigger_index: usize,
+        target: Option<ReplayTarget>,
+    },
+    AttachCard {
+        seat: usize,
+        in_play_index: usize,
+        target: Option<ReplayTarget>,
+    },
+    EquipCard {
+        seat: usize,
+        in_play_index: usize,
+        target: Option<ReplayTarget>,
+    },
+    // Abilities go by the order they were offered in, for the same reason
+    RespondAbility {
+        seat: usize,
+        ability: usize,
+        response: bool,
+        target: Option<ReplayTarget>,
+    },
+    ResolveStack,
+    AdvanceTurn,
+}
+
+impl Replay {
+    // Snapshots the seats of a game that's about to start.
+    pub(crate) async fn new(game: &Game, seed: u64) -> Self {
+        let mut players = vec![];
+        for seat in &game.seats {
+            let seat = seat.lock().await;
+            let mut deck = vec![];
+            for card in &seat.deck.draw_pile {
+                let card = card.lock().await;
+                deck.push(ReplayCard {
+                    id: card.id.clone(),
+                    name: card.name.clone(),
+                });
+            }
+            players.push(ReplayPlayer {
+                name: seat.name.clone(),
+                health: seat.stat_manager.get_stat_value(StatType::Health),
+                deck,
+            });
+        }
+
+        Self {
+            seed,
+            auto_pass_priority: game.auto_pass_priority,
+            players,
+            inputs: vec![],
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self).map_err(|err| err.to_string())
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        serde_json::from_str(json).map_err(|err| format!("Not a replay: {}", err))
+    }
+
+    // Rebuilds the game from scratch and feeds it every recorded input.
+    pub async fn play(&self) -> Result<Game, String> {
+        let mut game = Game::new();
+        game.auto_pass_priority = self.auto_pass_priority;
+        for player in &self.players {
+            let mut deck = vec![];
+            for replay_card in &player.deck {
+                let mut card = card_named(&replay_card.name)
+                    .ok_or_else(|| format!("No card named {}", replay_card.name))?;
+                card.id = replay_card.id.clone();
+                deck.push(card);
+            }
+            game.add_player(Player::new(&player.name, player.health, deck))
+                .await;
+        }
+
+        game.start_with_seed(self.seed).await;
+        for (step, input) in self.inputs.iter().enumerate() {
+            apply_input(&mut game, input)
+                .await
+                .map_err(|err| format!("Replay diverged at input {}: {}", step, err))?;
+        }
+
+        Ok(game)
+    }
+}
+
+async fn apply_input(game: &mut Game, input: &ReplayInput) -> Result<(), String> {
+    match input {
+        ReplayInput::Mulligan { seat } => {
+            let player = seat_player(game, *seat)?;
+            game.mulligan(&player).await
+        }
+        ReplayInput::KeepHand { seat, hand_indexes } => {
+            let player = seat_player(game, *seat)?;
+            game.keep_hand(&player, hand_indexes.clone()).await
+        }
+        ReplayInput::PlayCard {
+            seat,
+            index,
+            target,
+        } => {
+            let player = seat_player(game, *seat)?;
+            let target = effect_target(game, target).await?;
+            game.play_card(&player, *index, target).await.map(|_| ())
+        }
+        ReplayInput::FlashbackCard {
+            seat,
+            index,
+            target,
+        } => {
+            let player = seat_player(game, *seat)?;
+            let target = effect_target(game, target).await?;
+            game.flashback_card(&player, *index, target)
+                .await
+                .map(|_| ())
+        }
+        ReplayInput::SuspendCard { seat, index } => {
+            let player = seat_player(game, *seat)?;
+            game.suspend_card(&player, *index).await
+        }
+        ReplayInput::TurnFaceUp {
+            seat,
+            in_play_index,
+        } => {
+            let player = seat_player(game, *seat)?;
+            game.turn_face_up(&player, *in_play_index).await
+        }
+        ReplayInput::ActivateCard {
+            seat,
+            in_play_index,
+            trigger_index,
+            target,
+        } => {
+            let player = seat_player(game, *seat)?;
+            let target = effect_target(game, target).await?;
+            let trigger_id = {
+                let player = player.lock().await;
+                let card = player
+                    .cards_in_play
+                    .get(*in_play_index)
+                    .ok_or_else(|| "No such card in play".to_string())?;
+                let card = card.lock().await;
+                card.triggers
+                    .get(*trigger_index)
+                    .map(|trigger| trigger.id.clone())
+                    .ok_or_else(|| format!("{} has no such ability", card.name))?
+            };
+            game.activate_card_action(&player, *in_play_index, target, trigger_id)
+                .await
+        }
+        ReplayInput::AttachCard {
+            seat,
+            in_play_index,
+            target,
+        } => {
+            let player = seat_player(game, *seat)?;
+            let target = effect_target(game, target).await?;
+            game.attach_card_action(&player, *in_play_index, target)
+                .await
+        }
+        ReplayInput::EquipCard {
+            seat,
+            in_play_index,
+            target,
+        } => {
+            let player = seat_player(game, *seat)?;
+            let target = effect_target(game, target).await?;
+            game.equip_card_action(&player, *in_play_index, target)
+                .await
+        }
+        ReplayInput::RespondAbility {
+            seat,
+            ability,
+            response,
+            target,
+        } => {
+            let player = seat_player(game, *seat)?;
+            let target = effect_target(game, target).await?;
+            let ability_id = game
+                .offered_ability(*ability)
+                .ok_or_else(|| format!("Ability {} isn't waiting on an answer", ability))?;
+            game.answer_ability(&player, ability_id, *response, target)
+                .await
+        }
+        ReplayInput::ResolveStack => {
+            game.record(ReplayInput::ResolveStack);
+            game.resolve_stack().await;
+            Ok(())
+        }
+        ReplayInput::AdvanceTurn => {
+            game.advance_turn().await;
+            Ok(())
+        }
+    }
+}
+
+fn seat_player(game: &Game, seat: usize) -> Result<Arc<Mutex<Player>>, String> {
+    game.seats
+        .get(seat)
+        .cloned()
+        .ok_or_else(|| format!("No player in seat {}", seat))
+}
+
+async fn effect_target(
+    game: &Game,
+    target: &Option<ReplayTarget>,
+) -> Result<Option<EffectTarget>, String> {
+    Ok(match target {
+        None => None,
+        Some(ReplayTarget::Player(seat)) => Some(EffectTarget::Player(seat_player(game, *seat)?)),
+        Some(ReplayTarget::Card(id)) => Some(EffectTarget::Card(
+            find_card(game, id)
+                .await
+                .ok_or_else(|| format!("No card with id {}", id))?,
+        )),
+        Some(ReplayTarget::Cards(ids)) => {
+            let mut cards = vec![];
+            for id in ids {
+                cards.push(
+                    find_card(game, id)
+                        .await
+                        .ok_or_else(|| format!("No card with id {}", id))?,
+                );
+            }
+            Some(EffectTarget::Cards(cards))
+        }
+    })
+}
+
+// Looks through every zone of every seat for the card with this id.
+async fn find_card(game: &Game, id: &str) -> Option<Arc<Mutex<Card>>> {
+    for seat in &game.seats {
+        let seat = seat.lock().await;
+        let zones = [
+            &seat.cards_in_play,
+            &seat.cards_in_hand,
+            &seat.deck.in_game,
+            &seat.deck.draw_pile,
+            &seat.deck.destroyed_pile,
+            &seat.deck.exiled,
+        ];
+        for card in zones.into_iter().flatten() {
+            if card.lock().await.id == id {
+                return Some(Arc::clone(card));
+            }
+        }
+    }
+
+    None
+}
+
+// The inverse of effect_target, for recording what an input was aimed at.
+pub(crate) async fn replay_target(
+    game: &Game,
+    target: &Option<EffectTarget>,
+) -> Option<ReplayTarget> {
+    match target.as_ref()? {
+        EffectTarget::Player(player) => game
+            .seats
+            .iter()
+            .position(|seat| Arc::ptr_eq(seat, player))
+            .map(ReplayTarget::Player),
+        EffectTarget::Card(card) => Some(ReplayTarget::Card(card.lock().await.id.clone())),
+        EffectTarget::Cards(cards) => {
+            let mut ids = vec![];
+            for card in cards {
+                ids.push(card.lock().await.id.clone());
+            }
+            Some(ReplayTarget::Cards(ids))
+        }
+    }
+}
+
+mod test {
+    use std::sync::Arc;
+
+    use tokio::sync::Mutex;
+
+    use crate::game::{
+        action::{
+            search_library::{SearchDestination, SearchLibraryAction},
+            CardActionWrapper, CardRequiredTarget, CardTargetTeam, PlayerActionTarget,
+        },
+        card::CreatureType,
+        decks::{black::create_hydra, green::create_priest_of_titania, red::create_red_deck},
+        effects::EffectTarget,
+        player::Player,
+        replay::{apply_input, Replay, ReplayInput, ReplayTarget},
+        stat::{StatType, Stats},
+        Game,
+    };
+
+    // Everything a player could see at the end of the game, by card id.
+    async fn final_state(game: &Game) -> Vec<(i8, Vec<String>, Vec<(String, bool)>, usize)> {
+        let mut state = vec![];
+        for seat in &game.seats {
+            let seat = seat.lock().await;
+            let mut hand = vec![];
+            for card in &seat.cards_in_hand {
+                hand.push(card.lock().await.id.clone());
+            }
+            let mut in_play = vec![];
+            for card in &seat.cards_in_play {
+                let card = card.lock().await;
+                in_play.push((card.id.clone(), card.tapped));
+            }
+            state.push((
+                seat.stat_manager.get_stat_value(StatType::Health),
+                hand,
+                in_play,
+                seat.deck.draw_pile.len(),
+            ));
+        }
+        state
+    }
+
+    // Plays a land, taps it and burns the opponent whenever it can.
+    async fn take_actions(game_arc: &Arc<Mutex<Game>>) {
+        let (player, opponent) = {
+            let game = game_arc.lock().await;
+            let player = Arc::clone(&game.current_turn.as_ref().unwrap().current_player);
+            let opponent = game.player_for_target(&player, &PlayerActionTarget::Opponent);
+            (player, opponent.unwrap())
+        };
+
+        for name in ["Fire", "Burn"] {
+            let index = {
+                let player = player.lock().await;
+                let mut index = None;
+                for (i, card) in player.cards_in_hand.iter().enumerate() {
+                    if card.lock().await.name == name {
+                        index = Some(i);
+                        break;
+                    }
+                }
+                index
+            };
+            let Some(index) = index else { continue };
+
+            if name == "Burn" {
+                let land = {
+                    let player = player.lock().await;
+                    let mut land = None;
+                    for (i, card) in player.cards_in_play.iter().enumerate() {
+                        let card = card.lock().await;
+                        if !card.tapped {
+                            land = Some((i, card.triggers[0].id.clone()));
+                        }
+                    }
+                    land
+                };
+                let Some((in_play_index, trigger_id)) = land else {
+                    continue;
+                };
+                let mut game = game_arc.lock().await;
+                if game
+                    .activate_card_action(&player, in_play_index, None, trigger_id)
+                    .await
+                    .is_err()
+                {
+                    continue;
+                }
+            }
+
+            let played = game_arc
+                .lock()
+                .await
+                .play_card(&player, index, Some(EffectTarget::Player(opponent.clone())))
+                .await;
+            if let Ok(card) = played {
+                Game::process_action_queue(Arc::clone(game_arc), card).await;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replay_reproduces_final_state() {
+        let mut game = Game::new().with_seed(11);
+        game.auto_pass_priority = true;
+        for name in ["first", "second"] {
+            let player = game
+                .add_player(Player::new(name, 20, create_red_deck()))
+                .await;
+            player.lock().await.deck.shuffle(&mut game.rng);
+        }
+        game.start().await;
+        let second = Arc::clone(&game.seats[1]);
+        game.mulligan(&second).await.unwrap();
+        game.keep_hand(&second, vec![0]).await.unwrap();
+        let first = Arc::clone(&game.seats[0]);
+        game.keep_hand(&first, vec![]).await.unwrap();
+
+        let game_arc = Arc::new(Mutex::new(game));
+        for _ in 0..24 {
+            take_actions(&game_arc).await;
+            game_arc.lock().await.advance_turn().await;
+        }
+
+        let game = game_arc.lock().await;
+        let replay = game.replay.clone().unwrap();
+        assert!(replay.inputs.iter().any(|input| matches!(
+            input,
+            ReplayInput::PlayCard {
+                target: Some(_),
+                ..
+            }
+        )));
+
+        let loaded = Replay::from_json(&replay.to_json().unwrap()).unwrap();
+        assert_eq!(loaded, replay);
+        let replayed = loaded.play().await.unwrap();
+        let state = final_state(&game).await;
+        assert!(state.iter().any(|(health, ..)| *health < 20));
+        assert_eq!(final_state(&replayed).await, state);
+        assert_eq!(replayed.turn_number, game.turn_number);
+        // Playing it back records the same game over again
+        assert_eq!(replayed.replay, game.replay);
+    }
+
+    // Has the first seat search their library for an elf, which no replay
+    // input asks for, and returns the ability it offers.
+    async fn search_for_elf(game: &mut Game) -> String {
+        let source = game.seats[0].lock().await.cards_in_hand[0].clone();
+        game.add_to_stack(Arc::new(CardActionWrapper {
+            card: source,
+            action: Arc::new(SearchLibraryAction {
+                filter: CardRequiredTarget::CreatureOfType(
+                    CreatureType::Elf,
+                    CardTargetTeam::Owner,
+                ),
+                to_zone: SearchDestination::Hand,
+            }),
+            target: None,
+        }));
+        game.resolve_stack().await;
+        game.abilities.keys().next().unwrap().clone()
+    }
+
+    #[tokio::test]
+    async fn test_replay_answers_abilities_by_the_order_they_were_offered() {
+        let mut deck = vec![create_priest_of_titania()];
+        deck.extend((0..8).map(|_| create_hydra()));
+        let mut game = Game::new().with_seed(5);
+        let player = game.add_player(Player::new("searcher", 20, deck)).await;
+        game.start().await;
+        game.keep_hand(&player, vec![]).await.unwrap();
+
+        let ability_id = search_for_elf(&mut game).await;
+        let elf = game.ability_choice(&ability_id, 0).unwrap();
+        let game_arc = Arc::new(Mutex::new(game));
+        Game::respond_player_ability(
+            game_arc.clone(),
+            &player,
+            ability_id,
+            true,
+            Some(EffectTarget::Card(elf)),
+        )
+        .await
+        .unwrap();
+
+        let game = game_arc.lock().await;
+        let replay = game.replay.clone().unwrap();
+        let answer = replay.inputs.last().unwrap().clone();
+        assert!(matches!(
+            answer,
+            ReplayInput::RespondAbility {
+                seat: 0,
+                ability: 0,
+                response: true,
+                target: Some(ReplayTarget::Card(_)),
+            }
+        ));
+
+        let mut before = replay.clone();
+        before.inputs.pop();
+        let mut replayed = before.play().await.unwrap();
+        search_for_elf(&mut replayed).await;
+        apply_input(&mut replayed, &answer).await.unwrap();
+        assert_eq!(final_state(&replayed).await, final_state(&game).await);
+        assert_eq!(replayed.replay, game.replay);
+    }
+}
@@ -31,6 +31,19 @@ pub enum StatType {
     Trample,
     Lifelink,
     Flying,
+    // Can't be destroyed, whether by damage or by an effect
+    Indestructible,
+    // Copied once for each spell cast before it this turn
+    Storm,
+    // Nobody can cast spells or activate non-mana abilities while it's on the stack
+    SplitSecond,
+    // Mill this many to return it from the graveyard to hand instead of drawing
+    Dredge,
+    // Its defending player picks how an attacker's damage is split among blockers
+    Banding,
+    UntapLocked,
+    LandsAllowed,
+    DrawsPerTurn,
 }
 
 impl Stat {
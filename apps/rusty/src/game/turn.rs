@@ -22,6 +22,37 @@ pub enum TurnPhase {
     Cleanup,
 }
 
+// The order phases happen in each turn. Anything walking or listing every
+// phase goes through this so it can't drift from the enum.
+pub const PHASE_ORDER: [TurnPhase; 12] = [
+    TurnPhase::Untap,
+    TurnPhase::Upkeep,
+    TurnPhase::Draw,
+    TurnPhase::Main,
+    TurnPhase::BeginningOfCombat,
+    TurnPhase::DeclareAttackers,
+    TurnPhase::DeclareBlockers,
+    TurnPhase::CombatDamage,
+    TurnPhase::EndOfCombat,
+    TurnPhase::Main2,
+    TurnPhase::End,
+    TurnPhase::Cleanup,
+];
+
+impl TurnPhase {
+    // Where this phase falls in PHASE_ORDER
+    pub fn index(&self) -> usize {
+        PHASE_ORDER
+            .iter()
+            .position(|phase| phase == self)
+            .unwrap_or_default()
+    }
+
+    pub fn next(&self) -> TurnPhase {
+        PHASE_ORDER[(self.index() + 1) % PHASE_ORDER.len()]
+    }
+}
+
 #[derive(Type, Debug, Clone, Deserialize, Serialize)]
 pub struct Turn {
     #[serde(skip_serializing, skip_deserializing)]
@@ -46,19 +77,28 @@ impl Turn {
     }
 
     pub fn next_phase(&mut self) {
-        self.phase = match self.phase {
-            TurnPhase::Untap => TurnPhase::Upkeep,
-            TurnPhase::Upkeep => TurnPhase::Draw,
-            TurnPhase::Draw => TurnPhase::Main,
-            TurnPhase::Main => TurnPhase::BeginningOfCombat,
-            TurnPhase::BeginningOfCombat => TurnPhase::DeclareAttackers,
-            TurnPhase::DeclareAttackers => TurnPhase::DeclareBlockers,
-            TurnPhase::DeclareBlockers => TurnPhase::CombatDamage,
-            TurnPhase::CombatDamage => TurnPhase::EndOfCombat,
-            TurnPhase::EndOfCombat => TurnPhase::Main2,
-            TurnPhase::Main2 => TurnPhase::End,
-            TurnPhase::End => TurnPhase::Cleanup,
-            TurnPhase::Cleanup => TurnPhase::Untap,
-        };
+        self.phase = self.phase.next();
+    }
+}
+
+mod test {
+    use std::sync::Arc;
+
+    use tokio::sync::Mutex;
+
+    use crate::game::player::Player;
+
+    use super::{Turn, TurnPhase, PHASE_ORDER};
+
+    #[test]
+    fn test_next_phase_walks_canonical_order() {
+        let player = Arc::new(Mutex::new(Player::new("player", 20, vec![])));
+        let mut turn = Turn::new(player, 0, 0);
+
+        for phase in PHASE_ORDER {
+            assert_eq!(turn.phase, phase);
+            turn.next_phase();
+        }
+        assert_eq!(turn.phase, TurnPhase::Untap);
     }
 }
@@ -0,0 +1,21 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+// Everywhere a card can be. Library, hand, graveyard and exile belong to the
+// card's owner; the stack and the battlefield to whoever controls it.
+#[derive(Type, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Zone {
+    Library,
+    Hand,
+    Stack,
+    Battlefield,
+    Graveyard,
+    Exile,
+}
+
+impl Zone {
+    // Zones that follow the owner rather than the controller
+    pub fn is_owners(&self) -> bool {
+        !matches!(self, Zone::Stack | Zone::Battlefield)
+    }
+}
@@ -4,11 +4,12 @@ use sqlx::{Pool, Postgres};
 use rspc::{Router, RouterBuilder};
 use serde::{Deserialize, Serialize};
 use specta::Type;
+use tracing::warn;
 
 use crate::{
     error::{AppError, AppResult},
-    models::user::User,
-    services::jwt::JwtService,
+    models::{account::Account, user::User},
+    services::{jwt::JwtService, totp_cipher::TotpCipher},
     Ctx,
 };
 
@@ -31,6 +32,21 @@ impl AuthResponse {
     }
 }
 
+#[derive(Type, Serialize)]
+pub struct AccountResponse {
+    pub email: String,
+    pub has_totp: bool,
+}
+
+impl From<Account> for AccountResponse {
+    fn from(account: Account) -> Self {
+        AccountResponse {
+            email: account.get_email().clone(),
+            has_totp: account.has_totp(),
+        }
+    }
+}
+
 #[derive(Type, Deserialize)]
 pub struct LoginArgs {
     username: String,
@@ -44,7 +60,7 @@ impl AuthenticationController {
             if user.verify_password(&args.password) {
                 return AuthResponse::new(&ctx.pool, user).await;
             }
-            println!("invalid password");
+            warn!("invalid password");
         }
 
         Ok(AuthResponse {
@@ -74,6 +90,34 @@ impl AuthenticationController {
         })
     }
 
+    pub async fn create_account(ctx: Ctx, email: String) -> AppResult<AccountResponse> {
+        let user = ctx.required_user()?;
+        if Account::find_by_email(&*ctx.pool, &email).await.is_ok() {
+            return Err(AppError::BadRequest("Email is already in use".to_owned()));
+        }
+
+        Ok(Account::create(&*ctx.pool, &user.sub, &email).await?.into())
+    }
+
+    // Returns the otpauth url for the user's authenticator app. Enrolling
+    // again needs a `code` from the current secret.
+    pub async fn enroll_totp(ctx: Ctx, code: Option<String>) -> AppResult<String> {
+        let user = ctx.required_user()?;
+        let mut account = Account::find_by_user(&*ctx.pool, &user.sub).await?;
+
+        Ok(account
+            .enroll_totp(&*ctx.pool, &TotpCipher::from_env()?, code.as_deref())
+            .await?
+            .get_url())
+    }
+
+    pub async fn verify_totp(ctx: Ctx, code: String) -> AppResult<bool> {
+        let user = ctx.required_user()?;
+        let account = Account::find_by_user(&*ctx.pool, &user.sub).await?;
+
+        Ok(account.verify_code(&code, &TotpCipher::from_env()?))
+    }
+
     pub async fn me(ctx: Ctx) -> AppResult<String> {
         Ok("hi".to_string())
     }
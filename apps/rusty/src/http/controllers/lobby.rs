@@ -6,14 +6,16 @@ use serde::{Deserialize, Serialize};
 use specta::Type;
 use tokio::sync::{Mutex, MutexGuard};
 use tokio_stream::StreamExt;
+use tracing::debug;
 
 use crate::{
     error::{AppError, AppResult},
-    game::FrontendTarget,
+    game::{FrontendTarget, PaymentChoice},
     lobby::{
-        lobby::{DeckSelector, Lobby, LobbyChat, LobbyData},
+        lobby::{DeckSelector, Lobby, LobbyChat, LobbyData, LobbyListing, LobbySettings},
         manager::{LobbyCommand, LobbyManager},
     },
+    models::match_result::MatchResult,
     services::jwt::{Claims, JwtService},
     Ctx,
 };
@@ -48,11 +50,74 @@ pub struct SelectDeckArgs {
     pub deck: DeckSelector,
 }
 
+#[derive(Type, Serialize, Deserialize)]
+pub struct KickPlayerArgs {
+    pub code: String,
+    pub user_id: String,
+}
+
+#[derive(Type, Serialize, Deserialize)]
+pub struct PassUntilMyTurnArgs {
+    pub code: String,
+    // False takes it back
+    pub passing: bool,
+}
+
+#[derive(Type, Serialize, Deserialize)]
+pub struct KeepHandArgs {
+    pub code: String,
+    // Hand indexes to put on the bottom, one per mulligan taken
+    pub bottom: Vec<i32>,
+}
+
+#[derive(Type, Serialize, Deserialize)]
+pub struct SideboardArgs {
+    pub code: String,
+    // Card ids going from the main deck to the sideboard
+    pub remove: Vec<String>,
+    // Card ids coming in from the sideboard
+    pub add: Vec<String>,
+}
+
+#[derive(Type, Serialize, Deserialize)]
+pub struct UpdateSettingsArgs {
+    pub code: String,
+    pub settings: LobbySettings,
+}
+
 #[derive(Type, Serialize, Deserialize)]
 pub struct PlayCardArgs {
     pub code: String,
     pub in_hand_index: i32,
     pub target: Option<FrontendTarget>,
+    // Tap lands for whatever the floating mana doesn't cover
+    #[serde(default)]
+    pub auto_pay: bool,
+    // The lands to tap instead, when the player wants to choose
+    #[serde(default)]
+    pub payment: Option<Vec<PaymentChoice>>,
+}
+
+// Suspending is paid from the player's floating mana
+#[derive(Type, Serialize, Deserialize)]
+pub struct SuspendCardArgs {
+    pub code: String,
+    pub in_hand_index: i32,
+}
+
+// Turning a morph face up is paid from the player's floating mana
+#[derive(Type, Serialize, Deserialize)]
+pub struct TurnFaceUpArgs {
+    pub code: String,
+    pub in_play_index: i32,
+}
+
+// Flashback is paid from the player's floating mana
+#[derive(Type, Serialize, Deserialize)]
+pub struct FlashbackCardArgs {
+    pub code: String,
+    pub graveyard_index: i32,
+    pub target: Option<FrontendTarget>,
 }
 
 #[derive(Deserialize, Type)]
@@ -72,14 +137,13 @@ pub struct LobbyChatArgs {
 }
 
 fn personalize_lobby_data_for_player(command: &mut LobbyCommand, user_id: &str) {
-    if let LobbyCommand::Updated(lobby_data) = command {
-        // For each player in the game state
-        for (id, player_state) in &mut lobby_data.game_state.players {
-            if id != user_id {
-                // Hide private information
-                player_state.hand.clear();
-            }
+    match command {
+        LobbyCommand::Updated(lobby_data) => lobby_data.redact_for(user_id),
+        // Only the choosing player gets to see the revealed cards
+        LobbyCommand::MandatoryExecuteAbility(ability) if ability.player_id != user_id => {
+            ability.choices.clear();
         }
+        _ => {}
     }
 }
 
@@ -97,7 +161,7 @@ impl LobbyController {
             .await
             .map_err(|_| AppError::BadRequest("No such lobby".to_string()))?;
 
-        lobby.lock().await.select_deck(user, deck).await;
+        lobby.lock().await.select_deck(user, deck).await?;
 
         ctx.lobby_manager.notify_lobby(&code).await.ok();
 
@@ -134,16 +198,26 @@ impl LobbyController {
         Ok(data)
     }
 
-    pub(crate) async fn turn(ctx: Ctx, join_code: String) -> AppResult<()> {
+    pub async fn match_history(ctx: Ctx) -> AppResult<Vec<MatchResult>> {
         let user = ctx.required_user()?;
+
+        Ok(MatchResult::history_for(&*ctx.pool, &user.sub).await?)
+    }
+
+    pub(crate) async fn export_replay(
+        ctx: Ctx,
+        join_code: String,
+        game_number: u32,
+    ) -> AppResult<String> {
+        ctx.required_user()?;
         ctx.lobby_manager
-            .advance_turn(&join_code, user)
+            .export_replay(&join_code, game_number as usize)
             .await
-            .ok_or(AppError::BadRequest(
-                "Bad lobby id or not your turn".to_string(),
-            ))?;
+    }
 
-        Ok(())
+    pub(crate) async fn turn(ctx: Ctx, join_code: String) -> AppResult<()> {
+        let user = ctx.required_user()?;
+        ctx.lobby_manager.advance_turn(&join_code, user).await
     }
 
     pub(crate) async fn action_card(ctx: Ctx, args: ActionCardArgs) -> AppResult<()> {
@@ -160,6 +234,13 @@ impl LobbyController {
         Ok(())
     }
 
+    pub(crate) async fn equip_card(ctx: Ctx, args: ActionCardArgs) -> AppResult<()> {
+        let user = ctx.required_user()?;
+        ctx.lobby_manager.equip_card(args, user).await?;
+
+        Ok(())
+    }
+
     pub(crate) async fn respond_optional_ability(
         ctx: Ctx,
         args: RespondOptionalAbility,
@@ -179,13 +260,98 @@ impl LobbyController {
         Ok(())
     }
 
+    pub(crate) async fn suspend_card(ctx: Ctx, args: SuspendCardArgs) -> AppResult<()> {
+        let user = ctx.required_user()?;
+        ctx.lobby_manager.suspend_card(args, user).await?;
+
+        Ok(())
+    }
+
+    pub(crate) async fn turn_face_up(ctx: Ctx, args: TurnFaceUpArgs) -> AppResult<()> {
+        let user = ctx.required_user()?;
+        ctx.lobby_manager.turn_face_up(args, user).await?;
+
+        Ok(())
+    }
+
+    pub(crate) async fn flashback_card(ctx: Ctx, args: FlashbackCardArgs) -> AppResult<()> {
+        let user = ctx.required_user()?;
+        ctx.lobby_manager.flashback_card(args, user).await?;
+
+        Ok(())
+    }
+
     pub(crate) async fn join(ctx: Ctx, join_code: String) -> AppResult<()> {
+        let user = ctx.required_user()?;
+        ctx.lobby_manager.join_lobby(&join_code, user).await?;
+        ctx.lobby_manager.notify_lobby(&join_code).await.ok();
+
+        Ok(())
+    }
+
+    pub(crate) async fn start(ctx: Ctx, code: String) -> AppResult<()> {
+        let user = ctx.required_user()?;
+        ctx.lobby_manager.start_lobby(&code, user).await?;
+
+        Ok(())
+    }
+
+    pub(crate) async fn kick(ctx: Ctx, args: KickPlayerArgs) -> AppResult<()> {
         let user = ctx.required_user()?;
         ctx.lobby_manager
-            .join_lobby(&join_code, user)
+            .kick_player(&args.code, user, &args.user_id)
+            .await?;
+
+        Ok(())
+    }
+
+    pub(crate) async fn concede(ctx: Ctx, code: String) -> AppResult<()> {
+        let user = ctx.required_user()?;
+        ctx.lobby_manager.concede(&code, &user.sub).await
+    }
+
+    pub(crate) async fn mulligan(ctx: Ctx, code: String) -> AppResult<()> {
+        let user = ctx.required_user()?;
+        ctx.lobby_manager.mulligan(&code, user).await
+    }
+
+    pub(crate) async fn pass_until_my_turn(ctx: Ctx, args: PassUntilMyTurnArgs) -> AppResult<()> {
+        let user = ctx.required_user()?;
+        ctx.lobby_manager
+            .pass_until_my_turn(&args.code, user, args.passing)
             .await
-            .ok_or(AppError::BadRequest("Bad lobby id".to_string()))?;
-        ctx.lobby_manager.notify_lobby(&join_code).await.ok();
+    }
+
+    pub(crate) async fn keep_hand(ctx: Ctx, args: KeepHandArgs) -> AppResult<()> {
+        let user = ctx.required_user()?;
+        let bottom = args
+            .bottom
+            .iter()
+            .map(|index| usize::try_from(*index))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| AppError::BadRequest("No such card in hand".to_string()))?;
+
+        ctx.lobby_manager.keep_hand(&args.code, user, bottom).await
+    }
+
+    pub(crate) async fn sideboard(ctx: Ctx, args: SideboardArgs) -> AppResult<()> {
+        let user = ctx.required_user()?;
+        ctx.lobby_manager
+            .sideboard(&args.code, user, &args.remove, &args.add)
+            .await
+    }
+
+    pub(crate) async fn update_settings(ctx: Ctx, args: UpdateSettingsArgs) -> AppResult<()> {
+        let user = ctx.required_user()?;
+        let lobby = ctx
+            .lobby_manager
+            .get_lobby(&args.code)
+            .await
+            .map_err(|_| AppError::BadRequest("No such lobby".to_string()))?;
+
+        lobby.lock().await.update_settings(user, args.settings)?;
+
+        ctx.lobby_manager.notify_lobby(&args.code).await.ok();
 
         Ok(())
     }
@@ -202,13 +368,52 @@ impl LobbyController {
         // println!("adding message to lobby {} {:?}", data.join_code, lobby);
 
         lobby.lock().await.message(user, args.text);
-        println!("added, notifying lobby");
+        debug!("added, notifying lobby");
         // lobby.lock().await.message(user, args.text);
         ctx.lobby_manager.notify_lobby(&args.lobby_id).await.ok();
 
         Ok(())
     }
 
+    pub(crate) async fn list_lobbies(ctx: Ctx) -> AppResult<Vec<LobbyListing>> {
+        Ok(ctx.lobby_manager.list_lobbies().await)
+    }
+
+    pub(crate) async fn find_my_lobby(ctx: Ctx, access_token: String) -> AppResult<Option<String>> {
+        let user = JwtService::decode(&access_token)
+            .map_err(|_| AppError::Unauthorized)?
+            .claims;
+
+        Ok(ctx.lobby_manager.find_lobby_for(&user).await)
+    }
+
+    pub(crate) async fn get_lobby_state(
+        ctx: Ctx,
+        join_code: String,
+        access_token: String,
+    ) -> AppResult<LobbyData> {
+        let user = JwtService::decode(&access_token)
+            .map_err(|_| AppError::Unauthorized)?
+            .claims;
+
+        ctx.lobby_manager.get_lobby_state(&join_code, &user).await
+    }
+
+    pub(crate) async fn legal_targets(
+        ctx: Ctx,
+        join_code: String,
+        ability_id: String,
+        access_token: String,
+    ) -> AppResult<Vec<FrontendTarget>> {
+        let user = JwtService::decode(&access_token)
+            .map_err(|_| AppError::Unauthorized)?
+            .claims;
+
+        ctx.lobby_manager
+            .legal_targets(&join_code, &ability_id, &user)
+            .await
+    }
+
     pub(crate) fn subscribe(
         ctx: Ctx,
         join_code: String,
@@ -226,6 +431,11 @@ impl LobbyController {
                                     yield lobby_data;
                                 }
                             },
+                            LobbyCommand::MulliganInfo(info) => {
+                                if info.player_id == user_claims.sub {
+                                    yield lobby_data;
+                                }
+                            },
                             _ => {
                                 personalize_lobby_data_for_player(&mut lobby_data, &user_claims.sub);
 
@@ -19,4 +19,19 @@ pub fn create_authentication_router() -> rspc::RouterBuilder<Ctx> {
                 Ok(AuthenticationController::login(ctx, args).await?)
             })
         })
+        .mutation("create_account", |t| {
+            t(|ctx, email: String| async move {
+                Ok(AuthenticationController::create_account(ctx, email).await?)
+            })
+        })
+        .mutation("enroll_totp", |t| {
+            t(|ctx, code: Option<String>| async move {
+                Ok(AuthenticationController::enroll_totp(ctx, code).await?)
+            })
+        })
+        .mutation("verify_totp", |t| {
+            t(|ctx, code: String| async move {
+                Ok(AuthenticationController::verify_totp(ctx, code).await?)
+            })
+        })
 }
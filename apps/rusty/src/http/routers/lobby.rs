@@ -13,11 +13,19 @@ use tokio_stream::wrappers::ReceiverStream;
 
 use crate::error::AppError;
 use crate::http::controllers::lobby::ActionCardArgs;
+use crate::http::controllers::lobby::FlashbackCardArgs;
+use crate::http::controllers::lobby::KeepHandArgs;
+use crate::http::controllers::lobby::KickPlayerArgs;
 use crate::http::controllers::lobby::LobbyChatArgs;
+use crate::http::controllers::lobby::PassUntilMyTurnArgs;
 use crate::http::controllers::lobby::PlayCardArgs;
 use crate::http::controllers::lobby::RespondMandatoryAbility;
 use crate::http::controllers::lobby::RespondOptionalAbility;
 use crate::http::controllers::lobby::SelectDeckArgs;
+use crate::http::controllers::lobby::SideboardArgs;
+use crate::http::controllers::lobby::SuspendCardArgs;
+use crate::http::controllers::lobby::TurnFaceUpArgs;
+use crate::http::controllers::lobby::UpdateSettingsArgs;
 use crate::services::jwt::JwtService;
 use crate::{http::controllers::lobby::LobbyController, lobby::lobby::LobbyData, Ctx};
 
@@ -34,6 +42,11 @@ pub fn create_lobby_router() -> rspc::RouterBuilder<Ctx> {
                 Ok(LobbyController::attach_card(ctx, args).await?)
             })
         })
+        .mutation("equip_card", |t| {
+            t(|ctx, args: ActionCardArgs| async move {
+                Ok(LobbyController::equip_card(ctx, args).await?)
+            })
+        })
         .mutation("respond_mandatory_ability", |t| {
             t(|ctx, args: RespondMandatoryAbility| async move {
                 Ok(LobbyController::respond_mandatory_ability(ctx, args).await?)
@@ -56,9 +69,53 @@ pub fn create_lobby_router() -> rspc::RouterBuilder<Ctx> {
                 },
             )
         })
+        .mutation("suspend_card", |t| {
+            t(|ctx, args: SuspendCardArgs| async move {
+                Ok(LobbyController::suspend_card(ctx, args).await?)
+            })
+        })
+        .mutation("turn_face_up", |t| {
+            t(|ctx, args: TurnFaceUpArgs| async move {
+                Ok(LobbyController::turn_face_up(ctx, args).await?)
+            })
+        })
+        .mutation("flashback_card", |t| {
+            t(|ctx, args: FlashbackCardArgs| async move {
+                Ok(LobbyController::flashback_card(ctx, args).await?)
+            })
+        })
         .mutation("join", |t| {
             t(|ctx, code: String| async move { Ok(LobbyController::join(ctx, code).await?) })
         })
+        .mutation("kick", |t| {
+            t(|ctx, args: KickPlayerArgs| async move { Ok(LobbyController::kick(ctx, args).await?) })
+        })
+        .mutation("concede", |t| {
+            t(|ctx, code: String| async move { Ok(LobbyController::concede(ctx, code).await?) })
+        })
+        .mutation("mulligan", |t| {
+            t(|ctx, code: String| async move { Ok(LobbyController::mulligan(ctx, code).await?) })
+        })
+        .mutation("keep_hand", |t| {
+            t(|ctx, args: KeepHandArgs| async move {
+                Ok(LobbyController::keep_hand(ctx, args).await?)
+            })
+        })
+        .mutation("pass_until_my_turn", |t| {
+            t(|ctx, args: PassUntilMyTurnArgs| async move {
+                Ok(LobbyController::pass_until_my_turn(ctx, args).await?)
+            })
+        })
+        .mutation("sideboard", |t| {
+            t(|ctx, args: SideboardArgs| async move {
+                Ok(LobbyController::sideboard(ctx, args).await?)
+            })
+        })
+        .mutation("update_settings", |t| {
+            t(|ctx, args: UpdateSettingsArgs| async move {
+                Ok(LobbyController::update_settings(ctx, args).await?)
+            })
+        })
         .mutation("select_deck", |t| {
             t(|ctx, args: SelectDeckArgs| async move {
                 Ok(LobbyController::select_deck(ctx, args).await?)
@@ -67,9 +124,40 @@ pub fn create_lobby_router() -> rspc::RouterBuilder<Ctx> {
         .mutation("ready", |t| {
             t(|ctx, code: String| async move { Ok(LobbyController::ready(ctx, code).await?) })
         })
+        .mutation("start", |t| {
+            t(|ctx, code: String| async move { Ok(LobbyController::start(ctx, code).await?) })
+        })
         .mutation("create", |t| {
             t(|ctx, _: Vec<String>| async move { Ok(LobbyController::create(ctx).await?) })
         })
+        .query("get_lobby_state", |t| {
+            t(|ctx, (code, access_token): (String, String)| async move {
+                Ok(LobbyController::get_lobby_state(ctx, code, access_token).await?)
+            })
+        })
+        .query("legal_targets", |t| {
+            t(
+                |ctx, (code, ability_id, access_token): (String, String, String)| async move {
+                    Ok(LobbyController::legal_targets(ctx, code, ability_id, access_token).await?)
+                },
+            )
+        })
+        .query("list_lobbies", |t| {
+            t(|ctx, _: ()| async move { Ok(LobbyController::list_lobbies(ctx).await?) })
+        })
+        .query("find_my_lobby", |t| {
+            t(|ctx, access_token: String| async move {
+                Ok(LobbyController::find_my_lobby(ctx, access_token).await?)
+            })
+        })
+        .query("match_history", |t| {
+            t(|ctx, _: ()| async move { Ok(LobbyController::match_history(ctx).await?) })
+        })
+        .query("export_replay", |t| {
+            t(|ctx, (code, game_number): (String, u32)| async move {
+                Ok(LobbyController::export_replay(ctx, code, game_number).await?)
+            })
+        })
         .subscription("subscribe", |t| {
             t(|ctx, (code, access_token): (String, String)| {
                 LobbyController::subscribe(ctx, code, access_token)
@@ -0,0 +1,55 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use specta::Type;
+
+// One submitted action, appended to a lobby's audit trail before the action is
+// applied so rejected attempts are kept alongside the accepted ones.
+#[derive(Type, Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct AuditEntry {
+    pub player_id: String,
+    pub timestamp: u64,
+    pub procedure: String,
+    pub args_hash: String,
+}
+
+impl AuditEntry {
+    pub fn new<T: Serialize>(player_id: &str, procedure: &str, args: &T) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_millis() as u64)
+            .unwrap_or_default();
+
+        Self {
+            player_id: player_id.to_string(),
+            timestamp,
+            procedure: procedure.to_string(),
+            args_hash: hash_args(args),
+        }
+    }
+
+    pub fn key(lobby_id: &str) -> String {
+        format!("{}:audit", lobby_id)
+    }
+}
+
+// SHA-256 rather than std's hasher, which can change between Rust versions and
+// would stop stored hashes from matching.
+fn hash_args<T: Serialize>(args: &T) -> String {
+    let serialized = serde_json::to_string(args).unwrap_or_default();
+
+    format!("{:x}", Sha256::digest(serialized.as_bytes()))
+}
+
+mod test {
+    use super::hash_args;
+
+    #[test]
+    fn test_args_hash_is_sha256_of_the_json() {
+        assert_eq!(
+            hash_args(&1),
+            "6b86b273ff34fce19d6b804eff5a3f5747ada4eaa22f1d49c01e52ddb7875b4b"
+        );
+    }
+}
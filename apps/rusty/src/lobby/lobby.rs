@@ -1,4 +1,4 @@
-use std::{borrow::BorrowMut, collections::HashMap, sync::Arc, thread::Thread};
+use std::{borrow::BorrowMut, collections::HashMap, sync::Arc, thread::Thread, time::Instant};
 
 use futures::StreamExt;
 
@@ -13,22 +13,114 @@ impl LobbyChat {
     }
 }
 
+#[derive(Type, Deserialize, Serialize, Debug, Clone)]
+pub struct LobbySettings {
+    pub max_players: u8,
+    // User ids holding a seat; nobody else can take it while they're away
+    pub reserved_seats: Vec<String>,
+    // Ranked two player games move both players' ratings when they finish
+    #[serde(default)]
+    pub ranked: bool,
+    // How long a player can be disconnected mid game before they concede
+    #[serde(default = "default_disconnect_grace_secs")]
+    pub disconnect_grace_secs: u32,
+    // Games in the match; the first to win a majority of them takes it
+    #[serde(default = "default_best_of")]
+    pub best_of: u8,
+    // Teaching games: nobody is asked to respond, spells just resolve
+    #[serde(default)]
+    pub auto_pass_priority: bool,
+    // Spells resolve straight away when nobody could respond to them anyway
+    #[serde(default = "default_skip_empty_priority_windows")]
+    pub skip_empty_priority_windows: bool,
+    // Shown in the lobby list; private lobbies can only be joined by code
+    #[serde(default)]
+    pub public: bool,
+    // Highlander: one copy of each card apart from basic lands
+    #[serde(default)]
+    pub singleton: bool,
+}
+impl Default for LobbySettings {
+    fn default() -> LobbySettings {
+        LobbySettings {
+            max_players: 2,
+            reserved_seats: vec![],
+            ranked: false,
+            disconnect_grace_secs: default_disconnect_grace_secs(),
+            best_of: default_best_of(),
+            auto_pass_priority: false,
+            skip_empty_priority_windows: default_skip_empty_priority_windows(),
+            public: false,
+            singleton: false,
+        }
+    }
+}
+
+fn default_disconnect_grace_secs() -> u32 {
+    60
+}
+
+fn default_best_of() -> u8 {
+    1
+}
+
+fn default_skip_empty_priority_windows() -> bool {
+    true
+}
+
 #[derive(Type, Deserialize, Serialize, Debug, Clone)]
 pub struct LobbyData {
     pub join_code: String,
     pub chat: Vec<LobbyChat>,
     pub game_state: GameState,
+    pub settings: LobbySettings,
+    // Games won so far this match, by user id
+    pub match_score: HashMap<String, u8>,
+    pub games_played: u8,
+}
+impl LobbyData {
+    // Hides everything `user_id` shouldn't see, i.e. every hand, revealed top
+    // card, face down card and decklist but their own.
+    pub fn redact_for(&mut self, user_id: &str) {
+        for (id, player_state) in &mut self.game_state.players {
+            if id != user_id {
+                player_state.hand.clear();
+                player_state.top_card = None;
+                player_state.face_down_cards.clear();
+                player_state.decklist = None;
+            }
+        }
+    }
+
+    fn match_decided(&self) -> bool {
+        let wins_needed = self.settings.best_of / 2 + 1;
+        self.games_played >= self.settings.best_of
+            || self.match_score.values().any(|wins| *wins >= wins_needed)
+    }
 }
+
 impl Default for LobbyData {
     fn default() -> LobbyData {
         LobbyData {
             join_code: ulid::Ulid::new().to_string(),
             chat: vec![],
             game_state: GameState::default(),
+            settings: LobbySettings::default(),
+            match_score: HashMap::new(),
+            games_played: 0,
         }
     }
 }
 
+#[derive(Type, Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct LobbyListing {
+    pub join_code: String,
+    pub player_count: u8,
+    pub max_players: u8,
+    pub ranked: bool,
+    pub best_of: u8,
+}
+
 #[derive(Type, Deserialize, Serialize, Debug)]
 pub struct Lobby {
     #[serde(skip_serializing, skip_deserializing)]
@@ -38,6 +130,22 @@ pub struct Lobby {
 
     #[serde(skip_serializing, skip_deserializing)]
     game: Arc<Mutex<Game>>,
+
+    #[serde(skip_serializing, skip_deserializing)]
+    started_at: Option<Instant>,
+
+    // Open subscriptions per user id
+    #[serde(skip_serializing, skip_deserializing)]
+    connections: HashMap<String, usize>,
+
+    // Bumped on every disconnect, so a grace timer can tell it's been
+    // superseded by a reconnect or a later disconnect
+    #[serde(skip_serializing, skip_deserializing)]
+    disconnects: HashMap<String, u64>,
+
+    // One per finished game of the match, in the order they were played
+    #[serde(skip_serializing, skip_deserializing)]
+    replays: Vec<Replay>,
 }
 
 impl Lobby {
@@ -100,11 +208,109 @@ impl Lobby {
             attacks
         };
 
+        let monarch = match self.game.lock().await.monarch.clone() {
+            Some(monarch) => Some(monarch.lock().await.name.clone()),
+            None => None,
+        };
+
+        let stack = self.game.lock().await.stack_view().await;
+
+        let mut deciding_hands = vec![];
+        {
+            let game = self.game.lock().await;
+            if game.awaiting_opening_hands {
+                for player in &game.players {
+                    let player = player.lock().await;
+                    if !player.kept_hand {
+                        deciding_hands.push(player.name.clone());
+                    }
+                }
+            }
+        }
+
         PublicGameInfo {
             current_turn: self.game.lock().await.current_turn.clone(),
             priority_queue,
             attacks,
             blocks,
+            monarch,
+            stack,
+            deciding_hands,
+        }
+    }
+
+    // Re-renders every player's board and hand from the running game.
+    pub async fn refresh_game_state(&mut self) {
+        let game = self.cloned_game().await;
+        let phase = {
+            self.data.game_state.public_info = self.get_state().await;
+            self.data
+                .game_state
+                .public_info
+                .current_turn
+                .clone()
+                .unwrap()
+                .phase
+        };
+
+        for (_, player) in self.data.game_state.players.iter_mut() {
+            let mut hand = Vec::new();
+            let mut cards_in_play = Vec::new();
+            let mut face_down_cards = Vec::new();
+            let mut spells = Vec::new();
+            let player_cards_in_play = &player.player.lock().await.cards_in_play.clone();
+            let player_spells = &player.player.lock().await.spells.clone();
+            let player_cards_in_hand = &player.player.lock().await.cards_in_hand.clone();
+            let (reveal, top_card) = {
+                let game_player = player.player.lock().await;
+                (
+                    game_player.reveal_top_card,
+                    game_player.deck.draw_pile.last().cloned(),
+                )
+            };
+
+            for card in player_cards_in_play {
+                let details = CardWithDetails::from_card_arc(card, phase, true, &game).await;
+                if details.card.face_down {
+                    cards_in_play.push(CardWithDetails::face_down(&details.card));
+                    face_down_cards.push(CardWithDetails::hidden_face(&details.card, phase).await);
+                } else {
+                    cards_in_play.push(details);
+                }
+            }
+
+            for card in player_spells {
+                spells.push(CardWithDetails::from_card_arc(card, phase, false, &game).await);
+            }
+
+            for card in player_cards_in_hand {
+                hand.push(CardWithDetails::from_card_arc(card, phase, false, &game).await);
+            }
+
+            let top_card = match (reveal, top_card) {
+                (Some(_), Some(card)) => {
+                    Some(CardWithDetails::from_card_arc(&card, phase, false, &game).await)
+                }
+                _ => None,
+            };
+
+            {
+                let game_player = player.player.lock().await;
+
+                player.public_info.spells = spells;
+                player.public_info.hand_size = hand.len() as i32;
+                player.public_info.cards_in_play = cards_in_play;
+                player.public_info.mana_pool = game_player.mana_pool.clone();
+                player.public_info.health =
+                    game_player.stat_manager.get_stat_value(StatType::Health);
+                player.public_info.revealed_top_card = match reveal {
+                    Some(TopCardReveal::Everyone) => top_card.clone(),
+                    _ => None,
+                };
+            }
+            player.hand = hand;
+            player.top_card = top_card;
+            player.face_down_cards = face_down_cards;
         }
     }
 
@@ -118,6 +324,7 @@ use serde::{Deserialize, Serialize};
 use specta::Type;
 use tokio::sync::{Mutex, RwLock};
 use tokio_stream::wrappers::ReceiverStream;
+use tracing::debug;
 use ulid::Ulid;
 
 #[derive(Type, Deserialize, Clone, Serialize, Debug)]
@@ -129,22 +336,42 @@ pub enum DeckSelector {
     Red,
 }
 
+impl DeckSelector {
+    fn decklist(&self) -> Decklist {
+        match self {
+            DeckSelector::Green => Decklist::new(create_green_deck(), create_green_sideboard()),
+            DeckSelector::Blue => Decklist::new(create_blue_deck(), create_blue_sideboard()),
+            DeckSelector::Black => Decklist::new(create_black_deck(), create_black_sideboard()),
+            DeckSelector::Angels => Decklist::new(create_angels_deck(), create_angels_sideboard()),
+            DeckSelector::Red => Decklist::new(create_red_deck(), create_red_sideboard()),
+        }
+    }
+}
+
 use crate::{
     error::{AppError, AppResult},
     game::{
         decks::{
-            black::create_black_deck, blue::create_blue_deck, green::create_green_deck,
-            red::create_red_deck, white::create_angels_deck, Deck,
+            black::{create_black_deck, create_black_sideboard},
+            blue::{create_blue_deck, create_blue_sideboard},
+            green::{create_green_deck, create_green_sideboard},
+            red::{create_red_deck, create_red_sideboard},
+            white::{create_angels_deck, create_angels_sideboard},
+            Deck, Decklist,
         },
         effects::EffectTarget,
-        player::Player,
+        player::{Player, TopCardReveal},
+        replay::Replay,
+        stat::{Stat, StatType, Stats},
+        turn::TurnPhase,
         Attack, Block, CardWithDetails, FrontendCardTarget, FrontendPileName, FrontendTarget, Game,
         GameState, GameStatus, PlayerState, PlayerStatus, PriorityQueue, PublicGameInfo,
     },
+    models::match_result::NewMatch,
     services::jwt::Claims,
 };
 
-use super::manager::LobbyManager;
+use super::manager::{LobbyManager, MulliganInfo};
 
 impl Lobby {
     pub async fn new(user: &Claims) -> Self {
@@ -153,17 +380,75 @@ impl Lobby {
             data: LobbyData::default(),
             client: None,
             game: Arc::new(Mutex::new(game)),
+            started_at: None,
+            connections: HashMap::new(),
+            disconnects: HashMap::new(),
+            replays: vec![],
         };
 
         let player = Player::new(&user.sub.clone(), 20, vec![]);
 
-        lobby.join(user).await;
+        lobby.join(user).await.ok();
 
         lobby
     }
 
-    pub async fn join(&mut self, user: &Claims) -> &mut Self {
+    fn has_open_seat(&self, user: &Claims) -> bool {
+        self.open_seats(Some(&user.sub)) > 0
+    }
+
+    // Seats `user_id` could take, leaving out any reserved for someone else.
+    fn open_seats(&self, user_id: Option<&str>) -> usize {
+        let players = &self.data.game_state.players;
+        let settings = &self.data.settings;
+        let held_for_others = settings
+            .reserved_seats
+            .iter()
+            .filter(|id| Some(id.as_str()) != user_id && !players.contains_key(*id))
+            .count();
+
+        (settings.max_players as usize).saturating_sub(players.len() + held_for_others)
+    }
+
+    // How the lobby shows up in the lobby list, if it should at all.
+    pub fn listing(&self) -> Option<LobbyListing> {
+        if !self.data.settings.public
+            || self.data.game_state.status != GameStatus::NeedsPlayers
+            || self.open_seats(None) == 0
+        {
+            return None;
+        }
+
+        Some(LobbyListing {
+            join_code: self.data.join_code.clone(),
+            player_count: self.data.game_state.players.len() as u8,
+            max_players: self.data.settings.max_players,
+            ranked: self.data.settings.ranked,
+            best_of: self.data.settings.best_of,
+        })
+    }
+
+    // Whether `user_id` has a seat in a game that isn't over yet.
+    pub fn is_seated(&self, user_id: &str) -> bool {
+        self.data.game_state.status != GameStatus::GameOver
+            && self.data.game_state.players.contains_key(user_id)
+    }
+
+    fn require_leader(&self, user: &Claims) -> AppResult<()> {
+        match self.data.game_state.players.get(&user.sub) {
+            Some(player) if player.is_leader => Ok(()),
+            _ => Err(AppError::BadRequest(
+                "Only the lobby leader can do that".to_string(),
+            )),
+        }
+    }
+
+    pub async fn join(&mut self, user: &Claims) -> AppResult<&mut Self> {
         if !self.data.game_state.players.contains_key(&user.sub) {
+            if !self.has_open_seat(user) {
+                return Err(AppError::BadRequest("Lobby is full".to_string()));
+            }
+
             let (index, player) = {
                 let mut game = self.game.lock().await;
                 let player = Player::from_claims(user);
@@ -183,43 +468,193 @@ impl Lobby {
                     .get_mut(&user.sub)
                     .unwrap()
                     .is_leader = true;
-                println!("setting leader");
+                debug!("setting leader");
             }
         }
 
         // println!("JOIN {:?}", self);
 
-        self
+        Ok(self)
+    }
+
+    // Starts the countdown to the game; only the leader can, and only once every
+    // seated player is ready.
+    pub fn start(&mut self, user: &Claims) -> AppResult<()> {
+        self.require_leader(user)?;
+        if !matches!(
+            self.data.game_state.status,
+            GameStatus::NeedsPlayers | GameStatus::Sideboarding
+        ) {
+            return Err(AppError::BadRequest(
+                "The game is already starting".to_string(),
+            ));
+        }
+
+        let players = &self.data.game_state.players;
+        if players.len() < 2 {
+            return Err(AppError::BadRequest("Waiting for more players".to_string()));
+        }
+        if !players
+            .values()
+            .all(|player| player.status == PlayerStatus::Ready)
+        {
+            return Err(AppError::BadRequest(
+                "Not every player is ready".to_string(),
+            ));
+        }
+        Self::check_seated_decks(players, &self.data.settings)?;
+
+        self.data.game_state.status = GameStatus::WaitingForStart(5);
+
+        Ok(())
     }
 
-    pub async fn select_deck(&mut self, user: &Claims, deck: DeckSelector) -> &mut Self {
+    pub async fn kick(&mut self, user: &Claims, user_id: &str) -> AppResult<()> {
+        self.require_leader(user)?;
+        if user.sub == user_id {
+            return Err(AppError::BadRequest("You cannot kick yourself".to_string()));
+        }
+        if self.data.game_state.status == GameStatus::InGame {
+            return Err(AppError::BadRequest(
+                "Players cannot be kicked once the game has started".to_string(),
+            ));
+        }
+
+        let kicked = self
+            .data
+            .game_state
+            .players
+            .remove(user_id)
+            .ok_or(AppError::BadRequest("No such player".to_string()))?;
+        self.data.settings.reserved_seats.retain(|id| id != user_id);
+
+        let mut game = self.game.lock().await;
+        game.remove_player(&kicked.player);
+        for player_state in self.data.game_state.players.values_mut() {
+            if let Some(index) = game.player_index(&player_state.player) {
+                player_state.player_index = index as i32;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn update_settings(&mut self, user: &Claims, settings: LobbySettings) -> AppResult<()> {
+        self.require_leader(user)?;
+        if (settings.max_players as usize) < self.data.game_state.players.len() {
+            return Err(AppError::BadRequest(
+                "There are already more players than that".to_string(),
+            ));
+        }
+        // Seated decks were checked against the old rules
+        if settings.singleton != self.data.settings.singleton {
+            Self::check_seated_decks(&self.data.game_state.players, &settings)?;
+        }
+
+        self.data.settings = settings;
+
+        Ok(())
+    }
+
+    pub async fn select_deck(&mut self, user: &Claims, deck: DeckSelector) -> AppResult<&mut Self> {
+        // Decks are locked in for the rest of a match once it's under way
+        if self.data.game_state.status == GameStatus::Sideboarding {
+            return Ok(self);
+        }
+        Self::check_deck_rules(&self.data.settings, &deck.decklist())?;
         if let Some(player) = self.data.game_state.players.get_mut(&user.sub) {
             player.deck = deck;
+            player.decklist = None;
         }
 
-        self
+        Ok(self)
+    }
+
+    // Format rules from the lobby settings that every decklist has to follow.
+    fn check_deck_rules(settings: &LobbySettings, decklist: &Decklist) -> AppResult<()> {
+        if settings.singleton {
+            decklist.validate_singleton().map_err(|duplicates| {
+                AppError::BadRequest(format!(
+                    "Singleton decks can only have one of each card, not {}",
+                    duplicates.join(", ")
+                ))
+            })?;
+        }
+
+        Ok(())
+    }
+
+    fn check_seated_decks(
+        players: &HashMap<String, PlayerState>,
+        settings: &LobbySettings,
+    ) -> AppResult<()> {
+        for (user_id, player) in players {
+            let decklist = player
+                .decklist
+                .clone()
+                .unwrap_or_else(|| player.deck.decklist());
+            Self::check_deck_rules(settings, &decklist).map_err(|err| match err {
+                AppError::BadRequest(reason) => {
+                    AppError::BadRequest(format!("{}'s deck is not allowed: {}", user_id, reason))
+                }
+                err => err,
+            })?;
+        }
+
+        Ok(())
     }
 
     pub async fn ready(&mut self, user: &Claims) -> &mut Self {
         if let Some(player) = self.data.game_state.players.get_mut(&user.sub) {
             player.status = PlayerStatus::Ready;
             let mut p = player.player.lock().await;
-            let deck = Deck::new(match player.deck {
-                DeckSelector::Green => create_green_deck(),
-                DeckSelector::Blue => create_blue_deck(),
-                DeckSelector::Black => create_black_deck(),
-                DeckSelector::Angels => create_angels_deck(),
-                DeckSelector::Red => create_red_deck(),
-            });
+            let decklist = player
+                .decklist
+                .get_or_insert_with(|| player.deck.decklist());
+            let deck = Deck::new(decklist.main.clone());
             deck.set_owner(&player.player).await;
 
             p.deck = deck;
-            p.deck.shuffle();
+            p.deck.shuffle(&mut self.game.lock().await.rng);
         }
 
         self
     }
 
+    // Trades cards between the main deck and sideboard between games.
+    pub fn sideboard(&mut self, user: &Claims, remove: &[String], add: &[String]) -> AppResult<()> {
+        if self.data.game_state.status != GameStatus::Sideboarding {
+            return Err(AppError::BadRequest(
+                "You can only sideboard between games".to_string(),
+            ));
+        }
+        let player = self
+            .data
+            .game_state
+            .players
+            .get_mut(&user.sub)
+            .ok_or(AppError::BadRequest("Not in this lobby".to_string()))?;
+        if player.status == PlayerStatus::Ready {
+            return Err(AppError::BadRequest(
+                "You're already ready for the next game".to_string(),
+            ));
+        }
+
+        // Swap a copy so a list that breaks the format rules is never kept
+        let mut decklist = player
+            .decklist
+            .clone()
+            .ok_or(AppError::BadRequest("You haven't got a deck".to_string()))?;
+        decklist.swap(remove, add).map_err(AppError::BadRequest)?;
+        Self::check_deck_rules(&self.data.settings, &decklist)?;
+
+        if let Some(player) = self.data.game_state.players.get_mut(&user.sub) {
+            player.decklist = Some(decklist);
+        }
+
+        Ok(())
+    }
+
     pub async fn attach_card(
         &mut self,
         player_index: usize,
@@ -239,6 +674,24 @@ impl Lobby {
         Ok(())
     }
 
+    pub async fn equip_card(
+        &mut self,
+        player_index: usize,
+        in_play_index: usize,
+        target: Option<EffectTarget>,
+    ) -> AppResult<()> {
+        let player = Arc::clone(&self.game.lock().await.players[player_index]);
+
+        self.game
+            .lock()
+            .await
+            .equip_card_action(&player, in_play_index, target)
+            .await
+            .map_err(AppError::BadRequest)?;
+
+        Ok(())
+    }
+
     pub async fn respond_mandatory_player_ability(
         &mut self,
         ability_id: String,
@@ -302,12 +755,218 @@ impl Lobby {
         Ok(())
     }
 
-    pub async fn advance_turn(&mut self) {
-        self.game.lock().await.advance_turn().await;
+    pub async fn advance_turn(&mut self) -> AppResult<()> {
+        self.game
+            .lock()
+            .await
+            .try_advance_turn()
+            .await
+            .map_err(|err| AppError::BadRequest(err.to_string()))
     }
 
     pub async fn start_game(&mut self) {
-        self.game.lock().await.start().await;
+        self.started_at = Some(Instant::now());
+        let mut game = self.game.lock().await;
+        game.auto_pass_priority = self.data.settings.auto_pass_priority;
+        game.skip_empty_priority_windows = self.data.settings.skip_empty_priority_windows;
+        game.start().await;
+    }
+
+    // Ends the game once at most one player is left standing, returning the
+    // result to record. Unless that decides the match everyone goes on to
+    // sideboard for the next game.
+    pub async fn finish_if_over(&mut self) -> Option<NewMatch> {
+        if self.data.game_state.status != GameStatus::InGame {
+            return None;
+        }
+
+        let game = self.game.lock().await;
+        if game.players.len() > 1 {
+            return None;
+        }
+
+        let winner = game.players.first().and_then(|winner| {
+            self.data
+                .game_state
+                .players
+                .iter()
+                .find(|(_, player_state)| Arc::ptr_eq(&player_state.player, winner))
+                .map(|(id, _)| id.clone())
+        });
+        let duration = self
+            .started_at
+            .map(|started_at| started_at.elapsed().as_secs())
+            .unwrap_or_default();
+        let new_match = NewMatch {
+            lobby_code: self.data.join_code.clone(),
+            players: self.data.game_state.players.keys().cloned().collect(),
+            winner,
+            turn_count: game.turn_number as i32,
+            duration_seconds: duration as i32,
+        };
+        self.replays.extend(game.replay.clone());
+        drop(game);
+
+        self.data.games_played += 1;
+        if let Some(winner) = &new_match.winner {
+            *self.data.match_score.entry(winner.clone()).or_default() += 1;
+        }
+        if self.data.match_decided() {
+            self.data.game_state.status = GameStatus::GameOver;
+        } else {
+            self.data.game_state.status = GameStatus::Sideboarding;
+            self.reset_for_next_game().await;
+        }
+
+        Some(new_match)
+    }
+
+    // Seats everyone in a fresh game, in the same order. The broadcast channel
+    // and rng carry over since the lobby's listeners and seed outlive a game.
+    async fn reset_for_next_game(&mut self) {
+        let mut game = self.game.lock().await;
+        let mut next_game = Game::new();
+        next_game.broadcast_sender = game.broadcast_sender.take();
+        std::mem::swap(&mut next_game.rng, &mut game.rng);
+        *game = next_game;
+
+        let mut seats: Vec<&mut PlayerState> = self.data.game_state.players.values_mut().collect();
+        seats.sort_by_key(|player_state| player_state.player_index);
+        for player_state in seats {
+            let name = player_state.player.lock().await.name.clone();
+            player_state.player = game.add_player(Player::new(&name, 20, vec![])).await;
+            player_state.player_index = game.players.len() as i32 - 1;
+            player_state.status = PlayerStatus::Spectator;
+        }
+    }
+
+    // Knocks `user_id` out of the game, e.g. after they've been gone too long.
+    pub async fn concede(&mut self, user_id: &str) -> AppResult<()> {
+        if self.data.game_state.status != GameStatus::InGame {
+            return Err(AppError::BadRequest("The game isn't running".to_string()));
+        }
+        let player = self
+            .data
+            .game_state
+            .players
+            .get(user_id)
+            .ok_or(AppError::BadRequest("Not in this lobby".to_string()))?
+            .player
+            .clone();
+
+        {
+            let mut player = player.lock().await;
+            let health = player.get_stat_value(StatType::Health);
+            if health > 0 {
+                player
+                    .stat_manager
+                    .add_stat("concede".to_string(), Stat::new(StatType::Health, -health));
+            }
+        }
+        self.game.lock().await.handle_deaths().await;
+
+        Ok(())
+    }
+
+    fn player_for(&self, user: &Claims) -> AppResult<Arc<Mutex<Player>>> {
+        Ok(self
+            .data
+            .game_state
+            .players
+            .get(&user.sub)
+            .ok_or(AppError::BadRequest("Not in this lobby".to_string()))?
+            .player
+            .clone())
+    }
+
+    pub async fn pass_until_my_turn(&mut self, user: &Claims, passing: bool) -> AppResult<()> {
+        if self.data.game_state.status != GameStatus::InGame {
+            return Err(AppError::BadRequest("The game isn't running".to_string()));
+        }
+        let player = self.player_for(user)?;
+        self.game
+            .lock()
+            .await
+            .pass_until_my_turn(&player, passing)
+            .await;
+
+        Ok(())
+    }
+
+    // Mulligans are only allowed before anyone has had a turn to play with.
+    pub async fn mulligan(&mut self, user: &Claims) -> AppResult<MulliganInfo> {
+        let player = self.player_for(user)?;
+        let phase = {
+            let mut game = self.game.lock().await;
+            if self.data.game_state.status != GameStatus::InGame {
+                return Err(AppError::BadRequest(
+                    "Mulligans are only allowed at the start of the game".to_string(),
+                ));
+            }
+            game.mulligan(&player).await.map_err(AppError::BadRequest)?;
+            game.current_turn
+                .as_ref()
+                .map(|turn| turn.phase)
+                .unwrap_or(TurnPhase::Untap)
+        };
+
+        let player = player.lock().await;
+        let mut hand = vec![];
+        for card in &player.cards_in_hand {
+            hand.push(CardWithDetails::from_card(card.lock().await.clone(), phase, false).await);
+        }
+
+        Ok(MulliganInfo {
+            player_id: user.sub.clone(),
+            hand,
+            mulligans_taken: player.mulligans_taken,
+            bottom_count: player.cards_to_bottom() as u8,
+        })
+    }
+
+    pub async fn keep_hand(&mut self, user: &Claims, hand_indexes: Vec<usize>) -> AppResult<()> {
+        let player = self.player_for(user)?;
+        self.game
+            .lock()
+            .await
+            .keep_hand(&player, hand_indexes)
+            .await
+            .map_err(AppError::BadRequest)
+    }
+
+    // The replay of the `game_number`th finished game, counting from 1.
+    pub fn replay(&self, game_number: usize) -> AppResult<&Replay> {
+        game_number
+            .checked_sub(1)
+            .and_then(|index| self.replays.get(index))
+            .ok_or(AppError::BadRequest(format!(
+                "No replay for game {}",
+                game_number
+            )))
+    }
+
+    pub fn connect(&mut self, user_id: &str) {
+        *self.connections.entry(user_id.to_string()).or_default() += 1;
+    }
+
+    // Returns the disconnect's generation once `user_id` has no subscriptions
+    // left, i.e. when a grace timer should start.
+    pub fn disconnect(&mut self, user_id: &str) -> Option<u64> {
+        let connections = self.connections.get_mut(user_id)?;
+        *connections = connections.saturating_sub(1);
+        if *connections > 0 {
+            return None;
+        }
+
+        let generation = self.disconnects.entry(user_id.to_string()).or_default();
+        *generation += 1;
+        Some(*generation)
+    }
+
+    // Still gone since the disconnect that started this grace timer.
+    pub fn is_disconnected(&self, user_id: &str, generation: u64) -> bool {
+        self.connections.get(user_id).copied().unwrap_or_default() == 0
+            && self.disconnects.get(user_id) == Some(&generation)
     }
 
     pub fn message(&mut self, user: &Claims, message: String) -> &mut Self {
@@ -320,11 +979,21 @@ impl Lobby {
 }
 
 mod test {
-    use std::{cell::RefCell, rc::Rc};
+    use std::{cell::RefCell, rc::Rc, slice::from_ref, sync::Arc};
 
+    use tokio::sync::Mutex;
     use tokio_stream::StreamExt;
 
-    use crate::{lobby::lobby::Lobby, services::jwt::Claims};
+    use crate::{
+        game::{
+            player::{Player, TopCardReveal},
+            stat::{Stat, StatType, Stats},
+            zone::Zone,
+            GameStatus,
+        },
+        lobby::lobby::{DeckSelector, Lobby, LobbySettings},
+        services::jwt::Claims,
+    };
 
     #[tokio::test]
     async fn test() {
@@ -355,6 +1024,307 @@ mod test {
             .borrow_mut()
             .join(&user_id2)
             .await
+            .unwrap()
             .message(&user_id2, "test".to_string());
     }
+
+    fn claims(sub: &str) -> Claims {
+        Claims {
+            sub: sub.to_string(),
+            jti: None,
+            exp: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_join_rejected_when_full() {
+        let mut lobby = Lobby::new(&claims("leader")).await;
+        lobby.join(&claims("second")).await.unwrap();
+
+        assert!(lobby.join(&claims("third")).await.is_err());
+        assert!(lobby.join(&claims("second")).await.is_ok());
+        assert_eq!(lobby.data.game_state.players.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_reserved_seat_is_held() {
+        let mut lobby = Lobby::new(&claims("leader")).await;
+        lobby
+            .update_settings(
+                &claims("leader"),
+                LobbySettings {
+                    max_players: 2,
+                    reserved_seats: vec!["friend".to_string()],
+                    ranked: false,
+                    disconnect_grace_secs: 60,
+                    best_of: 1,
+                    auto_pass_priority: false,
+                    skip_empty_priority_windows: true,
+                    public: false,
+                    singleton: false,
+                },
+            )
+            .unwrap();
+
+        assert!(lobby.join(&claims("stranger")).await.is_err());
+        assert!(lobby.join(&claims("friend")).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_only_leader_starts_when_ready() {
+        let mut lobby = Lobby::new(&claims("leader")).await;
+        lobby.join(&claims("second")).await.unwrap();
+
+        assert!(lobby.start(&claims("leader")).is_err());
+        lobby.ready(&claims("leader")).await;
+        lobby.ready(&claims("second")).await;
+
+        assert!(lobby.start(&claims("second")).is_err());
+        assert_eq!(lobby.data.game_state.status, GameStatus::NeedsPlayers);
+
+        lobby.start(&claims("leader")).unwrap();
+        assert_eq!(lobby.data.game_state.status, GameStatus::WaitingForStart(5));
+    }
+
+    #[tokio::test]
+    async fn test_decklists_are_checked_against_the_current_rules() {
+        let mut lobby = Lobby::new(&claims("leader")).await;
+        lobby.join(&claims("second")).await.unwrap();
+        lobby.ready(&claims("leader")).await;
+        lobby.ready(&claims("second")).await;
+
+        let singleton = LobbySettings {
+            singleton: true,
+            ..lobby.data.settings.clone()
+        };
+        assert!(lobby.update_settings(&claims("leader"), singleton).is_err());
+        assert!(!lobby.data.settings.singleton);
+
+        // Rules that changed under the seated decks still stop the game
+        lobby.data.settings.singleton = true;
+        assert!(lobby.start(&claims("leader")).is_err());
+        assert_eq!(lobby.data.game_state.status, GameStatus::NeedsPlayers);
+    }
+
+    #[tokio::test]
+    async fn test_leader_kicks_player() {
+        let mut lobby = Lobby::new(&claims("leader")).await;
+        lobby.join(&claims("second")).await.unwrap();
+
+        assert!(lobby.kick(&claims("second"), "leader").await.is_err());
+        lobby.kick(&claims("leader"), "second").await.unwrap();
+
+        assert!(!lobby.data.game_state.players.contains_key("second"));
+        assert_eq!(lobby.cloned_game().await.lock().await.players.len(), 1);
+        assert!(lobby.join(&claims("third")).await.is_ok());
+        assert_eq!(lobby.data.game_state.players["third"].player_index, 1);
+    }
+
+    #[tokio::test]
+    async fn test_last_player_standing_wins() {
+        let mut lobby = Lobby::new(&claims("leader")).await;
+        lobby.join(&claims("second")).await.unwrap();
+        lobby.data.game_state.status = GameStatus::InGame;
+        lobby.start_game().await;
+        assert!(lobby.finish_if_over().await.is_none());
+
+        let game = lobby.cloned_game().await;
+        let second = lobby.data.game_state.players["second"].player.clone();
+        second
+            .lock()
+            .await
+            .stat_manager
+            .add_stat("damage".to_string(), Stat::new(StatType::Health, -20));
+        game.lock().await.handle_deaths().await;
+
+        let result = lobby.finish_if_over().await.unwrap();
+        assert_eq!(result.winner.as_deref(), Some("leader"));
+        assert_eq!(result.lobby_code, lobby.data.join_code);
+        assert_eq!(result.players.len(), 2);
+        assert_eq!(lobby.data.game_state.status, GameStatus::GameOver);
+        // Only recorded once
+        assert!(lobby.finish_if_over().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_sideboard_swaps_carry_into_game_two() {
+        let leader = claims("leader");
+        let second = claims("second");
+        let mut lobby = Lobby::new(&leader).await;
+        lobby.join(&second).await.unwrap();
+        lobby.data.settings.best_of = 3;
+        lobby
+            .select_deck(&second, DeckSelector::Black)
+            .await
+            .unwrap();
+        lobby.ready(&leader).await;
+        lobby.ready(&second).await;
+        lobby.start(&leader).unwrap();
+        lobby.data.game_state.status = GameStatus::InGame;
+        lobby.start_game().await;
+
+        let kill = |player: Arc<Mutex<Player>>| async move {
+            player
+                .lock()
+                .await
+                .stat_manager
+                .add_stat("damage".to_string(), Stat::new(StatType::Health, -20));
+        };
+        kill(lobby.data.game_state.players["second"].player.clone()).await;
+        lobby.cloned_game().await.lock().await.handle_deaths().await;
+        assert!(lobby.finish_if_over().await.is_some());
+        assert_eq!(lobby.data.match_score["leader"], 1);
+        assert_eq!(lobby.data.game_state.status, GameStatus::Sideboarding);
+
+        let decklist = lobby.data.game_state.players["second"]
+            .decklist
+            .clone()
+            .unwrap();
+        let hydra = decklist
+            .main
+            .iter()
+            .find(|card| card.name == "Voracious Hydra")
+            .unwrap()
+            .id
+            .clone();
+        let spirit = decklist.sideboard[0].id.clone();
+        // Dropping below the registered deck size isn't allowed
+        assert!(lobby.sideboard(&second, from_ref(&hydra), &[]).is_err());
+        lobby
+            .sideboard(&second, from_ref(&hydra), from_ref(&spirit))
+            .unwrap();
+
+        lobby.ready(&leader).await;
+        lobby.ready(&second).await;
+        assert!(lobby
+            .sideboard(&second, from_ref(&spirit), from_ref(&hydra))
+            .is_err());
+        lobby.start(&leader).unwrap();
+        lobby.data.game_state.status = GameStatus::InGame;
+        lobby.start_game().await;
+
+        let game = lobby.cloned_game().await;
+        assert_eq!(game.lock().await.players.len(), 2);
+        let player = lobby.data.game_state.players["second"].player.clone();
+        {
+            let player = player.lock().await;
+            let ids: Vec<String> = {
+                let mut ids = vec![];
+                for card in player.deck.draw_pile.iter().chain(&player.cards_in_hand) {
+                    ids.push(card.lock().await.id.clone());
+                }
+                ids
+            };
+            assert_eq!(ids.len(), decklist.main.len());
+            assert!(ids.contains(&spirit));
+            assert!(!ids.contains(&hydra));
+        }
+
+        // Winning game two takes the match
+        kill(player).await;
+        game.lock().await.handle_deaths().await;
+        assert!(lobby.finish_if_over().await.is_some());
+        assert_eq!(lobby.data.match_score["leader"], 2);
+        assert_eq!(lobby.data.games_played, 2);
+        assert_eq!(lobby.data.game_state.status, GameStatus::GameOver);
+    }
+
+    #[tokio::test]
+    async fn test_revealed_top_card_is_only_shown_to_its_owner() {
+        let leader = claims("leader");
+        let mut lobby = Lobby::new(&leader).await;
+        lobby.join(&claims("second")).await.unwrap();
+        lobby.ready(&leader).await;
+        lobby.ready(&claims("second")).await;
+        lobby.start(&leader).unwrap();
+        lobby.data.game_state.status = GameStatus::InGame;
+        lobby.start_game().await;
+
+        let second = lobby.data.game_state.players["second"].player.clone();
+        let top_id = {
+            let mut second = second.lock().await;
+            second.reveal_top_card = Some(TopCardReveal::Owner);
+            let top = second.deck.draw_pile.last().unwrap().clone();
+            let id = top.lock().await.id.clone();
+            id
+        };
+        lobby.refresh_game_state().await;
+
+        let mut owner_view = lobby.data.clone();
+        owner_view.redact_for("second");
+        let shown = owner_view.game_state.players["second"].top_card.as_ref();
+        assert_eq!(shown.unwrap().card.id, top_id);
+        assert!(owner_view.game_state.players["second"]
+            .public_info
+            .revealed_top_card
+            .is_none());
+
+        let mut opponent_view = lobby.data.clone();
+        opponent_view.redact_for("leader");
+        assert!(opponent_view.game_state.players["second"]
+            .top_card
+            .is_none());
+        assert!(opponent_view.game_state.players["leader"]
+            .top_card
+            .is_none());
+
+        // Revealed to everyone, it's public
+        second.lock().await.reveal_top_card = Some(TopCardReveal::Everyone);
+        lobby.refresh_game_state().await;
+        let mut opponent_view = lobby.data.clone();
+        opponent_view.redact_for("leader");
+        let shown = opponent_view.game_state.players["second"]
+            .public_info
+            .revealed_top_card
+            .as_ref();
+        assert_eq!(shown.unwrap().card.id, top_id);
+    }
+
+    #[tokio::test]
+    async fn test_face_down_cards_are_only_shown_to_their_controller() {
+        let leader = claims("leader");
+        let mut lobby = Lobby::new(&leader).await;
+        lobby.join(&claims("second")).await.unwrap();
+        lobby.ready(&leader).await;
+        lobby.ready(&claims("second")).await;
+        lobby.start(&leader).unwrap();
+        lobby.data.game_state.status = GameStatus::InGame;
+        lobby.start_game().await;
+
+        let second = lobby.data.game_state.players["second"].player.clone();
+        let top = second.lock().await.deck.draw_pile.last().unwrap().clone();
+        let (id, name, card_type, power) = {
+            let mut top = top.lock().await;
+            let (card_type, power) = (top.card_type.clone(), top.get_stat_value(StatType::Power));
+            top.turn_face_down();
+            (top.id.clone(), top.name.clone(), card_type, power)
+        };
+        lobby
+            .cloned_game()
+            .await
+            .lock()
+            .await
+            .move_card(&top, Zone::Library, Zone::Battlefield)
+            .await
+            .unwrap();
+        lobby.refresh_game_state().await;
+
+        let mut owner_view = lobby.data.clone();
+        owner_view.redact_for("second");
+        let owner_state = &owner_view.game_state.players["second"];
+        let hidden = &owner_state.face_down_cards[0].card;
+        assert_eq!(hidden.name, name);
+        assert_eq!(hidden.card_type, card_type);
+        assert_eq!(hidden.get_stat_value(StatType::Power), power);
+        assert!(hidden.face_down);
+        let public = &owner_state.public_info.cards_in_play[0].card;
+        assert_eq!(public.id, id);
+        assert_eq!(public.name, "");
+
+        let mut opponent_view = lobby.data.clone();
+        opponent_view.redact_for("leader");
+        let opponent_state = &opponent_view.game_state.players["second"];
+        assert!(opponent_state.face_down_cards.is_empty());
+        assert_eq!(opponent_state.public_info.cards_in_play[0].card.name, "");
+    }
 }
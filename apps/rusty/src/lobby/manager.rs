@@ -1,38 +1,59 @@
 use futures::stream::StreamExt;
 use futures::Stream;
-use redis::aio::PubSub;
+use redis::aio::{MultiplexedConnection, PubSub};
 use redis::{AsyncCommands, Client};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use specta::Type;
-use tokio::sync::mpsc;
+use sqlx::{Pool, Postgres};
+use tokio::sync::{mpsc, watch};
 use tokio::task;
 use tokio::time::timeout;
 use tokio_stream::wrappers::ReceiverStream;
+use tracing::{debug, error, info, instrument, warn};
 
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, MutexGuard};
 
-use super::lobby::{Lobby, LobbyData};
+use super::audit::AuditEntry;
+use super::lobby::{Lobby, LobbyData, LobbyListing};
 use crate::error::{AppError, AppResult};
 use crate::game::action::{CardAction, CardRequiredTarget};
 use crate::game::card::Card;
+use crate::game::cost::Cost;
 use crate::game::effects::EffectTarget;
-use crate::game::mana::ManaType;
+use crate::game::mana::{ManaPool, ManaType};
 use crate::game::player::Player;
-use crate::game::stat::Stats;
-use crate::game::{ActionType, CardWithDetails, FrontendTarget, Game, GameStatus, PlayerStatus};
+use crate::game::{
+    ActionType, CardWithDetails, FrontendCardTarget, FrontendTarget, Game, GameStatus,
+    StackItemView,
+};
 use crate::http::controllers::lobby::{
-    ActionCardArgs, PlayCardArgs, RespondMandatoryAbility, RespondOptionalAbility,
+    ActionCardArgs, FlashbackCardArgs, PlayCardArgs, RespondMandatoryAbility,
+    RespondOptionalAbility, SuspendCardArgs, TurnFaceUpArgs,
 };
+use crate::metrics::METRICS;
+use crate::models::error::{ModelError, ModelResult};
+use crate::models::match_result::{MatchResult, NewMatch};
+use crate::models::rating::{Rating, K_FACTOR};
 use crate::services::jwt::{Claims, JwtService};
 
+// How long to wait on a lobby's lock before assuming something is stuck
+const LOBBY_LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+
 #[derive(Clone)]
 pub struct LobbyManager {
     redis_client: Arc<redis::Client>,
+    // Opened the first time it's needed, then shared by every publish and
+    // audit write. Dropped on I/O errors so the next call reconnects.
+    redis_conn: Arc<Mutex<Option<MultiplexedConnection>>>,
     lobbies: Arc<Mutex<HashMap<String, Arc<Mutex<Lobby>>>>>,
+    // Flipped to true once the server starts shutting down
+    shutdown: Arc<watch::Sender<bool>>,
+    // Where finished matches are recorded, if anywhere
+    pool: Option<Arc<Pool<Postgres>>>,
 }
 
 #[derive(Type, Deserialize, Clone, Serialize, Debug)]
@@ -56,6 +77,9 @@ pub struct ExecuteAbility {
     card: CardWithDetails,
     details: AbilityDetails,
     pub player_id: String,
+    pub choices: Vec<CardWithDetails>,
+    // The full cost, mana included
+    pub costs: Vec<Cost>,
 }
 
 impl ExecuteAbility {
@@ -81,8 +105,53 @@ impl ExecuteAbility {
                 meets_requirements,
             },
             player_id,
+            choices: vec![],
+            costs: vec![],
         }
     }
+
+    pub fn with_costs(mut self, costs: Vec<Cost>) -> Self {
+        self.costs = costs;
+        self
+    }
+
+    pub fn with_choices(mut self, choices: Vec<CardWithDetails>) -> Self {
+        self.choices = choices;
+        self
+    }
+}
+
+// Ticks down while a player is away, until they reconnect or concede.
+#[derive(Type, Deserialize, Clone, Serialize, Debug)]
+pub struct DisconnectCountdown {
+    pub user_id: String,
+    pub seconds_remaining: u32,
+}
+
+// Sent to a player after they mulligan, so they know how many cards to bottom.
+#[derive(Type, Deserialize, Clone, Serialize, Debug)]
+pub struct MulliganInfo {
+    pub player_id: String,
+    pub hand: Vec<CardWithDetails>,
+    pub mulligans_taken: u8,
+    pub bottom_count: u8,
+}
+
+// Sent whenever a mana ability adds to someone's pool. Mana abilities skip the
+// stack, so this is the only notice anyone gets of them.
+#[derive(Type, Deserialize, Clone, Serialize, Debug)]
+pub struct ManaAdded {
+    pub player_id: String,
+    pub card_id: String,
+    pub mana: Vec<ManaType>,
+}
+
+// A player's whole pool after it changed, so floating mana shows up without
+// waiting for the next full update.
+#[derive(Type, Deserialize, Clone, Serialize, Debug)]
+pub struct ManaPoolUpdate {
+    pub player_id: String,
+    pub mana_pool: ManaPool,
 }
 
 #[derive(Type, Clone, Deserialize, Serialize, Debug)]
@@ -94,6 +163,11 @@ pub enum LobbyCommand {
     TurnMessages(LobbyTurnMessage),
     AskExecuteAbility(ExecuteAbility),
     MandatoryExecuteAbility(ExecuteAbility),
+    DisconnectCountdown(DisconnectCountdown),
+    MulliganInfo(MulliganInfo),
+    ManaAdded(ManaAdded),
+    ManaPoolUpdated(ManaPoolUpdate),
+    StackUpdated(Vec<StackItemView>),
 }
 
 impl std::fmt::Debug for LobbyManager {
@@ -145,6 +219,18 @@ impl LobbyManager {
         Ok(lobby_id)
     }
 
+    // Locks a lobby, giving up after `wait` in case something is holding on
+    // to it for good.
+    async fn lock_lobby(
+        lobby: &Arc<Mutex<Lobby>>,
+        wait: Duration,
+    ) -> AppResult<MutexGuard<'_, Lobby>> {
+        timeout(wait, lobby.lock()).await.map_err(|_| {
+            warn!("Timeout trying to acquire lobby lock");
+            AppError::Timeout("Timeout while locking lobby".to_string())
+        })
+    }
+
     pub async fn get_lobby(&self, join_code: &String) -> AppResult<Arc<Mutex<Lobby>>> {
         // Lock the `lobbies` to get the lobby reference.
         let lobbies = self.lobbies.lock().await;
@@ -160,22 +246,33 @@ impl LobbyManager {
 
     // Stream game updates from Redis for a specific lobby
     pub async fn subscribe_to_lobby_updates(
-        &self,
+        self: &Arc<Self>,
         lobby_id: String,
         access_token: String,
     ) -> AppResult<impl Stream<Item = LobbyCommand>> {
         let user = JwtService::decode(&access_token).or(Err(AppError::Unauthorized))?;
         let (tx, rx) = mpsc::channel::<LobbyCommand>(100);
 
-        println!("{:?} has joined!", user.claims);
+        info!(lobby_id = %lobby_id, user = %user.claims.sub, "player joined");
+        self.player_connected(&lobby_id, &user.claims.sub).await;
 
         // Clone redis client so it can be passed into the async block.
         let redis_client = Arc::clone(&self.redis_client);
+        let shutdown = self.shutdown.subscribe();
+        let lobby_manager_weak = Arc::downgrade(self);
 
         // Spawn the Redis subscription in a new task, but keep the mutex scope minimal
         tokio::spawn(async move {
-            if let Err(e) = Self::handle_lobby_subscription(redis_client, lobby_id, tx).await {
-                eprintln!("Error in subscription: {:?}", e);
+            if let Err(e) =
+                Self::handle_lobby_subscription(redis_client, lobby_id.clone(), tx, shutdown).await
+            {
+                warn!("Error in subscription: {:?}", e);
+            }
+
+            if let Some(lobby_manager) = lobby_manager_weak.upgrade() {
+                lobby_manager
+                    .player_disconnected(&lobby_id, &user.claims.sub)
+                    .await;
             }
         });
 
@@ -188,16 +285,31 @@ impl LobbyManager {
         redis_client: Arc<redis::Client>,
         lobby_id: String,
         tx: mpsc::Sender<LobbyCommand>,
+        mut shutdown: watch::Receiver<bool>,
     ) -> Result<(), Box<dyn std::error::Error>> {
+        if *shutdown.borrow() {
+            return Ok(());
+        }
+
         let mut pubsub_conn = redis_client.get_async_pubsub().await?;
         pubsub_conn.subscribe(&lobby_id).await?;
 
         let mut pubsub_stream = pubsub_conn.on_message();
-        while let Some(message) = pubsub_stream.next().await {
+        loop {
+            let message = tokio::select! {
+                message = pubsub_stream.next() => message,
+                _ = shutdown.wait_for(|closing| *closing) => break,
+                // The subscriber went away
+                _ = tx.closed() => break,
+            };
+            let Some(message) = message else {
+                break;
+            };
+
             let payload: String = message.get_payload()?;
             if let Ok(game) = serde_json::from_str::<LobbyCommand>(&payload) {
                 if tx.send(game).await.is_err() {
-                    eprintln!("Receiver dropped");
+                    warn!("Receiver dropped");
                     break;
                 }
             }
@@ -205,66 +317,240 @@ impl LobbyManager {
         Ok(())
     }
 
-    pub async fn join_lobby(&self, lobby_id: &str, user: &Claims) -> Option<()> {
+    pub async fn player_connected(&self, lobby_id: &str, user_id: &str) {
+        if let Ok(lobby) = self.get_lobby(&lobby_id.to_string()).await {
+            lobby.lock().await.connect(user_id);
+        }
+    }
+
+    // Once a player in a running game has dropped their last subscription they
+    // get the lobby's grace period to come back before they concede.
+    pub async fn player_disconnected(self: &Arc<Self>, lobby_id: &str, user_id: &str) {
+        let Ok(lobby) = self.get_lobby(&lobby_id.to_string()).await else {
+            return;
+        };
+        let (generation, grace_secs) = {
+            let mut lobby = lobby.lock().await;
+            let generation = lobby.disconnect(user_id);
+            if lobby.data.game_state.status != GameStatus::InGame {
+                return;
+            }
+            (generation, lobby.data.settings.disconnect_grace_secs)
+        };
+        let Some(generation) = generation else {
+            return;
+        };
+
+        let lobby_manager = Arc::clone(self);
+        let lobby_id = lobby_id.to_string();
+        let user_id = user_id.to_string();
+        tokio::spawn(async move {
+            lobby_manager
+                .concede_after_grace(&lobby_id, &user_id, generation, grace_secs)
+                .await;
+        });
+    }
+
+    async fn concede_after_grace(
+        &self,
+        lobby_id: &str,
+        user_id: &str,
+        generation: u64,
+        grace_secs: u32,
+    ) {
+        for seconds_remaining in (1..=grace_secs).rev() {
+            if !self.still_disconnected(lobby_id, user_id, generation).await {
+                return;
+            }
+            self.send_command(
+                lobby_id,
+                LobbyCommand::DisconnectCountdown(DisconnectCountdown {
+                    user_id: user_id.to_string(),
+                    seconds_remaining,
+                }),
+            )
+            .await
+            .ok();
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+
+        if self.still_disconnected(lobby_id, user_id, generation).await {
+            if let Err(e) = self.concede(lobby_id, user_id).await {
+                warn!(lobby_id, user_id, "failed to concede: {:?}", e);
+            }
+        }
+    }
+
+    async fn still_disconnected(&self, lobby_id: &str, user_id: &str, generation: u64) -> bool {
+        if self.is_shutting_down() {
+            return false;
+        }
+        match self.get_lobby(&lobby_id.to_string()).await {
+            Ok(lobby) => {
+                let lobby = lobby.lock().await;
+                lobby.data.game_state.status == GameStatus::InGame
+                    && lobby.is_disconnected(user_id, generation)
+            }
+            Err(_) => false,
+        }
+    }
+
+    #[instrument(skip(self))]
+    pub async fn concede(&self, lobby_id: &str, user_id: &str) -> AppResult<()> {
+        let lobby = self.get_lobby(&lobby_id.to_string()).await?;
+        lobby.lock().await.concede(user_id).await?;
+        self.notify_lobby(lobby_id).await.ok();
+
+        Ok(())
+    }
+
+    #[instrument(skip_all, fields(lobby_id = %lobby_id, user = %user.sub))]
+    pub async fn mulligan(&self, lobby_id: &str, user: &Claims) -> AppResult<()> {
+        let lobby = self.get_lobby(&lobby_id.to_string()).await?;
+        let info = lobby.lock().await.mulligan(user).await?;
+        METRICS.action_processed("mulligan");
+        self.send_command(lobby_id, LobbyCommand::MulliganInfo(info))
+            .await
+            .ok();
+        self.notify_lobby(lobby_id).await.ok();
+
+        Ok(())
+    }
+
+    pub async fn pass_until_my_turn(
+        &self,
+        lobby_id: &str,
+        user: &Claims,
+        passing: bool,
+    ) -> AppResult<()> {
+        let lobby = self.get_lobby(&lobby_id.to_string()).await?;
+        lobby.lock().await.pass_until_my_turn(user, passing).await?;
+        METRICS.action_processed("pass_until_my_turn");
+        self.notify_lobby(lobby_id).await.ok();
+
+        Ok(())
+    }
+
+    pub async fn keep_hand(
+        &self,
+        lobby_id: &str,
+        user: &Claims,
+        hand_indexes: Vec<usize>,
+    ) -> AppResult<()> {
+        let lobby = self.get_lobby(&lobby_id.to_string()).await?;
+        lobby.lock().await.keep_hand(user, hand_indexes).await?;
+        METRICS.action_processed("keep_hand");
+        self.notify_lobby(lobby_id).await.ok();
+
+        Ok(())
+    }
+
+    pub async fn sideboard(
+        &self,
+        lobby_id: &str,
+        user: &Claims,
+        remove: &[String],
+        add: &[String],
+    ) -> AppResult<()> {
+        let lobby = self.get_lobby(&lobby_id.to_string()).await?;
+        lobby.lock().await.sideboard(user, remove, add)?;
+        self.notify_lobby(lobby_id).await.ok();
+
+        Ok(())
+    }
+
+    // Public lobbies that are still waiting on players and have a free seat.
+    pub async fn list_lobbies(&self) -> Vec<LobbyListing> {
+        let lobbies: Vec<Arc<Mutex<Lobby>>> = self.lobbies.lock().await.values().cloned().collect();
+        let mut listings = vec![];
+        for lobby in lobbies {
+            if let Some(listing) = lobby.lock().await.listing() {
+                listings.push(listing);
+            }
+        }
+        listings.sort_by(|a, b| a.join_code.cmp(&b.join_code));
+
+        listings
+    }
+
+    // The join code of the lobby `user` is seated in, so they can get back to it.
+    pub async fn find_lobby_for(&self, user: &Claims) -> Option<String> {
+        let lobbies: Vec<Arc<Mutex<Lobby>>> = self.lobbies.lock().await.values().cloned().collect();
+        for lobby in lobbies {
+            let lobby = lobby.lock().await;
+            if lobby.is_seated(&user.sub) {
+                return Some(lobby.data.join_code.clone());
+            }
+        }
+
+        None
+    }
+
+    #[instrument(skip_all, fields(lobby_id = %lobby_id, user = %user.sub))]
+    pub async fn join_lobby(&self, lobby_id: &str, user: &Claims) -> AppResult<()> {
         {
             let hash_map = self.lobbies.lock().await;
-            let lobby = hash_map.get(lobby_id)?;
-            lobby.lock().await.join(user).await;
+            let lobby = hash_map
+                .get(lobby_id)
+                .ok_or(AppError::BadRequest("Bad lobby id".to_string()))?;
+            lobby.lock().await.join(user).await?;
         }
         // lobby.lock().await.message(user, args.text);
         self.notify_lobby(lobby_id).await.ok();
 
-        Some(())
+        Ok(())
+    }
+
+    #[instrument(skip_all, fields(lobby_id = %lobby_id, user = %user.sub))]
+    pub async fn start_lobby(&self, lobby_id: &str, user: &Claims) -> AppResult<()> {
+        let lobby = self.get_lobby(&lobby_id.to_string()).await?;
+        lobby.lock().await.start(user)?;
+        self.notify_lobby(lobby_id).await.ok();
+
+        Ok(())
+    }
+
+    pub async fn kick_player(&self, lobby_id: &str, user: &Claims, user_id: &str) -> AppResult<()> {
+        let lobby = self.get_lobby(&lobby_id.to_string()).await?;
+        lobby.lock().await.kick(user, user_id).await?;
+        self.notify_lobby(lobby_id).await.ok();
+
+        Ok(())
     }
 
-    pub async fn advance_turn(&self, lobby_id: &str, user: &Claims) -> Option<()> {
+    #[instrument(skip_all, fields(lobby_id = %lobby_id, user = %user.sub))]
+    pub async fn advance_turn(&self, lobby_id: &str, user: &Claims) -> AppResult<()> {
         // TODO: check if current turn is user's turn..
-        {
-            let hash_map = self.lobbies.lock().await;
-            let lobby = hash_map.get(lobby_id)?;
-            lobby.lock().await.advance_turn().await;
-        }
+        let lobby = self.get_lobby(&lobby_id.to_string()).await?;
+        lobby.lock().await.advance_turn().await?;
+        METRICS.action_processed("advance_turn");
         // lobby.lock().await.message(user, args.text);
         self.notify_lobby(lobby_id).await.ok();
 
-        Some(())
+        Ok(())
     }
 
+    // `ability_id` is the prompt being answered, if any. Library picks index
+    // into the cards it showed rather than the whole library.
     pub async fn convert(
         target: Option<FrontendTarget>,
         lobby: &Arc<Mutex<Lobby>>,
+        ability_id: Option<&str>,
     ) -> Option<EffectTarget> {
         match target {
             Some(target) => match target {
-                FrontendTarget::Card(frontend_card_target) => match frontend_card_target.pile {
-                    crate::game::FrontendPileName::Hand => {
-                        let player = Arc::clone(
-                            &lobby.lock().await.cloned_game().await.lock().await.players
-                                [frontend_card_target.player_index as usize],
-                        );
-                        let card = &player.lock().await.cards_in_hand
-                            [frontend_card_target.card_index as usize];
-                        Some(EffectTarget::Card(Arc::clone(&card)))
-                    }
-                    crate::game::FrontendPileName::Play => {
-                        let player = Arc::clone(
-                            &lobby.lock().await.cloned_game().await.lock().await.players
-                                [frontend_card_target.player_index as usize],
-                        );
-                        let card = &player.lock().await.cards_in_play
-                            [frontend_card_target.card_index as usize];
-                        Some(EffectTarget::Card(Arc::clone(&card)))
-                    }
-                    crate::game::FrontendPileName::Spell => {
-                        let player = Arc::clone(
-                            &lobby.lock().await.cloned_game().await.lock().await.players
-                                [frontend_card_target.player_index as usize],
+                FrontendTarget::Card(frontend_card_target) => Some(EffectTarget::Card(
+                    Self::convert_card(frontend_card_target, lobby, ability_id).await?,
+                )),
+                FrontendTarget::Cards(frontend_card_targets) => {
+                    let mut cards = vec![];
+                    for frontend_card_target in frontend_card_targets {
+                        cards.push(
+                            Self::convert_card(frontend_card_target, lobby, ability_id).await?,
                         );
-                        let card =
-                            &player.lock().await.spells[frontend_card_target.card_index as usize];
-                        Some(EffectTarget::Card(Arc::clone(&card)))
                     }
-                },
+                    Some(EffectTarget::Cards(cards))
+                }
                 FrontendTarget::Player(player_index) => Some(EffectTarget::Player(Arc::clone(
                     &lobby.lock().await.cloned_game().await.lock().await.players
                         [player_index as usize],
@@ -274,14 +560,43 @@ impl LobbyManager {
         }
     }
 
+    async fn convert_card(
+        frontend_card_target: FrontendCardTarget,
+        lobby: &Arc<Mutex<Lobby>>,
+        ability_id: Option<&str>,
+    ) -> Option<Arc<Mutex<Card>>> {
+        let game = lobby.lock().await.cloned_game().await;
+        let game = game.lock().await;
+        let index = frontend_card_target.card_index as usize;
+        if matches!(
+            frontend_card_target.pile,
+            crate::game::FrontendPileName::Library
+        ) {
+            return game.ability_choice(ability_id?, index);
+        }
+
+        let player = Arc::clone(&game.players[frontend_card_target.player_index as usize]);
+        let player = player.lock().await;
+        let card = match frontend_card_target.pile {
+            crate::game::FrontendPileName::Hand => player.cards_in_hand.get(index),
+            crate::game::FrontendPileName::Play => player.cards_in_play.get(index),
+            crate::game::FrontendPileName::Spell => player.spells.get(index),
+            crate::game::FrontendPileName::Library => None,
+        };
+
+        card.cloned()
+    }
+
+    #[instrument(skip_all, fields(lobby_id = %args.code, user = %user.sub))]
     pub async fn attach_card(&self, args: ActionCardArgs, user: &Claims) -> AppResult<()> {
+        self.audit(&args.code, user, "attach_card", &args).await;
         let lobby_id = args.code;
         {
             let hash_map = self.lobbies.lock().await;
             let lobby = hash_map
                 .get(&lobby_id)
                 .ok_or_else(|| AppError::BadRequest("Bad lobby".to_string()))?;
-            let target = Self::convert(args.target, lobby).await;
+            let target = Self::convert(args.target, lobby, None).await;
             lobby
                 .lock()
                 .await
@@ -291,22 +606,51 @@ impl LobbyManager {
                     target,
                 )
                 .await?;
-            println!("attached card, notifying lobby");
+            debug!("attached card, notifying lobby");
         }
         // lobby.lock().await.message(user, args.text);
+        METRICS.action_processed("attach_card");
         self.notify_lobby(&lobby_id).await.ok();
 
         Ok(())
     }
 
+    #[instrument(skip_all, fields(lobby_id = %args.code, user = %user.sub))]
+    pub async fn equip_card(&self, args: ActionCardArgs, user: &Claims) -> AppResult<()> {
+        self.audit(&args.code, user, "equip_card", &args).await;
+        let lobby_id = args.code;
+        {
+            let hash_map = self.lobbies.lock().await;
+            let lobby = hash_map
+                .get(&lobby_id)
+                .ok_or_else(|| AppError::BadRequest("Bad lobby".to_string()))?;
+            let target = Self::convert(args.target, lobby, None).await;
+            lobby
+                .lock()
+                .await
+                .equip_card(
+                    args.player_index as usize,
+                    args.in_play_index as usize,
+                    target,
+                )
+                .await?;
+        }
+        METRICS.action_processed("equip_card");
+        self.notify_lobby(&lobby_id).await.ok();
+
+        Ok(())
+    }
+
+    #[instrument(skip_all, fields(lobby_id = %args.code, user = %user.sub))]
     pub async fn action_card(&self, args: ActionCardArgs, user: &Claims) -> AppResult<()> {
+        self.audit(&args.code, user, "action_card", &args).await;
         let lobby_id = args.code;
         {
             let hash_map = self.lobbies.lock().await;
             let lobby = hash_map
                 .get(&lobby_id)
                 .ok_or_else(|| AppError::BadRequest("Bad lobby".to_string()))?;
-            let target = { Self::convert(args.target, lobby).await };
+            let target = { Self::convert(args.target, lobby, None).await };
             lobby
                 .lock()
                 .await
@@ -317,9 +661,10 @@ impl LobbyManager {
                     args.trigger_id,
                 )
                 .await?;
-            println!("actioned card, notifying lobby");
+            debug!("actioned card, notifying lobby");
         }
         // lobby.lock().await.message(user, args.text);
+        METRICS.action_processed("action_card");
         self.notify_lobby(&lobby_id).await.ok();
 
         Ok(())
@@ -330,13 +675,15 @@ impl LobbyManager {
         args: RespondMandatoryAbility,
         user: &Claims,
     ) -> AppResult<()> {
+        self.audit(&args.code, user, "respond_mandatory_player_ability", &args)
+            .await;
         let lobby_id = args.code;
         {
             let hash_map = self.lobbies.lock().await;
             let lobby = hash_map
                 .get(&lobby_id)
                 .ok_or_else(|| AppError::BadRequest("Bad lobby".to_string()))?;
-            let target = { Self::convert(args.target, lobby).await };
+            let target = { Self::convert(args.target, lobby, Some(&args.ability_id)).await };
             let player = lobby
                 .lock()
                 .await
@@ -353,9 +700,10 @@ impl LobbyManager {
                 .await
                 .respond_mandatory_player_ability(args.ability_id, player, target)
                 .await?;
-            println!("actioned card, notifying lobby");
+            debug!("actioned card, notifying lobby");
         }
         // lobby.lock().await.message(user, args.text);
+        METRICS.action_processed("respond_mandatory_player_ability");
         self.notify_lobby(&lobby_id).await.ok();
 
         Ok(())
@@ -366,13 +714,15 @@ impl LobbyManager {
         args: RespondOptionalAbility,
         user: &Claims,
     ) -> AppResult<()> {
+        self.audit(&args.code, user, "respond_optional_player_ability", &args)
+            .await;
         let lobby_id = args.code;
         {
             let hash_map = self.lobbies.lock().await;
             let lobby = hash_map
                 .get(&lobby_id)
                 .ok_or_else(|| AppError::BadRequest("Bad lobby".to_string()))?;
-            let target = { Self::convert(args.target, lobby).await };
+            let target = { Self::convert(args.target, lobby, Some(&args.ability_id)).await };
             let player = lobby
                 .lock()
                 .await
@@ -389,15 +739,18 @@ impl LobbyManager {
                 .await
                 .respond_optional_player_ability(args.ability_id, player, target, args.response)
                 .await?;
-            println!("actioned card, notifying lobby");
+            debug!("actioned card, notifying lobby");
         }
         // lobby.lock().await.message(user, args.text);
+        METRICS.action_processed("respond_optional_player_ability");
         self.notify_lobby(&lobby_id).await.ok();
 
         Ok(())
     }
 
+    #[instrument(skip_all, fields(lobby_id = %args.code, user = %user.sub))]
     pub async fn play_card(&self, args: PlayCardArgs, user: &Claims) -> AppResult<()> {
+        self.audit(&args.code, user, "play_card", &args).await;
         let lobby_id = args.code.clone();
         let lobby_arc = self.get_lobby(&lobby_id).await?;
         let (player_arc, card_arc) = {
@@ -407,7 +760,7 @@ impl LobbyManager {
                 player.clone().lock().await.cards_in_hand[args.in_hand_index as usize].clone();
             (player, card)
         };
-        let target = Self::convert(args.target, &lobby_arc).await;
+        let target = Self::convert(args.target, &lobby_arc, None).await;
         let game_arc = {
             let lobby = lobby_arc.lock().await;
             lobby.cloned_game().await
@@ -417,10 +770,20 @@ impl LobbyManager {
 
         {
             let mut game = game_arc.lock().await;
+            if let Some(plan) = &args.payment {
+                game.pay_with(&player_arc, args.in_hand_index as usize, plan)
+                    .await
+                    .map_err(AppError::BadRequest)?;
+            } else if args.auto_pay {
+                game.auto_pay(&player_arc, args.in_hand_index as usize)
+                    .await
+                    .map_err(AppError::BadRequest)?;
+            }
             game.play_card(&player_arc, args.in_hand_index as usize, target.clone())
                 .await
                 .map_err(|x| AppError::BadRequest(x))?;
         }
+        METRICS.action_processed("play_card");
 
         let ga = Arc::clone(&game_arc);
         tokio::spawn(async move {
@@ -431,84 +794,187 @@ impl LobbyManager {
         Ok(())
     }
 
+    #[instrument(skip_all, fields(lobby_id = %args.code, user = %user.sub))]
+    pub async fn suspend_card(&self, args: SuspendCardArgs, user: &Claims) -> AppResult<()> {
+        self.audit(&args.code, user, "suspend_card", &args).await;
+        let lobby_arc = self.get_lobby(&args.code).await?;
+        let player_arc = Self::seated_player(&lobby_arc, user).await?;
+        let game_arc = lobby_arc.lock().await.cloned_game().await;
+
+        game_arc
+            .lock()
+            .await
+            .suspend_card(&player_arc, args.in_hand_index as usize)
+            .await
+            .map_err(AppError::BadRequest)?;
+        METRICS.action_processed("suspend_card");
+        self.notify_lobby(&args.code).await.ok();
+
+        Ok(())
+    }
+
+    #[instrument(skip_all, fields(lobby_id = %args.code, user = %user.sub))]
+    pub async fn turn_face_up(&self, args: TurnFaceUpArgs, user: &Claims) -> AppResult<()> {
+        self.audit(&args.code, user, "turn_face_up", &args).await;
+        let lobby_arc = self.get_lobby(&args.code).await?;
+        let player_arc = Self::seated_player(&lobby_arc, user).await?;
+        let game_arc = lobby_arc.lock().await.cloned_game().await;
+
+        game_arc
+            .lock()
+            .await
+            .turn_face_up(&player_arc, args.in_play_index as usize)
+            .await
+            .map_err(AppError::BadRequest)?;
+        METRICS.action_processed("turn_face_up");
+        self.notify_lobby(&args.code).await.ok();
+
+        Ok(())
+    }
+
+    #[instrument(skip_all, fields(lobby_id = %args.code, user = %user.sub))]
+    pub async fn flashback_card(&self, args: FlashbackCardArgs, user: &Claims) -> AppResult<()> {
+        self.audit(&args.code, user, "flashback_card", &args).await;
+        let lobby_arc = self.get_lobby(&args.code).await?;
+        let player_arc = Self::seated_player(&lobby_arc, user).await?;
+        let target = Self::convert(args.target, &lobby_arc, None).await;
+        let game_arc = lobby_arc.lock().await.cloned_game().await;
+
+        let card_arc = game_arc
+            .lock()
+            .await
+            .flashback_card(&player_arc, args.graveyard_index as usize, target)
+            .await
+            .map_err(AppError::BadRequest)?;
+        METRICS.action_processed("flashback_card");
+
+        tokio::spawn(async move {
+            Game::process_action_queue(game_arc, card_arc).await;
+        });
+
+        Ok(())
+    }
+
+    async fn seated_player(
+        lobby: &Arc<Mutex<Lobby>>,
+        user: &Claims,
+    ) -> AppResult<Arc<Mutex<Player>>> {
+        lobby
+            .lock()
+            .await
+            .data
+            .game_state
+            .players
+            .get(&user.sub)
+            .map(|player| Arc::clone(&player.player))
+            .ok_or(AppError::BadRequest("Not in this lobby".to_string()))
+    }
+
     pub async fn update_game_state(&self, lobby_id: &str) {
         let hash_map = self.lobbies.lock().await;
         let mut lobby = hash_map.get(lobby_id).unwrap().lock().await;
-        let game = lobby.cloned_game().await;
         match lobby.data.game_state.status {
-            GameStatus::NeedsPlayers => {
-                let all_ready = lobby
-                    .data
-                    .game_state
-                    .players
-                    .values()
-                    .all(|player| player.status == PlayerStatus::Ready);
-
-                if all_ready && lobby.data.game_state.players.len() > 1 {
-                    lobby.data.game_state.status = GameStatus::WaitingForStart(5);
-                }
-            }
+            // The leader kicks off the countdown with Lobby::start
+            GameStatus::NeedsPlayers => {}
             GameStatus::WaitingForStart(duration) => {
                 lobby.data.game_state.status = GameStatus::WaitingForStart(duration - 1);
                 if lobby.data.game_state.status == GameStatus::WaitingForStart(1) {
                     lobby.data.game_state.status = GameStatus::InGame;
                     lobby.start_game().await;
+                    METRICS.active_lobbies.inc();
                 }
             }
             GameStatus::InGame => {
-                let phase = {
-                    lobby.data.game_state.public_info = lobby.get_state().await;
-                    lobby
-                        .data
-                        .game_state
-                        .public_info
-                        .current_turn
-                        .clone()
-                        .unwrap()
-                        .phase
-                };
-
-                for (_, player) in lobby.data.game_state.players.iter_mut() {
-                    let mut hand = Vec::new();
-                    let mut cards_in_play = Vec::new();
-                    let mut spells = Vec::new();
-                    let player_cards_in_play = &player.player.lock().await.cards_in_play.clone();
-                    let player_spells = &player.player.lock().await.spells.clone();
-                    let player_cards_in_hand = &player.player.lock().await.cards_in_hand.clone();
-
-                    for card in player_cards_in_play {
-                        cards_in_play.push(
-                            CardWithDetails::from_card_arc(card, phase.clone(), true, &game).await,
-                        );
-                    }
-
-                    for card in player_spells {
-                        spells.push(
-                            CardWithDetails::from_card_arc(card, phase.clone(), false, &game).await,
-                        );
-                    }
-
-                    for card in player_cards_in_hand {
-                        hand.push(
-                            CardWithDetails::from_card_arc(card, phase.clone(), false, &game).await,
-                        );
-                    }
+                lobby.refresh_game_state().await;
+                if let Some(new_match) = lobby.finish_if_over().await {
+                    METRICS.active_lobbies.dec();
+                    self.record_match(new_match, lobby.data.settings.ranked);
+                }
+            }
+            // Players swap cards and ready up, then the leader starts again
+            GameStatus::Sideboarding => {}
+            GameStatus::GameOver => {}
+        }
+    }
 
-                    {
-                        let game_player = player.player.lock().await;
+    fn record_match(&self, new_match: NewMatch, ranked: bool) {
+        let Some(pool) = self.pool.clone() else {
+            return;
+        };
 
-                        player.public_info.spells = spells;
-                        player.public_info.hand_size = hand.len() as i32;
-                        player.public_info.cards_in_play = cards_in_play;
-                        player.public_info.mana_pool = game_player.mana_pool.clone();
-                        player.public_info.health = game_player
-                            .stat_manager
-                            .get_stat_value(crate::game::stat::StatType::Health);
-                    }
-                    player.hand = hand;
+        tokio::spawn(async move {
+            if let Err(e) = MatchResult::record(&*pool, &new_match).await {
+                error!(lobby_id = %new_match.lobby_code, "failed to record match: {:?}", e);
+            }
+            if ranked {
+                if let Err(e) = Self::update_ratings(&pool, &new_match).await {
+                    error!(
+                        lobby_id = %new_match.lobby_code,
+                        "failed to update ratings: {:?}",
+                        e
+                    );
                 }
             }
+        });
+    }
+
+    // Ratings only make sense head to head, so anything but a decided two
+    // player game is left alone.
+    async fn update_ratings(pool: &Pool<Postgres>, new_match: &NewMatch) -> ModelResult<()> {
+        let Some(winner) = &new_match.winner else {
+            return Ok(());
+        };
+        if new_match.players.len() != 2 {
+            return Ok(());
         }
+        let Some(loser) = new_match.players.iter().find(|player| *player != winner) else {
+            return Ok(());
+        };
+
+        let mut conn = pool
+            .acquire()
+            .await
+            .map_err(|e| ModelError::SqlError(e.to_string()))?;
+        Rating::record_result(&mut conn, winner, loser, K_FACTOR).await?;
+
+        Ok(())
+    }
+
+    // A snapshot of the lobby as `user` would see it in the next broadcast.
+    pub async fn get_lobby_state(&self, lobby_id: &str, user: &Claims) -> AppResult<LobbyData> {
+        let lobby = self.get_lobby(&lobby_id.to_string()).await?;
+        let mut lobby = Self::lock_lobby(&lobby, LOBBY_LOCK_TIMEOUT).await?;
+        if lobby.data.game_state.status == GameStatus::InGame {
+            lobby.refresh_game_state().await;
+        }
+
+        let mut data = lobby.data.clone();
+        data.redact_for(&user.sub);
+
+        Ok(data)
+    }
+
+    pub async fn legal_targets(
+        &self,
+        lobby_id: &str,
+        ability_id: &str,
+        user: &Claims,
+    ) -> AppResult<Vec<FrontendTarget>> {
+        let lobby = self.get_lobby(&lobby_id.to_string()).await?;
+        let (player, game) = {
+            let lobby = lobby.lock().await;
+            let player = lobby
+                .data
+                .game_state
+                .players
+                .get(&user.sub)
+                .map(|state| Arc::clone(&state.player))
+                .ok_or_else(|| AppError::BadRequest("Not in this game".to_string()))?;
+            (player, lobby.cloned_game().await)
+        };
+        let game = game.lock().await;
+
+        Ok(game.legal_targets(&player, ability_id).await)
     }
 
     pub async fn send_command(
@@ -517,54 +983,579 @@ impl LobbyManager {
         command: LobbyCommand,
     ) -> Result<(), Box<dyn std::error::Error>> {
         // Step 1: Get the Redis connection
-        let mut redis_conn = self.redis_client.get_multiplexed_async_connection().await?;
+        let mut redis_conn = self.redis_connection().await?;
 
         let lobby_data = serde_json::to_string(&command)?;
         // Step 5: Publish the data to Redis.
-        redis_conn.publish(lobby_id, lobby_data).await?;
+        let timer = METRICS.redis_publish_seconds.start_timer();
+        self.check_redis(redis_conn.publish(lobby_id, lobby_data).await)
+            .await?;
+        timer.observe_duration();
 
         Ok(())
     }
 
-    pub async fn notify_lobby(&self, lobby_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+    #[instrument(level = "debug", skip(self))]
+    pub async fn notify_lobby(&self, lobby_id: &str) -> AppResult<()> {
         self.update_game_state(lobby_id).await;
 
         // Step 1: Get the Redis connection
-        let mut redis_conn = self.redis_client.get_multiplexed_async_connection().await?;
+        let mut redis_conn = self
+            .redis_connection()
+            .await
+            .map_err(|e| AppError::InternalServerError(e.to_string()))?;
 
         // Step 2: Lock `lobbies` and extract the `lobby` reference.
-        let lobby = {
-            let lobbies = self.lobbies.lock().await;
-            let lobby = lobbies.get(lobby_id).ok_or("Lobby not found")?.clone();
-            lobby // release lobbies lock here
-        };
+        let lobby = self.get_lobby(&lobby_id.to_string()).await?;
 
         // Step 3: Now lock the `lobby` with a timeout to detect potential deadlock.
-        let data = LobbyCommand::Updated({
-            let lobby = match timeout(Duration::from_secs(5), lobby.lock()).await {
-                Ok(lock) => lock.data.clone(),
-                Err(_) => {
-                    eprintln!("Timeout trying to acquire lobby lock");
-                    return Err("Timeout while locking lobby".into());
-                }
-            };
-            lobby
-        });
+        let data = LobbyCommand::Updated(
+            Self::lock_lobby(&lobby, LOBBY_LOCK_TIMEOUT)
+                .await?
+                .data
+                .clone(),
+        );
 
         // Step 4: Serialize the lobby data.
-        let lobby_data = serde_json::to_string(&data)?;
+        let lobby_data = serde_json::to_string(&data)
+            .map_err(|e| AppError::InternalServerError(e.to_string()))?;
 
         // Step 5: Publish the data to Redis.
-        redis_conn.publish(lobby_id, lobby_data).await?;
+        let timer = METRICS.redis_publish_seconds.start_timer();
+        self.check_redis(redis_conn.publish::<_, _, ()>(lobby_id, lobby_data).await)
+            .await
+            .map_err(|e| AppError::InternalServerError(e.to_string()))?;
+        timer.observe_duration();
 
         Ok(())
     }
 
+    pub async fn record_action<T: Serialize>(
+        &self,
+        lobby_id: &str,
+        user: &Claims,
+        procedure: &str,
+        args: &T,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut redis_conn = self.redis_connection().await?;
+        let entry = serde_json::to_string(&AuditEntry::new(&user.sub, procedure, args))?;
+        self.check_redis(
+            redis_conn
+                .rpush::<_, _, ()>(AuditEntry::key(lobby_id), entry)
+                .await,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    // A finished game of the lobby's match as replay JSON, for saving to a file.
+    pub async fn export_replay(&self, lobby_id: &String, game_number: usize) -> AppResult<String> {
+        let lobby = self.get_lobby(lobby_id).await?;
+        let lobby = lobby.lock().await;
+        lobby
+            .replay(game_number)?
+            .to_json()
+            .map_err(AppError::InternalServerError)
+    }
+
+    pub async fn audit_trail(
+        &self,
+        lobby_id: &str,
+    ) -> Result<Vec<AuditEntry>, Box<dyn std::error::Error>> {
+        let mut redis_conn = self.redis_connection().await?;
+        let entries: Vec<String> = self
+            .check_redis(redis_conn.lrange(AuditEntry::key(lobby_id), 0, -1).await)
+            .await?;
+
+        Ok(entries
+            .iter()
+            .filter_map(|entry| serde_json::from_str(entry).ok())
+            .collect())
+    }
+
+    async fn redis_connection(&self) -> redis::RedisResult<MultiplexedConnection> {
+        let mut redis_conn = self.redis_conn.lock().await;
+        if let Some(conn) = redis_conn.as_ref() {
+            return Ok(conn.clone());
+        }
+
+        let conn = self.redis_client.get_multiplexed_async_connection().await?;
+        *redis_conn = Some(conn.clone());
+        Ok(conn)
+    }
+
+    // Forgets the shared connection when `result` shows it's broken, so the
+    // next call opens a new one.
+    async fn check_redis<T>(&self, result: redis::RedisResult<T>) -> redis::RedisResult<T> {
+        if let Err(err) = &result {
+            if err.is_io_error() || err.is_connection_dropped() {
+                warn!("dropping the redis connection: {}", err);
+                *self.redis_conn.lock().await = None;
+            }
+        }
+
+        result
+    }
+
+    // Records an action in the audit trail without failing the action itself.
+    async fn audit<T: Serialize>(&self, lobby_id: &str, user: &Claims, procedure: &str, args: &T) {
+        if let Err(err) = self.record_action(lobby_id, user, procedure, args).await {
+            error!(
+                "unable to audit {} in lobby {}: {}",
+                procedure, lobby_id, err
+            );
+        }
+    }
+
     pub async fn new(redis_url: &str) -> Result<Self, Box<dyn std::error::Error>> {
         let client = redis::Client::open(redis_url)?;
+        let (shutdown, _) = watch::channel(false);
         Ok(Self {
             redis_client: Arc::new(client),
+            redis_conn: Arc::new(Mutex::new(None)),
             lobbies: Arc::new(Mutex::new(HashMap::new())),
+            shutdown: Arc::new(shutdown),
+            pool: None,
         })
     }
+
+    pub fn with_pool(mut self, pool: Arc<Pool<Postgres>>) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+
+    // Tells the players of every running game that the server is going away
+    // and closes all Redis subscriptions. Lobbies only live in memory, so this
+    // is the last chance anyone gets to hear about it.
+    pub async fn shutdown(&self) {
+        let lobby_ids: Vec<String> = self.lobbies.lock().await.keys().cloned().collect();
+        for lobby_id in lobby_ids {
+            let in_game = match self.get_lobby(&lobby_id).await {
+                Ok(lobby) => lobby.lock().await.data.game_state.status == GameStatus::InGame,
+                Err(_) => false,
+            };
+            if in_game {
+                self.send_command(
+                    &lobby_id,
+                    LobbyCommand::DebugMessage("The server is shutting down".to_string()),
+                )
+                .await
+                .ok();
+            }
+        }
+
+        self.shutdown.send_replace(true);
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        *self.shutdown.borrow()
+    }
+}
+
+mod test {
+    use std::{sync::Arc, time::Duration};
+
+    use crate::{
+        error::AppError,
+        game::{
+            action::{delayed::SacrificeSelfAction, CardRequiredTarget},
+            decks::{
+                blue::{create_divination, create_island},
+                green::create_durkwood_baloth,
+            },
+            effects::EffectTarget,
+            mana::ManaType,
+            turn::TurnPhase,
+            zone::Zone,
+            Ability, ActionType, FrontendCardTarget, FrontendPileName, FrontendTarget, GameStatus,
+        },
+        http::controllers::lobby::{FlashbackCardArgs, PlayCardArgs, SuspendCardArgs},
+        lobby::manager::{LobbyCommand, LobbyManager},
+        metrics::METRICS,
+        services::jwt::Claims,
+    };
+
+    fn claims(sub: &str) -> Claims {
+        Claims {
+            sub: sub.to_string(),
+            jti: None,
+            exp: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_redis_connection_is_retried() {
+        // Nothing listens here, so every attempt to connect fails
+        let manager = LobbyManager::new("redis://127.0.0.1:1/").await.unwrap();
+        let message = || LobbyCommand::DebugMessage("hello".to_string());
+
+        assert!(manager.send_command("lobby", message()).await.is_err());
+        assert!(manager.redis_conn.lock().await.is_none());
+        // Not stuck on the first failure
+        assert!(manager.send_command("lobby", message()).await.is_err());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a running Redis"]
+    async fn test_audit_trail_records_actions_in_order() {
+        let manager = LobbyManager::new("redis://127.0.0.1/").await.unwrap();
+        let lobby_id = ulid::Ulid::new().to_string();
+        let first = Claims {
+            sub: "first".to_string(),
+            jti: None,
+            exp: 0,
+        };
+        let second = Claims {
+            sub: "second".to_string(),
+            jti: None,
+            exp: 0,
+        };
+        let args = PlayCardArgs {
+            code: lobby_id.clone(),
+            in_hand_index: 0,
+            target: None,
+            auto_pay: false,
+            payment: None,
+        };
+
+        manager
+            .record_action(&lobby_id, &first, "play_card", &args)
+            .await
+            .unwrap();
+        manager
+            .record_action(&lobby_id, &second, "action_card", &args)
+            .await
+            .unwrap();
+
+        let trail = manager.audit_trail(&lobby_id).await.unwrap();
+        assert_eq!(trail.len(), 2);
+        assert_eq!(trail[0].player_id, "first");
+        assert_eq!(trail[0].procedure, "play_card");
+        assert_eq!(trail[1].player_id, "second");
+        assert_eq!(trail[1].procedure, "action_card");
+        assert_eq!(trail[0].args_hash, trail[1].args_hash);
+        assert!(trail[0].timestamp <= trail[1].timestamp);
+    }
+
+    #[tokio::test]
+    async fn test_lobby_state_hides_other_hands() {
+        let manager = Arc::new(LobbyManager::new("redis://127.0.0.1/").await.unwrap());
+        let leader = claims("leader");
+        let second = claims("second");
+        let code = manager.create_lobby(&leader).await.unwrap();
+        manager.join_lobby(&code, &second).await.unwrap();
+        {
+            let lobby = manager.get_lobby(&code).await.unwrap();
+            let mut lobby = lobby.lock().await;
+            lobby.ready(&leader).await;
+            lobby.ready(&second).await;
+            lobby.data.game_state.status = GameStatus::InGame;
+            lobby.start_game().await;
+        }
+
+        let state = manager.get_lobby_state(&code, &leader).await.unwrap();
+        let players = &state.game_state.players;
+        assert_eq!(players["leader"].hand.len(), 6);
+        assert_eq!(state.game_state.public_info.deciding_hands.len(), 2);
+        assert!(players["second"].hand.is_empty());
+        assert_eq!(players["second"].public_info.hand_size, 6);
+
+        let state = manager
+            .get_lobby_state(&code, &claims("spectator"))
+            .await
+            .unwrap();
+        for player in state.game_state.players.values() {
+            assert!(player.hand.is_empty());
+            assert_eq!(player.public_info.hand_size, 6);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_library_pick_indexes_the_offered_choices() {
+        let manager = Arc::new(LobbyManager::new("redis://127.0.0.1/").await.unwrap());
+        let leader = claims("leader");
+        let second = claims("second");
+        let code = manager.create_lobby(&leader).await.unwrap();
+        manager.join_lobby(&code, &second).await.unwrap();
+        let lobby = manager.get_lobby(&code).await.unwrap();
+        {
+            let mut lobby = lobby.lock().await;
+            lobby.ready(&leader).await;
+            lobby.ready(&second).await;
+            lobby.data.game_state.status = GameStatus::InGame;
+            lobby.start_game().await;
+        }
+
+        let game = lobby.lock().await.cloned_game().await;
+        let (offered, ability_id) = {
+            let mut game = game.lock().await;
+            let player = game.players[0].clone();
+            let offered = player.lock().await.deck.draw_pile[1].clone();
+            let ability = Ability::new(
+                Arc::new(tokio::sync::Mutex::new(create_island())),
+                vec![],
+                CardRequiredTarget::None,
+                Arc::new(|_| Arc::new(SacrificeSelfAction {})),
+                "Choose a card from your library".to_string(),
+                ActionType::None,
+            )
+            .with_chooser(player)
+            .with_choices(vec![offered.clone()]);
+            game.ask_mandatory_player_ability(ability).await;
+            let ability_id = game.abilities.keys().next().unwrap().clone();
+            (offered, ability_id)
+        };
+
+        let target = LobbyManager::convert(
+            Some(FrontendTarget::Card(FrontendCardTarget {
+                player_index: 0,
+                pile: FrontendPileName::Library,
+                card_index: 0,
+            })),
+            &lobby,
+            Some(&ability_id),
+        )
+        .await;
+        let Some(EffectTarget::Card(picked)) = target else {
+            panic!("expected a card");
+        };
+        assert!(Arc::ptr_eq(&picked, &offered));
+    }
+
+    #[tokio::test]
+    async fn test_disconnected_player_concedes_after_grace() {
+        let manager = Arc::new(LobbyManager::new("redis://127.0.0.1/").await.unwrap());
+        let leader = claims("leader");
+        let second = claims("second");
+        let code = manager.create_lobby(&leader).await.unwrap();
+        manager.join_lobby(&code, &second).await.unwrap();
+        let lobby = manager.get_lobby(&code).await.unwrap();
+        {
+            let mut lobby = lobby.lock().await;
+            lobby.data.settings.disconnect_grace_secs = 1;
+            lobby.ready(&leader).await;
+            lobby.ready(&second).await;
+            lobby.data.game_state.status = GameStatus::InGame;
+            lobby.start_game().await;
+        }
+
+        // Coming back in time keeps them in the game
+        manager.player_connected(&code, "second").await;
+        manager.player_disconnected(&code, "second").await;
+        manager.player_connected(&code, "second").await;
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        assert_eq!(
+            lobby.lock().await.data.game_state.status,
+            GameStatus::InGame
+        );
+
+        manager.player_disconnected(&code, "second").await;
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+
+        let lobby = lobby.lock().await;
+        assert_eq!(lobby.data.game_state.status, GameStatus::GameOver);
+        let second = lobby.data.game_state.players["second"].player.clone();
+        assert!(!second.lock().await.is_alive);
+        assert_eq!(lobby.cloned_game().await.lock().await.players.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_lists_only_public_lobbies_with_open_seats() {
+        let manager = Arc::new(LobbyManager::new("redis://127.0.0.1/").await.unwrap());
+        let open = manager.create_lobby(&claims("open")).await.unwrap();
+        let private = manager.create_lobby(&claims("private")).await.unwrap();
+        let full = manager.create_lobby(&claims("full")).await.unwrap();
+        for code in [&open, &full] {
+            manager
+                .get_lobby(code)
+                .await
+                .unwrap()
+                .lock()
+                .await
+                .data
+                .settings
+                .public = true;
+        }
+        manager.join_lobby(&full, &claims("second")).await.unwrap();
+
+        let listings = manager.list_lobbies().await;
+        assert_eq!(listings.len(), 1);
+        assert_eq!(listings[0].join_code, open);
+        assert_eq!(listings[0].player_count, 1);
+        assert_eq!(listings[0].max_players, 2);
+        assert!(!listings.iter().any(|listing| listing.join_code == private));
+    }
+
+    #[tokio::test]
+    async fn test_finds_the_lobby_a_player_is_seated_in() {
+        let manager = Arc::new(LobbyManager::new("redis://127.0.0.1/").await.unwrap());
+        manager.create_lobby(&claims("someone")).await.unwrap();
+        let code = manager.create_lobby(&claims("leader")).await.unwrap();
+        manager.join_lobby(&code, &claims("second")).await.unwrap();
+
+        assert_eq!(
+            manager.find_lobby_for(&claims("second")).await,
+            Some(code.clone())
+        );
+        assert_eq!(manager.find_lobby_for(&claims("stranger")).await, None);
+
+        // Nothing to get back to once the match is over
+        manager
+            .get_lobby(&code)
+            .await
+            .unwrap()
+            .lock()
+            .await
+            .data
+            .game_state
+            .status = GameStatus::GameOver;
+        assert_eq!(manager.find_lobby_for(&claims("second")).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_stuck_lobby_lock_times_out() {
+        let manager = Arc::new(LobbyManager::new("redis://127.0.0.1/").await.unwrap());
+        let code = manager.create_lobby(&claims("leader")).await.unwrap();
+        let lobby = manager.get_lobby(&code).await.unwrap();
+
+        let held = lobby.lock().await;
+        let result = LobbyManager::lock_lobby(&lobby, Duration::from_millis(50)).await;
+        assert!(matches!(result, Err(AppError::Timeout(_))));
+
+        drop(held);
+        assert!(LobbyManager::lock_lobby(&lobby, Duration::from_millis(50))
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_playing_a_card_counts_as_an_action() {
+        let manager = Arc::new(LobbyManager::new("redis://127.0.0.1/").await.unwrap());
+        let leader = claims("leader");
+        let second = claims("second");
+        let code = manager.create_lobby(&leader).await.unwrap();
+        manager.join_lobby(&code, &second).await.unwrap();
+        let lobby = manager.get_lobby(&code).await.unwrap();
+        let player = {
+            let mut lobby = lobby.lock().await;
+            lobby.ready(&leader).await;
+            lobby.ready(&second).await;
+            lobby.data.game_state.status = GameStatus::InGame;
+            lobby.start_game().await;
+            lobby.data.game_state.players["leader"].player.clone()
+        };
+        let kept = METRICS.actions.with_label_values(&["keep_hand"]);
+        let kept_before = kept.get();
+        manager.keep_hand(&code, &leader, vec![]).await.unwrap();
+        manager.keep_hand(&code, &second, vec![]).await.unwrap();
+        assert!(kept.get() >= kept_before + 2);
+        player
+            .lock()
+            .await
+            .cards_in_hand
+            .insert(0, Arc::new(tokio::sync::Mutex::new(create_island())));
+
+        let played = METRICS.actions.with_label_values(&["play_card"]);
+        let before = played.get();
+        let args = PlayCardArgs {
+            code: code.clone(),
+            in_hand_index: 0,
+            target: None,
+            auto_pay: false,
+            payment: None,
+        };
+        manager.play_card(args, &leader).await.unwrap();
+
+        assert!(played.get() > before);
+    }
+
+    #[tokio::test]
+    async fn test_suspending_restarts_the_priority_loop() {
+        let manager = Arc::new(LobbyManager::new("redis://127.0.0.1/").await.unwrap());
+        let leader = claims("leader");
+        let second = claims("second");
+        let code = manager.create_lobby(&leader).await.unwrap();
+        manager.join_lobby(&code, &second).await.unwrap();
+        let lobby = manager.get_lobby(&code).await.unwrap();
+        let (game, player) = {
+            let mut lobby = lobby.lock().await;
+            lobby.ready(&leader).await;
+            lobby.ready(&second).await;
+            lobby.data.game_state.status = GameStatus::InGame;
+            lobby.start_game().await;
+            (
+                lobby.cloned_game().await,
+                lobby.data.game_state.players["leader"].player.clone(),
+            )
+        };
+        manager.keep_hand(&code, &leader, vec![]).await.unwrap();
+        manager.keep_hand(&code, &second, vec![]).await.unwrap();
+        game.lock().await.skip_to_phase(TurnPhase::Main).await;
+        let baloth = Arc::new(tokio::sync::Mutex::new(create_durkwood_baloth()));
+        {
+            let mut player = player.lock().await;
+            player.cards_in_hand.insert(0, baloth.clone());
+            player.mana_pool.add_mana(ManaType::Green);
+        }
+        game.lock().await.current_priority_player = Some((player.clone(), 15, ActionType::None));
+
+        let args = SuspendCardArgs {
+            code: code.clone(),
+            in_hand_index: 0,
+        };
+        manager.suspend_card(args, &leader).await.unwrap();
+
+        assert!(player.lock().await.zone_contains(Zone::Exile, &baloth));
+        assert_eq!(game.lock().await.performed_action(), ActionType::PlayedCard);
+    }
+
+    #[tokio::test]
+    async fn test_flashback_route_casts_from_the_graveyard() {
+        let manager = Arc::new(LobbyManager::new("redis://127.0.0.1/").await.unwrap());
+        let leader = claims("leader");
+        let second = claims("second");
+        let code = manager.create_lobby(&leader).await.unwrap();
+        manager.join_lobby(&code, &second).await.unwrap();
+        let lobby = manager.get_lobby(&code).await.unwrap();
+        let (game, player) = {
+            let mut lobby = lobby.lock().await;
+            lobby.ready(&leader).await;
+            lobby.ready(&second).await;
+            lobby.data.game_state.status = GameStatus::InGame;
+            lobby.start_game().await;
+            (
+                lobby.cloned_game().await,
+                lobby.data.game_state.players["leader"].player.clone(),
+            )
+        };
+        manager.keep_hand(&code, &leader, vec![]).await.unwrap();
+        manager.keep_hand(&code, &second, vec![]).await.unwrap();
+        game.lock().await.skip_to_phase(TurnPhase::Main).await;
+        let divination = Arc::new(tokio::sync::Mutex::new(
+            create_divination().with_flashback(vec![ManaType::Blue, ManaType::Colorless]),
+        ));
+        divination.lock().await.owner = Some(player.clone());
+        {
+            let mut player = player.lock().await;
+            player.zone_mut(Zone::Graveyard).push(divination.clone());
+            player.mana_pool.add_mana(ManaType::Blue);
+            player.mana_pool.add_mana(ManaType::Colorless);
+        }
+
+        let args = FlashbackCardArgs {
+            code: code.clone(),
+            graveyard_index: 0,
+            target: None,
+        };
+        manager.flashback_card(args, &leader).await.unwrap();
+
+        // Exiled as soon as it resolves
+        let mut player = player.lock().await;
+        assert!(!player.zone_contains(Zone::Graveyard, &divination));
+        assert!(
+            player.zone_contains(Zone::Stack, &divination)
+                || player.zone_contains(Zone::Exile, &divination)
+        );
+    }
 }
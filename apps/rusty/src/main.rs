@@ -1,10 +1,15 @@
-use std::{fs::write, future::IntoFuture, path::PathBuf, sync::Arc};
+use std::{
+    fs::write,
+    future::{Future, IntoFuture},
+    path::PathBuf,
+    sync::Arc,
+};
 
 use axum::{
     http::{
         header::{AUTHORIZATION, CONTENT_TYPE},
         request::Parts,
-        Method,
+        HeaderValue, Method,
     },
     routing::get,
 };
@@ -12,6 +17,7 @@ use database::create_connection;
 use error::{AppError, AppResult};
 use http::routers::create_router;
 use lobby::manager::LobbyManager;
+use metrics::METRICS;
 use services::jwt::{Claims, JwtService};
 use sqlx::{Executor, Pool, Postgres};
 use tokio::sync::Mutex;
@@ -19,37 +25,102 @@ use totp_rs::{Algorithm, Secret, TOTP};
 
 use rspc::Router;
 use tower_http::cors::{AllowOrigin, CorsLayer};
+use tracing::info;
+use tracing_subscriber::EnvFilter;
 
 async fn create_pool() -> Arc<Pool<Postgres>> {
     let database_url = dotenv::var("DATABASE_URL").unwrap();
     create_connection(&database_url).await
 }
 
-async fn create_lobby_manager() -> Arc<LobbyManager> {
-    let manager = LobbyManager::new("redis://127.0.0.1/").await.unwrap();
+async fn create_lobby_manager(pool: Option<Arc<Pool<Postgres>>>) -> Arc<LobbyManager> {
+    let mut manager = LobbyManager::new("redis://127.0.0.1/").await.unwrap();
+    if let Some(pool) = pool {
+        manager = manager.with_pool(pool);
+    }
     Arc::new(manager)
 }
 
-async fn create_app() -> axum::Router {
-    let router = create_router();
+// Origins from the comma separated ALLOWED_ORIGINS. Debug builds fall back to
+// the local frontend when it isn't set, release builds allow nothing.
+fn allowed_origins(value: Option<String>) -> Vec<HeaderValue> {
+    let value = match value {
+        Some(value) => value,
+        None if cfg!(debug_assertions) => "http://localhost:1420".to_string(),
+        None => return vec![],
+    };
+
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|origin| !origin.is_empty())
+        .filter_map(|origin| HeaderValue::from_str(origin).ok())
+        .collect()
+}
+
+fn create_cors_layer(origins: Vec<HeaderValue>) -> CorsLayer {
     let allowed_headers = [CONTENT_TYPE, AUTHORIZATION];
     let allowed_methods = [Method::GET, Method::POST, Method::OPTIONS];
-    let pool = create_pool().await;
-    let lobby_manager = create_lobby_manager().await;
+
+    CorsLayer::new()
+        .allow_methods(allowed_methods)
+        .allow_headers(allowed_headers)
+        .allow_origin(AllowOrigin::list(origins))
+        .allow_credentials(true)
+}
+
+// Log filter from LOG_LEVEL, in the same syntax as RUST_LOG (e.g.
+// "info,tfa::game=debug"). Anything missing or unparsable falls back to info.
+fn log_filter(value: Option<String>) -> EnvFilter {
+    value
+        .and_then(|value| EnvFilter::try_new(value).ok())
+        .unwrap_or_else(|| EnvFilter::new("info"))
+}
+
+// Resolves on ctrl-c or, on unix, SIGTERM.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c().await.ok();
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
+
+// Waits for `signal`, then lets the lobby manager wind down before the server
+// stops accepting connections and drains the ones it has.
+async fn shutdown_on(signal: impl Future<Output = ()>, lobby_manager: Arc<LobbyManager>) {
+    signal.await;
+    info!("shutting down");
+    lobby_manager.shutdown().await;
+}
+
+async fn create_app(pool: Arc<Pool<Postgres>>, lobby_manager: Arc<LobbyManager>) -> axum::Router {
+    let router = create_router();
+    let origins = allowed_origins(dotenv::var("ALLOWED_ORIGINS").ok());
 
     axum::Router::new()
         .route("/", get(|| async { "Hello 'rspc'!" }))
+        .route("/metrics", get(|| async { METRICS.render() }))
         .nest(
             "/rspc",
             rspc_axum::endpoint(router, |parts: Parts| Ctx::new(pool, parts, lobby_manager)),
         )
-        .layer(
-            CorsLayer::new()
-                .allow_methods(allowed_methods)
-                .allow_headers(allowed_headers)
-                .allow_origin(AllowOrigin::mirror_request())
-                .allow_credentials(true),
-        )
+        .layer(create_cors_layer(origins))
 }
 
 mod database;
@@ -57,6 +128,7 @@ mod error;
 mod game;
 mod http;
 mod lobby;
+mod metrics;
 mod models;
 mod services;
 
@@ -94,24 +166,20 @@ impl Ctx {
     }
 }
 
-// async fn handler(context: Ctx) {
-//     let account = Account::find(&context.pool.clone(), "test".to_string())
-//         .await
-//         .expect("hi");
-
-//     let totp = &account.get_current_code().expect("hi");
-//     let token = totp.generate_current().unwrap();
-
-//     println!("{:?}, token: {}", account, token);
-// }
-
 #[tokio::main]
 async fn main() {
-    // handler(context).await;
+    tracing_subscriber::fmt()
+        .with_env_filter(log_filter(dotenv::var("LOG_LEVEL").ok()))
+        .init();
 
-    let app = create_app().await;
+    let pool = create_pool().await;
+    let lobby_manager = create_lobby_manager(Some(pool.clone())).await;
+    let app = create_app(pool, lobby_manager.clone()).await;
     let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown_on(shutdown_signal(), lobby_manager))
+        .await
+        .unwrap();
 
     // let totp = TOTP::new(
     //     Algorithm::SHA1,
@@ -148,3 +216,53 @@ fn tst() {
     let token = totp.generate_current().unwrap();
     println!("{}", token);
 }
+
+#[tokio::test]
+async fn test_cors_only_reflects_allowed_origins() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let origins = allowed_origins(Some(
+        "http://good.example, http://other.example".to_string(),
+    ));
+    assert_eq!(origins.len(), 2);
+    let app = axum::Router::new()
+        .route("/", get(|| async { "ok" }))
+        .layer(create_cors_layer(origins));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    let request = |origin: &'static str| async move {
+        let mut stream = tokio::net::TcpStream::connect(address).await.unwrap();
+        let request = format!(
+            "GET / HTTP/1.1\r\nHost: localhost\r\nOrigin: {}\r\nConnection: close\r\n\r\n",
+            origin
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response.to_lowercase()
+    };
+
+    let allowed = request("http://good.example").await;
+    assert!(allowed.contains("access-control-allow-origin: http://good.example"));
+    let disallowed = request("http://evil.example").await;
+    assert!(!disallowed.contains("access-control-allow-origin"));
+}
+
+#[tokio::test]
+async fn test_shutdown_signal_closes_lobby_manager() {
+    let lobby_manager = create_lobby_manager(None).await;
+    let (trigger, signal) = tokio::sync::oneshot::channel::<()>();
+    let shutdown = tokio::spawn(shutdown_on(
+        async {
+            signal.await.ok();
+        },
+        lobby_manager.clone(),
+    ));
+
+    assert!(!lobby_manager.is_shutting_down());
+    trigger.send(()).unwrap();
+    shutdown.await.unwrap();
+    assert!(lobby_manager.is_shutting_down());
+}
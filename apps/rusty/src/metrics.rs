@@ -0,0 +1,73 @@
+use std::sync::LazyLock;
+
+use prometheus::{Histogram, HistogramOpts, IntCounterVec, IntGauge, Opts, Registry, TextEncoder};
+
+pub static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+// Everything the server reports about itself, scraped from /metrics.
+pub struct Metrics {
+    registry: Registry,
+    pub active_lobbies: IntGauge,
+    // Player actions that went through, by procedure
+    pub actions: IntCounterVec,
+    pub priority_window_seconds: Histogram,
+    pub redis_publish_seconds: Histogram,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new_custom(Some("tfa".to_string()), None).unwrap();
+
+        let active_lobbies =
+            IntGauge::new("active_lobbies", "Lobbies with a game in progress").unwrap();
+        let actions = IntCounterVec::new(
+            Opts::new("actions_total", "Player actions processed"),
+            &["procedure"],
+        )
+        .unwrap();
+        let priority_window_seconds = Histogram::with_opts(
+            HistogramOpts::new(
+                "priority_window_seconds",
+                "How long a player held priority before acting or passing",
+            )
+            .buckets(vec![0.05, 0.1, 0.25, 0.5, 1.0, 2.0, 3.0, 5.0]),
+        )
+        .unwrap();
+        let redis_publish_seconds = Histogram::with_opts(
+            HistogramOpts::new(
+                "redis_publish_seconds",
+                "Time taken to publish a lobby message to Redis",
+            )
+            .buckets(vec![0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25]),
+        )
+        .unwrap();
+
+        registry.register(Box::new(active_lobbies.clone())).unwrap();
+        registry.register(Box::new(actions.clone())).unwrap();
+        registry
+            .register(Box::new(priority_window_seconds.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(redis_publish_seconds.clone()))
+            .unwrap();
+
+        Self {
+            registry,
+            active_lobbies,
+            actions,
+            priority_window_seconds,
+            redis_publish_seconds,
+        }
+    }
+
+    pub fn action_processed(&self, procedure: &str) {
+        self.actions.with_label_values(&[procedure]).inc();
+    }
+
+    // The Prometheus text format
+    pub fn render(&self) -> String {
+        TextEncoder::new()
+            .encode_to_string(&self.registry.gather())
+            .unwrap_or_default()
+    }
+}
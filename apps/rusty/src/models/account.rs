@@ -0,0 +1,203 @@
+use sqlx::{query, query_as, PgExecutor};
+use totp_rs::{Algorithm, Secret, TOTP};
+use ulid::Ulid;
+
+use super::error::{ModelError, ModelResult};
+use crate::services::totp_cipher::TotpCipher;
+
+const TOTP_ISSUER: &str = "tfa";
+
+#[derive(Debug)]
+pub struct Account {
+    id: String,
+    email: String,
+    // Sealed with a TotpCipher, never stored as is
+    totp_secret: Option<String>,
+}
+
+impl Account {
+    pub async fn create(
+        executor: impl PgExecutor<'_>,
+        user_id: &String,
+        email: &String,
+    ) -> ModelResult<Account> {
+        query_as!(
+            Account,
+            "insert into accounts (id, user_id, email) values ($1, $2, $3) returning id, email, totp_secret",
+            Ulid::new().to_string(),
+            user_id,
+            email
+        )
+        .fetch_one(executor)
+        .await
+        .map_err(|e| ModelError::SqlError(e.to_string()))
+    }
+
+    pub async fn find_by_email(
+        executor: impl PgExecutor<'_>,
+        email: &String,
+    ) -> ModelResult<Account> {
+        query_as!(
+            Account,
+            "select id, email, totp_secret from accounts where email = $1",
+            email
+        )
+        .fetch_one(executor)
+        .await
+        .map_err(not_found)
+    }
+
+    pub async fn find_by_user(
+        executor: impl PgExecutor<'_>,
+        user_id: &String,
+    ) -> ModelResult<Account> {
+        query_as!(
+            Account,
+            "select id, email, totp_secret from accounts where user_id = $1",
+            user_id
+        )
+        .fetch_one(executor)
+        .await
+        .map_err(not_found)
+    }
+
+    // Generates and stores a new secret. Replacing an existing one needs a
+    // current code from it first. The returned TOTP is what the user adds to
+    // their authenticator.
+    pub async fn enroll_totp(
+        &mut self,
+        executor: impl PgExecutor<'_>,
+        cipher: &TotpCipher,
+        current_code: Option<&str>,
+    ) -> ModelResult<TOTP> {
+        if self.has_totp() && !current_code.is_some_and(|code| self.verify_code(code, cipher)) {
+            return Err(ModelError::TotpError(
+                "A current code is needed to replace the existing secret".to_string(),
+            ));
+        }
+
+        let secret = Secret::generate_secret().to_encoded().to_string();
+        let secret = cipher.seal(&secret).map_err(ModelError::TotpError)?;
+        query!(
+            "update accounts set totp_secret = $1 where id = $2",
+            secret,
+            self.id
+        )
+        .execute(executor)
+        .await
+        .map_err(|e| ModelError::SqlError(e.to_string()))?;
+
+        self.totp_secret = Some(secret);
+        self.get_current_code(cipher)
+    }
+
+    pub fn get_current_code(&self, cipher: &TotpCipher) -> ModelResult<TOTP> {
+        let secret = self
+            .totp_secret
+            .as_ref()
+            .ok_or_else(|| ModelError::TotpError("Account has no TOTP secret".to_string()))?;
+        let secret = cipher.open(secret).map_err(ModelError::TotpError)?;
+        let secret = Secret::Encoded(secret)
+            .to_bytes()
+            .map_err(|e| ModelError::TotpError(format!("{:?}", e)))?;
+
+        TOTP::new(
+            Algorithm::SHA1,
+            6,
+            1,
+            30,
+            secret,
+            Some(TOTP_ISSUER.to_string()),
+            self.email.clone(),
+        )
+        .map_err(|e| ModelError::TotpError(e.to_string()))
+    }
+
+    pub fn verify_code(&self, code: &str, cipher: &TotpCipher) -> bool {
+        self.get_current_code(cipher)
+            .ok()
+            .and_then(|totp| totp.check_current(code).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn get_email(&self) -> &String {
+        &self.email
+    }
+
+    pub fn has_totp(&self) -> bool {
+        self.totp_secret.is_some()
+    }
+}
+
+fn not_found(e: sqlx::Error) -> ModelError {
+    match e {
+        sqlx::Error::RowNotFound => ModelError::NotFound("No account found".to_string()),
+        e => ModelError::SqlError(e.to_string()),
+    }
+}
+
+mod test {
+    use sqlx::{query, Connection};
+
+    use super::Account;
+    use crate::{
+        models::{error::ModelError, test::database},
+        services::totp_cipher::TotpCipher,
+    };
+
+    #[tokio::test]
+    #[ignore = "needs Postgres"]
+    async fn test_create_and_find_account() {
+        let mut conn = database().await;
+        let cipher = TotpCipher::new("account test key");
+        // Everything happens in a transaction that is rolled back when dropped
+        let mut tx = conn.begin().await.unwrap();
+
+        let user_id = "account-test-user".to_string();
+        let email = "account-test@example.com".to_string();
+        query!(
+            "insert into users (id, password) values ($1, $2)",
+            user_id,
+            "hash"
+        )
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+
+        let mut account = Account::create(&mut *tx, &user_id, &email).await.unwrap();
+        assert!(!account.has_totp());
+        let totp = account.enroll_totp(&mut *tx, &cipher, None).await.unwrap();
+
+        let found = Account::find_by_email(&mut *tx, &email).await.unwrap();
+        assert_eq!(found.id, account.id);
+        assert!(found.verify_code(&totp.generate_current().unwrap(), &cipher));
+        assert!(!found.verify_code("not a code", &cipher));
+        // Only the sealed secret is stored
+        let stored = found.totp_secret.clone().unwrap();
+        assert_ne!(stored, totp.get_secret_base32());
+        assert!(!found.verify_code(
+            &totp.generate_current().unwrap(),
+            &TotpCipher::new("another key")
+        ));
+
+        let by_user = Account::find_by_user(&mut *tx, &user_id).await.unwrap();
+        assert_eq!(by_user.get_email(), &email);
+        assert!(matches!(
+            Account::find_by_user(&mut *tx, &"nobody".to_string()).await,
+            Err(ModelError::NotFound(_))
+        ));
+
+        // Replacing the secret needs a code from the current one
+        let mut account = by_user;
+        assert!(account.enroll_totp(&mut *tx, &cipher, None).await.is_err());
+        assert!(account
+            .enroll_totp(&mut *tx, &cipher, Some("000000"))
+            .await
+            .is_err());
+        let replaced = account
+            .enroll_totp(&mut *tx, &cipher, Some(&totp.generate_current().unwrap()))
+            .await
+            .unwrap();
+        assert_ne!(replaced.get_secret_base32(), totp.get_secret_base32());
+    }
+}
@@ -5,12 +5,16 @@ pub type ModelResult<T> = Result<T, ModelError>;
 #[derive(Debug)]
 pub enum ModelError {
     SqlError(String),
+    NotFound(String),
+    TotpError(String),
 }
 
 impl From<ModelError> for AppError {
     fn from(err: ModelError) -> AppError {
         match err {
             ModelError::SqlError(s) => AppError::InternalServerError(s),
+            ModelError::NotFound(s) => AppError::BadRequest(s),
+            ModelError::TotpError(s) => AppError::BadRequest(s),
         }
     }
 }
@@ -0,0 +1,106 @@
+use serde::Serialize;
+use specta::Type;
+use sqlx::{query_as, PgExecutor};
+use ulid::Ulid;
+
+use super::error::{ModelError, ModelResult};
+
+// A finished game, as it's written to the matches table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewMatch {
+    pub lobby_code: String,
+    // User ids of everyone who was seated
+    pub players: Vec<String>,
+    pub winner: Option<String>,
+    pub turn_count: i32,
+    pub duration_seconds: i32,
+}
+
+#[derive(Debug, Type, Serialize)]
+pub struct MatchResult {
+    pub id: String,
+    pub lobby_code: String,
+    pub players: Vec<String>,
+    pub winner: Option<String>,
+    pub turn_count: i32,
+    pub duration_seconds: i32,
+}
+
+impl MatchResult {
+    pub async fn record(
+        executor: impl PgExecutor<'_>,
+        new_match: &NewMatch,
+    ) -> ModelResult<MatchResult> {
+        query_as!(
+            MatchResult,
+            "insert into matches (id, lobby_code, players, winner, turn_count, duration_seconds)
+            values ($1, $2, $3, $4, $5, $6)
+            returning id, lobby_code, players, winner, turn_count, duration_seconds",
+            Ulid::new().to_string(),
+            new_match.lobby_code,
+            &new_match.players,
+            new_match.winner,
+            new_match.turn_count,
+            new_match.duration_seconds
+        )
+        .fetch_one(executor)
+        .await
+        .map_err(|e| ModelError::SqlError(e.to_string()))
+    }
+
+    // Most recent first.
+    pub async fn history_for(
+        executor: impl PgExecutor<'_>,
+        user_id: &String,
+    ) -> ModelResult<Vec<MatchResult>> {
+        query_as!(
+            MatchResult,
+            "select id, lobby_code, players, winner, turn_count, duration_seconds
+            from matches where $1 = any(players) order by finished_at desc, id desc",
+            user_id
+        )
+        .fetch_all(executor)
+        .await
+        .map_err(|e| ModelError::SqlError(e.to_string()))
+    }
+}
+
+mod test {
+    use sqlx::Connection;
+
+    use super::{MatchResult, NewMatch};
+    use crate::models::test::database;
+
+    #[tokio::test]
+    #[ignore = "needs Postgres"]
+    async fn test_record_and_fetch_match_history() {
+        let mut conn = database().await;
+        let mut tx = conn.begin().await.unwrap();
+
+        let recorded = MatchResult::record(
+            &mut *tx,
+            &NewMatch {
+                lobby_code: "match-test-lobby".to_string(),
+                players: vec!["match-test-a".to_string(), "match-test-b".to_string()],
+                winner: Some("match-test-a".to_string()),
+                turn_count: 9,
+                duration_seconds: 300,
+            },
+        )
+        .await
+        .unwrap();
+
+        let history = MatchResult::history_for(&mut *tx, &"match-test-b".to_string())
+            .await
+            .unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].id, recorded.id);
+        assert_eq!(history[0].winner.as_deref(), Some("match-test-a"));
+        assert_eq!(history[0].turn_count, 9);
+
+        let history = MatchResult::history_for(&mut *tx, &"match-test-c".to_string())
+            .await
+            .unwrap();
+        assert!(history.is_empty());
+    }
+}
@@ -1,2 +1,18 @@
+pub mod account;
 pub mod error;
+pub mod match_result;
+pub mod rating;
 pub mod user;
+
+mod test {
+    use sqlx::{Connection, PgConnection};
+
+    // The model tests need a migrated Postgres at DATABASE_URL, so they're
+    // ignored by default. Run them with `cargo test -- --ignored`.
+    pub(crate) async fn database() -> PgConnection {
+        let database_url = dotenv::var("DATABASE_URL").expect("DATABASE_URL is not set");
+        PgConnection::connect(&database_url)
+            .await
+            .expect("postgres is not available")
+    }
+}
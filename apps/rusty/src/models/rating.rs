@@ -0,0 +1,126 @@
+use sqlx::{query, Connection, PgConnection};
+
+use super::error::{ModelError, ModelResult};
+
+pub const DEFAULT_RATING: i32 = 1200;
+pub const K_FACTOR: i32 = 32;
+
+// Points the winner takes from the loser, scaled by how unlikely the win was.
+pub fn elo_change(winner_rating: i32, loser_rating: i32, k_factor: i32) -> i32 {
+    let expected = 1.0 / (1.0 + 10f64.powf((loser_rating - winner_rating) as f64 / 400.0));
+
+    (k_factor as f64 * (1.0 - expected)).round() as i32
+}
+
+pub struct Rating {}
+
+impl Rating {
+    // Moves points from the loser to the winner, returning both new ratings.
+    pub async fn record_result(
+        conn: &mut PgConnection,
+        winner: &String,
+        loser: &String,
+        k_factor: i32,
+    ) -> ModelResult<(i32, i32)> {
+        let mut tx = conn
+            .begin()
+            .await
+            .map_err(|e| ModelError::SqlError(e.to_string()))?;
+
+        // Unrated players start from the default
+        for user_id in [winner, loser] {
+            query!(
+                "insert into ratings (user_id, rating) values ($1, $2) on conflict (user_id) do nothing",
+                user_id,
+                DEFAULT_RATING
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| ModelError::SqlError(e.to_string()))?;
+        }
+        // Lock both rows so concurrent results can't overwrite each other
+        let rows = query!(
+            "select user_id, rating from ratings where user_id = any($1) order by user_id for update",
+            &[winner.clone(), loser.clone()]
+        )
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| ModelError::SqlError(e.to_string()))?;
+        let rating_of = |user_id: &String| {
+            rows.iter()
+                .find(|row| &row.user_id == user_id)
+                .map(|row| row.rating)
+                .unwrap_or(DEFAULT_RATING)
+        };
+
+        let change = elo_change(rating_of(winner), rating_of(loser), k_factor);
+        let ratings = (rating_of(winner) + change, rating_of(loser) - change);
+        for (user_id, rating) in [(winner, ratings.0), (loser, ratings.1)] {
+            query!(
+                "update ratings set rating = $1, games_played = games_played + 1 where user_id = $2",
+                rating,
+                user_id
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| ModelError::SqlError(e.to_string()))?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| ModelError::SqlError(e.to_string()))?;
+
+        Ok(ratings)
+    }
+}
+
+mod test {
+    use sqlx::{query, Connection};
+
+    use super::{elo_change, Rating, DEFAULT_RATING};
+    use crate::models::test::database;
+
+    #[test]
+    fn test_elo_change() {
+        assert_eq!(elo_change(1200, 1200, 32), 16);
+        // Beating a stronger player is worth more than beating a weaker one
+        assert_eq!(elo_change(1200, 1400, 32), 24);
+        assert_eq!(elo_change(1400, 1200, 32), 8);
+        assert_eq!(elo_change(1200, 1200, 16), 8);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs Postgres"]
+    async fn test_ranked_result_moves_ratings() {
+        let mut conn = database().await;
+        let mut tx = conn.begin().await.unwrap();
+
+        let winner = "rating-test-winner".to_string();
+        let loser = "rating-test-loser".to_string();
+        for user_id in [&winner, &loser] {
+            query!(
+                "insert into users (id, password) values ($1, $2)",
+                user_id,
+                "hash"
+            )
+            .execute(&mut *tx)
+            .await
+            .unwrap();
+        }
+        let ratings = Rating::record_result(&mut tx, &winner, &loser, 32)
+            .await
+            .unwrap();
+        assert_eq!(ratings, (DEFAULT_RATING + 16, DEFAULT_RATING - 16));
+
+        // The winner is now favoured, so a rematch win is worth less
+        let ratings = Rating::record_result(&mut tx, &winner, &loser, 32)
+            .await
+            .unwrap();
+        assert_eq!(ratings, (DEFAULT_RATING + 31, DEFAULT_RATING - 31));
+        let row = query!("select rating from ratings where user_id = $1", &loser)
+            .fetch_one(&mut *tx)
+            .await
+            .unwrap();
+        assert_eq!(row.rating, ratings.1);
+    }
+}
@@ -0,0 +1,72 @@
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Nonce,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use sha2::{Digest, Sha256};
+
+use crate::error::{AppError, AppResult};
+
+// Seals TOTP secrets before they go into the database, with AES-256-GCM under
+// a key derived from TOTP_ENCRYPTION_KEY. Sealed values are the nonce followed
+// by the ciphertext, base64 encoded.
+pub struct TotpCipher {
+    cipher: Aes256Gcm,
+}
+
+impl TotpCipher {
+    pub fn new(passphrase: &str) -> Self {
+        let key = Sha256::digest(passphrase.as_bytes());
+        Self {
+            cipher: Aes256Gcm::new(&key),
+        }
+    }
+
+    pub fn from_env() -> AppResult<Self> {
+        let passphrase = dotenv::var("TOTP_ENCRYPTION_KEY").map_err(|_| {
+            AppError::InternalServerError("TOTP_ENCRYPTION_KEY is not set".to_string())
+        })?;
+
+        Ok(Self::new(&passphrase))
+    }
+
+    pub fn seal(&self, secret: &str) -> Result<String, String> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, secret.as_bytes())
+            .map_err(|e| e.to_string())?;
+
+        let mut sealed = nonce.to_vec();
+        sealed.extend(ciphertext);
+        Ok(STANDARD.encode(sealed))
+    }
+
+    pub fn open(&self, sealed: &str) -> Result<String, String> {
+        let sealed = STANDARD.decode(sealed).map_err(|e| e.to_string())?;
+        if sealed.len() < 12 {
+            return Err("Sealed secret is too short".to_string());
+        }
+        let (nonce, ciphertext) = sealed.split_at(12);
+        let secret = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|e| e.to_string())?;
+
+        String::from_utf8(secret).map_err(|e| e.to_string())
+    }
+}
+
+mod test {
+    use super::TotpCipher;
+
+    #[test]
+    fn test_sealed_secret_only_opens_with_the_same_key() {
+        let cipher = TotpCipher::new("a key");
+        let sealed = cipher.seal("JBSWY3DPEHPK3PXP").unwrap();
+
+        assert!(!sealed.contains("JBSWY3DPEHPK3PXP"));
+        assert_eq!(cipher.open(&sealed).unwrap(), "JBSWY3DPEHPK3PXP");
+        assert!(TotpCipher::new("another key").open(&sealed).is_err());
+    }
+}